@@ -0,0 +1,48 @@
+-- Migration: Create restore_execution_logs table
+-- Stores one row per restore (rclone copy from a backup destination back to a local directory)
+
+CREATE TABLE restore_execution_logs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    backup_job_id UUID NOT NULL REFERENCES backup_jobs(id) ON DELETE CASCADE,
+
+    -- Execution details
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    status VARCHAR(20) NOT NULL DEFAULT 'running', -- running, completed, failed, cancelled
+
+    -- What was restored and where to
+    rclone_command TEXT NOT NULL,
+    source_path TEXT NOT NULL,      -- original source of the mapping
+    destination_path TEXT NOT NULL, -- backup destination the files were read from
+    target_path TEXT NOT NULL,      -- local directory the files were written to
+    path_filters TEXT[] NOT NULL DEFAULT '{}', -- sub-paths / globs requested (empty = everything)
+
+    -- Transfer statistics
+    files_transferred INTEGER DEFAULT 0,
+    files_checked INTEGER DEFAULT 0,
+    bytes_transferred BIGINT DEFAULT 0,
+    transfer_rate_mbps REAL,
+    duration_seconds INTEGER,
+
+    -- Error tracking
+    error_count INTEGER DEFAULT 0,
+    error_message TEXT,
+
+    -- Raw logs for debugging
+    rclone_stdout TEXT,
+    rclone_stderr TEXT,
+
+    -- Metadata
+    triggered_by VARCHAR(20) DEFAULT 'manual', -- manual, api, verification
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_restore_execution_logs_job_id ON restore_execution_logs(backup_job_id);
+CREATE INDEX idx_restore_execution_logs_status ON restore_execution_logs(status);
+CREATE INDEX idx_restore_execution_logs_started_at ON restore_execution_logs(started_at DESC);
+
+CREATE TRIGGER trigger_restore_execution_logs_updated_at
+    BEFORE UPDATE ON restore_execution_logs
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
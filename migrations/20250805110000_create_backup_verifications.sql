@@ -0,0 +1,53 @@
+-- Migration: Post-backup restore verification
+-- After a mapping is backed up, a sample (or all) of its files is restored to a
+-- temp directory and the SHA256 is compared against file_catalog.content_hash.
+
+ALTER TABLE backup_jobs
+    ADD COLUMN verify_after_backup BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN verification_sample_size INTEGER; -- NULL = verify every cataloged file
+
+CREATE TABLE backup_verifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    backup_job_id UUID NOT NULL REFERENCES backup_jobs(id) ON DELETE CASCADE,
+    execution_log_id UUID REFERENCES backup_execution_logs(id) ON DELETE SET NULL,
+    restore_log_id UUID REFERENCES restore_execution_logs(id) ON DELETE SET NULL,
+
+    source_path TEXT NOT NULL,
+    destination_path TEXT NOT NULL,
+    sample_size INTEGER, -- requested sample, NULL = all files
+
+    status VARCHAR(20) NOT NULL DEFAULT 'running', -- running, completed, failed
+    files_checked INTEGER NOT NULL DEFAULT 0,
+    files_passed INTEGER NOT NULL DEFAULT 0,
+    files_failed INTEGER NOT NULL DEFAULT 0,
+    restorability_percent REAL, -- files_passed / files_checked * 100
+    error_message TEXT,
+
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_backup_verifications_job_id ON backup_verifications(backup_job_id, started_at DESC);
+CREATE INDEX idx_backup_verifications_status ON backup_verifications(status);
+
+CREATE TRIGGER trigger_backup_verifications_updated_at
+    BEFORE UPDATE ON backup_verifications
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE backup_verification_files (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    verification_id UUID NOT NULL REFERENCES backup_verifications(id) ON DELETE CASCADE,
+    file_catalog_id UUID REFERENCES file_catalog(id) ON DELETE SET NULL,
+    file_path TEXT NOT NULL,
+    expected_hash VARCHAR(64) NOT NULL,
+    actual_hash VARCHAR(64), -- NULL when the file could not be restored
+    passed BOOLEAN NOT NULL,
+    error_message TEXT,
+    checked_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_backup_verification_files_verification ON backup_verification_files(verification_id);
+CREATE INDEX idx_backup_verification_files_failed ON backup_verification_files(verification_id) WHERE passed = FALSE;
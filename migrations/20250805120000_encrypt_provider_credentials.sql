@@ -0,0 +1,17 @@
+-- Migration: Encrypt cloud provider credentials at rest
+-- Credentials are stored as 'enc:v1:<base64>' (AES-256-GCM with the master key),
+-- which is longer than the plaintext, so the columns become TEXT.
+
+ALTER TABLE cloud_providers
+    ALTER COLUMN access_key TYPE TEXT,
+    ALTER COLUMN secret_key TYPE TEXT,
+    ALTER COLUMN b2_application_key TYPE TEXT;
+
+-- Existing plaintext rows cannot be encrypted here: the master key never reaches
+-- the database. The application encrypts them on startup
+-- (db::encrypt_plaintext_provider_credentials) once B2CLI_MASTER_KEY or
+-- B2CLI_MASTER_KEY_FILE is configured.
+
+COMMENT ON COLUMN cloud_providers.access_key IS 'Encrypted with the master key (enc:v1:...); plaintext only when no master key is configured';
+COMMENT ON COLUMN cloud_providers.secret_key IS 'Encrypted with the master key (enc:v1:...); plaintext only when no master key is configured';
+COMMENT ON COLUMN cloud_providers.b2_application_key IS 'Encrypted with the master key (enc:v1:...); plaintext only when no master key is configured';
@@ -0,0 +1,22 @@
+-- Migration: Persistent key-derivation settings
+-- Salt, Argon2 parameters and an encrypted key-check value, so the same master
+-- password derives the same key across restarts and a wrong one is rejected.
+-- Single-row table: there is one master key per installation.
+
+CREATE TABLE crypto_settings (
+    id SMALLINT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    version INTEGER NOT NULL,
+    algorithm VARCHAR(50) NOT NULL DEFAULT 'AES-256-GCM',
+    salt VARCHAR(64) NOT NULL,
+    argon2_memory_kib INTEGER NOT NULL,
+    argon2_iterations INTEGER NOT NULL,
+    argon2_parallelism INTEGER NOT NULL,
+    key_check TEXT NOT NULL, -- known constant encrypted with the derived key
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER trigger_crypto_settings_updated_at
+    BEFORE UPDATE ON crypto_settings
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
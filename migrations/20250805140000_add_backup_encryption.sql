@@ -0,0 +1,14 @@
+-- Migration: Client-side encryption for backup jobs
+-- Jobs with encryption = 'rclone_crypt' upload through an rclone crypt layer.
+-- Each job gets its own random crypt password/salt, sealed with the master key.
+
+ALTER TABLE backup_jobs
+    ADD COLUMN encryption VARCHAR(20) NOT NULL DEFAULT 'none'
+    CHECK (encryption IN ('none', 'rclone_crypt'));
+
+CREATE TABLE backup_job_keys (
+    backup_job_id UUID PRIMARY KEY REFERENCES backup_jobs(id) ON DELETE CASCADE,
+    crypt_password TEXT NOT NULL, -- sealed (enc:v1:...)
+    crypt_salt TEXT NOT NULL,     -- sealed (enc:v1:...)
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,9 @@
+-- Migration: Named ignore templates for backup jobs and scan configs
+-- Each entry is a built-in template name (rust, node, python, ...) or 'auto',
+-- which detects the templates from marker files in each source root.
+
+ALTER TABLE backup_jobs
+    ADD COLUMN ignore_templates TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE scan_configs
+    ADD COLUMN ignore_templates TEXT[] NOT NULL DEFAULT '{}';
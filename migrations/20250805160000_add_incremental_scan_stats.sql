@@ -0,0 +1,15 @@
+-- Migration: Incremental scanning
+-- Unchanged files (same size and mtime) reuse the cataloged hash instead of being read again.
+-- force_rehash / paranoid_sample_rate control when they are re-hashed anyway.
+
+ALTER TABLE scan_jobs
+    ADD COLUMN force_rehash BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN paranoid_sample_rate DOUBLE PRECISION NOT NULL DEFAULT 0,
+    ADD COLUMN files_hashed BIGINT NOT NULL DEFAULT 0,       -- files read to compute the hash
+    ADD COLUMN files_hash_skipped BIGINT NOT NULL DEFAULT 0, -- unchanged files, hash reused
+    ADD COLUMN hash_mismatches BIGINT NOT NULL DEFAULT 0;    -- re-hashed with same size/mtime but different content
+
+ALTER TABLE scan_configs
+    ADD COLUMN force_rehash BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN paranoid_sample_rate DOUBLE PRECISION NOT NULL DEFAULT 0
+        CHECK (paranoid_sample_rate >= 0 AND paranoid_sample_rate <= 1);
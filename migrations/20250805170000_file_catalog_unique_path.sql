@@ -0,0 +1,12 @@
+-- Migration: Unique constraint on file_catalog.file_path
+-- The scanner writes catalog rows in batches with INSERT ... ON CONFLICT (file_path)
+-- and derives file_history rows in the same statement.
+-- The unique index created alongside file_history is promoted to a real constraint,
+-- and the older non-unique index on the same column is dropped.
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_file_catalog_path_unique ON file_catalog(file_path);
+
+ALTER TABLE file_catalog
+    ADD CONSTRAINT file_catalog_file_path_key UNIQUE USING INDEX idx_file_catalog_path_unique;
+
+DROP INDEX IF EXISTS idx_file_catalog_path;
@@ -0,0 +1,10 @@
+-- Migration: Deleted file detection
+-- After a completed scan, catalog rows under the scanned root that were not seen
+-- (last_scan_at older than the scan start) are marked is_active = FALSE and get a
+-- file_history row with scan_type = 'deleted'. GET /files/changes lists these events.
+
+ALTER TABLE scan_jobs
+    ADD COLUMN files_deleted BIGINT NOT NULL DEFAULT 0; -- rows marked inactive by this scan
+
+-- Deletion pass filters active rows by path prefix and last_scan_at
+CREATE INDEX idx_file_catalog_active_last_scan ON file_catalog(last_scan_at) WHERE is_active = TRUE;
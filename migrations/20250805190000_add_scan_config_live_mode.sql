@@ -0,0 +1,7 @@
+-- Migration: Live catalog mode for scan configs
+-- Configs with live_mode keep a filesystem watcher on root_path and apply changes to
+-- file_catalog/file_history incrementally. Each watcher session records its history
+-- rows under a scan_jobs row with scan_type = 'live'.
+
+ALTER TABLE scan_configs
+    ADD COLUMN live_mode BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,15 @@
+-- Migration: Change-driven backups
+-- Jobs with trigger_mode = 'watch' watch their mapping source paths and run after
+-- watch_quiet_seconds without changes, at most watch_max_delay_seconds after the
+-- first pending change and never sooner than watch_min_interval_seconds after the
+-- previous watch-triggered run. Those runs are logged with triggered_by = 'watch'.
+
+ALTER TABLE backup_jobs
+    ADD COLUMN trigger_mode VARCHAR(20) NOT NULL DEFAULT 'schedule'
+        CHECK (trigger_mode IN ('schedule', 'watch')),
+    ADD COLUMN watch_quiet_seconds INTEGER NOT NULL DEFAULT 60
+        CHECK (watch_quiet_seconds > 0),
+    ADD COLUMN watch_min_interval_seconds INTEGER NOT NULL DEFAULT 300
+        CHECK (watch_min_interval_seconds > 0),
+    ADD COLUMN watch_max_delay_seconds INTEGER NOT NULL DEFAULT 1800
+        CHECK (watch_max_delay_seconds > 0);
@@ -0,0 +1,17 @@
+-- Migration: Text content of cataloged files for content search
+-- Scan configs with index_content extract the text of plain text documents and source
+-- files into file_content. content_hash is the file hash the text was extracted from;
+-- the scanner only re-extracts when file_catalog.content_hash no longer matches it.
+
+CREATE TABLE file_content (
+    file_catalog_id UUID PRIMARY KEY REFERENCES file_catalog(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    content_hash VARCHAR(64),
+    extracted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE scan_configs
+    ADD COLUMN index_content BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN content_max_bytes BIGINT NOT NULL DEFAULT 1048576 CHECK (content_max_bytes > 0),
+    -- NULL means the built-in extension list
+    ADD COLUMN content_extensions TEXT[];
@@ -0,0 +1,14 @@
+-- Migration: MIME detection for cataloged files
+-- The scanner now fills file_catalog.mime_type from the file's leading bytes (falling back
+-- to the extension) and stores a broad category for filtering. Files whose content does not
+-- match their extension get metadata.mime_mismatch = {"detected": ..., "extension": ...}.
+
+ALTER TABLE file_catalog
+    ADD COLUMN mime_category VARCHAR(20)
+        CHECK (mime_category IN ('image', 'video', 'audio', 'archive', 'document', 'source_code', 'text', 'other'));
+
+CREATE INDEX idx_file_catalog_mime_category ON file_catalog(mime_category);
+
+-- Scan configs can leave whole categories out of the catalog (e.g. video)
+ALTER TABLE scan_configs
+    ADD COLUMN exclude_mime_categories TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,15 @@
+-- Migration: POSIX metadata in the catalog
+-- The scanner now stores ownership, permissions, inode/device and link count under
+-- file_catalog.metadata.unix, the target of symlinks that are not followed under
+-- metadata.symlink_target (those links are cataloged with mime_type 'inode/symlink'
+-- and no content hash) and, when enabled, extended attributes under metadata.xattrs.
+
+-- Scan configs can follow symlinks and capture xattrs/ACLs
+ALTER TABLE scan_configs
+    ADD COLUMN follow_symlinks BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN capture_xattrs BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Restores can reapply the cataloged ownership and permissions to the restored files
+ALTER TABLE restore_execution_logs
+    ADD COLUMN permissions_applied INTEGER,
+    ADD COLUMN permission_errors INTEGER;
@@ -0,0 +1,42 @@
+-- Migration: Create backup_runs table
+-- Persistent run queue: every execution of a backup job (manual, cron or watch trigger)
+-- is queued here and picked up by the worker pool. A run covers every mapping of the
+-- job; each source -> destination transfer still gets its own backup_execution_logs row.
+
+CREATE TABLE backup_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    backup_job_id UUID NOT NULL REFERENCES backup_jobs(id) ON DELETE CASCADE,
+    schedule_id UUID REFERENCES backup_schedules(id) ON DELETE SET NULL,
+    triggered_by VARCHAR(20) NOT NULL DEFAULT 'manual', -- manual, scheduler, watch
+
+    status VARCHAR(20) NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'succeeded', 'failed', 'cancelled')),
+
+    -- Progress, updated as each destination finishes
+    destinations_total INTEGER NOT NULL DEFAULT 0,
+    destinations_completed INTEGER NOT NULL DEFAULT 0,
+    destinations_failed INTEGER NOT NULL DEFAULT 0,
+    files_transferred BIGINT NOT NULL DEFAULT 0,
+    bytes_transferred BIGINT NOT NULL DEFAULT 0,
+    error_message TEXT,
+
+    queued_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMP WITH TIME ZONE,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Workers claim the oldest queued run
+CREATE INDEX idx_backup_runs_queued ON backup_runs(queued_at) WHERE status = 'queued';
+CREATE INDEX idx_backup_runs_job_id ON backup_runs(backup_job_id, queued_at DESC);
+
+CREATE TRIGGER trigger_backup_runs_updated_at
+    BEFORE UPDATE ON backup_runs
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE backup_execution_logs
+    ADD COLUMN backup_run_id UUID REFERENCES backup_runs(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_backup_execution_logs_run_id ON backup_execution_logs(backup_run_id);
@@ -0,0 +1,21 @@
+-- Migration: Overlap policies for backup runs
+-- overlap_policy decides what happens when a run of a job is requested while a
+-- previous run of the same job is still queued or running:
+--   queue           - the new run waits for the previous one (default)
+--   skip            - the new run is recorded as 'skipped' and never executes
+--   cancel_previous - previous runs are cancelled and the new one is queued
+-- Runs of one job never execute concurrently, whatever the policy; that is enforced
+-- with Postgres advisory locks so it also holds across b2cli instances.
+
+ALTER TABLE backup_jobs
+    ADD COLUMN overlap_policy VARCHAR(20) NOT NULL DEFAULT 'queue'
+        CHECK (overlap_policy IN ('queue', 'skip', 'cancel_previous'));
+
+ALTER TABLE backup_runs DROP CONSTRAINT backup_runs_status_check;
+ALTER TABLE backup_runs
+    ADD CONSTRAINT backup_runs_status_check
+        CHECK (status IN ('queued', 'running', 'succeeded', 'failed', 'cancelled', 'skipped')),
+    -- Why a run was skipped or cancelled
+    ADD COLUMN status_reason TEXT,
+    -- Cancellation requested for a run executing in another instance; its worker polls this
+    ADD COLUMN cancel_requested_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,33 @@
+-- Migration: Scan schedules run by the cron scheduler
+-- Flags and counters are always set, timestamps carry their time zone and
+-- last_run_status also records cancelled scans. next_run_at is the next tick
+-- of the schedule in the scheduler (NULL while disabled).
+
+UPDATE scan_schedules
+SET recursive = COALESCE(recursive, TRUE),
+    enabled = COALESCE(enabled, TRUE),
+    exclude_patterns = COALESCE(exclude_patterns, '{}'),
+    total_runs = COALESCE(total_runs, 0),
+    successful_runs = COALESCE(successful_runs, 0),
+    failed_runs = COALESCE(failed_runs, 0),
+    created_at = COALESCE(created_at, CURRENT_TIMESTAMP),
+    updated_at = COALESCE(updated_at, CURRENT_TIMESTAMP);
+
+ALTER TABLE scan_schedules
+    ALTER COLUMN recursive SET NOT NULL,
+    ALTER COLUMN enabled SET NOT NULL,
+    ALTER COLUMN exclude_patterns SET DEFAULT '{}',
+    ALTER COLUMN exclude_patterns SET NOT NULL,
+    ALTER COLUMN total_runs SET NOT NULL,
+    ALTER COLUMN successful_runs SET NOT NULL,
+    ALTER COLUMN failed_runs SET NOT NULL,
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL,
+    ALTER COLUMN last_run_at TYPE TIMESTAMP WITH TIME ZONE,
+    ALTER COLUMN next_run_at TYPE TIMESTAMP WITH TIME ZONE,
+    ALTER COLUMN created_at TYPE TIMESTAMP WITH TIME ZONE,
+    ALTER COLUMN updated_at TYPE TIMESTAMP WITH TIME ZONE,
+    ADD CONSTRAINT scan_schedules_last_run_status_check
+        CHECK (last_run_status IN ('running', 'success', 'failed', 'cancelled'));
+
+COMMENT ON COLUMN scan_schedules.cron_expression IS 'Formato cron com segundos: SEC MIN HOUR DAY MONTH WEEKDAY. Ex: "0 0 2 * * *" = 2AM diariamente, "0 0 3 * * Sun" = 3AM domingos';
@@ -0,0 +1,11 @@
+-- Migration: One run per cron tick of a schedule
+-- Every b2cli instance registers the same schedules, so a tick fires once per
+-- instance. scheduled_for records the tick that queued the run and the unique
+-- index keeps only the first enqueue of each (schedule, tick).
+
+ALTER TABLE backup_runs
+    ADD COLUMN scheduled_for TIMESTAMP WITH TIME ZONE;
+
+CREATE UNIQUE INDEX idx_backup_runs_schedule_tick
+    ON backup_runs (schedule_id, scheduled_for)
+    WHERE scheduled_for IS NOT NULL;
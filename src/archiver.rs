@@ -259,7 +259,7 @@ impl LogArchiver {
         let logs = sqlx::query_as!(
             crate::models::BackupExecutionLog,
             r#"
-            SELECT id, backup_job_id, schedule_id, started_at, completed_at, status,
+            SELECT id, backup_job_id, schedule_id, backup_run_id, started_at, completed_at, status,
                    rclone_command, source_path, destination_path, rclone_config,
                    files_transferred, files_checked, files_deleted, bytes_transferred,
                    transfer_rate_mbps, duration_seconds, error_count, retry_count,
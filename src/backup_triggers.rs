@@ -0,0 +1,369 @@
+//! Backups disparados por mudanças nas origens.
+//!
+//! Jobs com `trigger_mode = watch` mantêm um watcher (`notify`) em cada origem
+//! dos seus mapeamentos. Cada mudança relevante (fora das regras de exclusão
+//! do job) adia a execução:
+//!
+//! - O backup roda depois de `watch_quiet_seconds` sem novas mudanças
+//! - Mudanças contínuas não adiam além de `watch_max_delay_seconds` contados
+//!   da primeira mudança pendente
+//! - Duas execuções disparadas pelo watcher ficam a pelo menos
+//!   `watch_min_interval_seconds` uma da outra; esse limite prevalece sobre
+//!   o atraso máximo
+//!
+//! Os disparos vão para a fila de execuções (`RunQueue`). Enquanto o job
+//! tem uma execução na fila ou em andamento, as mudanças ficam pendentes
+//! para a próxima. As execuções são registradas com `triggered_by = "watch"`;
+//! um schedule cron do mesmo job continua funcionando normalmente.
+
+use crate::backup_worker;
+use crate::db;
+use crate::ignore::IgnoreMatcher;
+use crate::models::{BackupJob, BackupTriggerMode};
+use crate::run_queue::RunQueue;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Tempos de disparo de um job em modo `watch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WatchSettings {
+    quiet: Duration,
+    min_interval: Duration,
+    max_delay: Duration,
+}
+
+impl WatchSettings {
+    fn from_job(job: &BackupJob) -> Self {
+        let seconds = |value: i32| Duration::from_secs(value.max(1) as u64);
+        Self {
+            quiet: seconds(job.watch_quiet_seconds),
+            min_interval: seconds(job.watch_min_interval_seconds),
+            max_delay: seconds(job.watch_max_delay_seconds),
+        }
+    }
+}
+
+/// Primeira e última mudança ainda não cobertas por um backup
+#[derive(Debug, Clone, Copy)]
+struct PendingChanges {
+    first: Instant,
+    last: Instant,
+}
+
+/// Momento em que as mudanças pendentes devem disparar um backup.
+///
+/// # Argumentos
+/// * `pending` - Mudanças acumuladas desde o último disparo
+/// * `last_run` - Início do último backup disparado pelo watcher
+/// * `settings` - Tempos configurados no job
+fn next_run_at(pending: PendingChanges, last_run: Option<Instant>, settings: &WatchSettings) -> Instant {
+    let settled = (pending.last + settings.quiet).min(pending.first + settings.max_delay);
+    match last_run {
+        Some(last_run) => settled.max(last_run + settings.min_interval),
+        None => settled,
+    }
+}
+
+/// Estado compartilhado entre os callbacks do `notify` e a task do job
+struct TriggerShared {
+    job_id: Uuid,
+    pending: Mutex<Option<PendingChanges>>,
+    wake: Notify,
+    stopped: AtomicBool,
+}
+
+impl TriggerShared {
+    fn record_change(&self) {
+        let now = Instant::now();
+        {
+            let mut pending = self.pending.lock().unwrap();
+            let first = pending.map_or(now, |changes| changes.first);
+            *pending = Some(PendingChanges { first, last: now });
+        }
+        self.wake.notify_one();
+    }
+
+    /// Reinicia a contagem das mudanças pendentes, adiando o disparo por um
+    /// período de silêncio completo (sem isso o atraso máximo já vencido
+    /// faria a task tentar de novo imediatamente)
+    fn defer(&self) {
+        let now = Instant::now();
+        *self.pending.lock().unwrap() = Some(PendingChanges { first: now, last: now });
+    }
+}
+
+/// Watchers de um job. Descartá-lo encerra a task do job.
+struct JobTrigger {
+    shared: Arc<TriggerShared>,
+    _watchers: Vec<RecommendedWatcher>,
+}
+
+impl Drop for JobTrigger {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Relaxed);
+        self.shared.wake.notify_one();
+    }
+}
+
+/// Registro dos jobs em modo `watch`, um conjunto de watchers por job
+pub struct BackupTriggers {
+    pool: PgPool,
+    run_queue: Arc<RunQueue>,
+    triggers: Mutex<HashMap<Uuid, JobTrigger>>,
+}
+
+impl BackupTriggers {
+    /// Cria um registro vazio; os disparos são enfileirados em `run_queue`
+    pub fn new(pool: PgPool, run_queue: Arc<RunQueue>) -> Self {
+        Self {
+            pool,
+            run_queue,
+            triggers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Inicia os watchers de todos os jobs ativos com `trigger_mode = watch`.
+    ///
+    /// Falhas em um job (ex.: origem inexistente) são registradas no log e
+    /// não impedem os demais.
+    ///
+    /// # Retorna
+    /// * Quantidade de jobs observados
+    pub async fn start_all(&self) -> Result<usize, sqlx::Error> {
+        let jobs = db::list_backup_jobs(&self.pool).await?;
+
+        let mut started = 0;
+        for job in jobs.iter().filter(|job| is_watch_job(job)) {
+            match self.sync_job(job).await {
+                Ok(()) => started += 1,
+                Err(e) => error!(job_id = %job.id, error = %e, "Falha ao observar origens do backup job"),
+            }
+        }
+
+        Ok(started)
+    }
+
+    /// Alinha os watchers de um job à sua configuração atual: inicia ou
+    /// substitui quando o job está em modo `watch`, e para caso contrário.
+    ///
+    /// # Erros
+    /// * Uma origem do job não pôde ser observada (ex.: não existe)
+    pub async fn sync_job(&self, job: &BackupJob) -> Result<(), notify::Error> {
+        if !is_watch_job(job) {
+            self.remove(job.id);
+            return Ok(());
+        }
+
+        let mappings: HashMap<String, Vec<String>> =
+            serde_json::from_value(job.mappings.clone()).unwrap_or_default();
+
+        let shared = Arc::new(TriggerShared {
+            job_id: job.id,
+            pending: Mutex::new(None),
+            wake: Notify::new(),
+            stopped: AtomicBool::new(false),
+        });
+
+        let mut watchers = Vec::with_capacity(mappings.len());
+        for source in mappings.keys() {
+            let root = PathBuf::from(source);
+            let matcher = match backup_worker::effective_ignores(source, &job.ignore_templates).await {
+                Ok(matcher) => matcher,
+                Err(e) => {
+                    warn!(job_id = %job.id, source = %source, error = %e, "Regras de exclusão indisponíveis; observando todas as mudanças");
+                    IgnoreMatcher::new::<&str>(&[])
+                }
+            };
+
+            let callback_shared = shared.clone();
+            let callback_root = root.clone();
+            let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
+                let relevant = match res {
+                    Ok(event) if event.need_rescan() => true,
+                    Ok(event) if matches!(event.kind, EventKind::Access(_)) => false,
+                    Ok(event) => event
+                        .paths
+                        .iter()
+                        .any(|path| !is_excluded(&callback_root, &matcher, path)),
+                    Err(e) => {
+                        // Sem saber o que foi perdido, o seguro é tratar como mudança
+                        warn!(root = %callback_root.display(), error = %e, "Erro no watcher de backup");
+                        true
+                    }
+                };
+                if relevant {
+                    callback_shared.record_change();
+                }
+            })?;
+            watcher.watch(&root, RecursiveMode::Recursive)?;
+            watchers.push(watcher);
+        }
+
+        tokio::spawn(run_trigger(
+            self.pool.clone(),
+            self.run_queue.clone(),
+            WatchSettings::from_job(job),
+            shared.clone(),
+        ));
+        info!(job_id = %job.id, sources = watchers.len(), "Backup job em modo watch");
+
+        self.triggers.lock().unwrap().insert(
+            job.id,
+            JobTrigger {
+                shared,
+                _watchers: watchers,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Para de observar as origens de um job
+    ///
+    /// # Retorna
+    /// * `true` se o job estava sendo observado
+    pub fn remove(&self, job_id: Uuid) -> bool {
+        self.triggers.lock().unwrap().remove(&job_id).is_some()
+    }
+
+    /// IDs dos jobs observados
+    pub fn watched_jobs(&self) -> Vec<Uuid> {
+        self.triggers.lock().unwrap().keys().copied().collect()
+    }
+}
+
+/// Confere se uma origem pode ser observada como `sync_job` faz, sem manter
+/// o watcher. Usado antes de gravar um job em modo `watch`.
+///
+/// # Erros
+/// * A origem não existe, não é legível ou o limite de watchers do SO acabou
+pub fn check_watchable(source: &str) -> Result<(), notify::Error> {
+    let mut watcher = notify::recommended_watcher(|_: Result<Event, notify::Error>| {})?;
+    watcher.watch(Path::new(source), RecursiveMode::Recursive)
+}
+
+fn is_watch_job(job: &BackupJob) -> bool {
+    job.is_active && job.trigger_mode == BackupTriggerMode::Watch
+}
+
+/// Caminhos fora da origem não são excluídos: o evento é da própria raiz
+fn is_excluded(root: &Path, matcher: &IgnoreMatcher, path: &Path) -> bool {
+    match path.strip_prefix(root) {
+        Ok(rel) if !rel.as_os_str().is_empty() => matcher.is_ignored(rel, path.is_dir()),
+        _ => false,
+    }
+}
+
+/// Task de um job: espera as mudanças assentarem e enfileira o backup
+async fn run_trigger(pool: PgPool, run_queue: Arc<RunQueue>, settings: WatchSettings, shared: Arc<TriggerShared>) {
+    let mut last_run: Option<Instant> = None;
+
+    loop {
+        if shared.stopped.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let pending = *shared.pending.lock().unwrap();
+        let Some(pending) = pending else {
+            shared.wake.notified().await;
+            continue;
+        };
+
+        let due = next_run_at(pending, last_run, &settings);
+        if Instant::now() < due {
+            // Novas mudanças acordam a task para recalcular o prazo
+            let _ = tokio::time::timeout_at(due, shared.wake.notified()).await;
+            continue;
+        }
+
+        let job = match db::get_backup_job_by_id(&pool, shared.job_id).await {
+            Ok(Some(job)) if is_watch_job(&job) => job,
+            Ok(_) => {
+                debug!(job_id = %shared.job_id, "Backup job não está mais em modo watch");
+                break;
+            }
+            Err(e) => {
+                error!(job_id = %shared.job_id, error = %e, "Falha ao carregar backup job; nova tentativa após o período de silêncio");
+                shared.defer();
+                continue;
+            }
+        };
+
+        match db::has_pending_backup_run(&pool, job.id).await {
+            Ok(false) => {}
+            Ok(true) => {
+                // Outra execução (manual, agendada ou deste watcher) na fila ou em andamento
+                debug!(job_id = %job.id, "Backup job com execução pendente; adiando disparo por mudanças");
+                shared.defer();
+                continue;
+            }
+            Err(e) => {
+                error!(job_id = %job.id, error = %e, "Falha ao consultar a fila de backups; nova tentativa após o período de silêncio");
+                shared.defer();
+                continue;
+            }
+        }
+
+        *shared.pending.lock().unwrap() = None;
+        last_run = Some(Instant::now());
+        match run_queue.enqueue(job.id, None, "watch").await {
+            Ok(run) => info!(job_id = %job.id, job_name = %job.name, run_id = %run.id, "Backup disparado por mudanças nas origens enfileirado"),
+            Err(e) => {
+                error!(job_id = %job.id, error = %e, "Falha ao enfileirar backup disparado por mudanças");
+                shared.defer();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> WatchSettings {
+        WatchSettings {
+            quiet: Duration::from_secs(60),
+            min_interval: Duration::from_secs(300),
+            max_delay: Duration::from_secs(1800),
+        }
+    }
+
+    #[test]
+    fn test_next_run_waits_for_quiet_period() {
+        let start = Instant::now();
+        let pending = PendingChanges {
+            first: start,
+            last: start + Duration::from_secs(10),
+        };
+        assert_eq!(next_run_at(pending, None, &settings()), start + Duration::from_secs(70));
+    }
+
+    #[test]
+    fn test_next_run_capped_by_max_delay() {
+        let start = Instant::now();
+        let pending = PendingChanges {
+            first: start,
+            last: start + Duration::from_secs(1790),
+        };
+        assert_eq!(next_run_at(pending, None, &settings()), start + Duration::from_secs(1800));
+    }
+
+    #[test]
+    fn test_min_interval_wins_over_max_delay() {
+        let start = Instant::now();
+        let pending = PendingChanges { first: start, last: start };
+        let mut settings = settings();
+        settings.max_delay = Duration::from_secs(30);
+        let last_run = Some(start);
+        assert_eq!(next_run_at(pending, last_run, &settings), start + Duration::from_secs(300));
+    }
+}
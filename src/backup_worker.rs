@@ -1,77 +1,68 @@
 use crate::AppError;
-use crate::models::{BackupJob, NewBackupExecutionLog};
-use crate::{db, rclone::RcloneWrapper};
-use crate::file_scanner::{FileScanner, ScanConfig};
+use crate::cancel::CancelToken;
+use crate::models::{BackupJob, BackupRun, NewBackupExecutionLog};
+use crate::{db, ignore, ignore_templates, remotes, run_locks, search, verification};
+use crate::file_scanner::{FileScanner, ScanCancelled, ScanConfig};
+use crate::ignore::IgnoreMatcher;
+use crate::rclone::{RcloneConfig, RcloneWrapper};
 use sqlx::PgPool;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
-/// Executa um backup job manualmente (sem schedule).
-/// 
-/// Wrapper para `perform_backup_with_schedule` quando o backup
-/// é executado manualmente via API ou interface.
-/// 
-/// # Argumentos
-/// * `pool` - Pool de conexão PostgreSQL
-/// * `job` - Backup job a ser executado
-/// 
-/// # Retorna
-/// * `Ok(())` - Backup executado com sucesso
-/// * `Err(AppError)` - Falha na execução
-/// 
-/// # Exemplos
-/// ```no_run
-/// let result = perform_backup(&pool, &job).await;
-/// ```
-pub async fn perform_backup(pool: &PgPool, job: &BackupJob) -> Result<(), AppError> {
-    perform_backup_with_schedule(pool, job, None).await
-}
-
-/// Executa um backup job com suporte a agendamento.
-/// 
-/// Esta é a função principal de execução de backup que:
+/// Executa uma execução reservada da fila (`backup_runs`).
+///
+/// Todo backup passa pela fila (manual, agendado ou `watch`); esta é a
+/// execução em si:
 /// 1. Atualiza status do job para RUNNING
-/// 2. Parsea os mapeamentos origem -> destinos
-/// 3. Executa rclone sync para cada mapeamento
-/// 4. Cria logs de execução detalhados
-/// 5. Atualiza status final e próxima execução do schedule
-/// 
+/// 2. Cataloga cada origem e executa rclone sync para cada destino, com as
+///    mesmas regras de exclusão (configuração + `.b2ignore`) usadas na catalogação
+/// 3. Cria logs de execução ligados à execução e grava o progresso (destinos
+///    concluídos, arquivos e bytes transferidos) conforme cada destino termina
+/// 4. Se `verify_after_backup` estiver ativo, verifica o restore de cada mapeamento
+/// 5. Atualiza o status final do job; o da execução fica a cargo de quem chama
+///
+/// Cancelar `cancel` interrompe o rclone em andamento (o log do destino fica
+/// `cancelled` com as estatísticas parciais), pula os destinos restantes e
+/// deixa o job com status CANCELLED.
+///
+/// Com `destination_slots`, cada transferência espera uma vaga no destino
+/// (compartilhada entre instâncias) antes de começar.
+///
 /// # Argumentos
 /// * `pool` - Pool de conexão PostgreSQL
-/// * `job` - Backup job a ser executado
-/// * `schedule_id` - ID do schedule que triggou a execução (opcional)
-/// 
+/// * `job` - Backup job da execução
+/// * `run` - Execução reservada por um worker da fila
+/// * `cancel` - Token de cancelamento da execução
+/// * `destination_slots` - Transferências simultâneas por destino (`None` = sem limite)
+///
 /// # Retorna
-/// * `Ok(())` - Backup executado com sucesso
-/// * `Err(AppError)` - Falha na execução
-/// 
-/// # Comportamento
-/// - Se qualquer transferência falhar, marca job como FAILED
-/// - Atualiza last_run e next_run do schedule automaticamente
-/// - Salva métricas detalhadas no backup_execution_logs
-/// - Usa rclone com logs estruturados para debugging
-/// 
-/// # Exemplos
-/// ```no_run
-/// // Backup manual
-/// let result = perform_backup_with_schedule(&pool, &job, None).await;
-/// 
-/// // Backup via scheduler
-/// let result = perform_backup_with_schedule(&pool, &job, Some(schedule_id)).await;
-/// ```
-pub async fn perform_backup_with_schedule(pool: &PgPool, job: &BackupJob, schedule_id: Option<Uuid>) -> Result<(), AppError> {
-    tracing::debug!(job_id = %job.id, job_name = %job.name, "Starting backup job");
+/// * `Ok(())` - Todas as transferências concluídas com sucesso
+/// * `Err(AppError)` - Alguma transferência falhou ou a execução foi cancelada
+pub async fn perform_backup_run(
+    pool: &PgPool,
+    job: &BackupJob,
+    run: &BackupRun,
+    cancel: &CancelToken,
+    destination_slots: Option<u32>,
+) -> Result<(), AppError> {
+    tracing::debug!(job_id = %job.id, job_name = %job.name, triggered_by = %run.triggered_by, "Starting backup job");
     
     // Update job status to RUNNING
     db::update_backup_job_status(pool, job.id, "RUNNING").await?;
 
     let mappings: std::collections::HashMap<String, Vec<String>> = serde_json::from_value(job.mappings.clone())?;
-    let rclone = RcloneWrapper::new(Default::default(), Some(PathBuf::from("./logs")));
-    
+
+    let destinations = mappings.values().map(Vec::len).sum::<usize>() as i32;
+    db::set_backup_run_destinations(pool, run.id, destinations).await?;
+
     let mut all_success = true;
     let mut scan_job_ids = Vec::new();
 
     for (source_path, destination_paths) in mappings {
+        if cancel.is_cancelled() {
+            break;
+        }
+
         // NOVO: Escanear origem ANTES do backup para catalogar arquivos
         tracing::info!(
             job_id = %job.id,
@@ -82,10 +73,11 @@ pub async fn perform_backup_with_schedule(pool: &PgPool, job: &BackupJob, schedu
         let scan_config = ScanConfig {
             root_path: PathBuf::from(&source_path),
             recursive: true,
+            ignore_templates: job.ignore_templates.clone(),
             ..Default::default()
         };
         
-        let mut scanner = FileScanner::new(pool.clone(), scan_config);
+        let mut scanner = FileScanner::new(pool.clone(), scan_config).with_cancel(cancel.clone());
         
         // Executar scan e aguardar conclusão
         match scanner.start_scan().await {
@@ -106,6 +98,18 @@ pub async fn perform_backup_with_schedule(pool: &PgPool, job: &BackupJob, schedu
                 .execute(pool)
                 .await?;
             }
+            Err(e) if e.is::<ScanCancelled>() => {
+                if let Some(cancelled) = e.downcast_ref::<ScanCancelled>() {
+                    sqlx::query!(
+                        "UPDATE scan_jobs SET backup_job_id = $1, scan_type = 'backup_pre' WHERE id = $2",
+                        job.id,
+                        cancelled.scan_job_id
+                    )
+                    .execute(pool)
+                    .await?;
+                }
+                tracing::info!(job_id = %job.id, "Catalogação cancelada");
+            }
             Err(e) => {
                 tracing::warn!(
                     job_id = %job.id,
@@ -114,32 +118,124 @@ pub async fn perform_backup_with_schedule(pool: &PgPool, job: &BackupJob, schedu
                 );
             }
         }
+
+        // As exclusões viram `--filter` do rclone para o upload bater com o catálogo
+        let filter_args = match effective_ignores(&source_path, &job.ignore_templates).await {
+            Ok(ignores) => rclone_filter_args(&ignores.to_rclone_filters()),
+            Err(e) => {
+                all_success = false;
+                tracing::error!(
+                    job_id = %job.id,
+                    error = %e,
+                    "Não foi possível ler as regras de exclusão de {}", source_path
+                );
+                for _ in &destination_paths {
+                    record_run_progress(pool, run.id, false, None).await;
+                }
+                continue;
+            }
+        };
+
         for destination in destination_paths {
+            if cancel.is_cancelled() {
+                break;
+            }
+
+            // Destinos provider://<uuid>/... viram um remote efêmero do rclone
+            let remote = match remotes::resolve_job_location(pool, job, &destination).await {
+                Ok(remote) => remote,
+                Err(e) => {
+                    all_success = false;
+                    tracing::error!(
+                        job_id = %job.id,
+                        error = %e,
+                        "Não foi possível resolver o destino {}", destination
+                    );
+                    record_run_progress(pool, run.id, false, None).await;
+                    continue;
+                }
+            };
+
+            // A vaga fica com a transferência até o fim do rclone; a chave é o
+            // destino resolvido, igual para todas as grafias e jobs que o usam
+            let _slot = match destination_slots {
+                Some(slots) => match run_locks::acquire_destination(pool, &remote.storage, slots, cancel).await? {
+                    Some(slot) => Some(slot),
+                    None => break,
+                },
+                None => None,
+            };
+
             // Criar log de execução
-            let triggered_by = if schedule_id.is_some() { "scheduler" } else { "manual" };
             let log_data = NewBackupExecutionLog {
                 backup_job_id: job.id,
-                schedule_id,
-                rclone_command: format!("rclone sync {:?} {:?}", source_path, destination),
+                schedule_id: run.schedule_id,
+                rclone_command: format!(
+                    "rclone sync {:?} {:?}{}",
+                    source_path,
+                    remote.location,
+                    filter_args
+                        .chunks(2)
+                        .map(|pair| format!(" {} {:?}", pair[0], pair[1]))
+                        .collect::<String>()
+                ),
                 source_path: source_path.clone(),
                 destination_path: destination.clone(),
                 rclone_config: None,
-                triggered_by: Some(triggered_by.to_string()),
+                triggered_by: Some(run.triggered_by.clone()),
+                backup_run_id: Some(run.id),
             };
 
             let execution_log = db::create_backup_execution_log(pool, &log_data).await?;
             
             // Executar rclone sync
-            match rclone.sync(execution_log.id, &source_path, &destination).await {
+            let rclone_config = RcloneConfig {
+                extra_flags: filter_args.clone(),
+                ..Default::default()
+            };
+            let rclone = RcloneWrapper::new(rclone_config, Some(PathBuf::from("./logs")))
+                .with_env(remote.env)
+                .with_cancel(cancel.clone());
+            match rclone.sync(execution_log.id, &source_path, &remote.location).await {
+                Ok(result) if cancel.is_cancelled() => {
+                    db::cancel_backup_execution_log(pool, execution_log.id, &result).await?;
+                    tracing::info!(
+                        job_id = %job.id,
+                        files_transferred = result.files_transferred,
+                        "Backup cancelado durante {} -> {}", source_path, destination
+                    );
+                }
                 Ok(result) => {
                     // Atualizar log com resultados
                     db::update_backup_execution_log_completion(pool, execution_log.id, &result).await?;
+                    if result.exit_code != 0 {
+                        all_success = false;
+                    }
+                    record_run_progress(pool, run.id, result.exit_code == 0, Some(&result)).await;
                     tracing::debug!(
                         job_id = %job.id,
                         files_transferred = result.files_transferred,
                         "Backup completed for path {} -> {}", source_path, destination
                     );
                     
+                    if job.verify_after_backup && result.exit_code == 0 {
+                        if let Err(e) = verification::verify_mapping(
+                            pool,
+                            job,
+                            Some(execution_log.id),
+                            &source_path,
+                            &destination,
+                        )
+                        .await
+                        {
+                            tracing::warn!(
+                                job_id = %job.id,
+                                error = %e,
+                                "Verificação de restore falhou para {} -> {}", source_path, destination
+                            );
+                        }
+                    }
+
                     // NOVO: Marcar arquivos como backupeados
                     if !scan_job_ids.is_empty() {
                         let update_result = sqlx::query!(
@@ -169,6 +265,7 @@ pub async fn perform_backup_with_schedule(pool: &PgPool, job: &BackupJob, schedu
                                 job_id = %job.id,
                                 "Arquivos marcados como backupeados"
                             );
+                            refresh_search_index(pool, &source_path).await;
                         }
                     }
                 }
@@ -179,11 +276,19 @@ pub async fn perform_backup_with_schedule(pool: &PgPool, job: &BackupJob, schedu
                         error = %e,
                         "Backup failed for path {} -> {}", source_path, destination
                     );
+                    db::fail_backup_execution_log(pool, execution_log.id, &e.to_string()).await?;
+                    record_run_progress(pool, run.id, false, None).await;
                 }
             }
         }
     }
 
+    if cancel.is_cancelled() {
+        db::update_backup_job_status(pool, job.id, "CANCELLED").await?;
+        tracing::info!(job_id = %job.id, "Backup job cancelado");
+        return Err(AppError::Conflict("Backup cancelled".to_string()));
+    }
+
     // Update job status based on result
     let final_status = if all_success { "COMPLETED" } else { "FAILED" };
     db::update_backup_job_status(pool, job.id, final_status).await?;
@@ -197,26 +302,73 @@ pub async fn perform_backup_with_schedule(pool: &PgPool, job: &BackupJob, schedu
     }
 }
 
+/// Soma uma transferência ao progresso da execução da fila.
+/// Falhas ao gravar o progresso não interrompem o backup.
+async fn record_run_progress(
+    pool: &PgPool,
+    run_id: Uuid,
+    succeeded: bool,
+    result: Option<&crate::models::RcloneExecutionResult>,
+) {
+    let (files, bytes) = result.map_or((0, 0), |r| (r.files_transferred as i64, r.bytes_transferred));
+    if let Err(e) = db::record_backup_run_destination(pool, run_id, succeeded, files, bytes).await {
+        tracing::warn!(run_id = %run_id, error = %e, "Falha ao registrar o progresso da execução");
+    }
+}
+
+/// Atualiza o status de backup da origem no índice de busca (no-op sem índice)
+async fn refresh_search_index(pool: &PgPool, source_path: &str) {
+    let Some(index) = search::index() else {
+        return;
+    };
+    let result = match index.reindex_under(pool, Some(source_path)).await {
+        Ok(_) => search::commit_global().await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::warn!(source = %source_path, error = %e, "Falha ao atualizar o índice de busca");
+    }
+}
+
+/// Regras de exclusão efetivas de uma origem de backup.
+///
+/// São os templates do job e os padrões padrão do scanner (os mesmos da
+/// catalogação pré-backup), somados aos `.b2ignore` encontrados na origem.
+///
+/// # Argumentos
+/// * `source_path` - Origem de um mapeamento do job
+/// * `templates` - `ignore_templates` do job
+pub async fn effective_ignores(source_path: &str, templates: &[String]) -> Result<IgnoreMatcher, AppError> {
+    let root = Path::new(source_path);
+    let base_patterns = ScanConfig::default().exclude_patterns;
+    let base = ignore_templates::base_matcher(root, templates, &base_patterns).await;
+    Ok(ignore::collect_effective(root, base).await?)
+}
+
+/// `--filter <regra>` para cada regra, no formato de `RcloneConfig.extra_flags`
+fn rclone_filter_args(filters: &[String]) -> Vec<String> {
+    filters
+        .iter()
+        .flat_map(|rule| ["--filter".to_string(), rule.clone()])
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
-    use uuid::Uuid;
     use std::collections::HashMap;
 
     fn create_test_job() -> BackupJob {
         BackupJob {
-            id: Uuid::new_v4(),
-            name: "Test Job".to_string(),
-            mappings: json!({
-                "/tmp/source": ["/tmp/dest1", "/tmp/dest2"],
-                "/home/docs": ["/backup/docs"]
-            }),
             status: "PENDING".to_string(),
-            is_active: true,
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-            deleted_at: None,
+            ..BackupJob::new(
+                "Test Job",
+                json!({
+                    "/tmp/source": ["/tmp/dest1", "/tmp/dest2"],
+                    "/home/docs": ["/backup/docs"]
+                }),
+            )
         }
     }
 
@@ -244,6 +396,27 @@ mod tests {
         assert_eq!(mappings["/home/docs"], vec!["/backup/docs"]);
     }
 
+    #[test]
+    fn test_rclone_filter_args() {
+        let filters = vec!["- *.tmp".to_string(), "+ keep.tmp".to_string()];
+        assert_eq!(
+            rclone_filter_args(&filters),
+            vec!["--filter", "- *.tmp", "--filter", "+ keep.tmp"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_effective_ignores_include_scanner_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".b2ignore"), "*.iso\n").unwrap();
+
+        let ignores = effective_ignores(&dir.path().to_string_lossy(), &[]).await.unwrap();
+
+        assert!(ignores.is_ignored(Path::new("node_modules/x.js"), false));
+        assert!(ignores.is_ignored(Path::new("images/disk.iso"), false));
+        assert!(!ignores.is_ignored(Path::new("docs/a.txt"), false));
+    }
+
     #[test]
     fn test_invalid_mappings() {
         let invalid_mappings = json!({
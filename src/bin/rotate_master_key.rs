@@ -0,0 +1,98 @@
+//! Rotação da chave mestra das credenciais dos provedores cloud.
+//!
+//! Recriptografa `access_key`, `secret_key` e `b2_application_key` de todos os
+//! provedores (inclusive inativos) e as senhas de criptografia dos backup jobs
+//! com uma nova chave, numa única transação.
+//!
+//! ```text
+//! # gerar uma nova chave
+//! cargo run --bin rotate_master_key -- --generate
+//!
+//! # rotacionar: chave atual em B2CLI_MASTER_KEY / B2CLI_MASTER_KEY_FILE / B2CLI_MASTER_PASSWORD
+//! cargo run --bin rotate_master_key -- --new-key-file /etc/b2cli/master.key.new
+//! B2CLI_NEW_MASTER_KEY=<base64> cargo run --bin rotate_master_key
+//! ```
+//!
+//! Sem chave atual configurada, só credenciais em texto plano podem ser lidas;
+//! nesse caso a rotação equivale a criptografar o banco pela primeira vez.
+
+use b2cli::{crypto, db, keystore};
+use sqlx::postgres::PgPoolOptions;
+use std::path::Path;
+use std::process::ExitCode;
+
+/// Variável de ambiente alternativa a `--new-key-file`
+const NEW_MASTER_KEY_ENV: &str = "B2CLI_NEW_MASTER_KEY";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let _ = dotenvy::dotenv();
+
+    match run(std::env::args().skip(1).collect()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Vec<String>) -> Result<(), crypto::CryptoError> {
+    let mut new_key_file: Option<String> = None;
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--generate" => {
+                println!("{}", crypto::generate_master_key());
+                return Ok(());
+            }
+            "--new-key-file" => {
+                new_key_file = Some(iter.next().ok_or("--new-key-file requires a path")?);
+            }
+            other => return Err(format!("unknown argument '{}'", other).into()),
+        }
+    }
+
+    let new_key = match (new_key_file, std::env::var(NEW_MASTER_KEY_ENV)) {
+        (Some(path), _) => crypto::read_master_key_file(Path::new(&path))?,
+        (None, Ok(encoded)) => crypto::decode_master_key(&encoded)?,
+        (None, Err(_)) => {
+            return Err(format!("new key required: --new-key-file <path> or {}", NEW_MASTER_KEY_ENV).into())
+        }
+    };
+
+    let database_url = std::env::var("DATABASE_URL").map_err(|_| "DATABASE_URL must be set")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await?;
+
+    let current = match keystore::manager_from_env(&pool).await? {
+        Some(current) => current,
+        None => {
+            eprintln!(
+                "warning: no current master key ({}, {} or {}), only plaintext credentials can be rotated",
+                crypto::MASTER_KEY_ENV,
+                crypto::MASTER_KEY_FILE_ENV,
+                crypto::MASTER_PASSWORD_ENV
+            );
+            crypto::CryptoManager::new()
+        }
+    };
+    if crypto::master_key_from_env()?.as_deref() == Some(new_key.as_slice()) {
+        return Err("new key is the same as the current key".into());
+    }
+
+    let target = crypto::CryptoManager::new();
+    target.init_with_key(&new_key).await?;
+
+    let rotated = db::rotate_provider_credentials(&pool, &current, &target).await?;
+    println!("Re-encrypted {} secret record(s) (cloud providers and backup job keys).", rotated);
+    println!(
+        "Update {} / {} to the new key before restarting b2cli.",
+        crypto::MASTER_KEY_ENV,
+        crypto::MASTER_KEY_FILE_ENV
+    );
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//! Cancelamento cooperativo de backups e varreduras em andamento.
+//!
+//! Quem executa o trabalho recebe um `CancelToken` e o consulta nos pontos
+//! em que parar é seguro (próxima entrada do walker, próximo destino do
+//! backup) ou espera por ele junto com o processo do rclone. Um
+//! `CancelRegistry` associa o ID da execução (backup run ou scan job) ao
+//! token enquanto ela roda, para que as rotas de cancelamento o encontrem.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+#[derive(Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// Sinal de cancelamento compartilhado; clones observam o mesmo sinal
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    state: Arc<TokenState>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pede o cancelamento; chamadas repetidas não têm efeito
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        self.state.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Completa quando o cancelamento for pedido (imediatamente, se já foi)
+    pub async fn cancelled(&self) {
+        let notified = self.state.notify.notified();
+        tokio::pin!(notified);
+        // Registra o interesse antes de olhar a flag: um `cancel` entre as
+        // duas coisas não se perde
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Tokens das execuções em andamento neste processo
+#[derive(Default)]
+pub struct CancelRegistry {
+    tokens: Mutex<HashMap<Uuid, CancelToken>>,
+}
+
+impl CancelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra `token` para `id` até o guard retornado ser descartado
+    pub fn register(self: &Arc<Self>, id: Uuid, token: CancelToken) -> Registration {
+        self.tokens.lock().unwrap().insert(id, token);
+        Registration {
+            registry: self.clone(),
+            id,
+        }
+    }
+
+    /// Cancela a execução `id`
+    ///
+    /// # Retorna
+    /// * `false` se nenhuma execução com esse ID está registrada neste processo
+    pub fn cancel(&self, id: Uuid) -> bool {
+        match self.tokens.lock().unwrap().get(&id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Mantém uma execução registrada; descartá-lo remove o registro
+pub struct Registration {
+    registry: Arc<CancelRegistry>,
+    id: Uuid,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.registry.tokens.lock().unwrap().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancelled_wakes_waiters_and_completes_after_cancel() {
+        let token = CancelToken::new();
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.cancelled().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiter.is_finished());
+
+        token.cancel();
+        tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        assert!(token.is_cancelled());
+        // Já cancelado: não espera
+        tokio::time::timeout(Duration::from_secs(1), token.cancelled()).await.unwrap();
+    }
+
+    #[test]
+    fn test_registry_cancels_only_while_registered() {
+        let registry = Arc::new(CancelRegistry::new());
+        let id = Uuid::new_v4();
+        let token = CancelToken::new();
+
+        let registration = registry.register(id, token.clone());
+        assert!(!registry.cancel(Uuid::new_v4()));
+        assert!(registry.cancel(id));
+        assert!(token.is_cancelled());
+
+        drop(registration);
+        assert!(!registry.cancel(id));
+    }
+}
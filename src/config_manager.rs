@@ -8,6 +8,7 @@
 //! - Sincronização automática com banco de dados
 
 // use crate::models::{CloudProvider, CloudProviderType, NewCloudProvider};
+use crate::crypto;
 use notify::{Event, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
@@ -335,6 +336,11 @@ export B2_SECRET_KEY="sua_chave_secreta"
         _filename: &str,
         config: &CloudProviderConfig,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        // Credenciais vão para o banco criptografadas com a chave mestra
+        let access_key = seal_optional(config.access_key.as_deref()).await?;
+        let secret_key = seal_optional(config.secret_key.as_deref()).await?;
+        let b2_application_key = seal_optional(config.b2_application_key.as_deref()).await?;
+
         // Verificar se já existe
         let existing = sqlx::query!(
             "SELECT id FROM cloud_providers WHERE name = $1",
@@ -366,11 +372,11 @@ export B2_SECRET_KEY="sua_chave_secreta"
                 config.endpoint,
                 config.region,
                 config.bucket,
-                config.access_key,
-                config.secret_key,
+                access_key,
+                secret_key,
                 config.use_b2_native_api,
                 config.b2_account_id,
-                config.b2_application_key,
+                b2_application_key,
                 config.enabled
             )
             .execute(&self.pool)
@@ -392,11 +398,11 @@ export B2_SECRET_KEY="sua_chave_secreta"
                 config.endpoint,
                 config.region,
                 config.bucket,
-                config.access_key,
-                config.secret_key,
+                access_key,
+                secret_key,
                 config.use_b2_native_api,
                 config.b2_account_id,
-                config.b2_application_key,
+                b2_application_key,
                 config.enabled
             )
             .execute(&self.pool)
@@ -460,4 +466,12 @@ export B2_SECRET_KEY="sua_chave_secreta"
         
         Ok(file_path)
     }
-}
\ No newline at end of file
+}
+
+/// Criptografa uma credencial opcional com a chave mestra
+async fn seal_optional(value: Option<&str>) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    match value {
+        Some(value) => Ok(Some(crypto::seal_credential(value).await.map_err(|e| e.to_string())?)),
+        None => Ok(None),
+    }
+}
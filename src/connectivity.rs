@@ -0,0 +1,401 @@
+//! Teste de conectividade de provedores cloud.
+//!
+//! Usa o mesmo remote efêmero dos backups (`remotes::provider_remote`) e faz
+//! um round-trip real com o rclone:
+//!
+//! 1. `lsd` na raiz do bucket (latência, permissão de listagem, bucket existe)
+//! 2. `rcat` de um objeto de teste em `path_prefix` (permissão de escrita)
+//! 3. `lsjson --stat` do objeto (confirma que foi gravado)
+//! 4. `deletefile` do objeto (permissão de remoção)
+//!
+//! Falhas do rclone são classificadas pelo stderr em credenciais inválidas,
+//! bucket inexistente, DNS, rede e permissão negada.
+
+use crate::models::{CloudProvider, ConnectivityStatus, ConnectivityTestResult};
+use crate::rclone::{RcloneCommandOutput, RcloneConfig, RcloneWrapper};
+use crate::remotes;
+use crate::{db, AppError};
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Tempo máximo de cada comando do rclone durante o teste
+const STEP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Conteúdo do objeto de teste gravado no provedor
+const PROBE_CONTENT: &[u8] = b"b2cli connectivity probe\n";
+
+/// Causa provável de uma falha de conectividade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectivityErrorKind {
+    /// Campos obrigatórios ausentes, o rclone nem chega a ser chamado
+    MissingCredentials,
+    /// Chave de acesso inexistente ou assinatura inválida
+    Authentication,
+    /// Bucket não existe
+    BucketNotFound,
+    /// Endpoint não resolve
+    Dns,
+    /// Conexão recusada, timeout, TLS
+    Network,
+    /// Credenciais válidas mas sem permissão para a operação
+    PermissionDenied,
+    /// rclone não está instalado
+    RcloneUnavailable,
+    Unknown,
+}
+
+/// Resultado de uma etapa do round-trip
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeStep {
+    pub step: &'static str,
+    pub success: bool,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ConnectivityErrorKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Resultado do round-trip contra o provedor
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeReport {
+    /// Caminho listado (`remote:bucket`)
+    pub bucket_path: String,
+    /// Objeto de teste gravado e removido
+    pub probe_object: String,
+    /// Latência da primeira chamada ao provedor (listagem)
+    pub latency_ms: Option<u64>,
+    pub can_list: bool,
+    pub can_write: bool,
+    /// `None` quando a escrita falhou e não havia o que remover
+    pub can_delete: Option<bool>,
+    /// Primeira falha encontrada
+    pub error_kind: Option<ConnectivityErrorKind>,
+    pub steps: Vec<ProbeStep>,
+}
+
+impl ProbeReport {
+    pub fn success(&self) -> bool {
+        self.can_list && self.can_write && self.can_delete == Some(true)
+    }
+
+    fn record(&mut self, step: &'static str, output: &RcloneCommandOutput) -> bool {
+        let success = output.success();
+        let error_kind = (!success).then(|| classify_rclone_error(&output.stderr));
+        if self.error_kind.is_none() {
+            self.error_kind = error_kind;
+        }
+        self.steps.push(ProbeStep {
+            step,
+            success,
+            duration_ms: output.duration.as_millis() as u64,
+            error_kind,
+            error: (!success).then(|| last_error_line(&output.stderr)),
+        });
+        success
+    }
+}
+
+/// Testa a conectividade de um provedor cadastrado e grava o resultado.
+///
+/// # Argumentos
+/// * `pool` - Pool de conexão PostgreSQL
+/// * `id` - UUID do provedor
+///
+/// # Retorna
+/// * `Ok(ConnectivityTestResult)` - Resultado do teste (sucesso ou falha classificada)
+/// * `Err(AppError::NotFound)` - Provedor não existe ou está inativo
+/// * `Err(AppError)` - Erro de banco de dados
+pub async fn test_provider(pool: &PgPool, id: Uuid) -> Result<ConnectivityTestResult, AppError> {
+    let provider = db::get_cloud_provider_by_id(pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Cloud provider with ID {} not found", id)))?;
+
+    let tested_at = Utc::now();
+    let mut details = serde_json::json!({
+        "provider_type": provider.provider_type,
+        "bucket": provider.bucket,
+        "use_native_api": provider.use_b2_native_api,
+    });
+
+    let result = if let Err(message) = check_required_fields(&provider) {
+        details["error_kind"] = serde_json::json!(ConnectivityErrorKind::MissingCredentials);
+        ConnectivityTestResult {
+            success: false,
+            status: ConnectivityStatus::Failed,
+            message,
+            tested_at,
+            details: Some(details),
+        }
+    } else {
+        let report = probe_provider(&provider).await;
+        let success = report.success();
+        let message = summarize(&report);
+        if let (Some(target), serde_json::Value::Object(extra)) =
+            (details.as_object_mut(), serde_json::to_value(&report)?)
+        {
+            target.extend(extra);
+        }
+        ConnectivityTestResult {
+            success,
+            status: if success { ConnectivityStatus::Success } else { ConnectivityStatus::Failed },
+            message,
+            tested_at,
+            details: Some(details),
+        }
+    };
+
+    db::record_cloud_provider_connectivity(pool, id, &result).await?;
+    Ok(result)
+}
+
+/// Executa o round-trip list/put/stat/delete contra o provedor.
+///
+/// Não acessa o banco; as etapas seguintes são puladas quando uma etapa
+/// da qual dependem falha.
+pub async fn probe_provider(provider: &CloudProvider) -> ProbeReport {
+    let remote = remotes::provider_remote(provider, "");
+    let bucket_path = format!("{}:{}", remotes::remote_name(provider.id), provider.bucket.trim_matches('/'));
+    let probe_object = remotes::provider_remote(
+        provider,
+        &format!(".b2cli-connectivity-{}", Uuid::new_v4().simple()),
+    )
+    .location;
+
+    // Sem retries: um teste de conectividade deve falhar rápido e com o erro original
+    let config = RcloneConfig {
+        extra_flags: ["--low-level-retries", "1", "--contimeout", "10s"]
+            .iter()
+            .map(|flag| flag.to_string())
+            .collect(),
+        ..RcloneConfig::default()
+    };
+    let rclone = RcloneWrapper::new(config, None).with_env(remote.env);
+    let mut report = ProbeReport {
+        bucket_path: bucket_path.clone(),
+        probe_object: probe_object.clone(),
+        latency_ms: None,
+        can_list: false,
+        can_write: false,
+        can_delete: None,
+        error_kind: None,
+        steps: Vec::new(),
+    };
+
+    let Some(list) = run_step(&rclone, &mut report, "list", &["lsd", &bucket_path], None).await else {
+        return report;
+    };
+    report.latency_ms = Some(list.duration.as_millis() as u64);
+    report.can_list = report.record("list", &list);
+    if !report.can_list && report.error_kind != Some(ConnectivityErrorKind::PermissionDenied) {
+        // Credenciais, bucket ou rede com problema: as próximas etapas falhariam igual
+        return report;
+    }
+
+    let Some(write) = run_step(&rclone, &mut report, "write", &["rcat", &probe_object], Some(PROBE_CONTENT)).await else {
+        return report;
+    };
+    report.can_write = report.record("write", &write);
+    if !report.can_write {
+        return report;
+    }
+
+    if let Some(stat) = run_step(&rclone, &mut report, "stat", &["lsjson", "--stat", &probe_object], None).await {
+        report.record("stat", &stat);
+    }
+
+    if let Some(delete) = run_step(&rclone, &mut report, "delete", &["deletefile", &probe_object], None).await {
+        report.can_delete = Some(report.record("delete", &delete));
+    }
+
+    report
+}
+
+/// Roda um comando; se o rclone nem puder ser executado, registra a falha e retorna `None`.
+async fn run_step(
+    rclone: &RcloneWrapper,
+    report: &mut ProbeReport,
+    step: &'static str,
+    args: &[&str],
+    stdin: Option<&[u8]>,
+) -> Option<RcloneCommandOutput> {
+    match rclone.run_command(args, stdin, STEP_TIMEOUT).await {
+        Ok(output) => Some(output),
+        Err(e) => {
+            report.error_kind.get_or_insert(ConnectivityErrorKind::RcloneUnavailable);
+            report.steps.push(ProbeStep {
+                step,
+                success: false,
+                duration_ms: 0,
+                error_kind: Some(ConnectivityErrorKind::RcloneUnavailable),
+                error: Some(format!("Failed to run rclone: {}", e)),
+            });
+            None
+        }
+    }
+}
+
+/// Classifica o stderr do rclone na causa provável da falha.
+pub fn classify_rclone_error(stderr: &str) -> ConnectivityErrorKind {
+    let text = stderr.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| text.contains(n));
+
+    if has(&["no such host", "server misbehaving", "temporary failure in name resolution", "dial tcp: lookup"]) {
+        ConnectivityErrorKind::Dns
+    } else if has(&[
+        "invalidaccesskeyid",
+        "signaturedoesnotmatch",
+        "invalidtoken",
+        "bad_auth_token",
+        "unauthorized",
+        "status code: 401",
+        "401 unauthorized",
+    ]) {
+        ConnectivityErrorKind::Authentication
+    } else if has(&["nosuchbucket", "bucket not found", "bucket_not_found", "directory not found"]) {
+        ConnectivityErrorKind::BucketNotFound
+    } else if has(&["accessdenied", "access denied", "forbidden", "status code: 403"]) {
+        ConnectivityErrorKind::PermissionDenied
+    } else if has(&[
+        "connection refused",
+        "i/o timeout",
+        "timed out",
+        "context deadline exceeded",
+        "connection reset",
+        "network is unreachable",
+        "x509:",
+        "tls:",
+    ]) {
+        ConnectivityErrorKind::Network
+    } else {
+        ConnectivityErrorKind::Unknown
+    }
+}
+
+/// Mesmas regras de campos obrigatórios da validação original, usadas como pré-checagem
+fn check_required_fields(provider: &CloudProvider) -> Result<(), String> {
+    let has_s3_keys = !provider.access_key.is_empty() && !provider.secret_key.is_empty();
+
+    match provider.provider_type.as_str() {
+        "backblaze_b2" if provider.use_b2_native_api => {
+            if provider.b2_account_id.is_some() && provider.b2_application_key.is_some() {
+                Ok(())
+            } else {
+                Err("Missing B2 native API credentials (account_id or application_key)".to_string())
+            }
+        }
+        "backblaze_b2" => {
+            if has_s3_keys && provider.endpoint.is_some() {
+                Ok(())
+            } else {
+                Err("Missing B2 S3 credentials (access_key, secret_key, or endpoint)".to_string())
+            }
+        }
+        "idrive_e2" => {
+            if has_s3_keys && provider.endpoint.is_some() {
+                Ok(())
+            } else {
+                Err("Missing IDrive e2 credentials (access_key, secret_key, or endpoint)".to_string())
+            }
+        }
+        "wasabi" | "scaleway" => {
+            if has_s3_keys && provider.region.is_some() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Missing {} credentials (access_key, secret_key, or region)",
+                    provider.provider_type
+                ))
+            }
+        }
+        other => Err(format!("Unsupported provider type: {}", other)),
+    }
+}
+
+fn summarize(report: &ProbeReport) -> String {
+    if report.success() {
+        return format!(
+            "Connected to {} (list, write and delete OK, latency {} ms)",
+            report.bucket_path,
+            report.latency_ms.unwrap_or_default()
+        );
+    }
+
+    let reason = match report.error_kind {
+        Some(ConnectivityErrorKind::Authentication) => "invalid credentials",
+        Some(ConnectivityErrorKind::BucketNotFound) => "bucket not found",
+        Some(ConnectivityErrorKind::Dns) => "endpoint hostname could not be resolved",
+        Some(ConnectivityErrorKind::Network) => "network error reaching the endpoint",
+        Some(ConnectivityErrorKind::PermissionDenied) => "permission denied",
+        Some(ConnectivityErrorKind::RcloneUnavailable) => "rclone is not available",
+        Some(ConnectivityErrorKind::MissingCredentials) => "missing credentials",
+        Some(ConnectivityErrorKind::Unknown) | None => "unexpected rclone error",
+    };
+    let failed_step = report
+        .steps
+        .iter()
+        .find(|s| !s.success)
+        .map(|s| s.step)
+        .unwrap_or("probe");
+
+    format!("Connectivity test failed at '{}': {}", failed_step, reason)
+}
+
+/// Última linha com conteúdo do stderr, que costuma trazer o erro final do rclone
+fn last_error_line(stderr: &str) -> String {
+    stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_rclone_error() {
+        assert_eq!(
+            classify_rclone_error("ERROR : : error listing: InvalidAccessKeyId: The AWS Access Key Id you provided does not exist"),
+            ConnectivityErrorKind::Authentication
+        );
+        assert_eq!(
+            classify_rclone_error("SignatureDoesNotMatch: The request signature we calculated does not match"),
+            ConnectivityErrorKind::Authentication
+        );
+        assert_eq!(
+            classify_rclone_error("Failed to lsd: directory not found"),
+            ConnectivityErrorKind::BucketNotFound
+        );
+        assert_eq!(
+            classify_rclone_error("NoSuchBucket: The specified bucket does not exist"),
+            ConnectivityErrorKind::BucketNotFound
+        );
+        assert_eq!(
+            classify_rclone_error("dial tcp: lookup s3.nowhere.invalid: no such host"),
+            ConnectivityErrorKind::Dns
+        );
+        assert_eq!(
+            classify_rclone_error("AccessDenied: Access Denied\n\tstatus code: 403"),
+            ConnectivityErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            classify_rclone_error("dial tcp 127.0.0.1:9: connect: connection refused"),
+            ConnectivityErrorKind::Network
+        );
+        assert_eq!(classify_rclone_error("something odd"), ConnectivityErrorKind::Unknown);
+    }
+
+    #[test]
+    fn test_last_error_line() {
+        assert_eq!(last_error_line("NOTICE: x\nERROR: boom\n\n"), "ERROR: boom");
+        assert_eq!(last_error_line(""), "");
+    }
+}
@@ -0,0 +1,121 @@
+//! Extração do conteúdo textual de arquivos para o índice de busca.
+//!
+//! Só arquivos de texto puro são lidos (texto, Markdown, CSV, JSON, TOML,
+//! código-fonte...), escolhidos pela extensão e limitados por tamanho. O texto
+//! extraído fica em `file_content`, junto com o hash do arquivo de que veio:
+//! enquanto o hash do catálogo não muda, o arquivo não é relido.
+
+use std::collections::HashSet;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+/// Maior arquivo lido por padrão (1 MiB)
+pub const DEFAULT_CONTENT_MAX_BYTES: i64 = 1024 * 1024;
+
+/// Extensões indexadas por padrão
+pub const DEFAULT_CONTENT_EXTENSIONS: &[&str] = &[
+    // Texto e documentação
+    "txt", "text", "md", "markdown", "rst", "adoc", "log",
+    // Dados e configuração
+    "csv", "tsv", "json", "jsonl", "toml", "yaml", "yml", "ini", "cfg", "conf", "xml", "env",
+    // Código-fonte
+    "rs", "py", "js", "jsx", "ts", "tsx", "go", "java", "kt", "scala", "c", "h", "cpp", "hpp",
+    "cs", "rb", "php", "swift", "lua", "sh", "bash", "zsh", "ps1", "sql", "html", "css", "scss",
+];
+
+/// Extensões padrão como `Vec`, para configurações
+pub fn default_extensions() -> Vec<String> {
+    DEFAULT_CONTENT_EXTENSIONS.iter().map(|ext| ext.to_string()).collect()
+}
+
+/// Quais arquivos têm o conteúdo extraído
+#[derive(Debug, Clone)]
+pub struct ContentPolicy {
+    max_bytes: i64,
+    extensions: HashSet<String>,
+}
+
+impl ContentPolicy {
+    /// # Argumentos
+    /// * `max_bytes` - Arquivos maiores são ignorados
+    /// * `extensions` - Extensões aceitas, sem ponto (comparadas sem diferenciar maiúsculas)
+    pub fn new(max_bytes: i64, extensions: &[String]) -> Self {
+        Self {
+            max_bytes,
+            extensions: extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Maior quantidade de bytes lida de um arquivo
+    pub fn max_bytes(&self) -> i64 {
+        self.max_bytes
+    }
+
+    /// Se o arquivo deve ter o conteúdo extraído
+    pub fn accepts(&self, path: &Path, file_size: i64) -> bool {
+        file_size <= self.max_bytes
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()))
+    }
+}
+
+/// Lê o texto de um arquivo, no máximo `max_bytes` bytes.
+///
+/// O limite vale mesmo se o arquivo cresceu depois do `stat` que o
+/// selecionou. Bytes que não são UTF-8 válido são substituídos. Um arquivo
+/// com bytes nulos é tratado como binário e resulta em texto vazio (que o
+/// PostgreSQL também não aceitaria em `TEXT`).
+pub async fn extract_text(path: &Path, max_bytes: i64) -> std::io::Result<String> {
+    let mut bytes = Vec::new();
+    tokio::fs::File::open(path)
+        .await?
+        .take(max_bytes.max(0) as u64)
+        .read_to_end(&mut bytes)
+        .await?;
+    if bytes.contains(&0) {
+        return Ok(String::new());
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_checks_extension_and_size() {
+        let policy = ContentPolicy::new(100, &["md".to_string(), ".RS".to_string()]);
+        assert!(policy.accepts(Path::new("/docs/README.MD"), 10));
+        assert!(policy.accepts(Path::new("/src/main.rs"), 100));
+        assert!(!policy.accepts(Path::new("/src/main.rs"), 101));
+        assert!(!policy.accepts(Path::new("/docs/contrato.pdf"), 10));
+        assert!(!policy.accepts(Path::new("/docs/Makefile"), 10));
+    }
+
+    #[tokio::test]
+    async fn test_binary_files_yield_empty_text() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let text = dir.path().join("notas.txt");
+        let binary = dir.path().join("dados.txt");
+        std::fs::write(&text, "orçamento anual").unwrap();
+        std::fs::write(&binary, [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+
+        assert_eq!(extract_text(&text, 1024).await.unwrap(), "orçamento anual");
+        assert_eq!(extract_text(&binary, 1024).await.unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn test_reads_at_most_max_bytes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("crescendo.log");
+        std::fs::write(&file, "linha 1\nlinha 2\n").unwrap();
+
+        assert_eq!(extract_text(&file, 7).await.unwrap(), "linha 1");
+        assert_eq!(extract_text(&file, 0).await.unwrap(), "");
+    }
+}
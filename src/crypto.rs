@@ -11,15 +11,45 @@ use aes_gcm::{
     Aes256Gcm, Key, Nonce,
 };
 use argon2::{
-    password_hash::{rand_core::RngCore, PasswordHasher, SaltString},
-    Argon2,
+    password_hash::{rand_core::RngCore, SaltString},
+    Algorithm, Argon2, Params, Version,
 };
 use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
 use tokio::sync::RwLock;
 use tracing::info;
 
+/// Prefixo dos valores criptografados gravados no banco (`enc:v1:<base64>`)
+///
+/// Permite distinguir credenciais antigas em texto plano das já criptografadas.
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Variável de ambiente com a chave mestra (32 bytes em base64)
+pub const MASTER_KEY_ENV: &str = "B2CLI_MASTER_KEY";
+
+/// Variável de ambiente com o caminho de um arquivo contendo a chave mestra
+pub const MASTER_KEY_FILE_ENV: &str = "B2CLI_MASTER_KEY_FILE";
+
+/// Versão do esquema de derivação de chave.
+///
+/// A versão 1 usava os primeiros 32 bytes da string PHC do Argon2 como chave;
+/// a 2 usa a saída bruta do Argon2id e exige key-check.
+pub const CRYPTO_CONFIG_VERSION: u32 = 2;
+
+/// Constante criptografada no key-check
+const KEY_CHECK_PLAINTEXT: &str = "b2cli-key-check";
+
+/// Variável de ambiente com a senha mestra (alternativa à chave bruta)
+pub const MASTER_PASSWORD_ENV: &str = "B2CLI_MASTER_PASSWORD";
+
+/// Erro das operações de credenciais, compatível com `sqlx::Error::Encode/Decode`
+pub type CryptoError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Chave mestra do processo, usada para as credenciais dos provedores
+static MASTER_KEY: OnceLock<CryptoManager> = OnceLock::new();
+
 /// Gerenciador central de criptografia do B2CLI
 /// 
 /// Responsável por:
@@ -30,33 +60,46 @@ use tracing::info;
 pub struct CryptoManager {
     /// Chave de criptografia derivada da senha mestra
     key: Arc<RwLock<Option<Key<Aes256Gcm>>>>,
-    /// Salt para derivação de chave
-    salt: String,
+    /// Salt, parâmetros do Argon2 e key-check usados na derivação
+    config: Arc<RwLock<CryptoConfig>>,
 }
 
 impl CryptoManager {
     /// Cria um novo gerenciador de criptografia
     /// 
-    /// Gera automaticamente um salt aleatório para derivação de chaves
+    /// Gera automaticamente um salt aleatório para derivação de chaves.
+    /// Para que a mesma senha abra dados de execuções anteriores use
+    /// `with_config` com a configuração persistida.
     pub fn new() -> Self {
+        Self::with_config(CryptoConfig::default())
+    }
+
+    /// Cria um gerenciador a partir de uma configuração persistida
+    /// (salt, parâmetros do Argon2 e key-check de uma execução anterior)
+    pub fn with_config(config: CryptoConfig) -> Self {
         Self {
             key: Arc::new(RwLock::new(None)),
-            salt: Self::generate_salt(),
+            config: Arc::new(RwLock::new(config)),
         }
     }
 
-    /// Gera um salt aleatório criptograficamente seguro
-    /// 
-    /// Usa o gerador de números aleatórios do sistema operacional
-    fn generate_salt() -> String {
-        let salt = SaltString::generate(&mut OsRng);
-        salt.to_string()
+    /// Configuração atual, incluindo o key-check gerado no primeiro `init_with_password`.
+    ///
+    /// É o que deve ser persistido (tabela `crypto_settings`).
+    pub async fn config(&self) -> CryptoConfig {
+        self.config.read().await.clone()
     }
 
     /// Inicializa o gerenciador com uma senha mestra
     /// 
-    /// A senha é usada para derivar a chave de criptografia usando Argon2.
-    /// Esta chave será usada para criptografar todas as credenciais.
+    /// A senha é usada para derivar a chave de criptografia usando Argon2id
+    /// com o salt e os parâmetros da configuração; a chave são os 32 bytes
+    /// brutos da saída do Argon2.
+    /// 
+    /// Se a configuração já tem um key-check, ele precisa abrir com a chave
+    /// derivada: uma senha errada é rejeitada em vez de gerar outra chave.
+    /// Sem key-check (primeira inicialização), um novo é gerado e a
+    /// configuração deve ser persistida em seguida.
     /// 
     /// # Arguments
     /// 
@@ -65,31 +108,76 @@ impl CryptoManager {
     /// # Returns
     /// 
     /// * `Ok(())` - Se a inicialização foi bem-sucedida
-    /// * `Err(e)` - Se houve erro na derivação da chave
+    /// * `Err(e)` - Senha incorreta, versão não suportada ou erro na derivação
     pub async fn init_with_password(&self, password: &str) -> Result<(), Box<dyn std::error::Error>> {
         info!("Inicializando gerenciador de criptografia");
-        
-        // Derivar chave da senha usando Argon2
-        let argon2 = Argon2::default();
-        let salt = SaltString::from_b64(&self.salt)
-            .map_err(|e| format!("Erro ao decodificar salt: {}", e))?;
-        
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)
-            .map_err(|e| format!("Erro ao derivar chave: {}", e))?
-            .to_string();
-        
-        // Usar os primeiros 32 bytes do hash como chave AES
-        let key_bytes = &password_hash.as_bytes()[..32];
-        let key = Key::<Aes256Gcm>::from_slice(key_bytes);
-        
+
+        let mut config = self.config.write().await;
+        if config.version != CRYPTO_CONFIG_VERSION {
+            return Err(format!(
+                "Versão de configuração de criptografia não suportada: {} (esperada {})",
+                config.version, CRYPTO_CONFIG_VERSION
+            )
+            .into());
+        }
+
+        let key = derive_key(password, &config).map_err(|e| e.to_string())?;
+        let cipher = Aes256Gcm::new(&key);
+
+        match config.key_check.as_deref() {
+            Some(key_check) => {
+                let opened = open_with(&cipher, key_check).map_err(|_| "Senha mestra incorreta")?;
+                if opened != KEY_CHECK_PLAINTEXT {
+                    return Err("Senha mestra incorreta".into());
+                }
+            }
+            None => {
+                config.key_check = Some(seal_with(&cipher, KEY_CHECK_PLAINTEXT).map_err(|e| e.to_string())?);
+                config.enabled = true;
+            }
+        }
+
         let mut stored_key = self.key.write().await;
-        *stored_key = Some(*key);
+        *stored_key = Some(key);
         
         info!("Chave de criptografia derivada com sucesso");
         Ok(())
     }
 
+    /// Inicializa o gerenciador com uma chave bruta de 32 bytes (AES-256)
+    ///
+    /// Usado para a chave mestra, que não passa por derivação de senha.
+    pub async fn init_with_key(&self, key_bytes: &[u8]) -> Result<(), CryptoError> {
+        if key_bytes.len() != 32 {
+            return Err(format!("A chave mestra deve ter 32 bytes, recebidos {}", key_bytes.len()).into());
+        }
+
+        let mut stored_key = self.key.write().await;
+        *stored_key = Some(*Key::<Aes256Gcm>::from_slice(key_bytes));
+        Ok(())
+    }
+
+    /// Criptografa uma credencial no formato `enc:v1:<base64>`
+    pub async fn seal(&self, plaintext: &str) -> Result<String, CryptoError> {
+        let ciphertext = self
+            .encrypt(plaintext)
+            .await
+            .map_err(|e| CryptoError::from(e.to_string()))?;
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, ciphertext))
+    }
+
+    /// Abre uma credencial gravada por `seal`; valores sem o prefixo são
+    /// texto plano legado e voltam como estão.
+    pub async fn open(&self, stored: &str) -> Result<String, CryptoError> {
+        match stored.strip_prefix(ENCRYPTED_PREFIX) {
+            Some(ciphertext) => self
+                .decrypt(ciphertext)
+                .await
+                .map_err(|e| CryptoError::from(e.to_string())),
+            None => Ok(stored.to_string()),
+        }
+    }
+
     /// Criptografa um texto
     pub async fn encrypt(&self, plaintext: &str) -> Result<String, Box<dyn std::error::Error>> {
         let key_lock = self.key.read().await;
@@ -97,25 +185,7 @@ impl CryptoManager {
             .as_ref()
             .ok_or("Criptografia não inicializada")?;
         
-        let cipher = Aes256Gcm::new(key);
-        
-        // Gerar nonce aleatório
-        let mut nonce_bytes = [0u8; 12];
-        OsRng.fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
-        
-        // Criptografar
-        let ciphertext = cipher
-            .encrypt(nonce, plaintext.as_bytes())
-            .map_err(|e| format!("Erro ao criptografar: {}", e))?;
-        
-        // Combinar nonce + ciphertext
-        let mut combined = Vec::new();
-        combined.extend_from_slice(&nonce_bytes);
-        combined.extend_from_slice(&ciphertext);
-        
-        // Codificar em base64
-        Ok(STANDARD.encode(combined))
+        Ok(seal_with(&Aes256Gcm::new(key), plaintext).map_err(|e| e.to_string())?)
     }
 
     /// Descriptografa um texto
@@ -125,25 +195,7 @@ impl CryptoManager {
             .as_ref()
             .ok_or("Criptografia não inicializada")?;
         
-        let cipher = Aes256Gcm::new(key);
-        
-        // Decodificar de base64
-        let combined = STANDARD.decode(ciphertext)?;
-        
-        // Separar nonce e ciphertext
-        if combined.len() < 12 {
-            return Err("Ciphertext inválido".into());
-        }
-        
-        let (nonce_bytes, ciphertext_bytes) = combined.split_at(12);
-        let nonce = Nonce::from_slice(nonce_bytes);
-        
-        // Descriptografar
-        let plaintext = cipher
-            .decrypt(nonce, ciphertext_bytes)
-            .map_err(|e| format!("Erro ao descriptografar: {}", e))?;
-        
-        Ok(String::from_utf8(plaintext)?)
+        Ok(open_with(&Aes256Gcm::new(key), ciphertext).map_err(|e| e.to_string())?)
     }
 
     /// Criptografa um caminho de arquivo
@@ -167,7 +219,152 @@ impl CryptoManager {
     }
 }
 
+/// Deriva a chave AES-256 da senha: 32 bytes brutos do Argon2id
+fn derive_key(password: &str, config: &CryptoConfig) -> Result<Key<Aes256Gcm>, CryptoError> {
+    let params = Params::new(
+        config.argon2_memory_kib,
+        config.argon2_iterations,
+        config.argon2_parallelism,
+        Some(32),
+    )
+    .map_err(|e| format!("Parâmetros do Argon2 inválidos: {}", e))?;
+
+    let salt = SaltString::from_b64(&config.salt)
+        .map_err(|e| format!("Erro ao decodificar salt: {}", e))?;
+    let mut salt_bytes = [0u8; 64];
+    let salt_bytes = salt
+        .decode_b64(&mut salt_bytes)
+        .map_err(|e| format!("Erro ao decodificar salt: {}", e))?;
+
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt_bytes, &mut key)
+        .map_err(|e| format!("Erro ao derivar chave: {}", e))?;
+
+    Ok(*Key::<Aes256Gcm>::from_slice(&key))
+}
+
+/// AES-256-GCM com nonce aleatório; saída em base64 de `nonce || ciphertext`
+fn seal_with(cipher: &Aes256Gcm, plaintext: &str) -> Result<String, CryptoError> {
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext.as_bytes())
+        .map_err(|e| format!("Erro ao criptografar: {}", e))?;
+
+    let mut combined = Vec::with_capacity(nonce_bytes.len() + ciphertext.len());
+    combined.extend_from_slice(&nonce_bytes);
+    combined.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(combined))
+}
+
+/// Inverso de `seal_with`
+fn open_with(cipher: &Aes256Gcm, encoded: &str) -> Result<String, CryptoError> {
+    let combined = STANDARD.decode(encoded)?;
+    if combined.len() < 12 {
+        return Err("Ciphertext inválido".into());
+    }
+
+    let (nonce_bytes, ciphertext_bytes) = combined.split_at(12);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext_bytes)
+        .map_err(|e| format!("Erro ao descriptografar: {}", e))?;
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+/// Se o valor gravado no banco está no formato criptografado
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Decodifica uma chave mestra em base64 (espaços e quebras de linha são ignorados)
+pub fn decode_master_key(encoded: &str) -> Result<Vec<u8>, CryptoError> {
+    let key = STANDARD.decode(encoded.trim())?;
+    if key.len() != 32 {
+        return Err(format!(
+            "A chave mestra deve ter 32 bytes em base64, decodificados {} bytes",
+            key.len()
+        )
+        .into());
+    }
+    Ok(key)
+}
+
+/// Gera uma nova chave mestra aleatória, já em base64
+pub fn generate_master_key() -> String {
+    generate_secret()
+}
+
+/// 32 bytes aleatórios em base64 (chaves e senhas geradas pelo sistema)
+pub fn generate_secret() -> String {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    STANDARD.encode(key)
+}
+
+/// Lê a chave mestra de `B2CLI_MASTER_KEY` ou do arquivo em `B2CLI_MASTER_KEY_FILE`.
+///
+/// # Retorna
+/// * `Ok(Some(chave))` - Chave de 32 bytes
+/// * `Ok(None)` - Nenhuma das variáveis está definida
+/// * `Err(e)` - Arquivo ilegível ou chave em formato inválido
+pub fn master_key_from_env() -> Result<Option<Vec<u8>>, CryptoError> {
+    if let Ok(encoded) = std::env::var(MASTER_KEY_ENV) {
+        return decode_master_key(&encoded).map(Some);
+    }
+    if let Ok(path) = std::env::var(MASTER_KEY_FILE_ENV) {
+        return read_master_key_file(Path::new(&path)).map(Some);
+    }
+    Ok(None)
+}
+
+/// Lê uma chave mestra (base64) de um arquivo
+pub fn read_master_key_file(path: &Path) -> Result<Vec<u8>, CryptoError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Falha ao ler chave mestra de {}: {}", path.display(), e))?;
+    decode_master_key(&content)
+}
+
+/// Instala a chave mestra do processo (uma única vez)
+pub fn set_master(crypto: CryptoManager) -> Result<(), CryptoError> {
+    MASTER_KEY
+        .set(crypto)
+        .map_err(|_| CryptoError::from("Chave mestra já inicializada"))
+}
+
+/// Gerenciador com a chave mestra do processo, se configurada
+pub fn master() -> Option<&'static CryptoManager> {
+    MASTER_KEY.get()
+}
+
+/// Criptografa uma credencial com a chave mestra (texto plano se não houver chave)
+pub async fn seal_credential(plaintext: &str) -> Result<String, CryptoError> {
+    match master() {
+        Some(crypto) => crypto.seal(plaintext).await,
+        None => Ok(plaintext.to_string()),
+    }
+}
+
+/// Descriptografa uma credencial gravada no banco
+pub async fn open_credential(stored: &str) -> Result<String, CryptoError> {
+    match master() {
+        Some(crypto) => crypto.open(stored).await,
+        None if is_sealed(stored) => Err(format!(
+            "Credencial criptografada mas nenhuma chave mestra configurada ({}, {} ou {})",
+            MASTER_KEY_ENV, MASTER_KEY_FILE_ENV, MASTER_PASSWORD_ENV
+        )
+        .into()),
+        None => Ok(stored.to_string()),
+    }
+}
+
 /// Estrutura para armazenar configuração de criptografia
+///
+/// Persistida na tabela `crypto_settings` para que a mesma senha mestra
+/// derive a mesma chave entre reinícios.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CryptoConfig {
     /// Se a criptografia está habilitada
@@ -181,6 +378,19 @@ pub struct CryptoConfig {
     
     /// Versão do esquema de criptografia
     pub version: u32,
+
+    /// Memória do Argon2id em KiB
+    pub argon2_memory_kib: u32,
+
+    /// Iterações do Argon2id
+    pub argon2_iterations: u32,
+
+    /// Paralelismo do Argon2id
+    pub argon2_parallelism: u32,
+
+    /// Constante conhecida criptografada com a chave derivada, usada para
+    /// rejeitar senha errada (`None` até a primeira inicialização)
+    pub key_check: Option<String>,
 }
 
 impl Default for CryptoConfig {
@@ -189,7 +399,11 @@ impl Default for CryptoConfig {
             enabled: false,
             salt: SaltString::generate(&mut OsRng).to_string(),
             algorithm: "AES-256-GCM".to_string(),
-            version: 1,
+            version: CRYPTO_CONFIG_VERSION,
+            argon2_memory_kib: Params::DEFAULT_M_COST,
+            argon2_iterations: Params::DEFAULT_T_COST,
+            argon2_parallelism: Params::DEFAULT_P_COST,
+            key_check: None,
         }
     }
 }
@@ -263,6 +477,73 @@ mod tests {
         assert_ne!(plaintext, encrypted);
     }
 
+    #[tokio::test]
+    async fn test_seal_and_open_with_raw_key() {
+        let crypto = CryptoManager::new();
+        let key = decode_master_key(&generate_master_key()).unwrap();
+        crypto.init_with_key(&key).await.unwrap();
+
+        let sealed = crypto.seal("secret-key").await.unwrap();
+        assert!(is_sealed(&sealed));
+        assert_eq!(crypto.open(&sealed).await.unwrap(), "secret-key");
+
+        // Texto plano legado passa direto
+        assert_eq!(crypto.open("legacy-plaintext").await.unwrap(), "legacy-plaintext");
+
+        // Outra chave não abre
+        let other = CryptoManager::new();
+        other
+            .init_with_key(&decode_master_key(&generate_master_key()).unwrap())
+            .await
+            .unwrap();
+        assert!(other.open(&sealed).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_persisted_config_survives_restart() {
+        let first = CryptoManager::new();
+        first.init_with_password("correct horse").await.unwrap();
+        let ciphertext = first.encrypt("provider-secret").await.unwrap();
+        let config = first.config().await;
+        assert!(config.key_check.is_some());
+
+        // "Reinício": novo gerenciador com a configuração persistida
+        let second = CryptoManager::with_config(config.clone());
+        second.init_with_password("correct horse").await.unwrap();
+        assert_eq!(second.decrypt(&ciphertext).await.unwrap(), "provider-secret");
+
+        let wrong = CryptoManager::with_config(config);
+        assert!(wrong.init_with_password("battery staple").await.is_err());
+        assert!(!wrong.is_enabled().await);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unknown_config_version() {
+        let config = CryptoConfig {
+            version: 1,
+            ..CryptoConfig::default()
+        };
+        let crypto = CryptoManager::with_config(config);
+        assert!(crypto.init_with_password("test_password_123").await.is_err());
+    }
+
+    #[test]
+    fn test_key_is_raw_argon2_output() {
+        let config = CryptoConfig::default();
+        let key = derive_key("test_password_123", &config).unwrap();
+
+        // A chave antiga eram os bytes ASCII da string PHC ("$argon2id$v=19$m=...")
+        assert!(!key.as_slice().starts_with(b"$argon2"));
+        assert_eq!(key, derive_key("test_password_123", &config).unwrap());
+    }
+
+    #[test]
+    fn test_decode_master_key_rejects_wrong_length() {
+        assert!(decode_master_key(&STANDARD.encode([0u8; 16])).is_err());
+        assert!(decode_master_key("not base64!").is_err());
+        assert_eq!(decode_master_key(&format!("{}\n", generate_master_key())).unwrap().len(), 32);
+    }
+
     #[tokio::test]
     async fn test_encrypted_field() {
         let crypto = CryptoManager::new();
@@ -1,7 +1,10 @@
 use crate::models::{
-    BackupJob, NewBackupJob, BackupSchedule, NewBackupSchedule, UpdateBackupJob, UpdateBackupSchedule,
-    CloudProvider, NewCloudProvider, UpdateCloudProvider, CloudProviderType, ConnectivityTestResult, ConnectivityStatus
+    BackupJob, BackupEncryption, BackupTriggerMode, NewBackupJob, OverlapPolicy, BackupSchedule, NewBackupSchedule, UpdateBackupJob, UpdateBackupSchedule,
+    ScanSchedule, NewScanSchedule, UpdateScanSchedule, ScanConfigRecord,
+    CloudProvider, NewCloudProvider, UpdateCloudProvider, CloudProviderType, ConnectivityTestResult
 };
+use crate::crypto::{self, CryptoConfig, CryptoManager};
+use crate::run_locks::{ENQUEUE_LOCKS, JOB_LOCKS};
 use sqlx::PgPool;
 use chrono::{DateTime, Utc, Datelike, Timelike, Duration};
 
@@ -120,19 +123,40 @@ fn calculate_next_run(cron_expr: &str) -> Option<DateTime<Utc>> {
 /// let (job, schedule) = create_backup_job(&pool, &new_job).await?;
 /// ```
 pub async fn create_backup_job(pool: &PgPool, new_job: &NewBackupJob) -> Result<(BackupJob, Option<BackupSchedule>), sqlx::Error> {
+    // O job e as senhas do crypt entram juntos: sem chave mestra nenhum dos dois é gravado
+    let mut tx = pool.begin().await?;
     let job = sqlx::query_as!(
         BackupJob,
         r#"
-        INSERT INTO backup_jobs (name, mappings)
-        VALUES ($1, $2)
-        RETURNING id, name, mappings, created_at, updated_at, deleted_at, status, is_active
+        INSERT INTO backup_jobs (name, mappings, verify_after_backup, verification_sample_size, encryption, ignore_templates,
+                                 trigger_mode, watch_quiet_seconds, watch_min_interval_seconds, watch_max_delay_seconds,
+                                 overlap_policy)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING id, name, mappings, created_at, updated_at, deleted_at, status, is_active,
+               verify_after_backup, verification_sample_size, encryption as "encryption: BackupEncryption", ignore_templates,
+               trigger_mode as "trigger_mode: BackupTriggerMode", watch_quiet_seconds, watch_min_interval_seconds, watch_max_delay_seconds,
+               overlap_policy as "overlap_policy: OverlapPolicy"
         "#,
         new_job.name,
-        serde_json::to_value(&new_job.mappings).unwrap()
+        serde_json::to_value(&new_job.mappings).unwrap(),
+        new_job.verify_after_backup.unwrap_or(false),
+        new_job.verification_sample_size,
+        new_job.encryption.unwrap_or_default().as_str(),
+        new_job.ignore_templates.as_deref().unwrap_or(&[]),
+        new_job.trigger_mode.unwrap_or_default().as_str(),
+        new_job.watch_quiet_seconds.unwrap_or(BackupTriggerMode::DEFAULT_QUIET_SECONDS),
+        new_job.watch_min_interval_seconds.unwrap_or(BackupTriggerMode::DEFAULT_MIN_INTERVAL_SECONDS),
+        new_job.watch_max_delay_seconds.unwrap_or(BackupTriggerMode::DEFAULT_MAX_DELAY_SECONDS),
+        new_job.overlap_policy.unwrap_or_default().as_str()
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    if job.encryption.is_encrypted() {
+        ensure_backup_job_crypt_keys(&mut tx, job.id).await?;
+    }
+    tx.commit().await?;
+
     if let Some(schedule_data) = &new_job.schedule {
         let schedule = create_backup_schedule(pool, job.id, schedule_data).await?;
         Ok((job, Some(schedule)))
@@ -161,7 +185,10 @@ pub async fn list_backup_jobs(pool: &PgPool) -> Result<Vec<BackupJob>, sqlx::Err
     let jobs = sqlx::query_as!(
         BackupJob,
         r#"
-        SELECT id, name, mappings, created_at, updated_at, deleted_at, status, is_active
+        SELECT id, name, mappings, created_at, updated_at, deleted_at, status, is_active,
+               verify_after_backup, verification_sample_size, encryption as "encryption: BackupEncryption", ignore_templates,
+               trigger_mode as "trigger_mode: BackupTriggerMode", watch_quiet_seconds, watch_min_interval_seconds, watch_max_delay_seconds,
+               overlap_policy as "overlap_policy: OverlapPolicy"
         FROM backup_jobs
         WHERE is_active = true
         ORDER BY created_at DESC
@@ -177,7 +204,10 @@ pub async fn get_backup_job_by_id(pool: &PgPool, id: uuid::Uuid) -> Result<Optio
     let job = sqlx::query_as!(
         BackupJob,
         r#"
-        SELECT id, name, mappings, created_at, updated_at, deleted_at, status, is_active
+        SELECT id, name, mappings, created_at, updated_at, deleted_at, status, is_active,
+               verify_after_backup, verification_sample_size, encryption as "encryption: BackupEncryption", ignore_templates,
+               trigger_mode as "trigger_mode: BackupTriggerMode", watch_quiet_seconds, watch_min_interval_seconds, watch_max_delay_seconds,
+               overlap_policy as "overlap_policy: OverlapPolicy"
         FROM backup_jobs
         WHERE id = $1 AND is_active = true
         "#,
@@ -190,21 +220,43 @@ pub async fn get_backup_job_by_id(pool: &PgPool, id: uuid::Uuid) -> Result<Optio
 }
 
 pub async fn update_backup_job(pool: &PgPool, id: uuid::Uuid, updated_job: &NewBackupJob) -> Result<Option<BackupJob>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
     let job = sqlx::query_as!(
         BackupJob,
         r#"
         UPDATE backup_jobs
-        SET name = $1, mappings = $2, updated_at = NOW()
-        WHERE id = $3 AND is_active = true
-        RETURNING id, name, mappings, created_at, updated_at, deleted_at, status, is_active
+        SET name = $1, mappings = $2, verify_after_backup = $3,
+            verification_sample_size = $4, encryption = COALESCE($5, encryption),
+            ignore_templates = $6, trigger_mode = $8, watch_quiet_seconds = $9,
+            watch_min_interval_seconds = $10, watch_max_delay_seconds = $11, overlap_policy = $12,
+            updated_at = NOW()
+        WHERE id = $7 AND is_active = true
+        RETURNING id, name, mappings, created_at, updated_at, deleted_at, status, is_active,
+               verify_after_backup, verification_sample_size, encryption as "encryption: BackupEncryption", ignore_templates,
+               trigger_mode as "trigger_mode: BackupTriggerMode", watch_quiet_seconds, watch_min_interval_seconds, watch_max_delay_seconds,
+               overlap_policy as "overlap_policy: OverlapPolicy"
         "#,
         updated_job.name,
         serde_json::to_value(&updated_job.mappings).unwrap(),
-        id
+        updated_job.verify_after_backup.unwrap_or(false),
+        updated_job.verification_sample_size,
+        updated_job.encryption.map(|e| e.as_str()),
+        updated_job.ignore_templates.as_deref().unwrap_or(&[]),
+        id,
+        updated_job.trigger_mode.unwrap_or_default().as_str(),
+        updated_job.watch_quiet_seconds.unwrap_or(BackupTriggerMode::DEFAULT_QUIET_SECONDS),
+        updated_job.watch_min_interval_seconds.unwrap_or(BackupTriggerMode::DEFAULT_MIN_INTERVAL_SECONDS),
+        updated_job.watch_max_delay_seconds.unwrap_or(BackupTriggerMode::DEFAULT_MAX_DELAY_SECONDS),
+        updated_job.overlap_policy.unwrap_or_default().as_str()
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *tx)
     .await?;
 
+    if updated_job.encryption.is_some_and(|e| e.is_encrypted()) && job.is_some() {
+        ensure_backup_job_crypt_keys(&mut tx, id).await?;
+    }
+    tx.commit().await?;
+
     Ok(job)
 }
 
@@ -295,13 +347,15 @@ pub async fn get_backup_schedule_by_job_id(pool: &PgPool, backup_job_id: uuid::U
     }
 }
 
+/// Schedules ativados de backup jobs ativos, os que devem estar no scheduler
 pub async fn list_active_schedules(pool: &PgPool) -> Result<Vec<BackupSchedule>, sqlx::Error> {
     let rows = sqlx::query!(
         r#"
-        SELECT id, backup_job_id, name, cron_expression, enabled, next_run, last_run, last_status, created_at, updated_at
-        FROM backup_schedules
-        WHERE enabled = true
-        ORDER BY created_at DESC
+        SELECT s.id, s.backup_job_id, s.name, s.cron_expression, s.enabled, s.next_run, s.last_run, s.last_status, s.created_at, s.updated_at
+        FROM backup_schedules s
+        JOIN backup_jobs j ON j.id = s.backup_job_id
+        WHERE s.enabled = true AND j.is_active = true
+        ORDER BY s.created_at DESC
         "#
     )
     .fetch_all(pool)
@@ -419,22 +473,55 @@ pub async fn patch_backup_job(pool: &PgPool, id: uuid::Uuid, patch_data: &Update
         } else {
             job.mappings
         };
-
+        let updated_verify = patch_data.verify_after_backup.unwrap_or(job.verify_after_backup);
+        let updated_sample_size = patch_data.verification_sample_size.unwrap_or(job.verification_sample_size);
+        let updated_encryption = patch_data.encryption.unwrap_or(job.encryption);
+        let updated_templates = patch_data
+            .ignore_templates
+            .clone()
+            .unwrap_or(job.ignore_templates);
+        let updated_trigger_mode = patch_data.trigger_mode.unwrap_or(job.trigger_mode);
+        let updated_quiet = patch_data.watch_quiet_seconds.unwrap_or(job.watch_quiet_seconds);
+        let updated_min_interval = patch_data.watch_min_interval_seconds.unwrap_or(job.watch_min_interval_seconds);
+        let updated_max_delay = patch_data.watch_max_delay_seconds.unwrap_or(job.watch_max_delay_seconds);
+        let updated_overlap_policy = patch_data.overlap_policy.unwrap_or(job.overlap_policy);
+
+        let mut tx = pool.begin().await?;
         let updated_job = sqlx::query_as!(
             BackupJob,
             r#"
             UPDATE backup_jobs
-            SET name = $1, mappings = $2, updated_at = NOW()
-            WHERE id = $3 AND is_active = true
-            RETURNING id, name, mappings, created_at, updated_at, deleted_at, status, is_active
+            SET name = $1, mappings = $2, verify_after_backup = $3,
+                verification_sample_size = $4, encryption = $5, ignore_templates = $6,
+                trigger_mode = $8, watch_quiet_seconds = $9, watch_min_interval_seconds = $10,
+                watch_max_delay_seconds = $11, overlap_policy = $12, updated_at = NOW()
+            WHERE id = $7 AND is_active = true
+            RETURNING id, name, mappings, created_at, updated_at, deleted_at, status, is_active,
+                   verify_after_backup, verification_sample_size, encryption as "encryption: BackupEncryption", ignore_templates,
+                   trigger_mode as "trigger_mode: BackupTriggerMode", watch_quiet_seconds, watch_min_interval_seconds, watch_max_delay_seconds,
+               overlap_policy as "overlap_policy: OverlapPolicy"
             "#,
             updated_name,
             updated_mappings,
-            id
+            updated_verify,
+            updated_sample_size,
+            updated_encryption.as_str(),
+            &updated_templates,
+            id,
+            updated_trigger_mode.as_str(),
+            updated_quiet,
+            updated_min_interval,
+            updated_max_delay,
+            updated_overlap_policy.as_str()
         )
-        .fetch_optional(pool)
+        .fetch_optional(&mut *tx)
         .await?;
 
+        if patch_data.encryption.is_some_and(|e| e.is_encrypted()) && updated_job.is_some() {
+            ensure_backup_job_crypt_keys(&mut tx, id).await?;
+        }
+        tx.commit().await?;
+
         Ok(updated_job)
     } else {
         Ok(None)
@@ -485,6 +572,241 @@ pub async fn patch_backup_schedule(pool: &PgPool, backup_job_id: uuid::Uuid, pat
     }
 }
 
+// ========================================
+// SCAN SCHEDULE FUNCTIONS
+// ========================================
+
+pub async fn create_scan_schedule(pool: &PgPool, new_schedule: &NewScanSchedule) -> Result<ScanSchedule, sqlx::Error> {
+    sqlx::query_as!(
+        ScanSchedule,
+        r#"
+        INSERT INTO scan_schedules (
+            name, description, root_path, recursive, max_depth,
+            exclude_patterns, cron_expression, enabled
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, name, description, root_path, recursive, max_depth, exclude_patterns,
+                  cron_expression, enabled, last_run_at, last_run_status, last_scan_job_id, next_run_at,
+                  total_runs, successful_runs, failed_runs,
+                  (CASE WHEN total_runs > 0 THEN successful_runs * 100.0 / total_runs ELSE 0 END)::FLOAT8 AS "success_rate!",
+                  created_at, updated_at
+        "#,
+        new_schedule.name,
+        new_schedule.description,
+        new_schedule.root_path,
+        new_schedule.recursive.unwrap_or(true),
+        new_schedule.max_depth,
+        &new_schedule.exclude_patterns,
+        new_schedule.cron_expression,
+        new_schedule.enabled.unwrap_or(true)
+    )
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_scan_schedule(pool: &PgPool, id: uuid::Uuid) -> Result<Option<ScanSchedule>, sqlx::Error> {
+    sqlx::query_as!(
+        ScanSchedule,
+        r#"
+        SELECT id, name, description, root_path, recursive, max_depth, exclude_patterns,
+               cron_expression, enabled, last_run_at, last_run_status, last_scan_job_id, next_run_at,
+               total_runs, successful_runs, failed_runs,
+               (CASE WHEN total_runs > 0 THEN successful_runs * 100.0 / total_runs ELSE 0 END)::FLOAT8 AS "success_rate!",
+               created_at, updated_at
+        FROM scan_schedules
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Agendamentos de scan, dos mais novos para os mais antigos.
+///
+/// # Argumentos
+/// * `enabled_only` - Apenas os ativados (os que devem estar no scheduler)
+pub async fn list_scan_schedules(pool: &PgPool, enabled_only: bool) -> Result<Vec<ScanSchedule>, sqlx::Error> {
+    sqlx::query_as!(
+        ScanSchedule,
+        r#"
+        SELECT id, name, description, root_path, recursive, max_depth, exclude_patterns,
+               cron_expression, enabled, last_run_at, last_run_status, last_scan_job_id, next_run_at,
+               total_runs, successful_runs, failed_runs,
+               (CASE WHEN total_runs > 0 THEN successful_runs * 100.0 / total_runs ELSE 0 END)::FLOAT8 AS "success_rate!",
+               created_at, updated_at
+        FROM scan_schedules
+        WHERE enabled OR NOT $1
+        ORDER BY created_at DESC
+        "#,
+        enabled_only
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Atualiza os campos informados; os demais mantêm o valor atual
+pub async fn patch_scan_schedule(pool: &PgPool, id: uuid::Uuid, patch_data: &UpdateScanSchedule) -> Result<Option<ScanSchedule>, sqlx::Error> {
+    sqlx::query_as!(
+        ScanSchedule,
+        r#"
+        UPDATE scan_schedules
+        SET name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            root_path = COALESCE($4, root_path),
+            recursive = COALESCE($5, recursive),
+            max_depth = COALESCE($6, max_depth),
+            exclude_patterns = COALESCE($7, exclude_patterns),
+            cron_expression = COALESCE($8, cron_expression),
+            enabled = COALESCE($9, enabled)
+        WHERE id = $1
+        RETURNING id, name, description, root_path, recursive, max_depth, exclude_patterns,
+                  cron_expression, enabled, last_run_at, last_run_status, last_scan_job_id, next_run_at,
+                  total_runs, successful_runs, failed_runs,
+                  (CASE WHEN total_runs > 0 THEN successful_runs * 100.0 / total_runs ELSE 0 END)::FLOAT8 AS "success_rate!",
+                  created_at, updated_at
+        "#,
+        id,
+        patch_data.name,
+        patch_data.description,
+        patch_data.root_path,
+        patch_data.recursive,
+        patch_data.max_depth,
+        patch_data.exclude_patterns.as_deref(),
+        patch_data.cron_expression,
+        patch_data.enabled
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn delete_scan_schedule(pool: &PgPool, id: uuid::Uuid) -> Result<u64, sqlx::Error> {
+    let rows_affected = sqlx::query!("DELETE FROM scan_schedules WHERE id = $1", id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+    Ok(rows_affected)
+}
+
+/// Marca o início de uma execução agendada (`last_run_status = 'running'`)
+pub async fn start_scan_schedule_run(pool: &PgPool, id: uuid::Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE scan_schedules SET last_run_at = NOW(), last_run_status = 'running' WHERE id = $1",
+        id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Grava o resultado de uma execução agendada e atualiza os contadores.
+///
+/// # Argumentos
+/// * `status` - `success`, `failed` ou `cancelled` (conta só no total)
+/// * `scan_job_id` - Scan job da execução, quando chegou a ser criado
+pub async fn finish_scan_schedule_run(
+    pool: &PgPool,
+    id: uuid::Uuid,
+    status: &str,
+    scan_job_id: Option<uuid::Uuid>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE scan_schedules
+        SET last_run_status = $2::TEXT,
+            last_scan_job_id = COALESCE($3, last_scan_job_id),
+            total_runs = total_runs + 1,
+            successful_runs = successful_runs + (CASE WHEN $2::TEXT = 'success' THEN 1 ELSE 0 END),
+            failed_runs = failed_runs + (CASE WHEN $2::TEXT = 'failed' THEN 1 ELSE 0 END)
+        WHERE id = $1
+        "#,
+        id,
+        status,
+        scan_job_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Grava o próximo disparo do agendamento no scheduler (`None` = fora do scheduler)
+pub async fn set_scan_schedule_next_run(
+    pool: &PgPool,
+    id: uuid::Uuid,
+    next_run_at: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE scan_schedules SET next_run_at = $2 WHERE id = $1",
+        id,
+        next_run_at
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ========================================
+// SCAN CONFIG FUNCTIONS
+// ========================================
+
+/// Configuração de scan ativa (não removida)
+pub async fn get_scan_config(pool: &PgPool, id: uuid::Uuid) -> Result<Option<ScanConfigRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        ScanConfigRecord,
+        r#"
+        SELECT id, name, status, root_path, recursive, follow_symlinks, max_depth,
+               exclude_patterns, ignore_templates, exclude_mime_categories, force_rehash,
+               paranoid_sample_rate, live_mode, index_content, content_max_bytes,
+               content_extensions, capture_xattrs
+        FROM scan_configs
+        WHERE id = $1 AND is_active = true
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Configurações ativas com `live_mode`, que devem ter um watcher
+pub async fn list_live_scan_configs(pool: &PgPool) -> Result<Vec<ScanConfigRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        ScanConfigRecord,
+        r#"
+        SELECT id, name, status, root_path, recursive, follow_symlinks, max_depth,
+               exclude_patterns, ignore_templates, exclude_mime_categories, force_rehash,
+               paranoid_sample_rate, live_mode, index_content, content_max_bytes,
+               content_extensions, capture_xattrs
+        FROM scan_configs
+        WHERE is_active = true AND live_mode = true
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Liga ou desliga o `live_mode` de uma configuração ativa
+///
+/// # Retorna
+/// * `None` se a configuração não existe ou foi removida
+pub async fn set_scan_config_live_mode(pool: &PgPool, id: uuid::Uuid, live_mode: bool) -> Result<Option<ScanConfigRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        ScanConfigRecord,
+        r#"
+        UPDATE scan_configs
+        SET live_mode = $2
+        WHERE id = $1 AND is_active = true
+        RETURNING id, name, status, root_path, recursive, follow_symlinks, max_depth,
+                  exclude_patterns, ignore_templates, exclude_mime_categories, force_rehash,
+                  paranoid_sample_rate, live_mode, index_content, content_max_bytes,
+                  content_extensions, capture_xattrs
+        "#,
+        id,
+        live_mode
+    )
+    .fetch_optional(pool)
+    .await
+}
+
 // ========================================
 // BACKUP EXECUTION LOGS FUNCTIONS
 // ========================================
@@ -497,9 +819,9 @@ pub async fn create_backup_execution_log(
         r#"
         INSERT INTO backup_execution_logs (
             backup_job_id, schedule_id, rclone_command, source_path, 
-            destination_path, rclone_config, triggered_by
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7)
-        RETURNING id, backup_job_id, schedule_id, started_at, completed_at, status,
+            destination_path, rclone_config, triggered_by, backup_run_id
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, backup_job_id, schedule_id, backup_run_id, started_at, completed_at, status,
                   rclone_command, source_path, destination_path, rclone_config,
                   files_transferred, files_checked, files_deleted, bytes_transferred,
                   transfer_rate_mbps, duration_seconds, error_count, retry_count,
@@ -512,7 +834,8 @@ pub async fn create_backup_execution_log(
         log_data.source_path,
         log_data.destination_path,
         log_data.rclone_config,
-        log_data.triggered_by.as_deref().unwrap_or("manual")
+        log_data.triggered_by.as_deref().unwrap_or("manual"),
+        log_data.backup_run_id
     )
     .fetch_one(pool)
     .await?;
@@ -521,6 +844,7 @@ pub async fn create_backup_execution_log(
         id: row.id,
         backup_job_id: row.backup_job_id,
         schedule_id: row.schedule_id,
+        backup_run_id: row.backup_run_id,
         started_at: row.started_at,
         completed_at: row.completed_at,
         status: row.status,
@@ -552,7 +876,25 @@ pub async fn update_backup_execution_log_completion(
     result: &crate::models::RcloneExecutionResult,
 ) -> Result<(), sqlx::Error> {
     let status = if result.exit_code == 0 { "completed" } else { "failed" };
-    
+    finish_backup_execution_log(pool, log_id, status, result).await
+}
+
+/// Marca um log de backup como cancelado, com as estatísticas parciais que
+/// o rclone registrou até ser interrompido.
+pub async fn cancel_backup_execution_log(
+    pool: &PgPool,
+    log_id: uuid::Uuid,
+    result: &crate::models::RcloneExecutionResult,
+) -> Result<(), sqlx::Error> {
+    finish_backup_execution_log(pool, log_id, "cancelled", result).await
+}
+
+async fn finish_backup_execution_log(
+    pool: &PgPool,
+    log_id: uuid::Uuid,
+    status: &str,
+    result: &crate::models::RcloneExecutionResult,
+) -> Result<(), sqlx::Error> {
     sqlx::query!(
         r#"
         UPDATE backup_execution_logs 
@@ -590,6 +932,31 @@ pub async fn update_backup_execution_log_completion(
     Ok(())
 }
 
+/// Marca um log de backup como falho quando o rclone não pôde ser executado.
+pub async fn fail_backup_execution_log(
+    pool: &PgPool,
+    log_id: uuid::Uuid,
+    error_message: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE backup_execution_logs
+        SET completed_at = NOW(),
+            status = 'failed',
+            error_count = 1,
+            error_message = $1,
+            updated_at = NOW()
+        WHERE id = $2
+        "#,
+        error_message,
+        log_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn list_backup_execution_logs(
     pool: &PgPool,
     backup_job_id: Option<uuid::Uuid>,
@@ -599,7 +966,7 @@ pub async fn list_backup_execution_logs(
 
     let rows = sqlx::query!(
         r#"
-        SELECT id, backup_job_id, schedule_id, started_at, completed_at, status,
+        SELECT id, backup_job_id, schedule_id, backup_run_id, started_at, completed_at, status,
                rclone_command, source_path, destination_path, rclone_config,
                files_transferred, files_checked, files_deleted, bytes_transferred,
                transfer_rate_mbps, duration_seconds, error_count, retry_count,
@@ -621,6 +988,7 @@ pub async fn list_backup_execution_logs(
             id: row.id,
             backup_job_id: row.backup_job_id,
             schedule_id: row.schedule_id,
+            backup_run_id: row.backup_run_id,
             started_at: row.started_at,
             completed_at: row.completed_at,
             status: row.status,
@@ -655,7 +1023,7 @@ pub async fn get_backup_execution_log_by_id(
 ) -> Result<Option<crate::models::BackupExecutionLog>, sqlx::Error> {
     let row = sqlx::query!(
         r#"
-        SELECT id, backup_job_id, schedule_id, started_at, completed_at, status,
+        SELECT id, backup_job_id, schedule_id, backup_run_id, started_at, completed_at, status,
                rclone_command, source_path, destination_path, rclone_config,
                files_transferred, files_checked, files_deleted, bytes_transferred,
                transfer_rate_mbps, duration_seconds, error_count, retry_count,
@@ -674,6 +1042,7 @@ pub async fn get_backup_execution_log_by_id(
             id: row.id,
             backup_job_id: row.backup_job_id,
             schedule_id: row.schedule_id,
+            backup_run_id: row.backup_run_id,
             started_at: row.started_at,
             completed_at: row.completed_at,
             status: row.status,
@@ -717,113 +1086,601 @@ pub async fn delete_backup_execution_log(
 }
 
 // ========================================
-// CLOUD PROVIDERS FUNCTIONS
+// BACKUP RUNS FUNCTIONS
 // ========================================
 
-/// Cria um novo provedor de armazenamento cloud.
-/// 
-/// Insere configurações de um provedor (Backblaze B2, IDrive e2, etc.)
-/// no banco de dados. Se `is_default` for true, remove o padrão atual.
-/// 
+/// Coloca uma execução do backup job na fila (`status = 'queued'`), sem
+/// aplicar a política de sobreposição do job (ver `enqueue_backup_run`).
+///
 /// # Argumentos
 /// * `pool` - Pool de conexão PostgreSQL
-/// * `new_provider` - Dados do novo provedor
-/// 
+/// * `backup_job_id` - Job a ser executado
+/// * `schedule_id` - Schedule que disparou a execução (opcional)
+/// * `triggered_by` - Origem do disparo (`manual`, `scheduler`, `watch`)
+pub async fn create_backup_run(
+    pool: &PgPool,
+    backup_job_id: uuid::Uuid,
+    schedule_id: Option<uuid::Uuid>,
+    triggered_by: &str,
+) -> Result<crate::models::BackupRun, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    insert_backup_run(&mut conn, backup_job_id, schedule_id, None, triggered_by, "queued", None).await
+}
+
+/// Enfileira uma execução aplicando o `overlap_policy` do job.
+///
+/// Com uma execução anterior do job ainda `queued` ou `running`:
+/// - `queue`: a nova entra na fila normalmente (os workers nunca rodam duas
+///   execuções do mesmo job ao mesmo tempo)
+/// - `skip`: a nova é gravada como `skipped`, com o motivo em `status_reason`
+/// - `cancel_previous`: as anteriores na fila são canceladas e as em
+///   andamento recebem um pedido de cancelamento (`cancel_requested_at`)
+///
+/// Enfileiramentos do mesmo job são serializados por um advisory lock, em
+/// todas as instâncias.
+///
 /// # Retorna
-/// * `Ok(CloudProvider)` - Provedor criado com sucesso
-/// * `Err(sqlx::Error)` - Erro de banco de dados
-/// 
-/// # Exemplos
-/// ```no_run
-/// use crate::models::{NewCloudProvider, CloudProviderType};
-/// 
-/// let new_provider = NewCloudProvider {
-///     name: "My Backblaze B2".to_string(),
-///     provider_type: CloudProviderType::BackblazeB2,
-///     bucket: "my-backup-bucket".to_string(),
-///     access_key: "key_id".to_string(),
-///     secret_key: "app_key".to_string(),
-///     endpoint: Some("https://s3.us-west-002.backblazeb2.com".to_string()),
-///     region: Some("us-west-002".to_string()),
-///     // ... outros campos
-/// };
-/// 
-/// let provider = create_cloud_provider(&pool, &new_provider).await?;
-/// ```
-pub async fn create_cloud_provider(
-    pool: &PgPool, 
-    new_provider: &NewCloudProvider
-) -> Result<CloudProvider, sqlx::Error> {
-    let provider_type_str = match new_provider.provider_type {
-        CloudProviderType::BackblazeB2 => "backblaze_b2",
-        CloudProviderType::IdriveE2 => "idrive_e2",
-        CloudProviderType::Wasabi => "wasabi",
-        CloudProviderType::Scaleway => "scaleway",
-    };
+/// * A nova execução e as execuções em andamento que devem ser interrompidas
+pub async fn enqueue_backup_run(
+    pool: &PgPool,
+    backup_job_id: uuid::Uuid,
+    schedule_id: Option<uuid::Uuid>,
+    triggered_by: &str,
+) -> Result<(crate::models::BackupRun, Vec<uuid::Uuid>), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    lock_job_enqueues(&mut tx, backup_job_id).await?;
+    let enqueued = apply_overlap_policy(&mut tx, backup_job_id, schedule_id, None, triggered_by).await?;
+    tx.commit().await?;
+    Ok(enqueued)
+}
 
-    // Se this provider deve ser default, remove o default atual
-    if new_provider.is_default.unwrap_or(false) {
-        sqlx::query!(
-            "UPDATE cloud_providers SET is_default = false WHERE is_default = true AND is_active = true"
-        )
-        .execute(pool)
-        .await?;
+/// Enfileira a execução de um disparo do cron de um schedule, como
+/// `enqueue_backup_run`.
+///
+/// Todas as instâncias registram os mesmos schedules, então cada disparo
+/// chega uma vez por instância; só o primeiro enfileiramento de cada
+/// `(schedule_id, scheduled_for)` vale. O lock do job serializa a checagem
+/// e o índice único em `backup_runs` a garante.
+///
+/// # Retorna
+/// * `None` se o disparo já foi enfileirado (por esta ou outra instância)
+pub async fn enqueue_scheduled_backup_run(
+    pool: &PgPool,
+    backup_job_id: uuid::Uuid,
+    schedule_id: uuid::Uuid,
+    scheduled_for: DateTime<Utc>,
+) -> Result<Option<(crate::models::BackupRun, Vec<uuid::Uuid>)>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    lock_job_enqueues(&mut tx, backup_job_id).await?;
+
+    let already_enqueued = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM backup_runs WHERE schedule_id = $1 AND scheduled_for = $2) as "exists!""#,
+        schedule_id,
+        scheduled_for
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if already_enqueued {
+        return Ok(None);
     }
 
-    let row = sqlx::query!(
+    let enqueued =
+        apply_overlap_policy(&mut tx, backup_job_id, Some(schedule_id), Some(scheduled_for), "scheduler").await?;
+    tx.commit().await?;
+    Ok(Some(enqueued))
+}
+
+/// Serializa os enfileiramentos do job, em todas as instâncias, até o fim da transação
+async fn lock_job_enqueues(conn: &mut sqlx::PgConnection, backup_job_id: uuid::Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1, hashtext($2))")
+        .bind(ENQUEUE_LOCKS)
+        .bind(backup_job_id.to_string())
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Grava a nova execução conforme o `overlap_policy` do job (ver `enqueue_backup_run`)
+async fn apply_overlap_policy(
+    conn: &mut sqlx::PgConnection,
+    backup_job_id: uuid::Uuid,
+    schedule_id: Option<uuid::Uuid>,
+    scheduled_for: Option<DateTime<Utc>>,
+    triggered_by: &str,
+) -> Result<(crate::models::BackupRun, Vec<uuid::Uuid>), sqlx::Error> {
+    let policy = sqlx::query_scalar!(
+        r#"SELECT overlap_policy::text as "overlap_policy!: OverlapPolicy" FROM backup_jobs WHERE id = $1"#,
+        backup_job_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .unwrap_or_default();
+
+    let pending = sqlx::query!(
         r#"
-        INSERT INTO cloud_providers (
-            name, provider_type, endpoint, region, bucket, path_prefix,
-            access_key, secret_key, b2_account_id, b2_application_key, 
-            use_b2_native_api, is_default
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
-        RETURNING id, name, provider_type, endpoint, region, bucket, path_prefix,
-                  access_key, secret_key, b2_account_id, b2_application_key,
-                  use_b2_native_api, is_active, is_default, test_connectivity_at,
-                  test_connectivity_status, test_connectivity_message,
-                  total_storage_bytes, total_egress_bytes, last_sync_at,
-                  created_at, updated_at
+        SELECT id, status FROM backup_runs
+        WHERE backup_job_id = $1 AND status IN ('queued', 'running')
+        ORDER BY queued_at
         "#,
-        new_provider.name,
-        provider_type_str,
-        new_provider.endpoint,
-        new_provider.region,
-        new_provider.bucket,
-        new_provider.path_prefix,
-        new_provider.access_key,
-        new_provider.secret_key,
-        new_provider.b2_account_id,
-        new_provider.b2_application_key,
-        new_provider.use_b2_native_api.unwrap_or(false),
-        new_provider.is_default.unwrap_or(false)
+        backup_job_id
     )
-    .fetch_one(pool)
+    .fetch_all(&mut *conn)
     .await?;
 
-    Ok(CloudProvider {
-        id: row.id,
-        name: row.name,
-        provider_type: row.provider_type,
-        endpoint: row.endpoint,
-        region: row.region,
-        bucket: row.bucket,
-        path_prefix: row.path_prefix,
-        access_key: row.access_key,
-        secret_key: row.secret_key,
-        b2_account_id: row.b2_account_id,
-        b2_application_key: row.b2_application_key,
-        use_b2_native_api: row.use_b2_native_api.unwrap_or(false),
-        is_active: row.is_active.unwrap_or(true),
-        is_default: row.is_default.unwrap_or(false),
-        test_connectivity_at: row.test_connectivity_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
-        test_connectivity_status: row.test_connectivity_status,
-        test_connectivity_message: row.test_connectivity_message,
-        total_storage_bytes: row.total_storage_bytes.unwrap_or(0),
-        total_egress_bytes: row.total_egress_bytes.unwrap_or(0),
-        last_sync_at: row.last_sync_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
+    let mut superseded = Vec::new();
+    let run = match (policy, pending.first()) {
+        (OverlapPolicy::Skip, Some(previous)) => {
+            let reason = format!("Run {} of this job is still {}", previous.id, previous.status);
+            insert_backup_run(conn, backup_job_id, schedule_id, scheduled_for, triggered_by, "skipped", Some(&reason)).await?
+        }
+        (OverlapPolicy::CancelPrevious, Some(_)) => {
+            let run = insert_backup_run(conn, backup_job_id, schedule_id, scheduled_for, triggered_by, "queued", None).await?;
+            let reason = format!("Superseded by run {}", run.id);
+
+            sqlx::query!(
+                r#"
+                UPDATE backup_runs
+                SET status = 'cancelled', status_reason = $2, finished_at = NOW()
+                WHERE backup_job_id = $1 AND status = 'queued' AND id <> $3
+                "#,
+                backup_job_id,
+                reason,
+                run.id
+            )
+            .execute(&mut *conn)
+            .await?;
+
+            superseded = sqlx::query_scalar!(
+                r#"
+                UPDATE backup_runs
+                SET cancel_requested_at = NOW(), status_reason = $2
+                WHERE backup_job_id = $1 AND status = 'running'
+                RETURNING id
+                "#,
+                backup_job_id,
+                reason
+            )
+            .fetch_all(&mut *conn)
+            .await?;
+
+            run
+        }
+        _ => insert_backup_run(conn, backup_job_id, schedule_id, scheduled_for, triggered_by, "queued", None).await?,
+    };
+
+    Ok((run, superseded))
+}
+
+/// Insere uma execução; `skipped` já nasce encerrada
+async fn insert_backup_run(
+    conn: &mut sqlx::PgConnection,
+    backup_job_id: uuid::Uuid,
+    schedule_id: Option<uuid::Uuid>,
+    scheduled_for: Option<DateTime<Utc>>,
+    triggered_by: &str,
+    status: &str,
+    status_reason: Option<&str>,
+) -> Result<crate::models::BackupRun, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::BackupRun,
+        r#"
+        INSERT INTO backup_runs (backup_job_id, schedule_id, scheduled_for, triggered_by, status, status_reason, finished_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CASE WHEN $7 THEN NOW() END)
+        RETURNING id, backup_job_id, schedule_id, scheduled_for, triggered_by, status, status_reason,
+                  destinations_total, destinations_completed, destinations_failed,
+                  files_transferred, bytes_transferred, error_message,
+                  queued_at, started_at, finished_at, cancel_requested_at
+        "#,
+        backup_job_id,
+        schedule_id,
+        scheduled_for,
+        triggered_by,
+        status,
+        status_reason,
+        status == "skipped"
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// Reserva a execução mais antiga da fila e a marca como `running`.
+///
+/// Só são consideradas execuções de jobs sem outra execução em andamento.
+/// A reserva pega, na própria `conn`, o advisory lock do job, que deve ser
+/// mantido até o fim do backup: é ele que impede execuções sobrepostas em
+/// outras instâncias e que diferencia uma execução viva de uma órfã.
+/// `FOR UPDATE SKIP LOCKED` garante que dois workers nunca peguem a mesma
+/// execução.
+///
+/// # Argumentos
+/// * `conn` - Conexão dedicada do worker (ver `run_locks::LockSession`)
+///
+/// # Retorna
+/// * `Ok(None)` - Nenhuma execução pode começar agora
+pub async fn claim_next_backup_run(
+    conn: &mut sqlx::PgConnection,
+) -> Result<Option<crate::models::BackupRun>, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::BackupRun,
+        r#"
+        UPDATE backup_runs
+        SET status = 'running', started_at = NOW()
+        WHERE id = (
+            SELECT r.id FROM backup_runs r
+            WHERE r.status = 'queued'
+              AND NOT EXISTS (
+                  SELECT 1 FROM backup_runs o
+                  WHERE o.backup_job_id = r.backup_job_id AND o.status = 'running'
+              )
+            ORDER BY r.queued_at
+            FOR UPDATE OF r SKIP LOCKED
+            LIMIT 1
+        )
+        AND pg_try_advisory_lock($1, hashtext(backup_job_id::text))
+        RETURNING id, backup_job_id, schedule_id, scheduled_for, triggered_by, status, status_reason,
+                  destinations_total, destinations_completed, destinations_failed,
+                  files_transferred, bytes_transferred, error_message,
+                  queued_at, started_at, finished_at, cancel_requested_at
+        "#,
+        JOB_LOCKS
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+/// Registra quantas transferências (origem -> destino) a execução terá
+pub async fn set_backup_run_destinations(
+    pool: &PgPool,
+    run_id: uuid::Uuid,
+    destinations_total: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE backup_runs SET destinations_total = $1 WHERE id = $2",
+        destinations_total,
+        run_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Soma uma transferência concluída (ou falha) ao progresso da execução
+pub async fn record_backup_run_destination(
+    pool: &PgPool,
+    run_id: uuid::Uuid,
+    succeeded: bool,
+    files_transferred: i64,
+    bytes_transferred: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE backup_runs
+        SET destinations_completed = destinations_completed + CASE WHEN $1 THEN 1 ELSE 0 END,
+            destinations_failed = destinations_failed + CASE WHEN $1 THEN 0 ELSE 1 END,
+            files_transferred = files_transferred + $2,
+            bytes_transferred = bytes_transferred + $3
+        WHERE id = $4
+        "#,
+        succeeded,
+        files_transferred,
+        bytes_transferred,
+        run_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Encerra uma execução com o status final (`succeeded`, `failed`, `cancelled`)
+pub async fn finish_backup_run(
+    pool: &PgPool,
+    run_id: uuid::Uuid,
+    status: &str,
+    error_message: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE backup_runs
+        SET status = $1, error_message = $2, finished_at = NOW()
+        WHERE id = $3
+        "#,
+        status,
+        error_message,
+        run_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Cancela uma execução que ainda está na fila.
+///
+/// # Retorna
+/// * A execução cancelada, ou `None` se ela não existe ou já saiu da fila
+pub async fn cancel_queued_backup_run(
+    pool: &PgPool,
+    run_id: uuid::Uuid,
+    reason: &str,
+) -> Result<Option<crate::models::BackupRun>, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::BackupRun,
+        r#"
+        UPDATE backup_runs
+        SET status = 'cancelled', status_reason = $2, finished_at = NOW()
+        WHERE id = $1 AND status = 'queued'
+        RETURNING id, backup_job_id, schedule_id, scheduled_for, triggered_by, status, status_reason,
+                  destinations_total, destinations_completed, destinations_failed,
+                  files_transferred, bytes_transferred, error_message,
+                  queued_at, started_at, finished_at, cancel_requested_at
+        "#,
+        run_id,
+        reason
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Pede o cancelamento de uma execução em andamento, em qualquer instância.
+/// O worker que a executa consulta `cancel_requested_at` periodicamente.
+///
+/// # Retorna
+/// * `false` se a execução não está `running`
+pub async fn request_backup_run_cancel(
+    pool: &PgPool,
+    run_id: uuid::Uuid,
+    reason: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE backup_runs
+        SET cancel_requested_at = NOW(), status_reason = $2
+        WHERE id = $1 AND status = 'running'
+        "#,
+        run_id,
+        reason
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Indica se foi pedido o cancelamento da execução
+pub async fn is_backup_run_cancel_requested(pool: &PgPool, run_id: uuid::Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM backup_runs WHERE id = $1 AND cancel_requested_at IS NOT NULL) AS "exists!""#,
+        run_id
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Marca como falhas as execuções `running` órfãs, cujo processo parou.
+///
+/// Uma execução viva mantém o advisory lock do job (ver
+/// `claim_next_backup_run`); se o lock está livre, ninguém a executa. Isso
+/// vale com várias instâncias: execuções de outra instância em andamento
+/// não são afetadas. Execuções ainda `queued` continuam na fila.
+///
+/// # Retorna
+/// * Quantidade de execuções interrompidas
+pub async fn fail_interrupted_backup_runs(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE backup_runs
+        SET status = 'failed', error_message = 'Interrupted: b2cli stopped while the run was in progress', finished_at = NOW()
+        WHERE status = 'running'
+          AND pg_try_advisory_xact_lock($1, hashtext(backup_job_id::text))
+        "#,
+        JOB_LOCKS
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Indica se o job tem uma execução na fila ou em andamento
+pub async fn has_pending_backup_run(pool: &PgPool, backup_job_id: uuid::Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM backup_runs WHERE backup_job_id = $1 AND status IN ('queued', 'running')) AS "exists!""#,
+        backup_job_id
+    )
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_backup_run_by_id(
+    pool: &PgPool,
+    run_id: uuid::Uuid,
+) -> Result<Option<crate::models::BackupRun>, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::BackupRun,
+        r#"
+        SELECT id, backup_job_id, schedule_id, scheduled_for, triggered_by, status, status_reason,
+               destinations_total, destinations_completed, destinations_failed,
+               files_transferred, bytes_transferred, error_message,
+               queued_at, started_at, finished_at, cancel_requested_at
+        FROM backup_runs
+        WHERE id = $1
+        "#,
+        run_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Execuções de um job, das mais recentes para as mais antigas.
+///
+/// # Argumentos
+/// * `status` - Filtra por status (ex.: `skipped`)
+/// * `limit` - Quantidade máxima de execuções
+pub async fn list_backup_runs(
+    pool: &PgPool,
+    backup_job_id: uuid::Uuid,
+    status: Option<&str>,
+    limit: i64,
+) -> Result<Vec<crate::models::BackupRun>, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::BackupRun,
+        r#"
+        SELECT id, backup_job_id, schedule_id, scheduled_for, triggered_by, status, status_reason,
+               destinations_total, destinations_completed, destinations_failed,
+               files_transferred, bytes_transferred, error_message,
+               queued_at, started_at, finished_at, cancel_requested_at
+        FROM backup_runs
+        WHERE backup_job_id = $1 AND ($2::TEXT IS NULL OR status = $2)
+        ORDER BY queued_at DESC
+        LIMIT $3
+        "#,
+        backup_job_id,
+        status,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Logs das transferências de uma execução, na ordem em que começaram
+pub async fn list_backup_run_execution_logs(
+    pool: &PgPool,
+    run_id: uuid::Uuid,
+) -> Result<Vec<crate::models::BackupExecutionLog>, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::BackupExecutionLog,
+        r#"
+        SELECT id, backup_job_id, schedule_id, backup_run_id, started_at, completed_at, status,
+               rclone_command, source_path, destination_path, rclone_config,
+               files_transferred, files_checked, files_deleted, bytes_transferred,
+               transfer_rate_mbps, duration_seconds, error_count, retry_count,
+               error_message, rclone_stdout, rclone_stderr, rclone_log_file_path,
+               triggered_by, created_at, updated_at
+        FROM backup_execution_logs
+        WHERE backup_run_id = $1
+        ORDER BY started_at
+        "#,
+        run_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// ========================================
+// CLOUD PROVIDERS FUNCTIONS
+// ========================================
+
+/// Cria um novo provedor de armazenamento cloud.
+/// 
+/// Insere configurações de um provedor (Backblaze B2, IDrive e2, etc.)
+/// no banco de dados. Se `is_default` for true, remove o padrão atual.
+/// 
+/// # Argumentos
+/// * `pool` - Pool de conexão PostgreSQL
+/// * `new_provider` - Dados do novo provedor
+/// 
+/// # Retorna
+/// * `Ok(CloudProvider)` - Provedor criado com sucesso
+/// * `Err(sqlx::Error)` - Erro de banco de dados
+/// 
+/// # Exemplos
+/// ```no_run
+/// use crate::models::{NewCloudProvider, CloudProviderType};
+/// 
+/// let new_provider = NewCloudProvider {
+///     name: "My Backblaze B2".to_string(),
+///     provider_type: CloudProviderType::BackblazeB2,
+///     bucket: "my-backup-bucket".to_string(),
+///     access_key: "key_id".to_string(),
+///     secret_key: "app_key".to_string(),
+///     endpoint: Some("https://s3.us-west-002.backblazeb2.com".to_string()),
+///     region: Some("us-west-002".to_string()),
+///     // ... outros campos
+/// };
+/// 
+/// let provider = create_cloud_provider(&pool, &new_provider).await?;
+/// ```
+pub async fn create_cloud_provider(
+    pool: &PgPool, 
+    new_provider: &NewCloudProvider
+) -> Result<CloudProvider, sqlx::Error> {
+    let provider_type_str = match new_provider.provider_type {
+        CloudProviderType::BackblazeB2 => "backblaze_b2",
+        CloudProviderType::IdriveE2 => "idrive_e2",
+        CloudProviderType::Wasabi => "wasabi",
+        CloudProviderType::Scaleway => "scaleway",
+    };
+
+    // Se this provider deve ser default, remove o default atual
+    if new_provider.is_default.unwrap_or(false) {
+        sqlx::query!(
+            "UPDATE cloud_providers SET is_default = false WHERE is_default = true AND is_active = true"
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    let access_key = seal_credential(&new_provider.access_key).await?;
+    let secret_key = seal_credential(&new_provider.secret_key).await?;
+    let b2_application_key = seal_optional_credential(new_provider.b2_application_key.as_deref()).await?;
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO cloud_providers (
+            name, provider_type, endpoint, region, bucket, path_prefix,
+            access_key, secret_key, b2_account_id, b2_application_key, 
+            use_b2_native_api, is_default
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING id, name, provider_type, endpoint, region, bucket, path_prefix,
+                  access_key, secret_key, b2_account_id, b2_application_key,
+                  use_b2_native_api, is_active, is_default, test_connectivity_at,
+                  test_connectivity_status, test_connectivity_message,
+                  total_storage_bytes, total_egress_bytes, last_sync_at,
+                  created_at, updated_at
+        "#,
+        new_provider.name,
+        provider_type_str,
+        new_provider.endpoint,
+        new_provider.region,
+        new_provider.bucket,
+        new_provider.path_prefix,
+        access_key,
+        secret_key,
+        new_provider.b2_account_id,
+        b2_application_key,
+        new_provider.use_b2_native_api.unwrap_or(false),
+        new_provider.is_default.unwrap_or(false)
+    )
+    .fetch_one(pool)
+    .await?;
+
+    open_provider_credentials(CloudProvider {
+        id: row.id,
+        name: row.name,
+        provider_type: row.provider_type,
+        endpoint: row.endpoint,
+        region: row.region,
+        bucket: row.bucket,
+        path_prefix: row.path_prefix,
+        access_key: row.access_key,
+        secret_key: row.secret_key,
+        b2_account_id: row.b2_account_id,
+        b2_application_key: row.b2_application_key,
+        use_b2_native_api: row.use_b2_native_api.unwrap_or(false),
+        is_active: row.is_active.unwrap_or(true),
+        is_default: row.is_default.unwrap_or(false),
+        test_connectivity_at: row.test_connectivity_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
+        test_connectivity_status: row.test_connectivity_status,
+        test_connectivity_message: row.test_connectivity_message,
+        total_storage_bytes: row.total_storage_bytes.unwrap_or(0),
+        total_egress_bytes: row.total_egress_bytes.unwrap_or(0),
+        last_sync_at: row.last_sync_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
         created_at: DateTime::from_naive_utc_and_offset(row.created_at, Utc),
         updated_at: DateTime::from_naive_utc_and_offset(row.updated_at, Utc),
     })
+    .await
 }
 
 /// Lista todos os provedores cloud ativos.
@@ -862,7 +1719,8 @@ pub async fn list_cloud_providers(pool: &PgPool) -> Result<Vec<CloudProvider>, s
     .fetch_all(pool)
     .await?;
 
-    let providers = rows.into_iter().map(|row| CloudProvider {
+    let mut providers = Vec::with_capacity(rows.len());
+    for provider in rows.into_iter().map(|row| CloudProvider {
         id: row.id,
         name: row.name,
         provider_type: row.provider_type,
@@ -885,7 +1743,9 @@ pub async fn list_cloud_providers(pool: &PgPool) -> Result<Vec<CloudProvider>, s
         last_sync_at: row.last_sync_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
         created_at: DateTime::from_naive_utc_and_offset(row.created_at, Utc),
         updated_at: DateTime::from_naive_utc_and_offset(row.updated_at, Utc),
-    }).collect();
+    }) {
+        providers.push(open_provider_credentials(provider).await?);
+    }
 
     Ok(providers)
 }
@@ -921,7 +1781,7 @@ pub async fn get_cloud_provider_by_id(
     .await?;
 
     if let Some(row) = row {
-        Ok(Some(CloudProvider {
+        Ok(Some(open_provider_credentials(CloudProvider {
             id: row.id,
             name: row.name,
             provider_type: row.provider_type,
@@ -944,7 +1804,7 @@ pub async fn get_cloud_provider_by_id(
             last_sync_at: row.last_sync_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
             created_at: DateTime::from_naive_utc_and_offset(row.created_at, Utc),
             updated_at: DateTime::from_naive_utc_and_offset(row.updated_at, Utc),
-        }))
+        }).await?))
     } else {
         Ok(None)
     }
@@ -979,6 +1839,10 @@ pub async fn update_cloud_provider(
     // Buscar dados atuais para fazer merge
     let current = get_cloud_provider_by_id(pool, id).await?;
     if let Some(current) = current {
+        let access_key = seal_optional_credential(update_data.access_key.as_deref()).await?;
+        let secret_key = seal_optional_credential(update_data.secret_key.as_deref()).await?;
+        let b2_application_key = seal_optional_credential(update_data.b2_application_key.as_deref()).await?;
+
         let row = sqlx::query!(
             r#"
             UPDATE cloud_providers 
@@ -1004,10 +1868,10 @@ pub async fn update_cloud_provider(
             update_data.region.as_ref().or(current.region.as_ref()),
             update_data.bucket.as_ref().unwrap_or(&current.bucket),
             update_data.path_prefix.as_ref().or(current.path_prefix.as_ref()),
-            update_data.access_key.as_ref(),
-            update_data.secret_key.as_ref(),
+            access_key,
+            secret_key,
             update_data.b2_account_id.as_ref(),
-            update_data.b2_application_key.as_ref(),
+            b2_application_key,
             update_data.use_b2_native_api.unwrap_or(current.use_b2_native_api),
             update_data.is_active.unwrap_or(current.is_active),
             update_data.is_default.unwrap_or(current.is_default),
@@ -1017,7 +1881,7 @@ pub async fn update_cloud_provider(
         .await?;
 
         if let Some(row) = row {
-            Ok(Some(CloudProvider {
+            Ok(Some(open_provider_credentials(CloudProvider {
                 id: row.id,
                 name: row.name,
                 provider_type: row.provider_type,
@@ -1040,7 +1904,7 @@ pub async fn update_cloud_provider(
                 last_sync_at: row.last_sync_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
                 created_at: DateTime::from_naive_utc_and_offset(row.created_at, Utc),
                 updated_at: DateTime::from_naive_utc_and_offset(row.updated_at, Utc),
-            }))
+            }).await?))
         } else {
             Ok(None)
         }
@@ -1049,6 +1913,27 @@ pub async fn update_cloud_provider(
     }
 }
 
+/// Conta backup jobs ativos cujos mapeamentos referenciam o provedor
+/// (`provider://<id>/...`).
+pub async fn count_backup_jobs_using_provider(
+    pool: &PgPool,
+    id: uuid::Uuid,
+) -> Result<i64, sqlx::Error> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM backup_jobs
+        WHERE is_active = true
+          AND strpos(mappings::text, $1) > 0
+        "#,
+        format!("{}{}", crate::remotes::PROVIDER_SCHEME, id)
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
 /// Remove um provedor cloud (soft delete).
 /// 
 /// # Argumentos
@@ -1077,88 +1962,25 @@ pub async fn delete_cloud_provider(
     Ok(result.rows_affected() > 0)
 }
 
-/// Testa conectividade de um provedor cloud.
-/// 
-/// Executa validação de credenciais baseada no tipo de provedor e campos obrigatórios.
-/// Atualiza o status de conectividade no banco de dados.
-/// 
-/// Implementa validação por tipo:
-/// - **Backblaze B2 Nativo**: Requer `b2_account_id` e `b2_application_key`
-/// - **Backblaze B2 S3**: Requer `access_key`, `secret_key` e `endpoint`
-/// - **IDrive e2**: Requer `access_key`, `secret_key` e `endpoint`
-/// - **Wasabi/Scaleway**: Requer `access_key`, `secret_key` e `region`
-/// 
-/// # Argumentos  
+/// Registra o resultado de um teste de conectividade no provedor.
+///
+/// O teste em si é feito por `connectivity::test_provider`; aqui só são
+/// atualizados `test_connectivity_at`, `test_connectivity_status` e
+/// `test_connectivity_message`.
+///
+/// # Argumentos
 /// * `pool` - Pool de conexão PostgreSQL
 /// * `id` - UUID do provedor
-/// 
+/// * `result` - Resultado do teste
+///
 /// # Retorna
-/// * `Ok(ConnectivityTestResult)` - Resultado da validação
+/// * `Ok(())` - Status atualizado
 /// * `Err(sqlx::Error)` - Erro de banco de dados
-/// 
-/// # Exemplo
-/// ```rust
-/// let result = test_cloud_provider_connectivity(&pool, provider_id).await?;
-/// if result.success {
-///     println!("Provider configured correctly!");
-/// }
-/// ```
-pub async fn test_cloud_provider_connectivity(
+pub async fn record_cloud_provider_connectivity(
     pool: &PgPool,
-    id: uuid::Uuid
-) -> Result<ConnectivityTestResult, sqlx::Error> {
-    let now = Utc::now();
-    
-    // Por enquanto, simula teste baseado na existência dos campos obrigatórios
-    let provider = match get_cloud_provider_by_id(pool, id).await? {
-        Some(p) => p,
-        None => {
-            return Ok(ConnectivityTestResult {
-                success: false,
-                status: ConnectivityStatus::Failed,
-                message: "Provider not found".to_string(),
-                tested_at: now,
-                details: Some(serde_json::json!({"error": "provider_not_found"})),
-            });
-        }
-    };
-    
-    // Validar campos obrigatórios baseado no tipo
-    let (success, status, message) = match provider.provider_type.as_str() {
-        "backblaze_b2" => {
-            if provider.use_b2_native_api {
-                if provider.b2_account_id.is_some() && provider.b2_application_key.is_some() {
-                    (true, ConnectivityStatus::Success, "B2 native API credentials validated".to_string())
-                } else {
-                    (false, ConnectivityStatus::Failed, "Missing B2 native API credentials (account_id or application_key)".to_string())
-                }
-            } else {
-                if !provider.access_key.is_empty() && !provider.secret_key.is_empty() && provider.endpoint.is_some() {
-                    (true, ConnectivityStatus::Success, "B2 S3-compatible credentials validated".to_string())
-                } else {
-                    (false, ConnectivityStatus::Failed, "Missing B2 S3 credentials (access_key, secret_key, or endpoint)".to_string())
-                }
-            }
-        }
-        "idrive_e2" => {
-            if !provider.access_key.is_empty() && !provider.secret_key.is_empty() && provider.endpoint.is_some() {
-                (true, ConnectivityStatus::Success, "IDrive e2 credentials validated".to_string())
-            } else {
-                (false, ConnectivityStatus::Failed, "Missing IDrive e2 credentials (access_key, secret_key, or endpoint)".to_string())
-            }
-        }
-        "wasabi" | "scaleway" => {
-            if !provider.access_key.is_empty() && !provider.secret_key.is_empty() && provider.region.is_some() {
-                (true, ConnectivityStatus::Success, format!("{} credentials validated", provider.provider_type))
-            } else {  
-                (false, ConnectivityStatus::Failed, format!("Missing {} credentials (access_key, secret_key, or region)", provider.provider_type))
-            }
-        }
-        _ => {
-            (false, ConnectivityStatus::Failed, format!("Unsupported provider type: {}", provider.provider_type))
-        }
-    };
-
+    id: uuid::Uuid,
+    result: &ConnectivityTestResult,
+) -> Result<(), sqlx::Error> {
     sqlx::query!(
         r#"
         UPDATE cloud_providers 
@@ -1168,26 +1990,683 @@ pub async fn test_cloud_provider_connectivity(
             updated_at = NOW()
         WHERE id = $4
         "#,
-        now.naive_utc(),
-        if success { "success" } else { "failed" },
-        message,
+        result.tested_at.naive_utc(),
+        if result.success { "success" } else { "failed" },
+        result.message,
         id
     )
     .execute(pool)
     .await?;
 
-    Ok(ConnectivityTestResult {
-        success,
+    Ok(())
+}
+
+// ========================================
+// PROVIDER CREDENTIAL ENCRYPTION
+// ========================================
+
+/// Criptografa uma credencial com a chave mestra antes de gravar
+async fn seal_credential(value: &str) -> Result<String, sqlx::Error> {
+    crypto::seal_credential(value).await.map_err(sqlx::Error::Encode)
+}
+
+async fn seal_optional_credential(value: Option<&str>) -> Result<Option<String>, sqlx::Error> {
+    match value {
+        Some(value) => Ok(Some(seal_credential(value).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Descriptografa as credenciais de um provedor lido do banco
+async fn open_provider_credentials(mut provider: CloudProvider) -> Result<CloudProvider, sqlx::Error> {
+    provider.access_key = crypto::open_credential(&provider.access_key)
+        .await
+        .map_err(sqlx::Error::Decode)?;
+    provider.secret_key = crypto::open_credential(&provider.secret_key)
+        .await
+        .map_err(sqlx::Error::Decode)?;
+    if let Some(key) = provider.b2_application_key.take() {
+        provider.b2_application_key = Some(crypto::open_credential(&key).await.map_err(sqlx::Error::Decode)?);
+    }
+    Ok(provider)
+}
+
+/// Busca a configuração de derivação de chave persistida.
+///
+/// # Retorna
+/// * `Ok(Some(CryptoConfig))` - Configuração gravada na primeira inicialização com senha
+/// * `Ok(None)` - Nenhuma senha mestra foi configurada ainda
+pub async fn get_crypto_settings(pool: &PgPool) -> Result<Option<CryptoConfig>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT version, algorithm, salt, argon2_memory_kib, argon2_iterations,
+               argon2_parallelism, key_check
+        FROM crypto_settings
+        WHERE id = 1
+        "#
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| CryptoConfig {
+        enabled: true,
+        salt: row.salt,
+        algorithm: row.algorithm,
+        version: row.version as u32,
+        argon2_memory_kib: row.argon2_memory_kib as u32,
+        argon2_iterations: row.argon2_iterations as u32,
+        argon2_parallelism: row.argon2_parallelism as u32,
+        key_check: Some(row.key_check),
+    }))
+}
+
+/// Grava a configuração de derivação de chave, se ainda não existir.
+///
+/// # Retorna
+/// * `Ok(true)` - Configuração gravada
+/// * `Ok(false)` - Já existia uma configuração (nada foi alterado)
+/// * `Err(sqlx::Error::Protocol)` - Configuração sem key-check
+pub async fn insert_crypto_settings(pool: &PgPool, config: &CryptoConfig) -> Result<bool, sqlx::Error> {
+    let key_check = config
+        .key_check
+        .as_deref()
+        .ok_or_else(|| sqlx::Error::Protocol("crypto settings without key check".to_string()))?;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO crypto_settings (
+            id, version, algorithm, salt, argon2_memory_kib, argon2_iterations,
+            argon2_parallelism, key_check
+        ) VALUES (1, $1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (id) DO NOTHING
+        "#,
+        config.version as i32,
+        config.algorithm,
+        config.salt,
+        config.argon2_memory_kib as i32,
+        config.argon2_iterations as i32,
+        config.argon2_parallelism as i32,
+        key_check
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Criptografa credenciais ainda em texto plano (linhas anteriores à chave mestra).
+///
+/// Idempotente: valores já criptografados não são tocados. Executado na
+/// inicialização quando há chave mestra configurada.
+///
+/// # Retorna
+/// * `Ok(n)` - Quantidade de provedores atualizados
+pub async fn encrypt_plaintext_provider_credentials(
+    pool: &PgPool,
+    crypto: &CryptoManager,
+) -> Result<u64, sqlx::Error> {
+    rewrite_provider_credentials(pool, crypto, crypto, true).await
+}
+
+/// Recriptografa as credenciais de todos os provedores (inclusive inativos)
+/// e as senhas de criptografia dos backup jobs com uma nova chave mestra,
+/// numa única transação.
+///
+/// # Argumentos
+/// * `pool` - Pool de conexão PostgreSQL
+/// * `current` - Gerenciador com a chave atual
+/// * `new_key` - Gerenciador com a nova chave
+///
+/// # Retorna
+/// * `Ok(n)` - Quantidade de registros recriptografados (provedores + backup jobs)
+/// * `Err(sqlx::Error::Decode)` - Alguma credencial não abre com a chave atual (nada é alterado)
+pub async fn rotate_provider_credentials(
+    pool: &PgPool,
+    current: &CryptoManager,
+    new_key: &CryptoManager,
+) -> Result<u64, sqlx::Error> {
+    rewrite_provider_credentials(pool, current, new_key, false).await
+}
+
+async fn rewrite_provider_credentials(
+    pool: &PgPool,
+    current: &CryptoManager,
+    target: &CryptoManager,
+    only_plaintext: bool,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, access_key, secret_key, b2_application_key
+        FROM cloud_providers
+        FOR UPDATE
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut updated = 0u64;
+    for row in rows {
+        let needs_rewrite = !only_plaintext
+            || !crypto::is_sealed(&row.access_key)
+            || !crypto::is_sealed(&row.secret_key)
+            || row.b2_application_key.as_deref().is_some_and(|k| !crypto::is_sealed(k));
+        if !needs_rewrite {
+            continue;
+        }
+
+        let access_key = recrypt(current, target, &row.access_key, only_plaintext).await?;
+        let secret_key = recrypt(current, target, &row.secret_key, only_plaintext).await?;
+        let b2_application_key = match row.b2_application_key.as_deref() {
+            Some(key) => Some(recrypt(current, target, key, only_plaintext).await?),
+            None => None,
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE cloud_providers
+            SET access_key = $2, secret_key = $3, b2_application_key = $4
+            WHERE id = $1
+            "#,
+            row.id,
+            access_key,
+            secret_key,
+            b2_application_key
+        )
+        .execute(&mut *tx)
+        .await?;
+        updated += 1;
+    }
+
+    // Senhas do rclone crypt dos backup jobs são sempre criptografadas na
+    // gravação, então só entram na rotação
+    if !only_plaintext {
+        let keys = sqlx::query!(
+            "SELECT backup_job_id, crypt_password, crypt_salt FROM backup_job_keys FOR UPDATE"
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for key in keys {
+            let password = recrypt(current, target, &key.crypt_password, false).await?;
+            let salt = recrypt(current, target, &key.crypt_salt, false).await?;
+            sqlx::query!(
+                "UPDATE backup_job_keys SET crypt_password = $2, crypt_salt = $3 WHERE backup_job_id = $1",
+                key.backup_job_id,
+                password,
+                salt
+            )
+            .execute(&mut *tx)
+            .await?;
+            updated += 1;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(updated)
+}
+
+async fn recrypt(
+    current: &CryptoManager,
+    target: &CryptoManager,
+    stored: &str,
+    only_plaintext: bool,
+) -> Result<String, sqlx::Error> {
+    if only_plaintext && crypto::is_sealed(stored) {
+        return Ok(stored.to_string());
+    }
+    let plaintext = current.open(stored).await.map_err(sqlx::Error::Decode)?;
+    target.seal(&plaintext).await.map_err(sqlx::Error::Encode)
+}
+
+/// Gera as senhas do rclone crypt de um backup job, se ainda não existirem.
+///
+/// As senhas são aleatórias e gravadas criptografadas com a chave mestra;
+/// nunca saem da máquina (vão para o rclone só por variável de ambiente).
+/// Uma vez criadas não mudam, para que backups antigos continuem legíveis.
+///
+/// Roda na mesma transação que grava `encryption` no job, para que um job
+/// criptografado nunca fique sem senhas.
+///
+/// # Retorna
+/// * `Err(sqlx::Error::Encode)` - Nenhuma chave mestra configurada
+pub async fn ensure_backup_job_crypt_keys(
+    conn: &mut sqlx::PgConnection,
+    backup_job_id: uuid::Uuid,
+) -> Result<(), sqlx::Error> {
+    let master = crypto::master().ok_or_else(|| {
+        sqlx::Error::Encode("encrypted backups require a master key (B2CLI_MASTER_KEY)".into())
+    })?;
+
+    let password = master.seal(&crypto::generate_secret()).await.map_err(sqlx::Error::Encode)?;
+    let salt = master.seal(&crypto::generate_secret()).await.map_err(sqlx::Error::Encode)?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO backup_job_keys (backup_job_id, crypt_password, crypt_salt)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (backup_job_id) DO NOTHING
+        "#,
+        backup_job_id,
+        password,
+        salt
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Busca as senhas do rclone crypt de um backup job, já descriptografadas.
+///
+/// # Retorna
+/// * `Ok(Some((password, salt)))` - Senhas do job
+/// * `Ok(None)` - O job nunca teve criptografia habilitada
+/// * `Err(sqlx::Error::Decode)` - Chave mestra ausente ou diferente da usada na gravação
+pub async fn get_backup_job_crypt_keys(
+    pool: &PgPool,
+    backup_job_id: uuid::Uuid,
+) -> Result<Option<(String, String)>, sqlx::Error> {
+    let row = sqlx::query!(
+        "SELECT crypt_password, crypt_salt FROM backup_job_keys WHERE backup_job_id = $1",
+        backup_job_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match row {
+        Some(row) => {
+            let password = crypto::open_credential(&row.crypt_password)
+                .await
+                .map_err(sqlx::Error::Decode)?;
+            let salt = crypto::open_credential(&row.crypt_salt)
+                .await
+                .map_err(sqlx::Error::Decode)?;
+            Ok(Some((password, salt)))
+        }
+        None => Ok(None),
+    }
+}
+
+// ========================================
+// RESTORE EXECUTION LOGS FUNCTIONS
+// ========================================
+
+pub async fn create_restore_execution_log(
+    pool: &PgPool,
+    log_data: &crate::models::NewRestoreExecutionLog,
+) -> Result<crate::models::RestoreExecutionLog, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::RestoreExecutionLog,
+        r#"
+        INSERT INTO restore_execution_logs (
+            backup_job_id, rclone_command, source_path, destination_path,
+            target_path, path_filters, triggered_by
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, backup_job_id, started_at, completed_at, status, rclone_command,
+                  source_path, destination_path, target_path, path_filters,
+                  files_transferred, files_checked, bytes_transferred, transfer_rate_mbps,
+                  duration_seconds, error_count, error_message, rclone_stdout, rclone_stderr,
+                  triggered_by, permissions_applied, permission_errors, created_at, updated_at
+        "#,
+        log_data.backup_job_id,
+        log_data.rclone_command,
+        log_data.source_path,
+        log_data.destination_path,
+        log_data.target_path,
+        &log_data.path_filters,
+        log_data.triggered_by.as_deref().unwrap_or("manual")
+    )
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn update_restore_execution_log_completion(
+    pool: &PgPool,
+    log_id: uuid::Uuid,
+    result: &crate::models::RcloneExecutionResult,
+) -> Result<(), sqlx::Error> {
+    let status = if result.exit_code == 0 { "completed" } else { "failed" };
+
+    sqlx::query!(
+        r#"
+        UPDATE restore_execution_logs
+        SET completed_at = NOW(),
+            status = $1,
+            files_transferred = $2,
+            files_checked = $3,
+            bytes_transferred = $4,
+            transfer_rate_mbps = $5,
+            duration_seconds = $6,
+            error_count = $7,
+            error_message = $8,
+            rclone_stdout = $9,
+            rclone_stderr = $10
+        WHERE id = $11
+        "#,
         status,
-        message,
-        tested_at: now,
-        details: Some(serde_json::json!({
-            "provider_type": provider.provider_type,
-            "bucket": provider.bucket,
-            "use_native_api": provider.use_b2_native_api,
-            "validation_only": true
-        })),
-    })
+        result.files_transferred,
+        result.files_checked,
+        result.bytes_transferred,
+        result.transfer_rate_mbps,
+        result.duration_seconds,
+        result.error_count,
+        if result.errors.is_empty() { None } else { Some(result.errors.join("; ")) },
+        result.stdout,
+        result.stderr,
+        log_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Marca um restore como falho quando o rclone nem chegou a rodar
+/// (binário ausente, diretório de destino inválido, etc.).
+pub async fn fail_restore_execution_log(
+    pool: &PgPool,
+    log_id: uuid::Uuid,
+    error_message: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE restore_execution_logs
+        SET completed_at = NOW(), status = 'failed', error_count = 1, error_message = $1
+        WHERE id = $2
+        "#,
+        error_message,
+        log_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Registra quantos arquivos restaurados tiveram dono e permissões reaplicados
+pub async fn update_restore_execution_log_permissions(
+    pool: &PgPool,
+    log_id: uuid::Uuid,
+    permissions_applied: i32,
+    permission_errors: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE restore_execution_logs
+        SET permissions_applied = $1, permission_errors = $2
+        WHERE id = $3
+        "#,
+        permissions_applied,
+        permission_errors,
+        log_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_restore_execution_log_by_id(
+    pool: &PgPool,
+    log_id: uuid::Uuid,
+) -> Result<Option<crate::models::RestoreExecutionLog>, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::RestoreExecutionLog,
+        r#"
+        SELECT id, backup_job_id, started_at, completed_at, status, rclone_command,
+               source_path, destination_path, target_path, path_filters,
+               files_transferred, files_checked, bytes_transferred, transfer_rate_mbps,
+               duration_seconds, error_count, error_message, rclone_stdout, rclone_stderr,
+               triggered_by, permissions_applied, permission_errors, created_at, updated_at
+        FROM restore_execution_logs
+        WHERE id = $1
+        "#,
+        log_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn list_restore_execution_logs(
+    pool: &PgPool,
+    backup_job_id: uuid::Uuid,
+    limit: Option<i32>,
+) -> Result<Vec<crate::models::RestoreExecutionLog>, sqlx::Error> {
+    let limit = limit.unwrap_or(50).min(200) as i64;
+
+    sqlx::query_as!(
+        crate::models::RestoreExecutionLog,
+        r#"
+        SELECT id, backup_job_id, started_at, completed_at, status, rclone_command,
+               source_path, destination_path, target_path, path_filters,
+               files_transferred, files_checked, bytes_transferred, transfer_rate_mbps,
+               duration_seconds, error_count, error_message, rclone_stdout, rclone_stderr,
+               triggered_by, permissions_applied, permission_errors, created_at, updated_at
+        FROM restore_execution_logs
+        WHERE backup_job_id = $1
+        ORDER BY started_at DESC
+        LIMIT $2
+        "#,
+        backup_job_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// ========================================
+// BACKUP VERIFICATION FUNCTIONS
+// ========================================
+
+pub async fn create_backup_verification(
+    pool: &PgPool,
+    backup_job_id: uuid::Uuid,
+    execution_log_id: Option<uuid::Uuid>,
+    source_path: &str,
+    destination_path: &str,
+    sample_size: Option<i32>,
+) -> Result<crate::models::BackupVerification, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::BackupVerification,
+        r#"
+        INSERT INTO backup_verifications (
+            backup_job_id, execution_log_id, source_path, destination_path, sample_size
+        ) VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, backup_job_id, execution_log_id, restore_log_id, source_path,
+                  destination_path, sample_size, status, files_checked, files_passed,
+                  files_failed, restorability_percent, error_message, started_at, completed_at
+        "#,
+        backup_job_id,
+        execution_log_id,
+        source_path,
+        destination_path,
+        sample_size
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Grava o resultado por arquivo e fecha a verificação com os totais.
+pub async fn complete_backup_verification(
+    pool: &PgPool,
+    verification_id: uuid::Uuid,
+    restore_log_id: Option<uuid::Uuid>,
+    files: &[crate::models::NewBackupVerificationFile],
+    restorability_percent: Option<f32>,
+) -> Result<crate::models::BackupVerification, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let catalog_ids: Vec<Option<uuid::Uuid>> = files.iter().map(|f| f.file_catalog_id).collect();
+    let paths: Vec<String> = files.iter().map(|f| f.file_path.clone()).collect();
+    let expected: Vec<String> = files.iter().map(|f| f.expected_hash.clone()).collect();
+    let actual: Vec<Option<String>> = files.iter().map(|f| f.actual_hash.clone()).collect();
+    let passed: Vec<bool> = files.iter().map(|f| f.passed).collect();
+    let errors: Vec<Option<String>> = files.iter().map(|f| f.error_message.clone()).collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO backup_verification_files (
+            verification_id, file_catalog_id, file_path, expected_hash,
+            actual_hash, passed, error_message
+        )
+        SELECT $1, * FROM UNNEST(
+            $2::uuid[], $3::text[], $4::varchar[], $5::varchar[], $6::bool[], $7::text[]
+        )
+        "#,
+        verification_id,
+        &catalog_ids as &[Option<uuid::Uuid>],
+        &paths,
+        &expected,
+        &actual as &[Option<String>],
+        &passed,
+        &errors as &[Option<String>]
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let files_passed = files.iter().filter(|f| f.passed).count() as i32;
+    let files_checked = files.len() as i32;
+
+    let verification = sqlx::query_as!(
+        crate::models::BackupVerification,
+        r#"
+        UPDATE backup_verifications
+        SET status = 'completed',
+            completed_at = NOW(),
+            restore_log_id = $2,
+            files_checked = $3,
+            files_passed = $4,
+            files_failed = $3::INTEGER - $4::INTEGER,
+            restorability_percent = $5
+        WHERE id = $1
+        RETURNING id, backup_job_id, execution_log_id, restore_log_id, source_path,
+                  destination_path, sample_size, status, files_checked, files_passed,
+                  files_failed, restorability_percent, error_message, started_at, completed_at
+        "#,
+        verification_id,
+        restore_log_id,
+        files_checked,
+        files_passed,
+        restorability_percent
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(verification)
+}
+
+/// Marca a verificação como falha (não foi possível restaurar/comparar).
+pub async fn fail_backup_verification(
+    pool: &PgPool,
+    verification_id: uuid::Uuid,
+    error_message: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE backup_verifications
+        SET status = 'failed', completed_at = NOW(), error_message = $2
+        WHERE id = $1
+        "#,
+        verification_id,
+        error_message
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn list_backup_verifications(
+    pool: &PgPool,
+    backup_job_id: uuid::Uuid,
+    limit: i64,
+) -> Result<Vec<crate::models::BackupVerification>, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::BackupVerification,
+        r#"
+        SELECT id, backup_job_id, execution_log_id, restore_log_id, source_path,
+               destination_path, sample_size, status, files_checked, files_passed,
+               files_failed, restorability_percent, error_message, started_at, completed_at
+        FROM backup_verifications
+        WHERE backup_job_id = $1
+        ORDER BY started_at DESC
+        LIMIT $2
+        "#,
+        backup_job_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn list_failed_verification_files(
+    pool: &PgPool,
+    verification_id: uuid::Uuid,
+) -> Result<Vec<crate::models::BackupVerificationFile>, sqlx::Error> {
+    sqlx::query_as!(
+        crate::models::BackupVerificationFile,
+        r#"
+        SELECT id, verification_id, file_catalog_id, file_path, expected_hash,
+               actual_hash, passed, error_message, checked_at
+        FROM backup_verification_files
+        WHERE verification_id = $1 AND passed = FALSE
+        ORDER BY file_path
+        "#,
+        verification_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Totais de restorability por job ativo, considerando apenas a verificação
+/// concluída mais recente de cada mapeamento (origem, destino).
+pub async fn list_job_restorability(
+    pool: &PgPool,
+    backup_job_id: Option<uuid::Uuid>,
+) -> Result<Vec<crate::models::JobRestorabilityScore>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        WITH latest AS (
+            SELECT DISTINCT ON (backup_job_id, source_path, destination_path)
+                   backup_job_id, files_checked, files_passed, completed_at
+            FROM backup_verifications
+            WHERE status = 'completed'
+            ORDER BY backup_job_id, source_path, destination_path, started_at DESC
+        )
+        SELECT j.id,
+               j.name,
+               COALESCE(SUM(l.files_checked), 0)::BIGINT AS "files_checked!",
+               COALESCE(SUM(l.files_passed), 0)::BIGINT AS "files_passed!",
+               MAX(l.completed_at) AS last_verified_at
+        FROM backup_jobs j
+        LEFT JOIN latest l ON l.backup_job_id = j.id
+        WHERE j.is_active = true
+          AND ($1::uuid IS NULL OR j.id = $1)
+        GROUP BY j.id, j.name
+        ORDER BY j.name
+        "#,
+        backup_job_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| crate::models::JobRestorabilityScore {
+            backup_job_id: row.id,
+            job_name: row.name,
+            files_checked: row.files_checked,
+            files_passed: row.files_passed,
+            restorability_percent: crate::verification::restorability_percent(
+                row.files_passed,
+                row.files_checked,
+            ),
+            last_verified_at: row.last_verified_at,
+        })
+        .collect())
 }
 
 #[cfg(test)]
@@ -0,0 +1,330 @@
+//! Metadados POSIX dos arquivos catalogados.
+//!
+//! O scanner grava em `file_catalog.metadata`:
+//! - `unix`: modo, dono e grupo (uid/gid e nomes), inode, device e número de
+//!   hardlinks (`UnixMetadata`)
+//! - `symlink_target`: destino de um link simbólico catalogado sem segui-lo
+//! - `xattrs`: atributos estendidos em base64, quando a configuração de scan
+//!   pede (as ACLs POSIX do Linux vêm aqui, como `system.posix_acl_*`)
+//!
+//! O restore usa `unix` para reaplicar permissões e dono (`apply`), e as
+//! auditorias de `GET /files/audit` consultam os mesmos campos.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Chave de `UnixMetadata` em `file_catalog.metadata`
+pub const UNIX_KEY: &str = "unix";
+
+/// Chave do destino de symlinks em `file_catalog.metadata`
+pub const SYMLINK_TARGET_KEY: &str = "symlink_target";
+
+/// Chave dos atributos estendidos em `file_catalog.metadata`
+pub const XATTRS_KEY: &str = "xattrs";
+
+/// Dono, permissões e identidade de um arquivo no sistema de arquivos
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnixMetadata {
+    /// `st_mode` completo (tipo + permissões)
+    pub mode: u32,
+    /// Permissões no formato do `ls` (`rwxr-x---`)
+    pub permissions: String,
+    pub uid: u32,
+    pub gid: u32,
+    /// Nome do usuário dono, se o uid existe neste sistema
+    pub owner: Option<String>,
+    /// Nome do grupo, se o gid existe neste sistema
+    pub group: Option<String>,
+    pub inode: u64,
+    pub device: u64,
+    pub nlink: u64,
+}
+
+/// Cache de nomes de usuários e grupos, resolvidos uma vez por id
+#[derive(Debug, Default)]
+pub struct OwnerNames {
+    users: Mutex<HashMap<u32, Option<String>>>,
+    groups: Mutex<HashMap<u32, Option<String>>>,
+}
+
+impl OwnerNames {
+    pub fn user(&self, uid: u32) -> Option<String> {
+        self.users.lock().unwrap().entry(uid).or_insert_with(|| lookup_user(uid)).clone()
+    }
+
+    pub fn group(&self, gid: u32) -> Option<String> {
+        self.groups.lock().unwrap().entry(gid).or_insert_with(|| lookup_group(gid)).clone()
+    }
+}
+
+/// Extrai os metadados POSIX de um `stat`/`lstat` já feito.
+///
+/// # Retorna
+/// * `None` fora de sistemas Unix
+#[cfg(unix)]
+pub fn unix_metadata(metadata: &std::fs::Metadata, names: &OwnerNames) -> Option<UnixMetadata> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(UnixMetadata {
+        mode: metadata.mode(),
+        permissions: permissions_string(metadata.mode()),
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        owner: names.user(metadata.uid()),
+        group: names.group(metadata.gid()),
+        inode: metadata.ino(),
+        device: metadata.dev(),
+        nlink: metadata.nlink(),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn unix_metadata(_metadata: &std::fs::Metadata, _names: &OwnerNames) -> Option<UnixMetadata> {
+    None
+}
+
+/// Identidade (device, inode) de um arquivo, usada para não percorrer o
+/// mesmo diretório duas vezes ao seguir symlinks
+#[cfg(unix)]
+pub fn identity(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+pub fn identity(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Objeto gravado em `file_catalog.metadata` (sem `xattrs`, lidos à parte)
+pub fn catalog_metadata(unix: Option<&UnixMetadata>, symlink_target: Option<&Path>) -> serde_json::Value {
+    let mut object = serde_json::Map::new();
+    if let Some(unix) = unix {
+        object.insert(UNIX_KEY.to_string(), serde_json::to_value(unix).unwrap_or_default());
+    }
+    if let Some(target) = symlink_target {
+        object.insert(
+            SYMLINK_TARGET_KEY.to_string(),
+            serde_json::Value::String(target.to_string_lossy().to_string()),
+        );
+    }
+    serde_json::Value::Object(object)
+}
+
+/// Permissões no formato do `ls -l`, sem o caractere de tipo
+pub fn permissions_string(mode: u32) -> String {
+    let bit = |mask: u32, c: char| if mode & mask != 0 { c } else { '-' };
+    // Execução combinada com setuid/setgid/sticky: `s`/`t`, ou `S`/`T` sem o `x`
+    let special = |exec: u32, special: u32, set: char| match (mode & exec != 0, mode & special != 0) {
+        (true, true) => set,
+        (false, true) => set.to_ascii_uppercase(),
+        (true, false) => 'x',
+        (false, false) => '-',
+    };
+
+    [
+        bit(0o400, 'r'),
+        bit(0o200, 'w'),
+        special(0o100, 0o4000, 's'),
+        bit(0o040, 'r'),
+        bit(0o020, 'w'),
+        special(0o010, 0o2000, 's'),
+        bit(0o004, 'r'),
+        bit(0o002, 'w'),
+        special(0o001, 0o1000, 't'),
+    ]
+    .iter()
+    .collect()
+}
+
+/// Reaplica dono, grupo e permissões a um arquivo restaurado.
+///
+/// Dono e permissões são tentados independentemente; o primeiro erro é
+/// retornado (trocar o dono normalmente exige root). Nada é alterado quando
+/// já confere.
+#[cfg(unix)]
+pub fn apply(path: &Path, unix: &UnixMetadata) -> std::io::Result<()> {
+    use std::os::unix::fs::{lchown, MetadataExt, PermissionsExt};
+
+    let current = std::fs::symlink_metadata(path)?;
+    let owner_result = if current.uid() != unix.uid || current.gid() != unix.gid {
+        lchown(path, Some(unix.uid), Some(unix.gid))
+    } else {
+        Ok(())
+    };
+
+    // Symlinks não têm permissões próprias no Linux; `chmod` alteraria o alvo
+    let wanted = unix.mode & 0o7777;
+    let mode_result = if !current.file_type().is_symlink() && current.mode() & 0o7777 != wanted {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(wanted))
+    } else {
+        Ok(())
+    };
+
+    owner_result.and(mode_result)
+}
+
+#[cfg(not(unix))]
+pub fn apply(_path: &Path, _unix: &UnixMetadata) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "metadados POSIX só podem ser aplicados em sistemas Unix",
+    ))
+}
+
+/// Lê os atributos estendidos de um arquivo (sem seguir symlinks), com os
+/// valores em base64.
+///
+/// Sistemas de arquivos sem suporte a xattrs resultam num mapa vazio.
+#[cfg(target_os = "linux")]
+pub fn read_xattrs(path: &Path) -> std::io::Result<BTreeMap<String, String>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let mut attributes = BTreeMap::new();
+
+    // SAFETY: buffer nulo com tamanho 0 só consulta o tamanho da lista
+    let size = unsafe { libc::llistxattr(c_path.as_ptr(), std::ptr::null_mut(), 0) };
+    if size < 0 {
+        let error = std::io::Error::last_os_error();
+        return match error.raw_os_error() {
+            Some(libc::ENOTSUP) => Ok(attributes),
+            _ => Err(error),
+        };
+    }
+    if size == 0 {
+        return Ok(attributes);
+    }
+
+    let mut names = vec![0u8; size as usize];
+    // SAFETY: `names` tem `names.len()` bytes graváveis
+    let size = unsafe { libc::llistxattr(c_path.as_ptr(), names.as_mut_ptr().cast(), names.len()) };
+    if size < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    for name in names[..size as usize].split(|byte| *byte == 0).filter(|name| !name.is_empty()) {
+        let c_name = CString::new(name)?;
+        // SAFETY: consulta de tamanho, como acima
+        let len = unsafe { libc::lgetxattr(c_path.as_ptr(), c_name.as_ptr(), std::ptr::null_mut(), 0) };
+        if len < 0 {
+            // Removido entre a listagem e a leitura
+            continue;
+        }
+        let mut value = vec![0u8; len as usize];
+        // SAFETY: `value` tem `value.len()` bytes graváveis
+        let len = unsafe {
+            libc::lgetxattr(c_path.as_ptr(), c_name.as_ptr(), value.as_mut_ptr().cast(), value.len())
+        };
+        if len < 0 {
+            continue;
+        }
+        value.truncate(len as usize);
+        attributes.insert(String::from_utf8_lossy(name).into_owned(), STANDARD.encode(&value));
+    }
+
+    Ok(attributes)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_xattrs(_path: &Path) -> std::io::Result<BTreeMap<String, String>> {
+    Ok(BTreeMap::new())
+}
+
+#[cfg(unix)]
+fn lookup_user(uid: u32) -> Option<String> {
+    let mut buffer = vec![0 as libc::c_char; 1024];
+    loop {
+        // SAFETY: `passwd` é preenchido por getpwuid_r com ponteiros para `buffer`
+        let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        let rc = unsafe { libc::getpwuid_r(uid, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+        if rc == libc::ERANGE && buffer.len() < 1 << 20 {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        if rc != 0 || result.is_null() {
+            return None;
+        }
+        // SAFETY: `pw_name` aponta para uma string terminada em nulo dentro de `buffer`
+        return Some(unsafe { std::ffi::CStr::from_ptr(entry.pw_name) }.to_string_lossy().into_owned());
+    }
+}
+
+#[cfg(unix)]
+fn lookup_group(gid: u32) -> Option<String> {
+    let mut buffer = vec![0 as libc::c_char; 1024];
+    loop {
+        // SAFETY: `group` é preenchido por getgrgid_r com ponteiros para `buffer`
+        let mut entry: libc::group = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        let rc = unsafe { libc::getgrgid_r(gid, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+        if rc == libc::ERANGE && buffer.len() < 1 << 20 {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        if rc != 0 || result.is_null() {
+            return None;
+        }
+        // SAFETY: `gr_name` aponta para uma string terminada em nulo dentro de `buffer`
+        return Some(unsafe { std::ffi::CStr::from_ptr(entry.gr_name) }.to_string_lossy().into_owned());
+    }
+}
+
+#[cfg(not(unix))]
+fn lookup_user(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn lookup_group(_gid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permissions_string() {
+        assert_eq!(permissions_string(0o100644), "rw-r--r--");
+        assert_eq!(permissions_string(0o100755), "rwxr-xr-x");
+        assert_eq!(permissions_string(0o104755), "rwsr-xr-x");
+        assert_eq!(permissions_string(0o102640), "rw-r-S---");
+        assert_eq!(permissions_string(0o041777), "rwxrwxrwt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture_and_apply_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("relatorio.txt");
+        std::fs::write(&path, "dados").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+        let names = OwnerNames::default();
+        let captured = unix_metadata(&std::fs::symlink_metadata(&path).unwrap(), &names).unwrap();
+        assert_eq!(captured.mode & 0o7777, 0o640);
+        assert_eq!(captured.permissions, "rw-r-----");
+        assert_eq!(captured.nlink, 1);
+
+        // Restaurado com outras permissões: volta ao que foi catalogado
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o666)).unwrap();
+        apply(&path, &captured).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o640);
+    }
+
+    #[test]
+    fn test_catalog_metadata_keys() {
+        let value = catalog_metadata(None, Some(Path::new("../atual")));
+        assert_eq!(value[SYMLINK_TARGET_KEY], "../atual");
+        assert!(value.get(UNIX_KEY).is_none());
+    }
+}
@@ -1,25 +1,70 @@
 //! Módulo de File Intelligence para varredura e catalogação de arquivos
 //!
 //! Este módulo fornece funcionalidades avançadas para:
-//! - Varredura recursiva de diretórios com filtros
+//! - Varredura recursiva de diretórios com filtros, em pipeline concorrente
 //! - Catalogação de arquivos com metadados completos
 //! - Detecção de duplicatas via SHA256
 //! - Estatísticas detalhadas por diretório
 //! - Integração com PostgreSQL para persistência
+//! - Atualização do índice de busca (`search`) junto com o catálogo
+//! - Extração opcional do texto de documentos para a busca por conteúdo
+//! - Detecção do tipo MIME pelo conteúdo (`mime_detect`)
+//! - Dono, permissões, xattrs e destino de symlinks (`file_metadata`)
+//! - Cancelamento de varreduras em andamento (`cancel_scan`)
 
-use async_recursion::async_recursion;
+use crate::cancel::{CancelRegistry, CancelToken};
+use crate::content_extract::{self, ContentPolicy};
+use crate::file_metadata::{self, OwnerNames, UnixMetadata};
+use crate::ignore::{IgnoreMatcher, IGNORE_FILE_NAME};
+use crate::ignore_templates;
+use crate::mime_detect::{self, MimeDetection};
+use crate::models::{MimeCategory, ScanConfigRecord, ScanSchedule};
+use crate::search;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fs::Metadata;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
 use std::time::SystemTime;
 use tokio::fs;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinSet;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// Varreduras completas em andamento neste processo, por scan job
+static RUNNING_SCANS: LazyLock<Arc<CancelRegistry>> = LazyLock::new(|| Arc::new(CancelRegistry::new()));
+
+/// Pede o cancelamento de uma varredura iniciada por `start_scan`.
+///
+/// O walker para na próxima entrada; o scan job fica `cancelled` com as
+/// estatísticas do que foi percorrido e `start_scan` retorna `ScanCancelled`.
+///
+/// # Retorna
+/// * `false` se a varredura não está em andamento neste processo
+pub fn cancel_scan(scan_job_id: Uuid) -> bool {
+    RUNNING_SCANS.cancel(scan_job_id)
+}
+
+/// Erro de `start_scan` quando a varredura foi cancelada
+#[derive(Debug)]
+pub struct ScanCancelled {
+    pub scan_job_id: Uuid,
+}
+
+impl std::fmt::Display for ScanCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Scan {} cancelled", self.scan_job_id)
+    }
+}
+
+impl std::error::Error for ScanCancelled {}
+
 /// Representa um arquivo catalogado no sistema
 /// 
 /// Esta struct contém todos os metadados de um arquivo incluindo:
@@ -66,18 +111,51 @@ pub struct DirectoryStats {
 /// Define parâmetros como:
 /// - Caminho raiz para varredura
 /// - Se deve ser recursivo
-/// - Filtros de inclusão/exclusão
+/// - Filtros de inclusão/exclusão (padrões no estilo `.gitignore`, relativos a `root_path`)
 /// - Limites de tamanho e profundidade
+/// - Limites de concorrência do pipeline (walker → workers de hash → escritor em lote)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanConfig {
     pub root_path: PathBuf,
     pub recursive: bool,
+    /// Segue links simbólicos (arquivos e diretórios). Sem isso, cada link é
+    /// catalogado como tal, com o destino em `metadata.symlink_target`
     pub follow_symlinks: bool,
     pub max_depth: Option<i32>,
+    /// Com padrões definidos, só entram arquivos que casam com um deles ou
+    /// que estão dentro de um diretório que casa (`docs/`, `src`)
     pub include_patterns: Vec<String>,
     pub exclude_patterns: Vec<String>,
+    /// Templates de exclusão (`rust`, `node`, ..., ou `auto`), aplicados antes de `exclude_patterns`
+    pub ignore_templates: Vec<String>,
     pub min_file_size: Option<i64>,
     pub max_file_size: Option<i64>,
+    /// Categorias de arquivo que este scan não cataloga. Vale só para o
+    /// catálogo: detectadas pelo conteúdo, não viram filtros do rclone (o
+    /// backup continua copiando esses arquivos), e arquivos já catalogados
+    /// não são alterados nem marcados como removidos
+    pub exclude_mime_categories: Vec<MimeCategory>,
+    /// Recalcula o hash de todos os arquivos, mesmo com tamanho e mtime iguais ao catálogo
+    pub force_rehash: bool,
+    /// Fração (0.0 a 1.0) dos arquivos inalterados que tem o hash recalculado
+    /// mesmo assim, para detectar corrupção silenciosa
+    pub paranoid_sample_rate: f64,
+    /// Extrai o texto dos arquivos aceitos por `content_extensions` para a
+    /// busca por conteúdo
+    pub index_content: bool,
+    /// Arquivos maiores que isso não têm o conteúdo extraído
+    pub content_max_bytes: i64,
+    /// Extensões (sem ponto) cujo conteúdo é extraído
+    pub content_extensions: Vec<String>,
+    /// Grava os atributos estendidos (e ACLs POSIX) em `metadata.xattrs`
+    pub capture_xattrs: bool,
+    /// Workers calculando hashes em paralelo
+    pub hash_workers: usize,
+    /// Capacidade das filas entre walker, workers e escritor; quando cheias,
+    /// o estágio anterior espera (backpressure)
+    pub queue_capacity: usize,
+    /// Arquivos gravados por transação no banco
+    pub db_batch_size: usize,
 }
 
 impl Default for ScanConfig {
@@ -91,13 +169,65 @@ impl Default for ScanConfig {
             exclude_patterns: vec![
                 "*.tmp".to_string(),
                 "*.cache".to_string(),
-                ".git/*".to_string(),
-                "node_modules/*".to_string(),
-                "target/*".to_string(),
-                "__pycache__/*".to_string(),
+                ".git/".to_string(),
+                "node_modules/".to_string(),
+                "target/".to_string(),
+                "__pycache__/".to_string(),
             ],
+            ignore_templates: vec![],
             min_file_size: None,
             max_file_size: None,
+            exclude_mime_categories: vec![],
+            force_rehash: false,
+            paranoid_sample_rate: 0.0,
+            index_content: false,
+            content_max_bytes: content_extract::DEFAULT_CONTENT_MAX_BYTES,
+            content_extensions: content_extract::default_extensions(),
+            capture_xattrs: false,
+            hash_workers: std::thread::available_parallelism()
+                .map_or(4, |n| n.get().clamp(2, 16)),
+            queue_capacity: 1024,
+            db_batch_size: 500,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// Configuração de uma linha de `scan_configs`, com os padrões do scanner
+    /// para as colunas vazias. Os limites do pipeline ficam nos padrões.
+    pub fn from_record(record: &ScanConfigRecord) -> Self {
+        Self {
+            root_path: PathBuf::from(&record.root_path),
+            recursive: record.recursive.unwrap_or(true),
+            follow_symlinks: record.follow_symlinks,
+            max_depth: record.max_depth,
+            exclude_patterns: record.exclude_patterns.clone().unwrap_or_default(),
+            ignore_templates: record.ignore_templates.clone(),
+            exclude_mime_categories: mime_detect::parse_categories(&record.exclude_mime_categories),
+            force_rehash: record.force_rehash,
+            paranoid_sample_rate: record.paranoid_sample_rate,
+            index_content: record.index_content,
+            content_max_bytes: record.content_max_bytes,
+            content_extensions: record
+                .content_extensions
+                .clone()
+                .unwrap_or_else(content_extract::default_extensions),
+            capture_xattrs: record.capture_xattrs,
+            ..Default::default()
+        }
+    }
+
+    /// Configuração de um agendamento de scan: `scan_schedules` só guarda a
+    /// pasta, `recursive`, `max_depth` e os padrões de exclusão; o resto fica
+    /// nos padrões do scanner. Scans agendados não usam templates de exclusão,
+    /// categorias MIME excluídas nem indexação de conteúdo.
+    pub fn from_schedule(schedule: &ScanSchedule) -> Self {
+        Self {
+            root_path: PathBuf::from(&schedule.root_path),
+            recursive: schedule.recursive,
+            max_depth: schedule.max_depth,
+            exclude_patterns: schedule.exclude_patterns.clone(),
+            ..Default::default()
         }
     }
 }
@@ -105,7 +235,7 @@ impl Default for ScanConfig {
 /// Scanner de arquivos principal do sistema
 /// 
 /// Responsável por:
-/// - Varrer diretórios de forma assíncrona
+/// - Varrer diretórios em pipeline (walker, workers de hash e escritor em lote)
 /// - Catalogar arquivos no banco de dados
 /// - Calcular hashes SHA256 para detecção de duplicatas
 /// - Gerar estatísticas por diretório
@@ -114,15 +244,49 @@ pub struct FileScanner {
     pool: PgPool,
     config: ScanConfig,
     scan_job_id: Option<Uuid>,
+    includes: IgnoreMatcher,
+    /// Templates + padrões da configuração, montados em `start_scan`; os
+    /// `.b2ignore` são somados durante a varredura
+    excludes: IgnoreMatcher,
+    owner_names: OwnerNames,
+    cancel: CancelToken,
+    /// Índice de busca atualizado pelo scan (o do processo, por padrão)
+    search_index: Option<Arc<search::SearchIndex>>,
 }
 
 impl FileScanner {
     /// Cria um novo scanner
     pub fn new(pool: PgPool, config: ScanConfig) -> Self {
+        let includes = IgnoreMatcher::new(&config.include_patterns);
         Self {
             pool,
             config,
             scan_job_id: None,
+            includes,
+            excludes: IgnoreMatcher::default(),
+            owner_names: OwnerNames::default(),
+            cancel: CancelToken::new(),
+            search_index: search::index(),
+        }
+    }
+
+    /// Usa `cancel` para interromper a varredura, além de `cancel_scan`
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Atualiza `index` no lugar do índice de busca do processo
+    pub fn with_search_index(mut self, index: Arc<search::SearchIndex>) -> Self {
+        self.search_index = Some(index);
+        self
+    }
+
+    /// Grava as mudanças pendentes no índice de busca, se houver um
+    async fn commit_search_index(&self) -> Result<(), search::SearchError> {
+        match self.search_index.clone() {
+            Some(index) => search::commit_index(index).await,
+            None => Ok(()),
         }
     }
 
@@ -144,6 +308,7 @@ impl FileScanner {
         self.scan_job_id = Some(job_id); // Armazenar o ID criado!
         tracing::info!(job_id = %job_id, "🔥 SCANNER: Job criado no banco");
         info!(job_id = %job_id, "🔥 SCANNER: Job criado no banco");
+        let _registration = RUNNING_SCANS.register(job_id, self.cancel.clone());
 
         // Atualizar status para running
         tracing::info!("🔥 SCANNER: Atualizando status do job para running");
@@ -157,29 +322,65 @@ impl FileScanner {
         tracing::info!(job_id = %job_id, "🔥 SCANNER: Status atualizado para running");
         info!(job_id = %job_id, "🔥 SCANNER: Status atualizado para running");
 
-        // Iniciar varredura
+        let suggested_templates = self.prepare_filters().await;
+
+        // Iniciar varredura: walker → workers de hash → escritor em lote
         debug!("🔥 SCANNER: Iniciando varredura do diretório");
         let mut stats = ScanStats::default();
-        match self.scan_directory(&self.config.root_path, 0, &mut stats).await {
-            Ok(_) => {
+        let root = (self.config.root_path.clone(), 0, Arc::new(self.excludes.clone()));
+        let directories = match self.run_pipeline(vec![], vec![root], &mut stats).await {
+            Ok(directories) => {
                 info!("🔥 SCANNER: Varredura do diretório concluída");
+                directories
             }
             Err(e) => {
                 tracing::error!(error = %e, error_debug = ?e, "🔥 SCANNER: Erro durante varredura");
                 return Err(e);
             }
+        };
+
+        let cancelled = self.cancel.is_cancelled();
+        if cancelled {
+            // Varredura parcial: estatísticas de diretório incompletas e nada
+            // pode ser dado como removido
+            info!(job_id = %job_id, files = stats.files_scanned, "Varredura cancelada");
+        } else {
+            for directory in &directories {
+                self.save_directory_stats(&directory.stats, directory.depth).await?;
+            }
+
+            // Só depois de uma varredura completa e sem erros: um arquivo que não
+            // pôde ser lido também não é visto, e não pode ser dado como removido
+            if stats.errors_count > 0 {
+                warn!(
+                    job_id = %job_id,
+                    errors = stats.errors_count,
+                    "Varredura com erros, nenhum arquivo marcado como removido"
+                );
+            } else {
+                stats.files_deleted = self.mark_deleted_files(job_id, &directories).await?;
+            }
+        }
+
+        if let Err(e) = self.commit_search_index().await {
+            warn!(job_id = %job_id, error = %e, "Falha ao gravar o índice de busca");
         }
 
         // Atualizar job com estatísticas finais
         sqlx::query!(
             r#"
             UPDATE scan_jobs 
-            SET status = 'completed',
+            SET status = $11,
                 completed_at = CURRENT_TIMESTAMP,
                 files_scanned = $2,
                 directories_scanned = $3,
                 total_size_bytes = $4,
                 errors_count = $5,
+                scan_output = $6,
+                files_hashed = $7,
+                files_hash_skipped = $8,
+                hash_mismatches = $9,
+                files_deleted = $10,
                 duration_seconds = EXTRACT(EPOCH FROM (CURRENT_TIMESTAMP - started_at))::INTEGER
             WHERE id = $1
             "#,
@@ -187,14 +388,34 @@ impl FileScanner {
             stats.files_scanned,
             stats.directories_scanned,
             stats.total_size,
-            stats.errors_count
+            stats.errors_count,
+            serde_json::json!({
+                "skipped_files": stats.skipped_files,
+                "skipped_directories": stats.skipped_directories,
+                "ignore_templates": self.config.ignore_templates,
+                "suggested_ignore_templates": suggested_templates,
+            }),
+            stats.files_hashed,
+            stats.files_hash_skipped,
+            stats.hash_mismatches,
+            stats.files_deleted,
+            if cancelled { "cancelled" } else { "completed" }
         )
         .execute(&self.pool)
         .await?;
 
+        if cancelled {
+            return Err(Box::new(ScanCancelled { scan_job_id: job_id }));
+        }
+
         info!(
             files = stats.files_scanned,
             directories = stats.directories_scanned,
+            skipped_files = stats.skipped_files,
+            skipped_directories = stats.skipped_directories,
+            hashed = stats.files_hashed,
+            hash_skipped = stats.files_hash_skipped,
+            deleted = stats.files_deleted,
             size_mb = stats.total_size / 1_048_576,
             "Varredura concluída"
         );
@@ -202,122 +423,354 @@ impl FileScanner {
         Ok(job_id)
     }
 
-    /// Varre um diretório recursivamente
-    #[async_recursion]
-    async fn scan_directory(
+    /// Monta os filtros de exclusão da raiz. Templates (inclusive `auto`)
+    /// dependem do conteúdo da raiz, por isso só são resolvidos aqui.
+    ///
+    /// # Retorna
+    /// * Templates detectados na raiz que a configuração ainda não aplica
+    async fn prepare_filters(&mut self) -> Vec<String> {
+        self.excludes = ignore_templates::base_matcher(
+            &self.config.root_path,
+            &self.config.ignore_templates,
+            &self.config.exclude_patterns,
+        )
+        .await;
+        let suggested_templates =
+            ignore_templates::suggestions(&self.config.root_path, &self.config.ignore_templates).await;
+        if !suggested_templates.is_empty() {
+            info!(suggested = ?suggested_templates, "Templates de exclusão sugeridos para a origem");
+        }
+        suggested_templates
+    }
+
+    /// Executa o pipeline: arquivos avulsos e árvores de diretórios entram no
+    /// walker, que alimenta os workers de hash, que alimentam o escritor.
+    ///
+    /// # Argumentos
+    /// * `files` - Arquivos já aprovados pelos filtros, enviados antes do walk
+    /// * `directories` - Diretórios a percorrer, com profundidade e filtros herdados
+    /// * `stats` - Estatísticas acumuladas da varredura
+    ///
+    /// # Retorna
+    /// * Estatísticas de cada diretório visitado
+    async fn run_pipeline(
         &self,
-        path: &Path,
-        depth: i32,
+        files: Vec<PendingFile>,
+        directories: Vec<(PathBuf, i32, Arc<IgnoreMatcher>)>,
         stats: &mut ScanStats,
-    ) -> Result<DirectoryStats, Box<dyn std::error::Error + Send + Sync>> {
-        debug!(path = %path.display(), depth = depth, "🔥 SCAN_DIR: Varrendo diretório");
+    ) -> Result<Vec<DirectoryNode>, Box<dyn std::error::Error + Send + Sync>> {
+        // O histórico de cada arquivo gravado referencia o scan job
+        let scan_job_id = self
+            .scan_job_id
+            .ok_or("Pipeline de varredura iniciado sem scan job")?;
+        let capacity = self.config.queue_capacity.max(1);
+        let (file_tx, file_rx) = mpsc::channel::<PendingFile>(capacity);
+        let (record_tx, record_rx) = mpsc::channel::<CatalogRecord>(capacity);
 
-        // Verificar profundidade máxima
-        if let Some(max_depth) = self.config.max_depth {
-            if depth > max_depth {
-                return Ok(DirectoryStats::default());
-            }
+        // Os workers disputam a mesma fila; se todos pararem, o envio do walker falha
+        let file_rx = Arc::new(Mutex::new(file_rx));
+        let policy = RehashPolicy {
+            force: self.config.force_rehash,
+            sample_rate: self.config.paranoid_sample_rate,
+            scan_job_id: self.scan_job_id,
+        };
+        let content_policy = self.config.index_content.then(|| {
+            ContentPolicy::new(self.config.content_max_bytes, &self.config.content_extensions)
+        });
+        let mut workers = JoinSet::new();
+        for _ in 0..self.config.hash_workers.max(1) {
+            workers.spawn(hash_worker(
+                file_rx.clone(),
+                record_tx.clone(),
+                policy.clone(),
+                content_policy.clone(),
+                self.config.exclude_mime_categories.clone(),
+                self.config.capture_xattrs,
+            ));
         }
+        drop(file_rx);
+        drop(record_tx);
 
-        let mut dir_stats = DirectoryStats {
-            path: path.to_string_lossy().to_string(),
-            total_files: 0,
-            direct_files: 0,
-            total_size: 0,
-            subdirectory_count: 0,
-            file_types: HashMap::new(),
+        let producer = async {
+            for file in files {
+                if file_tx.send(file).await.is_err() {
+                    return Err("Workers de hash encerraram antes do fim da varredura".into());
+                }
+            }
+            self.walk(directories, file_tx, stats).await
         };
 
-        // Ler conteúdo do diretório
-        debug!(path = %path.display(), "Lendo conteúdo do diretório");
-        let mut entries = match fs::read_dir(path).await {
-            Ok(entries) => {
-                debug!(path = %path.display(), "Diretório lido com sucesso");
-                entries
-            }
+        let directories = match tokio::try_join!(producer, self.write_catalog(record_rx, scan_job_id)) {
+            Ok((directories, ())) => directories,
             Err(e) => {
-                tracing::error!(path = %path.display(), error = %e, "Erro ao ler diretório");
-                return Err(e.into());
+                workers.abort_all();
+                return Err(e);
             }
         };
-        
-        debug!(path = %path.display(), "Iniciando loop de processamento de entries");
-        let mut processed_count = 0;
-        while let Some(entry) = entries.next_entry().await? {
-            let entry_path = entry.path();
-            debug!(entry = %entry_path.display(), count = processed_count, "Processando entry");
-            
-            let metadata = match entry.metadata().await {
-                Ok(m) => {
-                    debug!(entry = %entry_path.display(), "Metadata obtido com sucesso");
-                    m
+
+        while let Some(worker_stats) = workers.join_next().await {
+            let worker_stats = worker_stats?;
+            stats.files_hashed += worker_stats.files_hashed;
+            stats.files_hash_skipped += worker_stats.files_hash_skipped;
+            stats.hash_mismatches += worker_stats.hash_mismatches;
+            stats.errors_count += worker_stats.errors_count;
+            stats.skipped_files += worker_stats.skipped_files;
+        }
+
+        Ok(directories)
+    }
+
+    /// Percorre as árvores a partir de `start` e envia os arquivos aceitos para os workers.
+    ///
+    /// O catálogo é consultado uma vez por diretório (por `parent_directory`),
+    /// e não por arquivo. As estatísticas de diretório são acumuladas aqui e
+    /// somadas de baixo para cima no final.
+    ///
+    /// Seguindo symlinks, cada diretório (por device e inode) é percorrido uma
+    /// única vez, o que também interrompe ciclos.
+    ///
+    /// # Retorna
+    /// * Estatísticas de cada diretório visitado, pais antes dos filhos
+    async fn walk(
+        &self,
+        start: Vec<(PathBuf, i32, Arc<IgnoreMatcher>)>,
+        tx: mpsc::Sender<PendingFile>,
+        stats: &mut ScanStats,
+    ) -> Result<Vec<DirectoryNode>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut nodes: Vec<DirectoryNode> = Vec::new();
+        let mut pending: Vec<_> = start
+            .into_iter()
+            .map(|(path, depth, excludes)| (path, depth, excludes, None))
+            .collect();
+        let mut visited: HashSet<(u64, u64)> = HashSet::new();
+
+        while let Some((path, depth, excludes, parent)) = pending.pop() {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+
+            debug!(path = %path.display(), depth = depth, "🔥 SCAN_DIR: Varrendo diretório");
+
+            // Verificar profundidade máxima
+            if let Some(max_depth) = self.config.max_depth {
+                if depth > max_depth {
+                    continue;
+                }
+            }
+
+            if self.config.follow_symlinks {
+                let identity = fs::metadata(&path).await.ok().and_then(|m| file_metadata::identity(&m));
+                if identity.is_some_and(|identity| !visited.insert(identity)) {
+                    debug!(path = %path.display(), "Diretório já percorrido por outro caminho");
+                    continue;
+                }
+            }
+
+            // Um `.b2ignore` aqui vale para este diretório e tudo abaixo dele
+            let excludes = match excludes.extended_with_dir(&path, self.relative_path(&path)).await {
+                Ok(Some(extended)) => {
+                    debug!(path = %path.display(), "Aplicando .b2ignore");
+                    Arc::new(extended)
                 }
+                Ok(None) => excludes,
                 Err(e) => {
-                    warn!(path = %entry_path.display(), error = %e, "Erro ao obter metadata");
+                    warn!(path = %path.display(), error = %e, "Erro ao ler .b2ignore");
                     stats.errors_count += 1;
-                    continue;
+                    excludes
                 }
             };
 
-            if metadata.is_dir() {
-                debug!(dir = %entry_path.display(), "Processando diretório");
-                // Processar subdiretório
-                dir_stats.subdirectory_count += 1;
-                stats.directories_scanned += 1;
-
-                if self.config.recursive {
-                    debug!(dir = %entry_path.display(), "Iniciando scan recursivo");
-                    let sub_stats = self.scan_directory(&entry_path, depth + 1, stats).await?;
-                    dir_stats.total_files += sub_stats.total_files;
-                    dir_stats.total_size += sub_stats.total_size;
-                    debug!(dir = %entry_path.display(), "Scan recursivo concluído");
+            let mut entries = match fs::read_dir(&path).await {
+                Ok(entries) => entries,
+                Err(e) => {
+                    tracing::error!(path = %path.display(), error = %e, "Erro ao ler diretório");
+                    return Err(e.into());
+                }
+            };
+
+            let mut known = self.catalog_snapshots(&path).await?;
+            let node_index = nodes.len();
+            let mut dir_stats = DirectoryStats {
+                path: path.to_string_lossy().to_string(),
+                ..Default::default()
+            };
+
+            while let Some(entry) = entries.next_entry().await? {
+                if self.cancel.is_cancelled() {
+                    debug!(path = %path.display(), "Varredura cancelada");
+                    break;
                 }
-            } else if metadata.is_file() {
-                debug!(file = %entry_path.display(), size = metadata.len(), "Processando arquivo");
-                
-                // Processar arquivo
-                if self.should_scan_file(&entry_path, &metadata)? {
-                    debug!(file = %entry_path.display(), "Arquivo aprovado para catalogação");
-                    match self.catalog_file(&entry_path, &metadata, depth).await {
-                        Ok(_) => {
-                            debug!(file = %entry_path.display(), "Arquivo catalogado com sucesso");
-                        }
-                        Err(e) => {
-                            tracing::error!(file = %entry_path.display(), error = %e, "Erro ao catalogar arquivo");
-                            return Err(e);
-                        }
+                let entry_path = entry.path();
+
+                let resolved = match entry.metadata().await {
+                    Ok(m) => self.resolve_symlink(&entry_path, m).await,
+                    Err(e) => Err(e),
+                };
+                let (metadata, symlink_target) = match resolved {
+                    Ok(resolved) => resolved,
+                    Err(e) => {
+                        warn!(path = %entry_path.display(), error = %e, "Erro ao obter metadata");
+                        stats.errors_count += 1;
+                        continue;
+                    }
+                };
+
+                if metadata.is_dir() {
+                    // Diretórios excluídos são podados: nem entramos neles
+                    if excludes.matches(self.relative_path(&entry_path), true) {
+                        debug!(dir = %entry_path.display(), "Diretório excluído pelos filtros");
+                        stats.skipped_directories += 1;
+                        continue;
+                    }
+
+                    dir_stats.subdirectory_count += 1;
+                    stats.directories_scanned += 1;
+
+                    if self.config.recursive {
+                        pending.push((entry_path, depth + 1, excludes.clone(), Some(node_index)));
                     }
-                    
+                } else if metadata.is_file() || symlink_target.is_some() {
+                    if !self.should_scan_file(&entry_path, &metadata, &excludes)? {
+                        debug!(file = %entry_path.display(), "Arquivo rejeitado pelos filtros");
+                        stats.skipped_files += 1;
+                        continue;
+                    }
+
+                    let file_size = metadata.len() as i64;
                     dir_stats.direct_files += 1;
                     dir_stats.total_files += 1;
-                    dir_stats.total_size += metadata.len() as i64;
+                    dir_stats.total_size += file_size;
                     stats.files_scanned += 1;
-                    stats.total_size += metadata.len() as i64;
+                    stats.total_size += file_size;
 
                     // Contar tipo de arquivo
                     if let Some(ext) = entry_path.extension() {
                         let ext_str = ext.to_string_lossy().to_lowercase();
                         *dir_stats.file_types.entry(ext_str).or_insert(0) += 1;
                     }
-                    
-                    debug!(file = %entry_path.display(), "Arquivo processado completamente");
-                } else {
-                    debug!(file = %entry_path.display(), "Arquivo rejeitado pelos filtros");
+
+                    let existing = known.remove(&*entry_path.to_string_lossy());
+                    let file = self.pending_file(entry_path, depth, &metadata, symlink_target, existing);
+
+                    // Fila cheia: espera os workers (backpressure)
+                    if tx.send(file).await.is_err() {
+                        return Err("Workers de hash encerraram antes do fim da varredura".into());
+                    }
                 }
             }
-            
-            processed_count += 1;
-            debug!(count = processed_count, "Entry processado");
+
+            nodes.push(DirectoryNode { stats: dir_stats, depth, parent });
         }
 
-        // Salvar estatísticas do diretório
-        self.save_directory_stats(&dir_stats, depth).await?;
+        // Filhos sempre vêm depois dos pais: somar em ordem reversa propaga os totais
+        for index in (0..nodes.len()).rev() {
+            if let Some(parent) = nodes[index].parent {
+                let (total_files, total_size) = (nodes[index].stats.total_files, nodes[index].stats.total_size);
+                nodes[parent].stats.total_files += total_files;
+                nodes[parent].stats.total_size += total_size;
+            }
+        }
 
-        Ok(dir_stats)
+        Ok(nodes)
+    }
+
+    /// Resolve um link simbólico conforme `follow_symlinks`.
+    ///
+    /// # Argumentos
+    /// * `path` - Caminho da entrada
+    /// * `metadata` - Metadata da própria entrada (sem seguir links)
+    ///
+    /// # Retorna
+    /// * Metadata a usar e, para links que não serão seguidos (inclusive
+    ///   links quebrados), o destino do link
+    async fn resolve_symlink(
+        &self,
+        path: &Path,
+        metadata: Metadata,
+    ) -> std::io::Result<(Metadata, Option<PathBuf>)> {
+        if !metadata.file_type().is_symlink() {
+            return Ok((metadata, None));
+        }
+        if self.config.follow_symlinks {
+            match fs::metadata(path).await {
+                Ok(target) => return Ok((target, None)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let target = fs::read_link(path).await?;
+        Ok((metadata, Some(target)))
+    }
+
+    /// Monta o arquivo enviado aos workers a partir do metadata já lido
+    fn pending_file(
+        &self,
+        path: PathBuf,
+        depth: i32,
+        metadata: &Metadata,
+        symlink_target: Option<PathBuf>,
+        existing: Option<CatalogSnapshot>,
+    ) -> PendingFile {
+        PendingFile {
+            path,
+            depth,
+            file_size: metadata.len() as i64,
+            created_at: metadata.created().ok().and_then(system_time_to_datetime),
+            modified_at: metadata.modified().ok().and_then(system_time_to_datetime),
+            accessed_at: metadata.accessed().ok().and_then(system_time_to_datetime),
+            unix: file_metadata::unix_metadata(metadata, &self.owner_names),
+            symlink_target,
+            existing,
+        }
+    }
+
+    /// Estado atual no catálogo dos arquivos de um diretório, por caminho
+    async fn catalog_snapshots(
+        &self,
+        directory: &Path,
+    ) -> Result<HashMap<String, CatalogSnapshot>, Box<dyn std::error::Error + Send + Sync>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT fc.file_path, fc.file_size, fc.content_hash, fc.modified_at, fc.mime_category,
+                   fct.content_hash AS "content_indexed_hash?"
+            FROM file_catalog fc
+            LEFT JOIN file_content fct ON fct.file_catalog_id = fc.id
+            WHERE fc.parent_directory = $1
+            "#,
+            directory.to_string_lossy().to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    row.file_path,
+                    CatalogSnapshot {
+                        file_size: row.file_size,
+                        content_hash: row.content_hash,
+                        modified_at: row.modified_at,
+                        mime_category: row.mime_category.as_deref().and_then(MimeCategory::parse),
+                        content_indexed_hash: row.content_indexed_hash,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// Caminho relativo à raiz do scan, usado pelos padrões de inclusão/exclusão
+    fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.config.root_path).unwrap_or(path)
     }
 
     /// Verifica se um arquivo deve ser varrido
-    fn should_scan_file(&self, _path: &Path, metadata: &Metadata) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    fn should_scan_file(
+        &self,
+        path: &Path,
+        metadata: &Metadata,
+        excludes: &IgnoreMatcher,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
         let file_size = metadata.len() as i64;
 
         // Verificar tamanho mínimo
@@ -334,213 +787,623 @@ impl FileScanner {
             }
         }
 
-        // TODO: Implementar include/exclude patterns com glob
+        // Padrões: exclusão vence; com includes definidos o arquivo, ou um
+        // diretório acima dele, precisa casar com um deles
+        let rel_path = self.relative_path(path);
+        if excludes.matches(rel_path, false) {
+            return Ok(false);
+        }
+        if !self.includes.is_empty() && !self.includes.is_ignored(rel_path, false) {
+            return Ok(false);
+        }
 
         Ok(true)
     }
 
-    /// Cataloga um arquivo no banco
-    async fn catalog_file(
+    /// Consome os arquivos já com hash e grava no catálogo em lotes
+    async fn write_catalog(
         &self,
-        path: &Path,
-        metadata: &Metadata,
-        depth: i32,
-    ) -> Result<Uuid, Box<dyn std::error::Error + Send + Sync>> {
-        debug!(path = %path.display(), "Iniciando catalogação de arquivo");
-        
-        let file_name = path.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+        mut rx: mpsc::Receiver<CatalogRecord>,
+        scan_job_id: Uuid,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let batch_size = self.config.db_batch_size.max(1);
+        let mut batch = Vec::with_capacity(batch_size);
 
-        let extension = path.extension()
-            .and_then(|e| e.to_str())
-            .map(|e| e.to_lowercase());
+        while rx.recv_many(&mut batch, batch_size).await > 0 {
+            self.write_batch(&batch, scan_job_id).await?;
+            batch.clear();
+        }
 
-        let parent_directory = path.parent()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "/".to_string());
+        Ok(())
+    }
 
-        let file_size = metadata.len() as i64;
-        debug!(path = %path.display(), size = file_size, "Iniciando cálculo de hash");
+    /// Grava um lote de arquivos com um único statement.
+    ///
+    /// Os registros vão como arrays (`UNNEST`) para um `INSERT ... ON CONFLICT
+    /// (file_path)` no catálogo, e o histórico é derivado no mesmo statement
+    /// comparando o resultado com o estado anterior das linhas: arquivos novos
+    /// ganham a entrada `initial`, arquivos alterados uma entrada `manual` com
+    /// o que mudou, e arquivos inalterados só têm `last_scan_at` atualizado.
+    /// O tipo MIME detectado substitui o anterior junto com a chave
+    /// `mime_mismatch` de `metadata`, presente só quando conteúdo e extensão
+    /// divergem. As chaves de `file_metadata` (dono, permissões, xattrs,
+    /// destino do link) são sempre substituídas pelas desta varredura.
+    ///
+    /// Um caminho não pode aparecer duas vezes no mesmo lote (o walker visita
+    /// cada arquivo uma única vez).
+    async fn write_batch(
+        &self,
+        batch: &[CatalogRecord],
+        scan_job_id: Uuid,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut file_paths = Vec::with_capacity(batch.len());
+        let mut file_names = Vec::with_capacity(batch.len());
+        let mut extensions = Vec::with_capacity(batch.len());
+        let mut file_sizes = Vec::with_capacity(batch.len());
+        let mut created_at = Vec::with_capacity(batch.len());
+        let mut modified_at = Vec::with_capacity(batch.len());
+        let mut accessed_at = Vec::with_capacity(batch.len());
+        let mut content_hashes = Vec::with_capacity(batch.len());
+        let mut parent_directories = Vec::with_capacity(batch.len());
+        let mut depths = Vec::with_capacity(batch.len());
+        let mut mime_types = Vec::with_capacity(batch.len());
+        let mut mime_categories = Vec::with_capacity(batch.len());
+        let mut extension_mimes = Vec::with_capacity(batch.len());
+        let mut metadata = Vec::with_capacity(batch.len());
+
+        for record in batch {
+            let file = &record.file;
+            file_paths.push(file.path.to_string_lossy().to_string());
+            file_names.push(
+                file.path.file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("unknown")
+                    .to_string(),
+            );
+            extensions.push(
+                file.path.extension()
+                    .and_then(|e| e.to_str())
+                    .map(|e| e.to_lowercase()),
+            );
+            file_sizes.push(file.file_size);
+            created_at.push(file.created_at);
+            modified_at.push(file.modified_at);
+            accessed_at.push(file.accessed_at);
+            content_hashes.push(record.content_hash.clone());
+            parent_directories.push(
+                file.path.parent()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "/".to_string()),
+            );
+            depths.push(file.depth);
+            mime_types.push(record.mime.as_ref().map(|mime| mime.mime_type.clone()));
+            mime_categories.push(record.mime.as_ref().map(|mime| mime.category.as_str().to_string()));
+            extension_mimes.push(record.mime.as_ref().and_then(|mime| mime.extension_mime.clone()));
+            metadata.push(record.metadata.clone());
+        }
+
+        let history_rows = sqlx::query!(
+            r#"
+            WITH input AS (
+                SELECT *
+                FROM UNNEST(
+                    $1::TEXT[], $2::TEXT[], $3::TEXT[], $4::BIGINT[], $5::TIMESTAMP[],
+                    $6::TIMESTAMP[], $7::TIMESTAMP[], $8::TEXT[], $9::TEXT[], $10::INTEGER[],
+                    $12::TEXT[], $13::TEXT[], $14::TEXT[], $15::JSONB[]
+                ) AS t(
+                    file_path, file_name, extension, file_size, created_at,
+                    modified_at, accessed_at, content_hash, parent_directory, depth,
+                    mime_type, mime_category, extension_mime, metadata
+                )
+            ),
+            previous AS (
+                SELECT fc.file_path, fc.file_size, fc.content_hash, fc.modified_at, fc.accessed_at
+                FROM file_catalog fc
+                JOIN input ON input.file_path = fc.file_path
+            ),
+            upserted AS (
+                INSERT INTO file_catalog (
+                    file_path, file_name, extension, file_size,
+                    created_at, modified_at, accessed_at,
+                    content_hash, parent_directory, depth,
+                    mime_type, mime_category, metadata
+                )
+                SELECT
+                    file_path, file_name, extension, file_size,
+                    created_at, modified_at, accessed_at,
+                    content_hash, parent_directory, depth,
+                    mime_type, mime_category,
+                    metadata || CASE WHEN extension_mime IS NULL THEN '{}'::JSONB
+                         ELSE jsonb_build_object('mime_mismatch', jsonb_build_object(
+                             'detected', mime_type, 'extension', extension_mime))
+                    END
+                FROM input
+                ON CONFLICT (file_path) DO UPDATE SET
+                    file_size = EXCLUDED.file_size,
+                    content_hash = EXCLUDED.content_hash,
+                    modified_at = EXCLUDED.modified_at,
+                    accessed_at = EXCLUDED.accessed_at,
+                    -- Tipo NULL: o worker reaproveitou o que já estava no catálogo
+                    mime_type = COALESCE(EXCLUDED.mime_type, file_catalog.mime_type),
+                    mime_category = COALESCE(EXCLUDED.mime_category, file_catalog.mime_category),
+                    metadata = (CASE WHEN EXCLUDED.mime_type IS NULL THEN COALESCE(file_catalog.metadata, '{}'::JSONB)
+                                ELSE COALESCE(file_catalog.metadata, '{}'::JSONB) - 'mime_mismatch'
+                                END) - ARRAY['unix', 'symlink_target', 'xattrs'] || EXCLUDED.metadata,
+                    last_scan_at = CURRENT_TIMESTAMP,
+                    is_active = TRUE
+                RETURNING id, file_path, file_size, content_hash, modified_at, accessed_at
+            )
+            INSERT INTO file_history (
+                file_catalog_id, scan_job_id, file_size, content_hash,
+                modified_at, accessed_at, size_changed, hash_changed,
+                modified_changed, accessed_changed, size_delta,
+                days_since_last_access, days_since_last_modification,
+                scan_type
+            )
+            SELECT
+                u.id, $11, u.file_size, u.content_hash,
+                u.modified_at, u.accessed_at,
+                p.file_path IS NOT NULL AND p.file_size <> u.file_size,
+                p.file_path IS NOT NULL AND p.content_hash IS DISTINCT FROM u.content_hash,
+                p.file_path IS NOT NULL AND p.modified_at IS DISTINCT FROM u.modified_at,
+                p.file_path IS NOT NULL AND p.accessed_at IS DISTINCT FROM u.accessed_at,
+                u.file_size - p.file_size,
+                CASE WHEN p.file_path IS NOT NULL THEN EXTRACT(DAY FROM (CURRENT_TIMESTAMP - u.accessed_at))::INTEGER END,
+                CASE WHEN p.file_path IS NOT NULL THEN EXTRACT(DAY FROM (CURRENT_TIMESTAMP - u.modified_at))::INTEGER END,
+                CASE WHEN p.file_path IS NULL THEN 'initial' ELSE 'manual' END
+            FROM upserted u
+            LEFT JOIN previous p ON p.file_path = u.file_path
+            WHERE p.file_path IS NULL
+               OR p.file_size <> u.file_size
+               OR p.content_hash IS DISTINCT FROM u.content_hash
+               OR p.modified_at IS DISTINCT FROM u.modified_at
+               OR p.accessed_at IS DISTINCT FROM u.accessed_at
+            "#,
+            &file_paths,
+            &file_names,
+            &extensions as &[Option<String>],
+            &file_sizes,
+            &created_at as &[Option<NaiveDateTime>],
+            &modified_at as &[Option<NaiveDateTime>],
+            &accessed_at as &[Option<NaiveDateTime>],
+            &content_hashes as &[Option<String>],
+            &parent_directories,
+            &depths,
+            scan_job_id,
+            &mime_types as &[Option<String>],
+            &mime_categories as &[Option<String>],
+            &extension_mimes as &[Option<String>],
+            &metadata as &[serde_json::Value]
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        debug!(files = batch.len(), history_rows = history_rows, "Lote gravado no catálogo");
 
-        // Sempre calcular hash para detecção de duplicados e integridade
-        let content_hash = Some(self.calculate_file_hash(path).await?);
-        debug!(path = %path.display(), "Hash calculado, inserindo no banco");
+        self.write_contents(batch, &file_paths).await?;
 
-        // Converter timestamps
-        let modified_at = metadata.modified()
-            .ok()
-            .and_then(|t| system_time_to_datetime(t));
+        if let Some(index) = &self.search_index {
+            if let Err(e) = index.reindex_paths(&self.pool, &file_paths).await {
+                warn!(error = %e, "Falha ao atualizar o índice de busca");
+            }
+        }
+
+        Ok(())
+    }
 
-        let accessed_at = metadata.accessed()
-            .ok()
-            .and_then(|t| system_time_to_datetime(t));
+    /// Sincroniza `file_content` com um lote já gravado no catálogo.
+    ///
+    /// Textos de arquivos cujo hash mudou são descartados (mesmo que o novo
+    /// conteúdo não seja extraído, ex.: o arquivo passou do tamanho máximo), e
+    /// os textos extraídos pelos workers são gravados com o hash atual.
+    ///
+    /// # Argumentos
+    /// * `batch` - Registros do lote
+    /// * `file_paths` - Caminhos do lote, na mesma ordem
+    async fn write_contents(
+        &self,
+        batch: &[CatalogRecord],
+        file_paths: &[String],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        sqlx::query!(
+            r#"
+            DELETE FROM file_content fct
+            USING file_catalog fc
+            WHERE fct.file_catalog_id = fc.id
+              AND fc.file_path = ANY($1)
+              AND fct.content_hash IS DISTINCT FROM fc.content_hash
+            "#,
+            file_paths
+        )
+        .execute(&self.pool)
+        .await?;
 
-        let created_at = metadata.created()
-            .ok()
-            .and_then(|t| system_time_to_datetime(t));
+        let (paths, contents): (Vec<String>, Vec<String>) = batch
+            .iter()
+            .zip(file_paths)
+            .filter_map(|(record, path)| record.content.clone().map(|content| (path.clone(), content)))
+            .unzip();
+        if paths.is_empty() {
+            return Ok(());
+        }
 
-        // Verificar se arquivo já existe
-        let existing_file = sqlx::query!(
+        sqlx::query!(
             r#"
-            SELECT id, file_size, content_hash, modified_at, accessed_at
+            INSERT INTO file_content (file_catalog_id, content, content_hash)
+            SELECT fc.id, t.content, fc.content_hash
+            FROM UNNEST($1::TEXT[], $2::TEXT[]) AS t(file_path, content)
+            JOIN file_catalog fc ON fc.file_path = t.file_path
+            ON CONFLICT (file_catalog_id) DO UPDATE SET
+                content = EXCLUDED.content,
+                content_hash = EXCLUDED.content_hash,
+                extracted_at = CURRENT_TIMESTAMP
+            "#,
+            &paths,
+            &contents
+        )
+        .execute(&self.pool)
+        .await?;
+
+        debug!(files = paths.len(), "Conteúdo extraído gravado");
+        Ok(())
+    }
+
+    /// Marca como inativos os arquivos da raiz que não foram vistos nesta varredura.
+    ///
+    /// Todo arquivo visto tem `last_scan_at` atualizado pelo escritor. Uma
+    /// linha ativa sob a raiz com `last_scan_at` anterior ao início do scan só
+    /// é dada como removida quando:
+    /// - o diretório mais próximo que ainda existe foi listado nesta varredura
+    ///   (diretórios podados, além de `max_depth` ou abaixo da raiz num scan
+    ///   não recursivo ficam como estão); e
+    /// - o arquivo não existe mais no disco: arquivos que só deixaram de passar
+    ///   pelos filtros (padrões, tamanho, categoria) continuam ativos.
+    ///
+    /// Cada removido ganha um evento `deleted` em `file_history`.
+    ///
+    /// # Argumentos
+    /// * `job_id` - Scan job da varredura
+    /// * `directories` - Diretórios listados pelo walker
+    ///
+    /// # Retorna
+    /// * Quantidade de arquivos marcados como removidos
+    async fn mark_deleted_files(
+        &self,
+        job_id: Uuid,
+        directories: &[DirectoryNode],
+    ) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+        let mut prefix = self.config.root_path.to_string_lossy().to_string();
+        if !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+
+        let stale: Vec<String> = sqlx::query_scalar!(
+            r#"
+            SELECT file_path
             FROM file_catalog
-            WHERE file_path = $1
+            WHERE is_active = TRUE
+              AND starts_with(file_path, $2)
+              AND last_scan_at < (SELECT started_at FROM scan_jobs WHERE id = $1)
             "#,
-            path.to_string_lossy().to_string()
+            job_id,
+            prefix
         )
-        .fetch_optional(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        let id = if let Some(existing) = existing_file {
-            // Arquivo já existe - registrar no histórico
-            
-            // Verificar o que mudou
-            let size_changed = existing.file_size != file_size;
-            let hash_changed = existing.content_hash != content_hash;
-            let modified_changed = existing.modified_at != modified_at;
-            let accessed_changed = existing.accessed_at != accessed_at;
-            
-            if size_changed || hash_changed || modified_changed || accessed_changed {
-                // Inserir no histórico
-                sqlx::query!(
-                    r#"
-                    INSERT INTO file_history (
-                        file_catalog_id, scan_job_id, file_size, content_hash,
-                        modified_at, accessed_at, size_changed, hash_changed,
-                        modified_changed, accessed_changed, size_delta,
-                        days_since_last_access, days_since_last_modification,
-                        scan_type
-                    ) VALUES (
-                        $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11,
-                        CASE WHEN $12::TIMESTAMP IS NOT NULL THEN EXTRACT(DAY FROM (CURRENT_TIMESTAMP - $12::TIMESTAMP))::INTEGER ELSE NULL END,
-                        CASE WHEN $13::TIMESTAMP IS NOT NULL THEN EXTRACT(DAY FROM (CURRENT_TIMESTAMP - $13::TIMESTAMP))::INTEGER ELSE NULL END,
-                        'manual'
-                    )
-                    "#,
-                    existing.id,
-                    self.scan_job_id.unwrap_or_default(),
-                    file_size,
-                    content_hash.clone(),
-                    modified_at,
-                    accessed_at,
-                    size_changed,
-                    hash_changed,
-                    modified_changed,
-                    accessed_changed,
-                    file_size - existing.file_size,
-                    accessed_at,
-                    modified_at
-                )
-                .execute(&self.pool)
-                .await?;
-                
-                // Atualizar file_catalog
-                sqlx::query!(
-                    r#"
-                    UPDATE file_catalog SET
-                        file_size = $2,
-                        content_hash = $3,
-                        modified_at = $4,
-                        accessed_at = $5,
-                        last_scan_at = CURRENT_TIMESTAMP,
-                        is_active = TRUE
-                    WHERE id = $1
-                    "#,
-                    existing.id,
-                    file_size,
-                    content_hash,
-                    modified_at,
-                    accessed_at
-                )
-                .execute(&self.pool)
-                .await?;
-            } else {
-                // Nada mudou, apenas atualizar last_scan_at
-                sqlx::query!(
-                    "UPDATE file_catalog SET last_scan_at = CURRENT_TIMESTAMP WHERE id = $1",
-                    existing.id
-                )
-                .execute(&self.pool)
-                .await?;
+        let listed: HashSet<&Path> = directories.iter().map(|d| Path::new(&d.stats.path)).collect();
+        let mut removed = Vec::new();
+        for file_path in stale {
+            let path = Path::new(&file_path);
+            if !self.listed_by_scan(path, &listed).await {
+                continue;
             }
-            
-            existing.id
-        } else {
-            // Novo arquivo - inserir no catálogo
-            let new_id = sqlx::query_scalar!(
+            match fs::symlink_metadata(path).await {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => removed.push(file_path),
+                _ => {}
+            }
+        }
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        let deleted = sqlx::query!(
+            r#"
+            WITH deleted AS (
+                UPDATE file_catalog SET
+                    is_active = FALSE
+                WHERE is_active = TRUE
+                  AND file_path = ANY($2)
+                RETURNING id, file_size, content_hash, modified_at, accessed_at
+            )
+            INSERT INTO file_history (
+                file_catalog_id, scan_job_id, file_size, content_hash,
+                modified_at, accessed_at, scan_type
+            )
+            SELECT id, $1, file_size, content_hash, modified_at, accessed_at, 'deleted'
+            FROM deleted
+            "#,
+            job_id,
+            &removed
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected() as i64;
+
+        if let Some(index) = &self.search_index {
+            index.remove_paths(&removed);
+        }
+
+        if deleted > 0 {
+            info!(job_id = %job_id, deleted = deleted, "Arquivos removidos marcados como inativos");
+        }
+
+        Ok(deleted)
+    }
+
+    /// Se o diretório mais próximo de `path` que ainda existe foi listado pelo walker
+    async fn listed_by_scan(&self, path: &Path, listed: &HashSet<&Path>) -> bool {
+        for dir in path.ancestors().skip(1) {
+            if listed.contains(dir) {
+                return true;
+            }
+            if dir == self.config.root_path || fs::symlink_metadata(dir).await.is_ok() {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Prepara o scanner para atualizações incrementais (modo live).
+    ///
+    /// Monta os filtros da raiz e abre um scan job `live`, que recebe as
+    /// entradas de histórico e os contadores de `apply_changes` até
+    /// `finish_live_session`.
+    ///
+    /// # Retorna
+    /// * ID do scan job da sessão
+    pub async fn start_live_session(
+        &mut self,
+        scan_config_id: Option<Uuid>,
+    ) -> Result<Uuid, Box<dyn std::error::Error + Send + Sync>> {
+        self.prepare_filters().await;
+
+        let job_id = self.create_scan_job().await?;
+        sqlx::query!(
+            r#"
+            UPDATE scan_jobs
+            SET status = 'running', started_at = CURRENT_TIMESTAMP,
+                scan_type = 'live', scan_config_id = $2
+            WHERE id = $1
+            "#,
+            job_id,
+            scan_config_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.scan_job_id = Some(job_id);
+        info!(job_id = %job_id, root_path = %self.config.root_path.display(), "Sessão live iniciada");
+
+        Ok(job_id)
+    }
+
+    /// Encerra a sessão aberta por `start_live_session`
+    pub async fn finish_live_session(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(job_id) = self.scan_job_id {
+            sqlx::query!(
                 r#"
-                INSERT INTO file_catalog (
-                    file_path, file_name, extension, file_size,
-                    created_at, modified_at, accessed_at,
-                    content_hash, parent_directory, depth
-                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-                RETURNING id
+                UPDATE scan_jobs
+                SET status = 'completed',
+                    completed_at = CURRENT_TIMESTAMP,
+                    duration_seconds = EXTRACT(EPOCH FROM (CURRENT_TIMESTAMP - started_at))::INTEGER
+                WHERE id = $1
                 "#,
-                path.to_string_lossy().to_string(),
-                file_name,
-                extension,
-                file_size,
-                created_at,
-                modified_at,
-                accessed_at,
-                content_hash.clone(),
-                parent_directory,
-                depth
+                job_id
             )
-            .fetch_one(&self.pool)
+            .execute(&self.pool)
             .await?;
-            
-            // Inserir primeira entrada no histórico
+        }
+        Ok(())
+    }
+
+    /// Aplica ao catálogo mudanças em caminhos avulsos (modo live).
+    ///
+    /// Cada caminho é reavaliado no disco, independente do evento que o trouxe:
+    /// arquivos existentes passam pelo pipeline normal (hash + escrita em lote),
+    /// diretórios existentes são percorridos por inteiro (ex.: renomeados para
+    /// dentro da raiz) e caminhos que não existem mais são marcados como
+    /// removidos, junto com tudo abaixo deles.
+    ///
+    /// # Argumentos
+    /// * `paths` - Caminhos absolutos sob a raiz; caminhos fora dela são ignorados
+    pub async fn apply_changes(
+        &self,
+        paths: &[PathBuf],
+    ) -> Result<LiveChangeStats, Box<dyn std::error::Error + Send + Sync>> {
+        let mut result = LiveChangeStats::default();
+        let mut stats = ScanStats::default();
+        let mut files = Vec::new();
+        let mut directories = Vec::new();
+        let mut removed = Vec::new();
+        let mut matchers: HashMap<PathBuf, Arc<IgnoreMatcher>> = HashMap::new();
+        let mut snapshots: HashMap<PathBuf, HashMap<String, CatalogSnapshot>> = HashMap::new();
+
+        for path in paths {
+            let Ok(rel_path) = path.strip_prefix(&self.config.root_path) else {
+                continue;
+            };
+            let Some(parent) = path.parent().filter(|_| !rel_path.as_os_str().is_empty()) else {
+                continue;
+            };
+
+            // Um `.b2ignore` alterado muda os filtros de uma subárvore inteira
+            if path.file_name().is_some_and(|name| name == IGNORE_FILE_NAME) {
+                result.needs_rescan = true;
+            }
+
+            let resolved = match fs::symlink_metadata(path).await {
+                Ok(metadata) => self.resolve_symlink(path, metadata).await,
+                Err(e) => Err(e),
+            };
+            let (metadata, symlink_target) = match resolved {
+                Ok(resolved) => resolved,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    removed.push(path.to_string_lossy().to_string());
+                    continue;
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Erro ao obter metadata");
+                    stats.errors_count += 1;
+                    continue;
+                }
+            };
+
+            let depth = rel_path.components().count() as i32 - 1;
+            let excludes = self.matcher_for(parent, &mut matchers).await;
+
+            if metadata.is_dir() {
+                let within_limits = self.config.recursive
+                    && self.config.max_depth.is_none_or(|max_depth| depth < max_depth);
+                if within_limits && !excludes.is_ignored(rel_path, true) {
+                    directories.push((path.clone(), depth + 1, excludes));
+                }
+            } else if metadata.is_file() || symlink_target.is_some() {
+                if (!self.config.recursive && depth > 0)
+                    || self.config.max_depth.is_some_and(|max_depth| depth > max_depth)
+                    || excludes.is_ignored(rel_path, false)
+                    || !self.should_scan_file(path, &metadata, &excludes)?
+                {
+                    stats.skipped_files += 1;
+                    continue;
+                }
+
+                if !snapshots.contains_key(parent) {
+                    snapshots.insert(parent.to_path_buf(), self.catalog_snapshots(parent).await?);
+                }
+                let existing = snapshots
+                    .get_mut(parent)
+                    .and_then(|known| known.remove(&*path.to_string_lossy()));
+
+                stats.files_scanned += 1;
+                stats.total_size += metadata.len() as i64;
+                files.push(self.pending_file(path.clone(), depth, &metadata, symlink_target, existing));
+            }
+        }
+
+        let walked = self.run_pipeline(files, directories, &mut stats).await?;
+        for directory in &walked {
+            self.save_directory_stats(&directory.stats, directory.depth).await?;
+        }
+        result.files_deleted = self.mark_removed_paths(&removed).await?;
+        result.files_updated = stats.files_scanned;
+        result.errors_count = stats.errors_count;
+
+        if let Err(e) = self.commit_search_index().await {
+            warn!(error = %e, "Falha ao gravar o índice de busca");
+        }
+
+        if let Some(job_id) = self.scan_job_id {
             sqlx::query!(
                 r#"
-                INSERT INTO file_history (
-                    file_catalog_id, scan_job_id, file_size, content_hash,
-                    modified_at, accessed_at, scan_type
-                ) VALUES ($1, $2, $3, $4, $5, $6, 'initial')
+                UPDATE scan_jobs SET
+                    files_scanned = COALESCE(files_scanned, 0) + $2,
+                    files_hashed = files_hashed + $3,
+                    files_hash_skipped = files_hash_skipped + $4,
+                    hash_mismatches = hash_mismatches + $5,
+                    files_deleted = files_deleted + $6,
+                    errors_count = COALESCE(errors_count, 0) + $7
+                WHERE id = $1
                 "#,
-                new_id,
-                self.scan_job_id.unwrap_or_default(),
-                file_size,
-                content_hash,
-                modified_at,
-                accessed_at
+                job_id,
+                stats.files_scanned,
+                stats.files_hashed,
+                stats.files_hash_skipped,
+                stats.hash_mismatches,
+                result.files_deleted,
+                stats.errors_count
             )
             .execute(&self.pool)
             .await?;
-            
-            new_id
-        };
+        }
 
-        debug!(file = %path.display(), id = %id, "Arquivo catalogado");
+        debug!(
+            updated = result.files_updated,
+            deleted = result.files_deleted,
+            "Mudanças aplicadas ao catálogo"
+        );
 
-        Ok(id)
+        Ok(result)
     }
 
-    /// Calcula o hash SHA256 de um arquivo
-    async fn calculate_file_hash(&self, path: &Path) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        use tokio::io::{AsyncReadExt, BufReader};
-        
-        debug!(path = %path.display(), "Calculando hash do arquivo");
-        
-        let file = fs::File::open(path).await?;
-        let mut reader = BufReader::new(file);
-        let mut hasher = Sha256::new();
-        let mut buffer = [0; 8192]; // Buffer de 8KB
-        
+    /// Filtros efetivos dentro de `dir`: os da raiz somados aos `.b2ignore` de
+    /// cada diretório entre a raiz e `dir`
+    async fn matcher_for(
+        &self,
+        dir: &Path,
+        cache: &mut HashMap<PathBuf, Arc<IgnoreMatcher>>,
+    ) -> Arc<IgnoreMatcher> {
+        if let Some(matcher) = cache.get(dir) {
+            return matcher.clone();
+        }
+
+        let mut matcher = Arc::new(self.excludes.clone());
+        let mut current = self.config.root_path.clone();
+        let rel_dir = self.relative_path(dir).to_path_buf();
+        let mut components = rel_dir.components();
         loop {
-            let bytes_read = reader.read(&mut buffer).await?;
-            if bytes_read == 0 {
-                break;
+            if let Ok(Some(extended)) = matcher.extended_with_dir(&current, self.relative_path(&current)).await {
+                matcher = Arc::new(extended);
+            }
+            match components.next() {
+                Some(component) => current.push(component),
+                None => break,
             }
-            hasher.update(&buffer[..bytes_read]);
         }
-        
-        let hash_result = format!("{:x}", hasher.finalize());
-        debug!(path = %path.display(), hash = %hash_result, "Hash calculado com sucesso");
-        
-        Ok(hash_result)
+
+        cache.insert(dir.to_path_buf(), matcher.clone());
+        matcher
+    }
+
+    /// Marca como removidos os caminhos informados e tudo abaixo deles
+    async fn mark_removed_paths(&self, paths: &[String]) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+        if paths.is_empty() {
+            return Ok(0);
+        }
+
+        let deleted = sqlx::query!(
+            r#"
+            WITH deleted AS (
+                UPDATE file_catalog SET
+                    is_active = FALSE
+                WHERE is_active = TRUE
+                  AND EXISTS (
+                      SELECT 1 FROM UNNEST($2::TEXT[]) AS removed(path)
+                      WHERE file_catalog.file_path = removed.path
+                         OR starts_with(file_catalog.file_path, removed.path || '/')
+                  )
+                RETURNING id, file_size, content_hash, modified_at, accessed_at
+            )
+            INSERT INTO file_history (
+                file_catalog_id, scan_job_id, file_size, content_hash,
+                modified_at, accessed_at, scan_type
+            )
+            SELECT id, $1, file_size, content_hash, modified_at, accessed_at, 'deleted'
+            FROM deleted
+            "#,
+            self.scan_job_id,
+            paths
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected() as i64;
+
+        if let Some(index) = &self.search_index {
+            index.remove_paths(paths);
+        }
+
+        Ok(deleted)
     }
 
     /// Salva estatísticas de um diretório
@@ -583,8 +1446,9 @@ impl FileScanner {
             r#"
             INSERT INTO scan_jobs (
                 root_path, recursive, follow_symlinks, max_depth,
-                include_patterns, exclude_patterns, min_file_size, max_file_size
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                include_patterns, exclude_patterns, min_file_size, max_file_size,
+                force_rehash, paranoid_sample_rate
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING id
             "#,
             self.config.root_path.to_string_lossy().to_string(),
@@ -594,7 +1458,9 @@ impl FileScanner {
             &self.config.include_patterns,
             &self.config.exclude_patterns,
             self.config.min_file_size,
-            self.config.max_file_size
+            self.config.max_file_size,
+            self.config.force_rehash,
+            self.config.paranoid_sample_rate
         )
         .fetch_one(&self.pool)
         .await?;
@@ -610,52 +1476,399 @@ struct ScanStats {
     directories_scanned: i64,
     total_size: i64,
     errors_count: i32,
+    /// Arquivos rejeitados por padrões ou limites de tamanho
+    skipped_files: i64,
+    /// Diretórios podados por padrões de exclusão
+    skipped_directories: i64,
+    /// Arquivos lidos para calcular o hash
+    files_hashed: i64,
+    /// Arquivos inalterados cujo hash veio do catálogo
+    files_hash_skipped: i64,
+    /// Hashes recalculados que divergiram do catálogo sem mudança de tamanho/mtime
+    hash_mismatches: i64,
+    /// Arquivos do catálogo que não existem mais na raiz
+    files_deleted: i64,
+}
+
+/// Resultado de uma aplicação incremental de mudanças (`apply_changes`)
+#[derive(Debug, Default, Clone)]
+pub struct LiveChangeStats {
+    /// Arquivos inseridos ou atualizados no catálogo
+    pub files_updated: i64,
+    /// Arquivos marcados como removidos
+    pub files_deleted: i64,
+    pub errors_count: i32,
+    /// Um `.b2ignore` mudou: os filtros precisam ser reaplicados com um scan completo
+    pub needs_rescan: bool,
+}
+
+/// Estado de um arquivo no catálogo antes da varredura, usado para decidir
+/// se o hash precisa ser recalculado
+struct CatalogSnapshot {
+    file_size: i64,
+    content_hash: Option<String>,
+    modified_at: Option<NaiveDateTime>,
+    /// `None` para arquivos catalogados antes da detecção de MIME
+    mime_category: Option<MimeCategory>,
+    /// Hash do arquivo quando o texto em `file_content` foi extraído
+    content_indexed_hash: Option<String>,
+}
+
+/// Arquivo aceito pelo walker, aguardando hash
+struct PendingFile {
+    path: PathBuf,
+    depth: i32,
+    file_size: i64,
+    created_at: Option<NaiveDateTime>,
+    modified_at: Option<NaiveDateTime>,
+    accessed_at: Option<NaiveDateTime>,
+    /// `None` fora de sistemas Unix
+    unix: Option<UnixMetadata>,
+    /// Destino de um link simbólico catalogado sem ser seguido
+    symlink_target: Option<PathBuf>,
+    /// `None` para arquivos ainda não catalogados
+    existing: Option<CatalogSnapshot>,
+}
+
+/// Arquivo pronto para ser gravado pelo escritor
+struct CatalogRecord {
+    file: PendingFile,
+    content_hash: Option<String>,
+    /// `None` quando o tipo salvo no catálogo foi reaproveitado
+    mime: Option<MimeDetection>,
+    /// Texto extraído; `None` quando não extraído nesta varredura
+    content: Option<String>,
+    /// Chaves de `file_metadata` para `file_catalog.metadata`
+    metadata: serde_json::Value,
+}
+
+/// Diretório visitado pelo walker
+struct DirectoryNode {
+    stats: DirectoryStats,
+    depth: i32,
+    /// Índice do pai na lista devolvida por `walk`
+    parent: Option<usize>,
+}
+
+/// Contadores de um worker de hash, somados ao `ScanStats` no final
+#[derive(Default)]
+struct HashStats {
+    files_hashed: i64,
+    files_hash_skipped: i64,
+    hash_mismatches: i64,
+    errors_count: i32,
+    /// Arquivos de categorias excluídas (o walker já os contou em `files_scanned`)
+    skipped_files: i64,
+}
+
+/// Quando um arquivo inalterado tem o hash recalculado mesmo assim
+#[derive(Clone)]
+struct RehashPolicy {
+    force: bool,
+    sample_rate: f64,
+    scan_job_id: Option<Uuid>,
+}
+
+impl RehashPolicy {
+    /// A amostragem é determinística por (arquivo, scan): estável dentro de uma
+    /// varredura, mas cada scan sorteia arquivos diferentes, então com taxa `r`
+    /// cada arquivo é reverificado em média a cada `1/r` scans.
+    fn applies(&self, path: &Path) -> bool {
+        self.force || in_paranoid_sample(path, self.scan_job_id, self.sample_rate)
+    }
+}
+
+/// Worker de hash: consome a fila do walker até ela fechar.
+///
+/// Arquivos com tamanho e mtime iguais ao catálogo reaproveitam o hash salvo
+/// (salvo `RehashPolicy`). Um arquivo que não pode ser lido (ex.: removido
+/// depois de listado) é contado como erro e fica fora do lote.
+///
+/// O tipo MIME é detectado antes do hash, para que arquivos de categorias
+/// excluídas nem sejam lidos por inteiro; arquivos inalterados reaproveitam a
+/// categoria do catálogo. Com `content` definido, o texto dos arquivos aceitos
+/// é extraído quando o hash difere daquele do texto já salvo.
+///
+/// Links simbólicos não seguidos não têm hash nem conteúdo: só o tipo
+/// `inode/symlink` e os metadados POSIX.
+async fn hash_worker(
+    queue: Arc<Mutex<mpsc::Receiver<PendingFile>>>,
+    out: mpsc::Sender<CatalogRecord>,
+    policy: RehashPolicy,
+    content: Option<ContentPolicy>,
+    exclude_mime: Vec<MimeCategory>,
+    capture_xattrs: bool,
+) -> HashStats {
+    let mut stats = HashStats::default();
+
+    loop {
+        let next = queue.lock().await.recv().await;
+        let Some(file) = next else { break };
+
+        let metadata = catalog_metadata(&file, capture_xattrs).await;
+
+        if file.symlink_target.is_some() {
+            if exclude_mime.contains(&MimeCategory::Other) {
+                stats.skipped_files += 1;
+                continue;
+            }
+            let record = CatalogRecord {
+                file,
+                content_hash: None,
+                mime: Some(mime_detect::symlink()),
+                content: None,
+                metadata,
+            };
+            if out.send(record).await.is_err() {
+                break;
+            }
+            continue;
+        }
+
+        let previous_hash = file.existing.as_ref().and_then(|existing| existing.content_hash.clone());
+        let unchanged = file.existing.as_ref().is_some_and(|existing| {
+            existing.file_size == file.file_size
+                && existing.modified_at == file.modified_at
+                && existing.content_hash.is_some()
+        });
+
+        let reuse_hash = unchanged && !policy.applies(&file.path);
+
+        let stored_category = file.existing.as_ref().and_then(|existing| existing.mime_category);
+        let mime = match stored_category.filter(|_| reuse_hash) {
+            Some(_) => None,
+            None => match mime_detect::detect_file(&file.path).await {
+                Ok(mime) => Some(mime),
+                Err(e) => {
+                    warn!(path = %file.path.display(), error = %e, "Erro ao detectar tipo do arquivo");
+                    stats.errors_count += 1;
+                    continue;
+                }
+            },
+        };
+        if let Some(mime) = mime.as_ref().filter(|mime| mime.extension_mime.is_some()) {
+            debug!(
+                path = %file.path.display(),
+                detected = %mime.mime_type,
+                extension = ?mime.extension_mime,
+                "Conteúdo não corresponde à extensão"
+            );
+        }
+        let category = mime.as_ref().map(|mime| mime.category).or(stored_category);
+        if category.is_some_and(|category| exclude_mime.contains(&category)) {
+            debug!(path = %file.path.display(), category = ?category, "Arquivo ignorado pela categoria");
+            stats.skipped_files += 1;
+            continue;
+        }
+
+        let content_hash = if reuse_hash {
+            debug!(path = %file.path.display(), "Arquivo inalterado, reaproveitando hash do catálogo");
+            stats.files_hash_skipped += 1;
+            previous_hash
+        } else {
+            let hash = match calculate_file_hash(&file.path).await {
+                Ok(hash) => hash,
+                Err(e) => {
+                    warn!(path = %file.path.display(), error = %e, "Erro ao calcular hash");
+                    stats.errors_count += 1;
+                    continue;
+                }
+            };
+            stats.files_hashed += 1;
+
+            if unchanged && previous_hash.as_deref() != Some(hash.as_str()) {
+                warn!(
+                    path = %file.path.display(),
+                    previous_hash = ?previous_hash,
+                    hash = %hash,
+                    "Conteúdo mudou sem alterar tamanho/mtime (possível corrupção)"
+                );
+                stats.hash_mismatches += 1;
+            }
+            Some(hash)
+        };
+
+        let indexed_hash = file.existing.as_ref().and_then(|existing| existing.content_indexed_hash.as_deref());
+        let needs_content = content
+            .as_ref()
+            .is_some_and(|content| content.accepts(&file.path, file.file_size))
+            && content_hash.is_some()
+            && indexed_hash != content_hash.as_deref();
+        let text = match content.as_ref() {
+            Some(policy) if needs_content => match content_extract::extract_text(&file.path, policy.max_bytes()).await {
+                Ok(text) => Some(text),
+                Err(e) => {
+                    // O arquivo continua catalogado, só sem conteúdo pesquisável
+                    warn!(path = %file.path.display(), error = %e, "Erro ao extrair conteúdo");
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        // Escritor parou (erro no banco): o erro é reportado por ele
+        let record = CatalogRecord {
+            file,
+            content_hash,
+            mime,
+            content: text,
+            metadata,
+        };
+        if out.send(record).await.is_err() {
+            break;
+        }
+    }
+
+    stats
+}
+
+/// Metadados POSIX de um arquivo para `file_catalog.metadata`.
+///
+/// Falha ao ler os xattrs não impede a catalogação: o arquivo fica só sem eles.
+async fn catalog_metadata(file: &PendingFile, capture_xattrs: bool) -> serde_json::Value {
+    let mut metadata = file_metadata::catalog_metadata(file.unix.as_ref(), file.symlink_target.as_deref());
+    if !capture_xattrs {
+        return metadata;
+    }
+
+    let path = file.path.clone();
+    match tokio::task::spawn_blocking(move || file_metadata::read_xattrs(&path)).await {
+        Ok(Ok(xattrs)) if !xattrs.is_empty() => {
+            metadata[file_metadata::XATTRS_KEY] = serde_json::json!(xattrs);
+        }
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => warn!(path = %file.path.display(), error = %e, "Erro ao ler atributos estendidos"),
+        Err(e) => warn!(path = %file.path.display(), error = %e, "Erro ao ler atributos estendidos"),
+    }
+    metadata
+}
+
+/// Calcula o hash SHA256 de um arquivo
+///
+/// Mesmo algoritmo usado para preencher `file_catalog.content_hash`, então o
+/// resultado pode ser comparado diretamente com o catálogo (ex.: verificação
+/// de restore).
+pub async fn calculate_file_hash(path: &Path) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    use std::io::Read;
+
+    debug!(path = %path.display(), "Calculando hash do arquivo");
+
+    // Leitura e SHA256 são CPU/IO bloqueantes: rodam fora das threads do runtime
+    let owned_path = path.to_path_buf();
+    let hash_result = tokio::task::spawn_blocking(move || -> std::io::Result<String> {
+        let mut file = std::fs::File::open(&owned_path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 64 * 1024]; // Buffer de 64KB
+
+        loop {
+            let bytes_read = file.read(&mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..bytes_read]);
+        }
+
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await??;
+
+    debug!(path = %path.display(), hash = %hash_result, "Hash calculado com sucesso");
+
+    Ok(hash_result)
+}
+
+/// Sorteio determinístico de um arquivo para reverificação, com probabilidade `rate`
+fn in_paranoid_sample(path: &Path, scan_job_id: Option<Uuid>, rate: f64) -> bool {
+    if rate <= 0.0 {
+        return false;
+    }
+    if rate >= 1.0 {
+        return true;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    scan_job_id.hash(&mut hasher);
+    (hasher.finish() as f64 / u64::MAX as f64) < rate
 }
 
 /// Converte SystemTime para NaiveDateTime
+///
+/// Trunca em microssegundos, a precisão do `TIMESTAMP` do PostgreSQL; assim o
+/// mtime lido do disco é comparável ao que está no catálogo.
 fn system_time_to_datetime(time: SystemTime) -> Option<chrono::NaiveDateTime> {
     time.duration_since(SystemTime::UNIX_EPOCH)
         .ok()
         .and_then(|d| {
             let timestamp = d.as_secs() as i64;
-            let nanos = d.subsec_nanos();
+            let nanos = d.subsec_micros() * 1_000;
             chrono::DateTime::from_timestamp(timestamp, nanos).map(|dt| dt.naive_utc())
         })
 }
 
-/// Busca arquivos no catálogo
-pub async fn search_files(
-    _pool: &PgPool,
-    query: Option<String>,
-    extension: Option<String>,
-    min_size: Option<i64>,
-    max_size: Option<i64>,
-    limit: i64,
-) -> Result<Vec<CatalogedFile>, sqlx::Error> {
-    let mut sql = String::from(
-        "SELECT * FROM file_catalog WHERE is_active = TRUE"
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
 
-    if let Some(q) = query {
-        sql.push_str(&format!(" AND file_name ILIKE '%{}%'", q));
+    #[test]
+    fn test_system_time_truncated_to_micros() {
+        let time = SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        let dt = system_time_to_datetime(time).unwrap();
+        assert_eq!(dt.and_utc().timestamp_subsec_nanos(), 123_456_000);
     }
 
-    if let Some(ext) = extension {
-        sql.push_str(&format!(" AND extension = '{}'", ext));
-    }
+    #[test]
+    fn test_schedule_config_keeps_scanner_defaults() {
+        let now = chrono::Utc::now();
+        let schedule = ScanSchedule {
+            id: Uuid::new_v4(),
+            name: "Nightly".to_string(),
+            description: None,
+            root_path: "/data".to_string(),
+            recursive: false,
+            max_depth: Some(3),
+            exclude_patterns: vec![],
+            cron_expression: "0 0 2 * * *".to_string(),
+            enabled: true,
+            last_run_at: None,
+            last_run_status: None,
+            last_scan_job_id: None,
+            next_run_at: None,
+            total_runs: 0,
+            successful_runs: 0,
+            failed_runs: 0,
+            success_rate: 0.0,
+            created_at: now,
+            updated_at: now,
+        };
 
-    if let Some(min) = min_size {
-        sql.push_str(&format!(" AND file_size >= {}", min));
+        let config = ScanConfig::from_schedule(&schedule);
+        assert_eq!(config.root_path, PathBuf::from("/data"));
+        assert!(!config.recursive);
+        assert_eq!(config.max_depth, Some(3));
+        // Os padrões do agendamento substituem os embutidos em `ScanConfig::default()`
+        assert!(config.exclude_patterns.is_empty());
+        assert!(config.ignore_templates.is_empty());
+        assert!(!config.index_content);
     }
 
-    if let Some(max) = max_size {
-        sql.push_str(&format!(" AND file_size <= {}", max));
+    #[test]
+    fn test_paranoid_sample_bounds() {
+        let path = Path::new("/data/file.bin");
+        let scan = Some(Uuid::new_v4());
+        assert!(!in_paranoid_sample(path, scan, 0.0));
+        assert!(in_paranoid_sample(path, scan, 1.0));
+        // Mesmo arquivo, mesmo scan: mesma decisão
+        assert_eq!(in_paranoid_sample(path, scan, 0.5), in_paranoid_sample(path, scan, 0.5));
     }
 
-    sql.push_str(&format!(" ORDER BY modified_at DESC LIMIT {}", limit));
-
-    // TODO: Usar query builder ou prepared statements adequados
-    // Esta é uma versão simplificada para demonstração
-
-    Ok(vec![])
-}
\ No newline at end of file
+    #[test]
+    fn test_paranoid_sample_rate_is_respected() {
+        let scan = Some(Uuid::new_v4());
+        let sampled = (0..10_000)
+            .filter(|i| in_paranoid_sample(Path::new(&format!("/data/{}", i)), scan, 0.1))
+            .count();
+        assert!((700..1300).contains(&sampled), "sampled {} of 10000", sampled);
+    }
+}
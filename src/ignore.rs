@@ -0,0 +1,787 @@
+//! Padrões de inclusão/exclusão no estilo `.gitignore`.
+//!
+//! Usado pelo `FileScanner` para filtrar arquivos e podar diretórios inteiros
+//! sem descer neles. Semântica seguida (a mesma do git):
+//! - `*` e `?` não atravessam `/`; `[abc]`/`[!a-z]` são classes de caracteres
+//! - `**/x`, `x/**` e `a/**/b` atravessam qualquer número de diretórios
+//! - padrão sem `/` (exceto no final) casa com o nome em qualquer profundidade;
+//!   com `/` no início ou no meio, é relativo à raiz do scan
+//! - `/` no final casa apenas com diretórios
+//! - `!` no início nega o padrão; o último padrão que casar decide
+//! - linhas vazias e começando com `#` são ignoradas
+//!
+//! Arquivos `.b2ignore` podem aparecer em qualquer diretório da origem. Suas
+//! regras valem só abaixo do diretório onde estão e, por virem depois das
+//! regras dos pais, têm prioridade sobre elas. Para o backup as mesmas regras
+//! são traduzidas para `--filter` do rclone (`to_rclone_filters`), de forma
+//! que catálogo e upload enxerguem o mesmo conjunto de arquivos. Como o rclone
+//! usa a primeira regra que casar e não sabe que um arquivo dentro de um
+//! diretório excluído não pode ser reincluído, os diretórios excluídos
+//! encontrados por `collect_effective` entram como regras explícitas.
+
+use crate::models::EffectiveFilterRule;
+use regex::Regex;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+/// Nome do arquivo de exclusões por diretório
+pub const IGNORE_FILE_NAME: &str = ".b2ignore";
+
+/// Origem das regras vindas da configuração (não de um `.b2ignore`)
+pub const CONFIG_SOURCE: &str = "config";
+
+/// Um padrão compilado
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    regex: Regex,
+    negated: bool,
+    dir_only: bool,
+    /// Padrão tem `/` no início ou no meio (relativo à base)
+    anchored: bool,
+    /// Glob sem `!`, `/` inicial e `/` final
+    glob: String,
+    /// Linha original
+    pattern: String,
+    /// Diretório (relativo à raiz, normalizado) onde a regra vale; vazio = raiz
+    base: String,
+    /// `config` ou caminho relativo do `.b2ignore`
+    source: String,
+}
+
+impl IgnoreRule {
+    /// Padrões do rclone equivalentes ao glob, já relativos à raiz
+    fn rclone_targets(&self) -> Vec<String> {
+        let glob = rclone_glob(&self.glob);
+        let (glob, anywhere) = match glob.strip_prefix("**/") {
+            Some(rest) => (rest.to_string(), true),
+            None => (glob.clone(), !self.anchored),
+        };
+        let base = if self.base.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", rclone_literal(&self.base))
+        };
+
+        // Sem `/` inicial o rclone casa pelo final do caminho, em qualquer nível
+        let targets = match (anywhere, base.is_empty()) {
+            (true, true) => vec![glob],
+            (true, false) => vec![format!("/{}**/{}", base, glob)],
+            (false, _) => vec![format!("/{}{}", base, glob)],
+        };
+
+        // No git `a/**/b` também casa `a/b`; no rclone `**` precisa de um diretório
+        targets.into_iter().flat_map(|t| expand_double_star(&t)).collect()
+    }
+}
+
+/// Conjunto de padrões no estilo `.gitignore`
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreMatcher {
+    rules: Vec<IgnoreRule>,
+    /// Diretórios podados por `collect_effective` (relativos à raiz, normalizados)
+    excluded_dirs: Vec<String>,
+}
+
+impl IgnoreMatcher {
+    /// Compila uma lista de padrões.
+    ///
+    /// Padrões inválidos (ex.: `[z-a]`) são descartados com um warning, para
+    /// que um padrão ruim numa configuração salva não impeça o scan.
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let mut matcher = Self::default();
+        matcher.add_patterns(patterns, Path::new(""), CONFIG_SOURCE);
+        matcher
+    }
+
+    /// Acrescenta padrões que valem apenas abaixo de `base`.
+    ///
+    /// Por entrarem depois dos existentes, vencem os padrões anteriores que
+    /// casarem com o mesmo caminho.
+    ///
+    /// # Argumentos
+    /// * `patterns` - Linhas no formato `.gitignore`
+    /// * `base` - Diretório relativo à raiz do scan onde os padrões valem
+    /// * `source` - Identificação da origem, exibida em `rules()`
+    pub fn add_patterns<S: AsRef<str>>(&mut self, patterns: &[S], base: &Path, source: &str) {
+        let base = normalize(base);
+        for p in patterns {
+            match parse_rule(p.as_ref()) {
+                Ok(Some(mut rule)) => {
+                    rule.base = base.clone();
+                    rule.source = source.to_string();
+                    self.rules.push(rule);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!(pattern = p.as_ref(), source, error = %e, "Padrão de exclusão inválido ignorado");
+                }
+            }
+        }
+    }
+
+    /// Lê o `.b2ignore` de `dir`, se existir, e devolve uma cópia do matcher
+    /// com as regras dele valendo a partir de `rel_dir`.
+    ///
+    /// # Retorna
+    /// * `Ok(None)` - O diretório não tem `.b2ignore`
+    /// * `Ok(Some(matcher))` - Matcher estendido
+    pub async fn extended_with_dir(&self, dir: &Path, rel_dir: &Path) -> std::io::Result<Option<Self>> {
+        let content = match tokio::fs::read_to_string(dir.join(IGNORE_FILE_NAME)).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let source = rel_dir.join(IGNORE_FILE_NAME);
+        let lines: Vec<&str> = content.lines().collect();
+        let mut matcher = self.clone();
+        matcher.add_patterns(&lines, rel_dir, &normalize(&source));
+        Ok(Some(matcher))
+    }
+
+    /// Regras na ordem em que são avaliadas (a última que casar decide)
+    pub fn rules(&self) -> Vec<EffectiveFilterRule> {
+        self.rules
+            .iter()
+            .map(|rule| EffectiveFilterRule {
+                source: rule.source.clone(),
+                pattern: rule.pattern.clone(),
+                base: rule.base.clone(),
+                negated: rule.negated,
+                dir_only: rule.dir_only,
+            })
+            .collect()
+    }
+
+    /// Traduz as regras para argumentos `--filter` do rclone.
+    ///
+    /// O rclone usa a primeira regra que casar, então as regras saem em dois blocos:
+    /// 1. Diretórios excluídos (`- dir/**`), antes de qualquer `+`, porque um
+    ///    arquivo dentro de um diretório excluído não pode ser reincluído. Uma
+    ///    regra de exclusão que nenhum `!` posterior pode anular vira um padrão
+    ///    genérico; as demais só valem pelos diretórios que `collect_effective`
+    ///    encontrou podados, já que o rclone não sabe desfazer a exclusão de um
+    ///    diretório reincluído.
+    /// 2. Arquivos, em ordem invertida (a última regra do `.gitignore` vira a
+    ///    primeira do rclone); regras terminadas em `/` não entram aqui.
+    ///
+    /// # Retorna
+    /// Regras no formato `- padrão`/`+ padrão`, uma por `--filter`
+    pub fn to_rclone_filters(&self) -> Vec<String> {
+        let mut filters = Vec::new();
+
+        let mut generic = Vec::new();
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.negated || self.rules[i + 1..].iter().any(|later| later.negated) {
+                continue;
+            }
+            for target in rule.rclone_targets() {
+                filters.push(format!("- {}/**", target));
+            }
+            generic.push(rule);
+        }
+        for dir in &self.excluded_dirs {
+            let covered = generic
+                .iter()
+                .any(|rule| relative_to_base(dir, &rule.base).is_some_and(|rel| rule.regex.is_match(rel)));
+            if !covered {
+                filters.push(format!("- /{}/**", rclone_literal(dir)));
+            }
+        }
+
+        for rule in self.rules.iter().rev().filter(|rule| !rule.dir_only) {
+            let sign = if rule.negated { '+' } else { '-' };
+            for target in rule.rclone_targets() {
+                filters.push(format!("{} {}", sign, target));
+            }
+        }
+
+        filters
+    }
+
+    /// `true` se não há nenhum padrão
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Verifica apenas o próprio caminho, sem olhar os diretórios pais.
+    ///
+    /// É o que um walker que já poda diretórios excluídos precisa: se chegou
+    /// até aqui, nenhum pai foi excluído.
+    ///
+    /// # Argumentos
+    /// * `rel_path` - Caminho relativo à raiz do scan
+    /// * `is_dir` - Se o caminho é um diretório (para padrões terminados em `/`)
+    pub fn matches(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let path = normalize(rel_path);
+        self.matches_normalized(&path, is_dir)
+    }
+
+    /// Verifica o caminho e todos os diretórios pais: um arquivo dentro de um
+    /// diretório excluído também está excluído, como no git.
+    pub fn is_ignored(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let path = normalize(rel_path);
+        let mut end = 0;
+        while let Some(pos) = path[end..].find('/') {
+            end += pos;
+            if self.matches_normalized(&path[..end], true) {
+                return true;
+            }
+            end += 1;
+        }
+        self.matches_normalized(&path, is_dir)
+    }
+
+    fn matches_normalized(&self, path: &str, is_dir: bool) -> bool {
+        if path.is_empty() {
+            return false;
+        }
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                (is_dir || !rule.dir_only)
+                    && relative_to_base(path, &rule.base).is_some_and(|rel| rule.regex.is_match(rel))
+            })
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+/// Percorre `root` reunindo os `.b2ignore` de todos os diretórios não
+/// excluídos, sobre as regras base (templates e padrões da configuração).
+///
+/// Diretórios excluídos são podados como no scanner, então um `.b2ignore`
+/// dentro deles não tem efeito. Uma origem inexistente não tem `.b2ignore`.
+///
+/// # Argumentos
+/// * `root` - Diretório de origem
+/// * `base` - Regras válidas em toda a árvore
+pub async fn collect_effective(root: &Path, base: IgnoreMatcher) -> std::io::Result<IgnoreMatcher> {
+    let mut matcher = base;
+    let mut pending = vec![PathBuf::new()];
+
+    while let Some(rel_dir) = pending.pop() {
+        let dir = root.join(&rel_dir);
+        if let Some(extended) = matcher.extended_with_dir(&dir, &rel_dir).await? {
+            matcher = extended;
+        }
+
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let mut subdirs = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let rel_path = rel_dir.join(entry.file_name());
+            if entry.file_type().await?.is_dir() {
+                if matcher.matches(&rel_path, true) {
+                    matcher.excluded_dirs.push(normalize(&rel_path));
+                } else {
+                    subdirs.push(rel_path);
+                }
+            }
+        }
+        // Pilha: ordem reversa para visitar em ordem alfabética, pais antes dos filhos
+        subdirs.sort();
+        pending.extend(subdirs.into_iter().rev());
+    }
+
+    Ok(matcher)
+}
+
+/// Parte de `path` abaixo de `base`, ou `None` se estiver fora dela
+fn relative_to_base<'a>(path: &'a str, base: &str) -> Option<&'a str> {
+    if base.is_empty() {
+        return Some(path);
+    }
+    path.strip_prefix(base)?.strip_prefix('/')
+}
+
+/// Ajusta um glob do git para a sintaxe de filtros do rclone: `{}` são
+/// alternativas no rclone e `[!...]` é escrito `[^...]`
+fn rclone_glob(glob: &str) -> String {
+    let mut out = String::with_capacity(glob.len());
+    let mut prev = None;
+    for c in glob.chars() {
+        match c {
+            '{' | '}' if prev != Some('\\') => {
+                out.push('\\');
+                out.push(c);
+            }
+            '!' if prev == Some('[') => out.push('^'),
+            _ => out.push(c),
+        }
+        prev = Some(c);
+    }
+    out
+}
+
+/// Caminho literal como glob do rclone (metacaracteres escapados)
+fn rclone_literal(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for c in path.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '{' | '}' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Variantes de um padrão do rclone com cada `/**/` valendo também como `/`
+fn expand_double_star(pattern: &str) -> Vec<String> {
+    match pattern.find("/**/") {
+        Some(pos) => {
+            let rest = &pattern[pos + 4..];
+            expand_double_star(rest)
+                .into_iter()
+                .flat_map(|tail| {
+                    [
+                        format!("{}/{}", &pattern[..pos], tail),
+                        format!("{}/**/{}", &pattern[..pos], tail),
+                    ]
+                })
+                .collect()
+        }
+        None => vec![pattern.to_string()],
+    }
+}
+
+/// Caminho relativo com `/` como separador, sem `.` e sem `/` inicial
+fn normalize(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Interpreta uma linha; `Ok(None)` para comentários e linhas vazias
+fn parse_rule(line: &str) -> Result<Option<IgnoreRule>, regex::Error> {
+    let mut pattern = line.trim_end();
+    if pattern.is_empty() || pattern.starts_with('#') {
+        return Ok(None);
+    }
+
+    let negated = pattern.starts_with('!');
+    if let Some(rest) = pattern.strip_prefix('!') {
+        pattern = rest;
+    } else if let Some(rest) = pattern.strip_prefix('\\').filter(|r| r.starts_with(['!', '#'])) {
+        // `\!` e `\#` são literais
+        pattern = rest;
+    }
+
+    let dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+        return Ok(None);
+    }
+
+    let mut regex = String::from("^");
+    if !anchored {
+        regex.push_str("(?:.*/)?");
+    }
+    regex.push_str(&glob_to_regex(pattern));
+    regex.push('$');
+
+    Ok(Some(IgnoreRule {
+        regex: Regex::new(&regex)?,
+        negated,
+        dir_only,
+        anchored,
+        glob: pattern.to_string(),
+        pattern: line.trim_end().to_string(),
+        base: String::new(),
+        source: CONFIG_SOURCE.to_string(),
+    }))
+}
+
+/// Traduz o glob (já sem `!`, `/` inicial e `/` final) para regex
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                let at_segment_start = i == 0 || chars[i - 1] == '/';
+                let next = chars.get(i + 2);
+                if at_segment_start && next == Some(&'/') {
+                    // `**/`: zero ou mais diretórios
+                    out.push_str("(?:.*/)?");
+                    i += 3;
+                } else if at_segment_start && next.is_none() {
+                    // `/**` no final: tudo dentro
+                    out.push_str(".*");
+                    i += 2;
+                } else {
+                    // `**` no meio de um nome vale como `*`
+                    out.push_str("[^/]*");
+                    i += 2;
+                }
+            }
+            '*' => {
+                out.push_str("[^/]*");
+                i += 1;
+            }
+            '?' => {
+                out.push_str("[^/]");
+                i += 1;
+            }
+            '[' => match class_end(&chars, i) {
+                Some(end) => {
+                    out.push('[');
+                    let mut j = i + 1;
+                    if matches!(chars[j], '!' | '^') {
+                        out.push('^');
+                        j += 1;
+                    }
+                    for &c in &chars[j..end] {
+                        if matches!(c, '\\' | '[' | ']' | '&' | '~') {
+                            out.push('\\');
+                        }
+                        out.push(c);
+                    }
+                    out.push(']');
+                    i = end + 1;
+                }
+                None => {
+                    out.push_str("\\[");
+                    i += 1;
+                }
+            },
+            '\\' if i + 1 < chars.len() => {
+                out.push_str(&regex::escape(&chars[i + 1].to_string()));
+                i += 2;
+            }
+            c => {
+                out.push_str(&regex::escape(&c.to_string()));
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+/// Posição do `]` que fecha a classe aberta em `start`
+fn class_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut j = start + 1;
+    if matches!(chars.get(j), Some('!') | Some('^')) {
+        j += 1;
+    }
+    // `]` logo após a abertura é literal
+    if chars.get(j) == Some(&']') {
+        j += 1;
+    }
+    (j..chars.len()).find(|&k| chars[k] == ']')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignored(patterns: &[&str], path: &str, is_dir: bool) -> bool {
+        IgnoreMatcher::new(patterns).is_ignored(Path::new(path), is_dir)
+    }
+
+    #[test]
+    fn test_basename_matches_at_any_depth() {
+        assert!(ignored(&["*.tmp"], "a.tmp", false));
+        assert!(ignored(&["*.tmp"], "deep/nested/a.tmp", false));
+        assert!(!ignored(&["*.tmp"], "a.tmp.txt", false));
+        assert!(ignored(&["node_modules"], "web/node_modules", true));
+    }
+
+    #[test]
+    fn test_star_does_not_cross_directories() {
+        assert!(ignored(&["docs/*.md"], "docs/a.md", false));
+        assert!(!ignored(&["docs/*.md"], "docs/sub/a.md", false));
+    }
+
+    #[test]
+    fn test_slash_anchors_to_root() {
+        assert!(ignored(&["/build"], "build", true));
+        assert!(!ignored(&["/build"], "src/build", true));
+        assert!(ignored(&["src/gen"], "src/gen", true));
+        assert!(!ignored(&["src/gen"], "lib/src/gen", true));
+    }
+
+    #[test]
+    fn test_trailing_slash_only_matches_directories() {
+        assert!(ignored(&["target/"], "target", true));
+        assert!(ignored(&["target/"], "crates/x/target", true));
+        assert!(!ignored(&["target/"], "target", false));
+    }
+
+    #[test]
+    fn test_files_inside_ignored_directory_are_ignored() {
+        assert!(ignored(&["target/"], "target/debug/b2cli", false));
+        assert!(ignored(&[".git/*"], ".git/HEAD", false));
+        // `.git/*` não casa com o próprio diretório
+        assert!(!ignored(&[".git/*"], ".git", true));
+    }
+
+    #[test]
+    fn test_double_star() {
+        let m = IgnoreMatcher::new(&["**/logs", "a/**/z.txt", "cache/**"]);
+        assert!(m.is_ignored(Path::new("logs"), true));
+        assert!(m.is_ignored(Path::new("x/y/logs"), true));
+        assert!(m.is_ignored(Path::new("a/z.txt"), false));
+        assert!(m.is_ignored(Path::new("a/b/c/z.txt"), false));
+        assert!(m.is_ignored(Path::new("cache/a/b"), false));
+        assert!(!m.is_ignored(Path::new("cache"), true));
+    }
+
+    #[test]
+    fn test_negation_last_match_wins() {
+        let m = IgnoreMatcher::new(&["*.log", "!keep.log"]);
+        assert!(m.is_ignored(Path::new("x.log"), false));
+        assert!(!m.is_ignored(Path::new("keep.log"), false));
+
+        let m = IgnoreMatcher::new(&["!keep.log", "*.log"]);
+        assert!(m.is_ignored(Path::new("keep.log"), false));
+    }
+
+    #[test]
+    fn test_question_mark_and_classes() {
+        assert!(ignored(&["file?.txt"], "file1.txt", false));
+        assert!(!ignored(&["file?.txt"], "file10.txt", false));
+        assert!(ignored(&["*.[oa]"], "lib.a", false));
+        assert!(!ignored(&["*.[!oa]"], "lib.a", false));
+        assert!(ignored(&["*.[!oa]"], "lib.c", false));
+    }
+
+    #[test]
+    fn test_comments_escapes_and_blank_lines() {
+        let m = IgnoreMatcher::new(&["# comentário", "", "\\#literal", "a\\*b"]);
+        assert!(m.is_ignored(Path::new("#literal"), false));
+        assert!(m.is_ignored(Path::new("a*b"), false));
+        assert!(!m.is_ignored(Path::new("aXb"), false));
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_skipped() {
+        let m = IgnoreMatcher::new(&["[z-a]", "*.tmp"]);
+        assert!(m.is_ignored(Path::new("x.tmp"), false));
+    }
+
+    #[test]
+    fn test_nested_rules_apply_below_their_directory() {
+        let mut m = IgnoreMatcher::new(&["*.log"]);
+        m.add_patterns(&["!keep.log", "/cache/"], Path::new("app"), "app/.b2ignore");
+
+        assert!(m.is_ignored(Path::new("x.log"), false));
+        assert!(m.is_ignored(Path::new("lib/keep.log"), false));
+        assert!(!m.is_ignored(Path::new("app/keep.log"), false));
+        assert!(!m.is_ignored(Path::new("app/sub/keep.log"), false));
+        assert!(m.is_ignored(Path::new("app/other.log"), false));
+        // `/cache/` é relativo a `app`
+        assert!(m.is_ignored(Path::new("app/cache"), true));
+        assert!(!m.is_ignored(Path::new("cache"), true));
+        assert!(!m.is_ignored(Path::new("app/sub/cache"), true));
+    }
+
+    #[test]
+    fn test_rclone_filters_order() {
+        let mut m = IgnoreMatcher::new(&["*.log", "target/"]);
+        m.add_patterns(&["!keep.log", "/out/*.bin"], Path::new("app"), "app/.b2ignore");
+
+        // `*.log` e `target/` têm um `!` depois: só valem para diretórios pelos
+        // diretórios podados de `collect_effective` (nenhum aqui)
+        assert_eq!(
+            m.to_rclone_filters(),
+            vec![
+                "- /app/out/*.bin/**",
+                "- /app/out/*.bin",
+                "+ /app/keep.log",
+                "+ /app/**/keep.log",
+                "- *.log",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rclone_glob_syntax() {
+        let m = IgnoreMatcher::new(&["**/build", "*.[!ch]", "{x}", "docs/**/draft.md"]);
+        assert_eq!(
+            m.to_rclone_filters(),
+            vec![
+                "- build/**",
+                "- *.[^ch]/**",
+                "- \\{x\\}/**",
+                "- /docs/draft.md/**",
+                "- /docs/**/draft.md/**",
+                "- /docs/draft.md",
+                "- /docs/**/draft.md",
+                "- \\{x\\}",
+                "- *.[^ch]",
+                "- build",
+            ]
+        );
+    }
+
+    /// Regex equivalente a um padrão de filtro do rclone
+    fn rclone_regex(pattern: &str) -> Regex {
+        let (mut re, pattern) = match pattern.strip_prefix('/') {
+            Some(rest) => (String::from("^"), rest),
+            None => (String::from("(?:^|/)"), pattern),
+        };
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '\\' => {
+                    re.push_str(&regex::escape(&chars[i + 1].to_string()));
+                    i += 1;
+                }
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    re.push_str(".*");
+                    i += 1;
+                }
+                '*' => re.push_str("[^/]*"),
+                '?' => re.push_str("[^/]"),
+                '[' => {
+                    let end = i + chars[i..].iter().position(|&c| c == ']').unwrap();
+                    re.extend(&chars[i..=end]);
+                    i = end;
+                }
+                c => re.push_str(&regex::escape(&c.to_string())),
+            }
+            i += 1;
+        }
+        re.push('$');
+        Regex::new(&re).unwrap()
+    }
+
+    /// O rclone usa a primeira regra que casar; sem nenhuma, o arquivo entra
+    fn rclone_excludes(filters: &[String], path: &str) -> bool {
+        filters
+            .iter()
+            .find(|filter| rclone_regex(&filter[2..]).is_match(path))
+            .is_some_and(|filter| filter.starts_with('-'))
+    }
+
+    fn files_below(root: &Path, rel_dir: &Path, out: &mut Vec<PathBuf>) {
+        for entry in std::fs::read_dir(root.join(rel_dir)).unwrap().flatten() {
+            let rel_path = rel_dir.join(entry.file_name());
+            if entry.file_type().unwrap().is_dir() {
+                files_below(root, &rel_path, out);
+            } else {
+                out.push(rel_path);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rclone_filters_agree_with_matcher() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let files = [
+            "a.log",
+            "src/keep.log",
+            "src/x.log",
+            "keep.log/inner.txt",
+            "keep.log/inner.log",
+            "old.log/f.txt",
+            "target/debug/app",
+            "target/debug/x.log",
+            "build/out.bin",
+            "build/keep.txt",
+            "cache/z.txt",
+            "lib/cache/z.txt",
+            "app/cache/a.txt",
+            "app/cache/b.tmp",
+            "app/sub/cache/c.txt",
+            "app/n.tmp",
+            "n.tmp",
+            "docs/draft.md",
+            "docs/a/b/draft.md",
+            "docs/readme.md",
+            "we[1]d/f.txt",
+        ];
+        for file in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "x").unwrap();
+        }
+        std::fs::write(root.join(IGNORE_FILE_NAME), "!keep.log\nbuild/\nwe*d/\n!build/keep.txt\n").unwrap();
+        std::fs::write(root.join("app/.b2ignore"), "!cache/\n*.tmp\n").unwrap();
+
+        let base = IgnoreMatcher::new(&["*.log", "target/", "cache/", "docs/**/draft.md", "!target/"]);
+        let m = collect_effective(root, base).await.unwrap();
+        let filters = m.to_rclone_filters();
+
+        let mut all = Vec::new();
+        files_below(root, Path::new(""), &mut all);
+        assert_eq!(all.len(), files.len() + 2);
+        for rel_path in all {
+            let path = normalize(&rel_path);
+            assert_eq!(
+                rclone_excludes(&filters, &path),
+                m.is_ignored(&rel_path, false),
+                "{} with filters {:?}",
+                path,
+                filters
+            );
+        }
+        // Diretório excluído não é reincluído por `!build/keep.txt`
+        assert!(m.is_ignored(Path::new("build/keep.txt"), false));
+        assert!(!m.is_ignored(Path::new("app/cache/a.txt"), false));
+    }
+
+    #[test]
+    fn test_rules_report_source() {
+        let mut m = IgnoreMatcher::new(&["*.tmp"]);
+        m.add_patterns(&["# comentário", "!a.tmp"], Path::new("docs"), "docs/.b2ignore");
+
+        let rules = m.rules();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].source, CONFIG_SOURCE);
+        assert_eq!(rules[1].source, "docs/.b2ignore");
+        assert_eq!(rules[1].pattern, "!a.tmp");
+        assert_eq!(rules[1].base, "docs");
+        assert!(rules[1].negated);
+    }
+
+    #[tokio::test]
+    async fn test_collect_effective_reads_nested_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("photos/raw")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        std::fs::write(root.join(IGNORE_FILE_NAME), "*.raw\n").unwrap();
+        std::fs::write(root.join("photos/.b2ignore"), "!*.raw\n").unwrap();
+        // Dentro de diretório excluído: não deve ser lido
+        std::fs::write(root.join("node_modules/.b2ignore"), "*\n").unwrap();
+
+        let m = collect_effective(root, IgnoreMatcher::new(&["node_modules/"])).await.unwrap();
+
+        let sources: Vec<String> = m.rules().into_iter().map(|r| r.source).collect();
+        assert_eq!(sources, vec![CONFIG_SOURCE, ".b2ignore", "photos/.b2ignore"]);
+        assert!(m.is_ignored(Path::new("a.raw"), false));
+        assert!(!m.is_ignored(Path::new("photos/raw/a.raw"), false));
+    }
+
+    #[tokio::test]
+    async fn test_collect_effective_missing_root() {
+        let m = collect_effective(Path::new("/nonexistent/b2cli/source"), IgnoreMatcher::new(&["*.tmp"]))
+            .await
+            .unwrap();
+        assert_eq!(m.rules().len(), 1);
+    }
+
+    #[test]
+    fn test_empty_matcher_ignores_nothing() {
+        let m = IgnoreMatcher::new::<&str>(&[]);
+        assert!(m.is_empty());
+        assert!(!m.is_ignored(Path::new("anything"), false));
+    }
+}
@@ -0,0 +1,291 @@
+//! Biblioteca de templates de exclusão por linguagem/projeto.
+//!
+//! Backup jobs e configurações de scan referenciam templates pelo nome
+//! (`"ignore_templates": ["rust", "node"]`). O nome especial `auto` aplica os
+//! templates detectados pelos arquivos marcadores (`Cargo.toml`,
+//! `package.json`, ...) na raiz da origem. Os padrões dos templates entram
+//! antes dos padrões explícitos e dos `.b2ignore`, que portanto podem
+//! sobrescrevê-los com `!`.
+
+use crate::ignore::{IgnoreMatcher, CONFIG_SOURCE};
+use crate::models::IgnoreTemplateInfo;
+use std::path::Path;
+
+/// Nome especial: detectar os templates pela origem
+pub const AUTO: &str = "auto";
+
+/// Um template embutido
+#[derive(Debug)]
+pub struct IgnoreTemplate {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Arquivos na raiz que indicam o tipo de projeto (`*.ext` casa pela extensão)
+    pub markers: &'static [&'static str],
+    /// Padrões no estilo `.gitignore`
+    pub patterns: &'static [&'static str],
+}
+
+/// Templates disponíveis, em ordem de exibição
+pub const TEMPLATES: &[IgnoreTemplate] = &[
+    IgnoreTemplate {
+        name: "rust",
+        description: "Rust/Cargo build output",
+        markers: &["Cargo.toml"],
+        patterns: &["target/", "**/*.rs.bk"],
+    },
+    IgnoreTemplate {
+        name: "node",
+        description: "Node.js dependencies, caches and build output",
+        markers: &["package.json"],
+        patterns: &[
+            "node_modules/",
+            ".npm/",
+            ".yarn/cache/",
+            ".pnpm-store/",
+            ".next/",
+            ".nuxt/",
+            ".parcel-cache/",
+            ".turbo/",
+            "coverage/",
+            "npm-debug.log*",
+            "yarn-error.log*",
+        ],
+    },
+    IgnoreTemplate {
+        name: "python",
+        description: "Python bytecode, virtualenvs and tool caches",
+        markers: &["pyproject.toml", "setup.py", "requirements.txt", "Pipfile"],
+        patterns: &[
+            "__pycache__/",
+            "*.py[cod]",
+            ".venv/",
+            "venv/",
+            ".tox/",
+            ".nox/",
+            ".pytest_cache/",
+            ".mypy_cache/",
+            ".ruff_cache/",
+            "*.egg-info/",
+            ".eggs/",
+        ],
+    },
+    IgnoreTemplate {
+        name: "jvm",
+        description: "Java/Kotlin/Scala build output (Maven, Gradle, sbt)",
+        markers: &["pom.xml", "build.gradle", "build.gradle.kts", "build.sbt"],
+        patterns: &["*.class", ".gradle/", "build/", "out/", "/target/", ".bsp/", ".metals/"],
+    },
+    IgnoreTemplate {
+        name: "dotnet",
+        description: ".NET build output and package caches",
+        markers: &["*.csproj", "*.fsproj", "*.sln", "global.json"],
+        patterns: &["bin/", "obj/", "*.user", ".vs/", "packages/", "TestResults/"],
+    },
+    IgnoreTemplate {
+        name: "go",
+        description: "Go vendored modules and binaries",
+        markers: &["go.mod"],
+        patterns: &["vendor/", "*.test", "*.out"],
+    },
+    IgnoreTemplate {
+        name: "macos-junk",
+        description: "macOS metadata files",
+        markers: &[],
+        patterns: &[".DS_Store", "._*", ".AppleDouble/", ".Spotlight-V100/", ".Trashes/", ".fseventsd/"],
+    },
+    IgnoreTemplate {
+        name: "windows-junk",
+        description: "Windows metadata files",
+        markers: &[],
+        patterns: &["Thumbs.db", "ehthumbs.db", "desktop.ini", "$RECYCLE.BIN/"],
+    },
+    IgnoreTemplate {
+        name: "ide",
+        description: "Editor and IDE state",
+        markers: &[],
+        patterns: &[".idea/", ".vscode/", "*.swp", "*.swo", "*~", ".project", ".classpath", ".settings/"],
+    },
+];
+
+/// Busca um template pelo nome
+pub fn find(name: &str) -> Option<&'static IgnoreTemplate> {
+    TEMPLATES.iter().find(|t| t.name == name)
+}
+
+/// Valida os nomes referenciados por um job ou configuração.
+///
+/// # Retorna
+/// * `Err(String)` - Mensagem listando os nomes desconhecidos
+pub fn validate(names: &[String]) -> Result<(), String> {
+    let unknown: Vec<&str> = names
+        .iter()
+        .map(String::as_str)
+        .filter(|name| *name != AUTO && find(name).is_none())
+        .collect();
+
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unknown ignore template(s): {}. Available: {}, {}",
+            unknown.join(", "),
+            AUTO,
+            TEMPLATES.iter().map(|t| t.name).collect::<Vec<_>>().join(", ")
+        ))
+    }
+}
+
+/// Detecta o tipo de projeto pelos arquivos marcadores na raiz da origem.
+///
+/// Uma origem inexistente ou ilegível não tem marcadores.
+pub async fn detect(root: &Path) -> Vec<&'static str> {
+    let mut file_names = Vec::new();
+    if let Ok(mut entries) = tokio::fs::read_dir(root).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            file_names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+
+    TEMPLATES
+        .iter()
+        .filter(|t| {
+            t.markers
+                .iter()
+                .any(|marker| file_names.iter().any(|name| marker_matches(marker, name)))
+        })
+        .map(|t| t.name)
+        .collect()
+}
+
+fn marker_matches(marker: &str, file_name: &str) -> bool {
+    match marker.strip_prefix('*') {
+        Some(suffix) => file_name.ends_with(suffix) && file_name.len() > suffix.len(),
+        None => file_name == marker,
+    }
+}
+
+/// Resolve os nomes para templates, trocando `auto` pelos detectados.
+/// Nomes desconhecidos são ignorados (ver `validate`); repetidos aparecem uma vez.
+pub async fn resolve(names: &[String], root: &Path) -> Vec<&'static IgnoreTemplate> {
+    let mut resolved: Vec<&'static IgnoreTemplate> = Vec::new();
+    for name in names {
+        let candidates = if name == AUTO {
+            detect(root).await.into_iter().filter_map(find).collect()
+        } else {
+            find(name).into_iter().collect::<Vec<_>>()
+        };
+        for template in candidates {
+            if !resolved.iter().any(|t| t.name == template.name) {
+                resolved.push(template);
+            }
+        }
+    }
+    resolved
+}
+
+/// Monta o matcher base de uma origem: templates primeiro, depois os padrões
+/// explícitos, para que estes possam negar algo que um template exclui.
+///
+/// # Argumentos
+/// * `root` - Origem (usada para `auto`)
+/// * `template_names` - Templates referenciados
+/// * `patterns` - Padrões explícitos da configuração
+pub async fn base_matcher(root: &Path, template_names: &[String], patterns: &[String]) -> IgnoreMatcher {
+    let mut matcher = IgnoreMatcher::default();
+    for template in resolve(template_names, root).await {
+        matcher.add_patterns(template.patterns, Path::new(""), &format!("template:{}", template.name));
+    }
+    matcher.add_patterns(patterns, Path::new(""), CONFIG_SOURCE);
+    matcher
+}
+
+/// Templates detectados na origem que ainda não estão aplicados
+pub async fn suggestions(root: &Path, template_names: &[String]) -> Vec<String> {
+    if template_names.iter().any(|n| n == AUTO) {
+        return vec![];
+    }
+    detect(root)
+        .await
+        .into_iter()
+        .filter(|name| !template_names.iter().any(|n| n == name))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Representação da biblioteca para a API
+pub fn list() -> Vec<IgnoreTemplateInfo> {
+    TEMPLATES
+        .iter()
+        .map(|t| IgnoreTemplateInfo {
+            name: t.name.to_string(),
+            description: t.description.to_string(),
+            markers: t.markers.iter().map(|m| m.to_string()).collect(),
+            patterns: t.patterns.iter().map(|p| p.to_string()).collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requested_templates_exist() {
+        for name in ["rust", "node", "python", "jvm", "dotnet", "macos-junk", "ide"] {
+            assert!(find(name).is_some(), "missing template {}", name);
+        }
+    }
+
+    #[test]
+    fn test_template_patterns_compile() {
+        for template in TEMPLATES {
+            let matcher = IgnoreMatcher::new(template.patterns);
+            assert_eq!(matcher.rules().len(), template.patterns.len(), "template {}", template.name);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_names() {
+        assert!(validate(&["rust".to_string(), AUTO.to_string()]).is_ok());
+        let err = validate(&["rust".to_string(), "cobol".to_string()]).unwrap_err();
+        assert!(err.contains("cobol"));
+    }
+
+    #[test]
+    fn test_marker_matches() {
+        assert!(marker_matches("Cargo.toml", "Cargo.toml"));
+        assert!(marker_matches("*.csproj", "App.csproj"));
+        assert!(!marker_matches("*.csproj", ".csproj"));
+        assert!(!marker_matches("Cargo.toml", "Cargo.lock"));
+    }
+
+    #[tokio::test]
+    async fn test_detect_and_auto() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+
+        assert_eq!(detect(dir.path()).await, vec!["rust", "node"]);
+
+        let names = vec!["ide".to_string(), AUTO.to_string(), "rust".to_string()];
+        let resolved: Vec<&str> = resolve(&names, dir.path()).await.iter().map(|t| t.name).collect();
+        assert_eq!(resolved, vec!["ide", "rust", "node"]);
+
+        assert_eq!(suggestions(dir.path(), &["rust".to_string()]).await, vec!["node"]);
+        assert!(suggestions(dir.path(), &[AUTO.to_string()]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_explicit_patterns_override_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let matcher = base_matcher(
+            dir.path(),
+            &["rust".to_string()],
+            &["!target/".to_string()],
+        )
+        .await;
+
+        assert!(!matcher.is_ignored(Path::new("target/debug/app"), false));
+        assert_eq!(matcher.rules()[0].source, "template:rust");
+    }
+}
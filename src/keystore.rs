@@ -0,0 +1,87 @@
+//! Carregamento da chave mestra a partir do ambiente e do banco.
+//!
+//! O salt, os parâmetros do Argon2 e o key-check da senha mestra ficam na
+//! tabela `crypto_settings`; este módulo lê e grava essa configuração e
+//! instala a chave do processo. A criptografia em si fica em `crypto`, sem
+//! acesso ao banco.
+
+use crate::crypto::{self, CryptoError, CryptoManager};
+use crate::db;
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+/// Monta o gerenciador da chave mestra a partir do ambiente.
+///
+/// Ordem: `B2CLI_MASTER_KEY`, `B2CLI_MASTER_KEY_FILE` e por fim
+/// `B2CLI_MASTER_PASSWORD` (derivada com a configuração de `crypto_settings`).
+///
+/// # Retorna
+/// * `Ok(Some(manager))` - Chave carregada
+/// * `Ok(None)` - Nenhuma variável configurada
+/// * `Err(e)` - Chave inválida ou senha incorreta
+pub async fn manager_from_env(pool: &PgPool) -> Result<Option<CryptoManager>, CryptoError> {
+    if let Some(key) = crypto::master_key_from_env()? {
+        let manager = CryptoManager::new();
+        manager.init_with_key(&key).await?;
+        return Ok(Some(manager));
+    }
+
+    match std::env::var(crypto::MASTER_PASSWORD_ENV) {
+        Ok(password) => Ok(Some(manager_from_password(pool, &password).await?)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Deriva a chave mestra de uma senha com o salt e o key-check persistidos.
+///
+/// Na primeira execução gera e grava a configuração; nas seguintes uma senha
+/// diferente da original é rejeitada.
+pub async fn manager_from_password(pool: &PgPool, password: &str) -> Result<CryptoManager, CryptoError> {
+    let stored = db::get_crypto_settings(pool).await?;
+    let first_run = stored.is_none();
+
+    let crypto = CryptoManager::with_config(stored.unwrap_or_default());
+    crypto
+        .init_with_password(password)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if first_run && !db::insert_crypto_settings(pool, &crypto.config().await).await? {
+        // Outro processo gravou a configuração antes: derivar de novo com a dele
+        let stored = db::get_crypto_settings(pool)
+            .await?
+            .ok_or("Configuração de criptografia não encontrada")?;
+        let crypto = CryptoManager::with_config(stored);
+        crypto
+            .init_with_password(password)
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(crypto);
+    }
+
+    Ok(crypto)
+}
+
+/// Inicializa a chave mestra do processo a partir do ambiente.
+///
+/// Sem chave configurada as credenciais continuam sendo gravadas em texto
+/// plano (com aviso no log), e credenciais já criptografadas não podem ser lidas.
+///
+/// # Retorna
+/// * `Ok(true)` - Chave mestra carregada
+/// * `Ok(false)` - Nenhuma chave configurada
+/// * `Err(e)` - Chave configurada mas inválida, ou senha incorreta
+pub async fn init_master_key(pool: &PgPool) -> Result<bool, CryptoError> {
+    let Some(manager) = manager_from_env(pool).await? else {
+        warn!(
+            "Nenhuma chave mestra configurada ({}, {} ou {}); credenciais dos provedores ficarão em texto plano",
+            crypto::MASTER_KEY_ENV, crypto::MASTER_KEY_FILE_ENV, crypto::MASTER_PASSWORD_ENV
+        );
+        return Ok(false);
+    };
+
+    crypto::set_master(manager)?;
+
+    info!("Chave mestra carregada, credenciais dos provedores serão criptografadas");
+    Ok(true)
+}
@@ -11,6 +11,8 @@ use std::path::StripPrefixError;
 use std::fmt;
 
 pub mod backup_worker;
+pub mod cancel;
+pub mod content_extract;
 pub mod db;
 pub mod logging;
 pub mod models;
@@ -18,14 +20,34 @@ pub mod rclone;
 pub mod routes;
 pub mod scheduler;
 pub mod archiver;
+pub mod file_metadata;
 pub mod file_scanner;
+pub mod ignore;
+pub mod ignore_templates;
+pub mod keystore;
+pub mod mime_detect;
+pub mod live_catalog;
+pub mod search;
+pub mod backup_triggers;
 pub mod config_manager;
+pub mod connectivity;
 pub mod crypto;
+pub mod remotes;
+pub mod restore;
+pub mod run_locks;
+pub mod run_queue;
+pub mod scan_schedules;
+pub mod schedule_registry;
+pub mod verification;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: PgPool,
     pub scheduler: Arc<JobScheduler>,
+    pub live_catalog: Arc<live_catalog::LiveCatalog>,
+    pub backup_triggers: Arc<backup_triggers::BackupTriggers>,
+    pub run_queue: Arc<run_queue::RunQueue>,
+    pub schedules: Arc<schedule_registry::ScheduleRegistry>,
 }
 
 #[derive(Debug)]
@@ -121,6 +143,18 @@ impl From<serde_json::Error> for AppError {
     }
 }
 
+impl From<search::SearchError> for AppError {
+    fn from(err: search::SearchError) -> AppError {
+        match err {
+            search::SearchError::InvalidQuery(_) => AppError::BadRequest(err.to_string()),
+            search::SearchError::Database(e) => AppError::SqlxError(e),
+            search::SearchError::AlreadyOpen(_) | search::SearchError::Index(_) => {
+                AppError::InternalServerError(err.to_string())
+            }
+        }
+    }
+}
+
 impl From<StripPrefixError> for AppError {
     fn from(err: StripPrefixError) -> AppError {
         AppError::StripPrefixError(err)
@@ -0,0 +1,348 @@
+//! Modo live do catálogo de arquivos.
+//!
+//! Configurações de scan com `live_mode` mantêm um watcher (`notify`) na raiz
+//! e aplicam criações, alterações, renomeações e remoções ao `file_catalog` e
+//! ao `file_history` sem varrer a árvore inteira:
+//!
+//! - Eventos são agregados por caminho e aplicados em lote depois de
+//!   `DEBOUNCE` sem novos eventos, ou no máximo `MAX_DELAY` após o primeiro
+//! - Cada caminho é reavaliado no disco (`FileScanner::apply_changes`), então
+//!   a ordem e o tipo dos eventos de um mesmo caminho não importam
+//! - Se a fila de eventos enche ou o sistema operacional avisa que perdeu
+//!   eventos (overflow), o watcher faz um scan completo para reconciliar
+//!
+//! Ao iniciar, o watcher também faz um scan completo, cobrindo o que mudou
+//! enquanto ninguém observava a raiz.
+
+use crate::db;
+use crate::file_scanner::{FileScanner, ScanConfig};
+use crate::models::LiveWatcherStatus;
+use chrono::{DateTime, Utc};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Tempo sem novos eventos antes de aplicar as mudanças acumuladas
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Atraso máximo entre o primeiro evento de um lote e sua aplicação
+pub const MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// Eventos aguardando o debounce; acima disso o watcher entra em overflow
+pub const EVENT_QUEUE_CAPACITY: usize = 10_000;
+
+/// Estados de um watcher expostos em `/readiness`
+const STATE_STARTING: &str = "starting";
+const STATE_WATCHING: &str = "watching";
+const STATE_RESCANNING: &str = "rescanning";
+const STATE_FAILED: &str = "failed";
+
+/// Estado compartilhado entre o callback do `notify`, a task do watcher e `/readiness`
+struct WatcherShared {
+    scan_config_id: Uuid,
+    root_path: String,
+    state: Mutex<&'static str>,
+    /// Eventos no canal, ainda não lidos pela task
+    queued_events: AtomicUsize,
+    /// Caminhos agregados aguardando o debounce
+    pending_paths: AtomicUsize,
+    /// Evento perdido (fila cheia ou aviso do SO): exige scan completo
+    overflowed: AtomicBool,
+    events_received: AtomicU64,
+    changes_applied: AtomicU64,
+    rescans: AtomicU64,
+    last_event_at: Mutex<Option<DateTime<Utc>>>,
+    last_error: Mutex<Option<String>>,
+}
+
+impl WatcherShared {
+    fn set_state(&self, state: &'static str) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    fn set_error(&self, message: String) {
+        *self.last_error.lock().unwrap() = Some(message);
+    }
+
+    fn status(&self) -> LiveWatcherStatus {
+        LiveWatcherStatus {
+            scan_config_id: self.scan_config_id,
+            root_path: self.root_path.clone(),
+            state: self.state.lock().unwrap().to_string(),
+            queue_depth: self.queued_events.load(Ordering::Relaxed) + self.pending_paths.load(Ordering::Relaxed),
+            overflowed: self.overflowed.load(Ordering::Relaxed),
+            events_received: self.events_received.load(Ordering::Relaxed),
+            changes_applied: self.changes_applied.load(Ordering::Relaxed),
+            rescans: self.rescans.load(Ordering::Relaxed),
+            last_event_at: *self.last_event_at.lock().unwrap(),
+            last_error: self.last_error.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Um watcher ativo. Descartá-lo para de observar a raiz; a task termina ao
+/// esvaziar a fila e encerra o scan job da sessão.
+struct LiveWatcher {
+    shared: Arc<WatcherShared>,
+    _watcher: RecommendedWatcher,
+}
+
+/// Registro dos watchers do modo live, um por configuração de scan
+pub struct LiveCatalog {
+    pool: PgPool,
+    watchers: Mutex<HashMap<Uuid, LiveWatcher>>,
+}
+
+impl LiveCatalog {
+    /// Cria um registro vazio
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            watchers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Inicia os watchers de todas as configurações ativas com `live_mode`.
+    ///
+    /// Falhas em uma configuração (ex.: raiz inexistente) são registradas no
+    /// log e não impedem as demais.
+    ///
+    /// # Retorna
+    /// * Quantidade de watchers iniciados
+    pub async fn start_all(&self) -> Result<usize, sqlx::Error> {
+        let configs = db::list_live_scan_configs(&self.pool).await?;
+
+        let mut started = 0;
+        for config in configs {
+            let scan_config = ScanConfig::from_record(&config);
+            match self.watch(config.id, scan_config) {
+                Ok(()) => started += 1,
+                Err(e) => error!(scan_config_id = %config.id, error = %e, "Falha ao iniciar watcher live"),
+            }
+        }
+
+        Ok(started)
+    }
+
+    /// Passa a observar a raiz de uma configuração, substituindo um watcher
+    /// anterior da mesma configuração.
+    pub fn watch(&self, scan_config_id: Uuid, config: ScanConfig) -> Result<(), notify::Error> {
+        let shared = Arc::new(WatcherShared {
+            scan_config_id,
+            root_path: config.root_path.to_string_lossy().to_string(),
+            state: Mutex::new(STATE_STARTING),
+            queued_events: AtomicUsize::new(0),
+            pending_paths: AtomicUsize::new(0),
+            overflowed: AtomicBool::new(false),
+            events_received: AtomicU64::new(0),
+            changes_applied: AtomicU64::new(0),
+            rescans: AtomicU64::new(0),
+            last_event_at: Mutex::new(None),
+            last_error: Mutex::new(None),
+        });
+
+        let (tx, rx) = mpsc::channel::<Vec<PathBuf>>(EVENT_QUEUE_CAPACITY);
+        let callback_shared = shared.clone();
+        let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
+            let shared = &callback_shared;
+            let paths = match res {
+                Ok(event) if event.need_rescan() => {
+                    shared.overflowed.store(true, Ordering::Relaxed);
+                    event.paths
+                }
+                Ok(event) if matches!(event.kind, EventKind::Access(_)) => return,
+                Ok(event) => event.paths,
+                Err(e) => {
+                    warn!(root = %shared.root_path, error = %e, "Erro no watcher live; agendando scan completo");
+                    shared.overflowed.store(true, Ordering::Relaxed);
+                    vec![]
+                }
+            };
+
+            shared.events_received.fetch_add(1, Ordering::Relaxed);
+            *shared.last_event_at.lock().unwrap() = Some(Utc::now());
+            // Mesmo sem caminhos o envio acorda a task, que confere `overflowed`
+            match tx.try_send(paths) {
+                Ok(()) => {
+                    shared.queued_events.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(_) => {
+                    // Canal cheio: o evento se perde, só um scan completo reconcilia
+                    shared.overflowed.store(true, Ordering::Relaxed);
+                }
+            }
+        })?;
+
+        let mode = if config.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher.watch(&config.root_path, mode)?;
+
+        tokio::spawn(run_watcher(self.pool.clone(), config, shared.clone(), rx));
+        info!(scan_config_id = %scan_config_id, root = %shared.root_path, "Watcher live iniciado");
+
+        self.watchers.lock().unwrap().insert(
+            scan_config_id,
+            LiveWatcher {
+                shared,
+                _watcher: watcher,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Para de observar a raiz de uma configuração
+    ///
+    /// # Retorna
+    /// * `true` se havia um watcher para a configuração
+    pub fn unwatch(&self, scan_config_id: Uuid) -> bool {
+        self.watchers.lock().unwrap().remove(&scan_config_id).is_some()
+    }
+
+    /// Estado de todos os watchers, para `/readiness`
+    pub fn statuses(&self) -> Vec<LiveWatcherStatus> {
+        let mut statuses: Vec<_> = self
+            .watchers
+            .lock()
+            .unwrap()
+            .values()
+            .map(|watcher| watcher.shared.status())
+            .collect();
+        statuses.sort_by(|a, b| a.root_path.cmp(&b.root_path));
+        statuses
+    }
+}
+
+/// Task de um watcher: scan inicial, depois debounce e aplicação dos eventos
+async fn run_watcher(
+    pool: PgPool,
+    config: ScanConfig,
+    shared: Arc<WatcherShared>,
+    mut rx: mpsc::Receiver<Vec<PathBuf>>,
+) {
+    full_rescan(&pool, &config, &shared).await;
+
+    let mut scanner = FileScanner::new(pool.clone(), config.clone());
+    if let Err(e) = scanner.start_live_session(Some(shared.scan_config_id)).await {
+        error!(root = %shared.root_path, error = %e, "Falha ao iniciar sessão live");
+        shared.set_error(e.to_string());
+        shared.set_state(STATE_FAILED);
+        return;
+    }
+    shared.set_state(STATE_WATCHING);
+
+    let mut pending: HashSet<PathBuf> = HashSet::new();
+    let mut first_event_at: Option<Instant> = None;
+
+    loop {
+        let received = match first_event_at {
+            None => rx.recv().await,
+            Some(first) => {
+                let wait = DEBOUNCE.min(MAX_DELAY.saturating_sub(first.elapsed()));
+                match tokio::time::timeout(wait, rx.recv()).await {
+                    Ok(received) => received,
+                    Err(_) => {
+                        // Silêncio pelo tempo do debounce (ou atraso máximo atingido)
+                        flush(&scanner, &pool, &config, &shared, &mut pending).await;
+                        first_event_at = None;
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let Some(paths) = received else {
+            break;
+        };
+        shared.queued_events.fetch_sub(1, Ordering::Relaxed);
+        pending.extend(paths);
+        first_event_at.get_or_insert_with(Instant::now);
+        shared.pending_paths.store(pending.len(), Ordering::Relaxed);
+
+        if shared.overflowed.load(Ordering::Relaxed) {
+            pending.clear();
+            shared.pending_paths.store(0, Ordering::Relaxed);
+            first_event_at = None;
+            full_rescan(&pool, &config, &shared).await;
+        } else if first_event_at.is_some_and(|first| first.elapsed() >= MAX_DELAY) {
+            flush(&scanner, &pool, &config, &shared, &mut pending).await;
+            first_event_at = None;
+        }
+    }
+
+    if let Err(e) = scanner.finish_live_session().await {
+        warn!(root = %shared.root_path, error = %e, "Falha ao encerrar sessão live");
+    }
+}
+
+/// Aplica os caminhos acumulados ao catálogo
+async fn flush(
+    scanner: &FileScanner,
+    pool: &PgPool,
+    config: &ScanConfig,
+    shared: &WatcherShared,
+    pending: &mut HashSet<PathBuf>,
+) {
+    let mut paths: Vec<PathBuf> = pending.drain().collect();
+    shared.pending_paths.store(0, Ordering::Relaxed);
+    if shared.overflowed.load(Ordering::Relaxed) {
+        full_rescan(pool, config, shared).await;
+        return;
+    }
+    if paths.is_empty() {
+        return;
+    }
+    paths.sort();
+
+    debug!(root = %shared.root_path, paths = paths.len(), "Aplicando mudanças do watcher live");
+    match scanner.apply_changes(&paths).await {
+        Ok(result) => {
+            shared
+                .changes_applied
+                .fetch_add((result.files_updated + result.files_deleted) as u64, Ordering::Relaxed);
+            if result.needs_rescan {
+                full_rescan(pool, config, shared).await;
+            }
+        }
+        Err(e) => {
+            // Mudanças descartadas: só um scan completo garante o catálogo em dia
+            error!(root = %shared.root_path, error = %e, "Falha ao aplicar mudanças do watcher live");
+            shared.set_error(e.to_string());
+            full_rescan(pool, config, shared).await;
+        }
+    }
+}
+
+/// Scan completo da raiz, vinculado à configuração
+async fn full_rescan(pool: &PgPool, config: &ScanConfig, shared: &WatcherShared) {
+    shared.set_state(STATE_RESCANNING);
+    shared.overflowed.store(false, Ordering::Relaxed);
+    shared.rescans.fetch_add(1, Ordering::Relaxed);
+    info!(root = %shared.root_path, "Watcher live: scan completo da raiz");
+
+    let mut scanner = FileScanner::new(pool.clone(), config.clone());
+    match scanner.start_scan().await {
+        Ok(scan_job_id) => {
+            let _ = sqlx::query!(
+                "UPDATE scan_jobs SET scan_config_id = $1 WHERE id = $2",
+                shared.scan_config_id,
+                scan_job_id
+            )
+            .execute(pool)
+            .await;
+        }
+        Err(e) => {
+            error!(root = %shared.root_path, error = %e, "Watcher live: falha no scan completo");
+            shared.set_error(e.to_string());
+        }
+    }
+
+    shared.set_state(STATE_WATCHING);
+}
@@ -1,18 +1,25 @@
 use axum::{
-    routing::{get, post},
+    routing::{get, patch, post},
     Router,
 };
 use b2cli::{
+    backup_triggers::BackupTriggers,
+    crypto,
+    keystore,
     db,
+    live_catalog::LiveCatalog,
     logging,
-    models::{BackupJob, NewBackupJob, BackupSchedule, NewBackupSchedule, UpdateBackupJob, UpdateBackupSchedule, BackupExecutionLog, NewBackupExecutionLog, ErrorResponse, CloudProvider, NewCloudProvider, UpdateCloudProvider, ConnectivityTestResult},
-    routes::{self, backups::*, health::*, readiness::*, logs::*, archive::*, providers::*, files::{create_scan_config, run_scan_config, list_scan_configs, list_scan_jobs, find_duplicate_files, get_scan_job_status}},
+    run_queue::{RunLimits, RunQueue},
+    schedule_registry::ScheduleRegistry,
+    models::{BackupJob, NewBackupJob, BackupSchedule, NewBackupSchedule, UpdateBackupJob, UpdateBackupSchedule, BackupExecutionLog, NewBackupExecutionLog, ErrorResponse, CloudProvider, NewCloudProvider, UpdateCloudProvider, ConnectivityTestResult, RestoreRequest, RestoreExecutionLog, BackupVerification, BackupVerificationFile, BackupVerificationReport, JobRestorabilityScore, FleetRestorabilityScore, EffectiveFilterRule, MappingEffectiveFilters, EffectiveFilters, IgnoreTemplateInfo, LiveWatcherStatus, BackupTriggerMode, FileSearchHit, SearchFacetCount, FileSearchFacets, FileSearchResponse, MimeCategory, MimeCategoryStats, MimeTypeStats, FileTypeStatsResponse, FileAuditCheck, FileAuditEntry, FileAuditResponse, BackupRun, BackupRunReport, OverlapPolicy, ScheduledJobStatus, SchedulerStatus, ScheduleKind, ScanSchedule, NewScanSchedule, UpdateScanSchedule},
+    routes::{self, backups::*, health::*, scan_schedules::*, readiness::*, logs::*, archive::*, providers::*, restore::*, runs::*, verification::*, ignore_templates::*, files::{create_scan_config, run_scan_config, list_scan_configs, list_scan_jobs, find_duplicate_files, get_scan_job_status, cancel_scan_job, set_scan_config_live_mode, list_file_changes, search_files, reindex_search, file_type_stats, audit_files}},
     scheduler,
+    search,
     AppState,
 };
 use sqlx::postgres::PgPoolOptions;
 use std::sync::Arc;
-use tracing::{debug, error, info};
+use tracing::{error, info};
 use utoipa::OpenApi;
 use utoipa_redoc::{Redoc, Servable};
 use utoipa_swagger_ui::SwaggerUi;
@@ -28,6 +35,10 @@ use utoipa_swagger_ui::SwaggerUi;
         routes::backups::delete_backup,
         routes::backups::update_backup,
         routes::backups::run_backup,
+        routes::runs::list_job_runs,
+        routes::runs::get_run,
+        routes::runs::cancel_run,
+        routes::backups::get_effective_filters,
         routes::backups::create_schedule,
         routes::backups::get_schedule,
         routes::backups::delete_schedule,
@@ -36,6 +47,11 @@ use utoipa_swagger_ui::SwaggerUi;
         routes::backups::patch_schedule,
         routes::backups::list_all_schedules,
         routes::backups::scheduler_status,
+        routes::restore::restore_backup,
+        routes::restore::list_backup_restores,
+        routes::restore::get_restore,
+        routes::verification::get_backup_verification,
+        routes::verification::get_fleet_restorability,
         routes::logs::list_logs,
         routes::logs::get_log,
         routes::logs::create_log,
@@ -62,14 +78,30 @@ use utoipa_swagger_ui::SwaggerUi;
         routes::files::list_scan_jobs,
         routes::files::find_duplicate_files,
         routes::files::get_scan_job_status,
+        routes::files::cancel_scan_job,
+        routes::files::set_scan_config_live_mode,
+        routes::files::list_file_changes,
+        routes::files::search_files,
+        routes::files::file_type_stats,
+        routes::files::audit_files,
+        routes::files::reindex_search,
+        routes::scan_schedules::create_scan_schedule,
+        routes::scan_schedules::list_scan_schedules,
+        routes::scan_schedules::get_scan_schedule,
+        routes::scan_schedules::update_scan_schedule,
+        routes::scan_schedules::delete_scan_schedule,
+        routes::scan_schedules::toggle_scan_schedule,
+        routes::ignore_templates::list_ignore_templates,
     ),
     components(
-        schemas(ReadinessResponse, DependencyStatus, BackupJob, NewBackupJob, BackupSchedule, NewBackupSchedule, UpdateBackupJob, UpdateBackupSchedule, BackupExecutionLog, NewBackupExecutionLog, routes::logs::LogsStatsResponse, ErrorResponse, CloudProvider, NewCloudProvider, UpdateCloudProvider, ConnectivityTestResult, routes::files::CreateScanConfig)
+        schemas(ReadinessResponse, DependencyStatus, BackupJob, NewBackupJob, BackupSchedule, NewBackupSchedule, UpdateBackupJob, UpdateBackupSchedule, BackupExecutionLog, NewBackupExecutionLog, routes::logs::LogsStatsResponse, ErrorResponse, CloudProvider, NewCloudProvider, UpdateCloudProvider, ConnectivityTestResult, routes::files::CreateScanConfig, routes::files::SetLiveMode, RestoreRequest, RestoreExecutionLog, BackupVerification, BackupVerificationFile, BackupVerificationReport, JobRestorabilityScore, FleetRestorabilityScore, EffectiveFilterRule, MappingEffectiveFilters, EffectiveFilters, IgnoreTemplateInfo, LiveWatcherStatus, BackupTriggerMode, FileSearchHit, SearchFacetCount, FileSearchFacets, FileSearchResponse, MimeCategory, MimeCategoryStats, MimeTypeStats, FileTypeStatsResponse, FileAuditCheck, FileAuditEntry, FileAuditResponse, BackupRun, BackupRunReport, OverlapPolicy, ScheduledJobStatus, SchedulerStatus, ScheduleKind, ScanSchedule, NewScanSchedule, UpdateScanSchedule)
     ),
     tags(
         (name = "System", description = "System health and status endpoints"),
         (name = "Backups", description = "Backup job management endpoints"),
         (name = "Schedules", description = "Schedule management endpoints"),
+        (name = "Restore", description = "Restore files from backup destinations"),
+        (name = "Verification", description = "Post-backup restore verification and restorability scores"),
         (name = "Logs", description = "Backup execution logs and statistics"),
         (name = "Log Management", description = "Log retention, archiving and lifecycle management"),
         (name = "Cloud Providers", description = "Cloud storage provider configuration and management"),
@@ -94,6 +126,29 @@ async fn main() {
         .await
         .expect("Failed to create database pool");
 
+    // Master key for provider credentials; plaintext rows left from before it
+    // was configured are encrypted right away
+    if keystore::init_master_key(&db_pool).await.expect("Invalid master key or password") {
+        let master = crypto::master().expect("Master key not initialized");
+        let encrypted = db::encrypt_plaintext_provider_credentials(&db_pool, master)
+            .await
+            .expect("Failed to encrypt existing provider credentials");
+        if encrypted > 0 {
+            info!("Encrypted credentials of {} existing cloud provider(s)", encrypted);
+        }
+    }
+
+    // Every backup run (manual, cron or watch) goes through the persistent run queue;
+    // overlap and concurrency limits are enforced with advisory locks across instances
+    let run_queue = Arc::new(RunQueue::new(db_pool.clone()).with_limits(RunLimits::from_env()));
+    let interrupted = run_queue
+        .start(RunQueue::workers_from_env())
+        .await
+        .expect("Failed to start the backup run queue");
+    if interrupted > 0 {
+        info!("Marked {} orphaned backup run(s) as failed", interrupted);
+    }
+
     // Create the scheduler
     let scheduler = scheduler::create_scheduler()
         .await
@@ -103,58 +158,56 @@ async fn main() {
     scheduler.start().await.expect("Failed to start scheduler");
     info!("Scheduler started successfully");
 
-    // Load schedules from the database and add them to the scheduler
-    let schedules = db::list_active_schedules(&db_pool)
-        .await
-        .expect("Failed to load schedules");
-    
-    info!("Loading {} schedule(s) from database", schedules.len());
-    
-    for schedule in schedules {
-        let db_pool_clone = db_pool.clone();
-        let schedule_id = schedule.id;
-        let backup_job_id = schedule.backup_job_id;
-        let job = tokio_cron_scheduler::Job::new_async(schedule.cron_expression.as_str(), move |_uuid, _l| {
-            let db_pool = db_pool_clone.clone();
-            Box::pin(async move {
-                debug!("Starting scheduled backup for job {}", backup_job_id);
-                if let Err(e) = db::update_schedule_last_run(&db_pool, schedule_id, "running").await {
-                    error!("Failed to update schedule status: {}", e);
-                }
+    // Load backup and scan schedules from the database; the registry keeps the scheduler
+    // in sync with schedule changes and reconciles with the database every minute
+    let scheduler = Arc::new(scheduler);
+    let schedules = Arc::new(ScheduleRegistry::new(db_pool.clone(), scheduler.clone(), run_queue.clone()));
+    let loaded = schedules.reconcile().await.expect("Failed to load schedules");
+    info!("Loaded {} schedule(s) from database", loaded);
+    schedules.start_reconciliation();
 
-                let job = db::get_backup_job_by_id(&db_pool, backup_job_id)
-                    .await
-                    .unwrap();
-                if let Some(job) = job {
-                    if let Err(e) = b2cli::backup_worker::perform_backup_with_schedule(&db_pool, &job, Some(schedule_id)).await {
-                        error!("Backup failed for job {}: {}", backup_job_id, e);
-                        if let Err(e) = db::update_schedule_last_run(&db_pool, schedule_id, "failed").await {
-                            error!("Failed to update schedule status: {}", e);
-                        }
-                        return;
-                    }
+    // File search index, kept in sync with file_catalog by scans and backups;
+    // a new (or schema-upgraded) index is filled from the catalog in the background
+    match search::init() {
+        Ok(index) if index.needs_rebuild() => {
+            let pool = db_pool.clone();
+            tokio::spawn(async move {
+                match index.reindex_under(&pool, None).await {
+                    Ok(indexed) => match search::commit_global().await {
+                        Ok(()) => info!("Search index built with {} file(s)", indexed),
+                        Err(e) => error!("Failed to commit search index: {}", e),
+                    },
+                    Err(e) => error!("Failed to build search index: {}", e),
                 }
+            });
+        }
+        Ok(_) => {}
+        Err(e) => error!("Search index unavailable, GET /files/search is disabled: {}", e),
+    }
 
-                if let Err(e) = db::update_schedule_last_run(&db_pool, schedule_id, "completed").await {
-                    error!("Failed to update schedule status: {}", e);
-                }
-            })
-        });
+    // Live-mode scan configs keep a filesystem watcher on their roots
+    let live_catalog = Arc::new(LiveCatalog::new(db_pool.clone()));
+    match live_catalog.start_all().await {
+        Ok(started) if started > 0 => info!("Started {} live catalog watcher(s)", started),
+        Ok(_) => {}
+        Err(e) => error!("Failed to load live scan configs: {}", e),
+    }
 
-        if let Ok(job) = job {
-            if let Err(e) = scheduler.add(job).await {
-                error!("Failed to add schedule '{}' to scheduler: {}", schedule.name, e);
-            } else {
-                debug!("Schedule '{}' loaded successfully", schedule.name);
-            }
-        } else if let Err(e) = job {
-            error!("Failed to create job for schedule '{}' with cron '{}': {}", schedule.name, schedule.cron_expression, e);
-        }
+    // Watch-mode backup jobs run when their source paths change
+    let backup_triggers = Arc::new(BackupTriggers::new(db_pool.clone(), run_queue.clone()));
+    match backup_triggers.start_all().await {
+        Ok(started) if started > 0 => info!("Watching sources of {} backup job(s)", started),
+        Ok(_) => {}
+        Err(e) => error!("Failed to load watch-mode backup jobs: {}", e),
     }
 
     let app_state = AppState {
         db_pool,
-        scheduler: Arc::new(scheduler),
+        scheduler,
+        live_catalog,
+        backup_triggers,
+        run_queue,
+        schedules,
     };
 
     let app = Router::new()
@@ -171,6 +224,10 @@ async fn main() {
                 .delete(delete_backup),
         )
         .route("/backups/{id}/run", post(run_backup))
+        .route("/backups/{id}/runs", get(list_job_runs))
+        .route("/runs/{id}", get(get_run))
+        .route("/runs/{id}/cancel", post(cancel_run))
+        .route("/backups/{id}/effective-filters", get(get_effective_filters))
         .route(
             "/backups/{id}/schedule",
             post(create_schedule)
@@ -181,6 +238,13 @@ async fn main() {
         )
         .route("/schedules", get(list_all_schedules))
         .route("/scheduler/status", get(scheduler_status))
+        // Restore endpoints
+        .route("/backups/{id}/restore", post(restore_backup))
+        .route("/backups/{id}/restores", get(list_backup_restores))
+        .route("/restores/{id}", get(get_restore))
+        // Verification endpoints
+        .route("/backups/{id}/verification", get(get_backup_verification))
+        .route("/verification/score", get(get_fleet_restorability))
         // Logs endpoints
         .route("/logs", get(list_logs).post(create_log))
         .route("/logs/{id}", get(get_log).delete(delete_log))
@@ -206,9 +270,26 @@ async fn main() {
         // File Catalog endpoints  
         .route("/files/scan", post(create_scan_config).get(list_scan_configs))
         .route("/files/scan/jobs", get(list_scan_jobs))
+        .route("/files/scan/jobs/{id}/cancel", post(cancel_scan_job))
+        .route("/files/scan/schedule", post(create_scan_schedule))
+        .route("/files/scan/schedules", get(list_scan_schedules))
+        .route(
+            "/files/scan/schedule/{id}",
+            get(get_scan_schedule)
+                .patch(update_scan_schedule)
+                .delete(delete_scan_schedule),
+        )
+        .route("/files/scan/schedule/{id}/toggle", patch(toggle_scan_schedule))
         .route("/files/scan/{id}/run", post(run_scan_config))
+        .route("/files/scan/{id}/live", patch(set_scan_config_live_mode))
         .route("/files/scan/{id}", get(get_scan_job_status))
         .route("/files/duplicates", get(find_duplicate_files))
+        .route("/files/changes", get(list_file_changes))
+        .route("/files/search", get(search_files))
+        .route("/files/stats", get(file_type_stats))
+        .route("/files/audit", get(audit_files))
+        .route("/files/search/reindex", post(reindex_search))
+        .route("/ignore-templates", get(list_ignore_templates))
         .with_state(app_state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
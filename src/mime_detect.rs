@@ -0,0 +1,315 @@
+//! Detecção do tipo MIME de arquivos.
+//!
+//! O tipo vem primeiro do conteúdo: os bytes iniciais são comparados com as
+//! assinaturas ("magic bytes") dos formatos binários mais comuns. Sem
+//! assinatura conhecida (texto, formatos raros), vale o tipo da extensão
+//! (`mime_guess`). Quando conteúdo e extensão discordam, como um `.pdf` que é
+//! na verdade um ZIP, o conteúdo vence e a divergência é reportada em
+//! `extension_mime`.
+//!
+//! Formatos que são contêineres de outros (`.docx` é um ZIP, `.doc` um
+//! arquivo OLE, `.mov` um ISO-BMFF) não contam como divergência: nesses casos
+//! a extensão é mais específica que a assinatura.
+
+use crate::models::MimeCategory;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+/// Bytes lidos do início de cada arquivo (a assinatura do tar fica em 257)
+pub const HEAD_BYTES: usize = 1024;
+
+/// Tipo usado quando nem o conteúdo nem a extensão indicam outro
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
+/// Tipo dos links simbólicos catalogados sem seguir o destino
+pub const SYMLINK: &str = "inode/symlink";
+
+/// Assinaturas: deslocamento, bytes esperados e tipo MIME
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (0, b"II*\x00", "image/tiff"),
+    (0, b"MM\x00*", "image/tiff"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"{\\rtf", "application/rtf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"PK\x05\x06", "application/zip"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"BZh", "application/x-bzip2"),
+    (0, b"\xfd7zXZ\x00", "application/x-xz"),
+    (0, b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (0, b"Rar!\x1a\x07", "application/x-rar-compressed"),
+    (0, b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (257, b"ustar", "application/x-tar"),
+    (4, b"ftyp", "video/mp4"),
+    (0, b"\x1a\x45\xdf\xa3", "video/x-matroska"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", "application/x-ole-storage"),
+    (0, b"\x7fELF", "application/x-executable"),
+    (0, b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    (0, b"\x00asm", "application/wasm"),
+];
+
+/// Formatos RIFF, identificados pelo tipo nos bytes 8..12
+const RIFF_TYPES: &[(&[u8], &str)] = &[
+    (b"WEBP", "image/webp"),
+    (b"WAVE", "audio/wav"),
+    (b"AVI ", "video/x-msvideo"),
+];
+
+/// Extensões de código-fonte (o tipo MIME delas costuma ser só `text/plain`)
+const SOURCE_CODE_EXTENSIONS: &[&str] = &[
+    "rs", "py", "js", "jsx", "mjs", "ts", "tsx", "go", "java", "kt", "scala", "c", "h", "cpp",
+    "hpp", "cc", "cs", "rb", "php", "swift", "lua", "pl", "r", "sh", "bash", "zsh", "ps1", "sql",
+    "html", "htm", "css", "scss", "vue", "svelte",
+];
+
+/// Resultado da detecção
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimeDetection {
+    pub mime_type: String,
+    pub category: MimeCategory,
+    /// Tipo indicado pela extensão, quando o conteúdo é de outro formato
+    pub extension_mime: Option<String>,
+}
+
+/// Tipo indicado pela assinatura dos bytes iniciais
+pub fn sniff(head: &[u8]) -> Option<&'static str> {
+    if head.len() >= 12 && head.starts_with(b"RIFF") {
+        return RIFF_TYPES
+            .iter()
+            .find(|(kind, _)| &head[8..12] == *kind)
+            .map(|(_, mime)| *mime);
+    }
+    SIGNATURES
+        .iter()
+        .find(|(offset, magic, _)| head.get(*offset..offset + magic.len()) == Some(*magic))
+        .map(|(_, _, mime)| *mime)
+}
+
+/// Detecta o tipo de um arquivo a partir do caminho e dos bytes iniciais.
+///
+/// # Argumentos
+/// * `path` - Caminho do arquivo (só a extensão é usada)
+/// * `head` - Até `HEAD_BYTES` bytes do início do arquivo
+pub fn detect(path: &Path, head: &[u8]) -> MimeDetection {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase);
+    let guess = mime_guess::from_path(path);
+    let guessed = guess.first_raw().filter(|mime| *mime != OCTET_STREAM);
+
+    let (mime_type, extension_mime) = match (sniff(head), guessed) {
+        (Some(sniffed), Some(guessed)) if guess.iter_raw().any(|mime| compatible(sniffed, mime)) => {
+            (guessed, None)
+        }
+        (Some(sniffed), Some(guessed)) => (sniffed, Some(guessed.to_string())),
+        (Some(sniffed), None) => (sniffed, None),
+        (None, Some(guessed)) => (guessed, None),
+        (None, None) if looks_like_text(head) => ("text/plain", None),
+        (None, None) => (OCTET_STREAM, None),
+    };
+
+    // Um conteúdo de outro formato não é código, mesmo com extensão de código
+    let category = match extension.as_deref() {
+        Some(ext) if extension_mime.is_none() && SOURCE_CODE_EXTENSIONS.contains(&ext) => {
+            MimeCategory::SourceCode
+        }
+        _ => category_of(mime_type),
+    };
+
+    MimeDetection {
+        mime_type: mime_type.to_string(),
+        category,
+        extension_mime,
+    }
+}
+
+/// Lê o início do arquivo e detecta o tipo
+pub async fn detect_file(path: &Path) -> std::io::Result<MimeDetection> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut head = Vec::with_capacity(HEAD_BYTES);
+    file.take(HEAD_BYTES as u64).read_to_end(&mut head).await?;
+    Ok(detect(path, &head))
+}
+
+/// Tipo de um link simbólico (o conteúdo do destino não é lido)
+pub fn symlink() -> MimeDetection {
+    MimeDetection {
+        mime_type: SYMLINK.to_string(),
+        category: MimeCategory::Other,
+        extension_mime: None,
+    }
+}
+
+/// Categorias salvas como texto (ex.: `scan_configs.exclude_mime_categories`);
+/// nomes desconhecidos são descartados
+pub fn parse_categories(names: &[String]) -> Vec<MimeCategory> {
+    names.iter().filter_map(|name| MimeCategory::parse(name)).collect()
+}
+
+/// Categoria de um tipo MIME
+pub fn category_of(mime_type: &str) -> MimeCategory {
+    let (top, sub) = mime_type.split_once('/').unwrap_or((mime_type, ""));
+    match top {
+        "image" => MimeCategory::Image,
+        "video" => MimeCategory::Video,
+        "audio" => MimeCategory::Audio,
+        "text" => MimeCategory::Text,
+        _ if is_document(sub) => MimeCategory::Document,
+        _ if is_archive(sub) => MimeCategory::Archive,
+        _ if matches!(sub, "json" | "xml" | "toml" | "yaml" | "x-yaml" | "x-sql" | "javascript") => {
+            MimeCategory::Text
+        }
+        _ => MimeCategory::Other,
+    }
+}
+
+fn is_document(subtype: &str) -> bool {
+    matches!(
+        subtype,
+        "pdf" | "rtf" | "msword" | "vnd.ms-excel" | "vnd.ms-powerpoint" | "epub+zip" | "x-ole-storage"
+    ) || subtype.starts_with("vnd.openxmlformats-officedocument.")
+        || subtype.starts_with("vnd.oasis.opendocument.")
+}
+
+fn is_archive(subtype: &str) -> bool {
+    matches!(
+        subtype,
+        "zip"
+            | "gzip"
+            | "x-gzip"
+            | "x-compressed"
+            | "x-bzip2"
+            | "x-xz"
+            | "x-7z-compressed"
+            | "x-rar-compressed"
+            | "vnd.rar"
+            | "zstd"
+            | "x-tar"
+            | "java-archive"
+            | "vnd.android.package-archive"
+    )
+}
+
+/// A extensão é do mesmo formato da assinatura, ou de um formato construído
+/// sobre ela
+fn compatible(sniffed: &str, guessed: &str) -> bool {
+    if sniffed == guessed {
+        return true;
+    }
+    match sniffed {
+        "application/zip" => {
+            guessed.starts_with("application/vnd.openxmlformats-officedocument.")
+                || guessed.starts_with("application/vnd.oasis.opendocument.")
+                || matches!(
+                    guessed,
+                    "application/epub+zip"
+                        | "application/java-archive"
+                        | "application/vnd.android.package-archive"
+                )
+        }
+        "application/x-ole-storage" => matches!(
+            guessed,
+            "application/msword" | "application/vnd.ms-excel" | "application/vnd.ms-powerpoint"
+        ),
+        "application/gzip" => matches!(guessed, "application/x-gzip" | "application/x-compressed"),
+        // ISO-BMFF (`ftyp`): MP4, QuickTime, M4A, HEIC, AVIF...
+        "video/mp4" => {
+            guessed.starts_with("video/")
+                || matches!(
+                    guessed,
+                    "audio/mp4" | "audio/m4a" | "audio/x-m4a" | "image/heic" | "image/heif" | "image/avif"
+                )
+        }
+        "video/x-matroska" => matches!(guessed, "video/webm" | "audio/webm"),
+        "audio/ogg" => matches!(guessed, "video/ogg" | "application/ogg"),
+        _ => false,
+    }
+}
+
+/// Sem bytes nulos e UTF-8 válido (o último caractere pode estar cortado)
+fn looks_like_text(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PDF: &[u8] = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n";
+    const ZIP: &[u8] = b"PK\x03\x04\x14\x00\x06\x00";
+
+    #[test]
+    fn test_content_wins_over_extension() {
+        let detection = detect(Path::new("/docs/contrato.pdf"), ZIP);
+        assert_eq!(detection.mime_type, "application/zip");
+        assert_eq!(detection.category, MimeCategory::Archive);
+        assert_eq!(detection.extension_mime.as_deref(), Some("application/pdf"));
+
+        let detection = detect(Path::new("/docs/contrato.pdf"), PDF);
+        assert_eq!(detection.mime_type, "application/pdf");
+        assert_eq!(detection.category, MimeCategory::Document);
+        assert_eq!(detection.extension_mime, None);
+    }
+
+    #[test]
+    fn test_containers_keep_the_specific_type() {
+        let detection = detect(Path::new("/docs/proposta.docx"), ZIP);
+        assert_eq!(
+            detection.mime_type,
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        );
+        assert_eq!(detection.category, MimeCategory::Document);
+        assert_eq!(detection.extension_mime, None);
+
+        let detection = detect(Path::new("/videos/ferias.mov"), b"\x00\x00\x00\x14ftypqt  ");
+        assert_eq!(detection.mime_type, "video/quicktime");
+        assert_eq!(detection.extension_mime, None);
+    }
+
+    #[test]
+    fn test_fallbacks_without_signature() {
+        let code = detect(Path::new("/src/main.rs"), b"fn main() {}\n");
+        assert_eq!(code.mime_type, "text/x-rust");
+        assert_eq!(code.category, MimeCategory::SourceCode);
+
+        let notes = detect(Path::new("/notas/LEIAME"), "orçamento".as_bytes());
+        assert_eq!(notes.mime_type, "text/plain");
+        assert_eq!(notes.category, MimeCategory::Text);
+
+        let blob = detect(Path::new("/dados/dump"), &[0x00, 0x13, 0x37, 0x00]);
+        assert_eq!(blob.mime_type, OCTET_STREAM);
+        assert_eq!(blob.category, MimeCategory::Other);
+    }
+
+    #[test]
+    fn test_sniff_riff_and_offsets() {
+        assert_eq!(sniff(b"RIFF\x24\x00\x00\x00WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"RIFF\x24\x00\x00\x00WAVEfmt "), Some("audio/wav"));
+
+        let mut tar = vec![0u8; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(sniff(&tar), Some("application/x-tar"));
+        assert_eq!(sniff(b"ust"), None);
+    }
+
+    #[test]
+    fn test_code_extension_with_binary_content_is_not_code() {
+        let detection = detect(Path::new("/src/payload.js"), b"\x7fELF\x02\x01\x01\x00");
+        assert_eq!(detection.mime_type, "application/x-executable");
+        assert_eq!(detection.category, MimeCategory::Other);
+        assert_eq!(detection.extension_mime.as_deref(), Some("text/javascript"));
+    }
+}
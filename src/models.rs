@@ -22,6 +22,128 @@ pub struct BackupJob {
     pub status: String,
     #[serde(skip_deserializing)]
     pub is_active: bool,
+    /// Restore and hash-check files after each mapping is backed up
+    pub verify_after_backup: bool,
+    /// Number of files sampled per verification (None = every cataloged file)
+    pub verification_sample_size: Option<i32>,
+    /// Client-side encryption of the backed up data
+    pub encryption: BackupEncryption,
+    /// Named exclusion templates (see `GET /ignore-templates`); `auto` detects them per source
+    #[schema(example = json!(["rust", "ide"]))]
+    pub ignore_templates: Vec<String>,
+    /// What starts the job besides manual runs
+    pub trigger_mode: BackupTriggerMode,
+    /// Watch mode: seconds without changes before a backup starts
+    pub watch_quiet_seconds: i32,
+    /// Watch mode: minimum seconds between the start of two watch-triggered runs
+    pub watch_min_interval_seconds: i32,
+    /// Watch mode: longest a change waits for a quiet period before a backup is forced
+    pub watch_max_delay_seconds: i32,
+    /// What a new run does while a previous run of the job is still pending
+    pub overlap_policy: OverlapPolicy,
+}
+
+impl BackupJob {
+    /// Unsaved job with the same defaults as the `backup_jobs` columns
+    pub fn new(name: impl Into<String>, mappings: serde_json::Value) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            mappings,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            status: "CREATED".to_string(),
+            is_active: true,
+            verify_after_backup: false,
+            verification_sample_size: None,
+            encryption: BackupEncryption::None,
+            ignore_templates: vec![],
+            trigger_mode: BackupTriggerMode::Schedule,
+            watch_quiet_seconds: BackupTriggerMode::DEFAULT_QUIET_SECONDS,
+            watch_min_interval_seconds: BackupTriggerMode::DEFAULT_MIN_INTERVAL_SECONDS,
+            watch_max_delay_seconds: BackupTriggerMode::DEFAULT_MAX_DELAY_SECONDS,
+            overlap_policy: OverlapPolicy::Queue,
+        }
+    }
+}
+
+/// Client-side encryption applied to a backup job's destinations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum BackupEncryption {
+    /// Files are uploaded as they are
+    #[default]
+    None,
+    /// Every destination is wrapped in an rclone `crypt` remote: contents and
+    /// file names are encrypted before leaving the machine
+    RcloneCrypt,
+}
+
+impl BackupEncryption {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupEncryption::None => "none",
+            BackupEncryption::RcloneCrypt => "rclone_crypt",
+        }
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        *self != BackupEncryption::None
+    }
+}
+
+/// What triggers a backup job, besides `POST /backups/{id}/run`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum BackupTriggerMode {
+    /// Only the cron schedule (if any)
+    #[default]
+    Schedule,
+    /// Source paths are watched; a run starts after changes settle down.
+    /// A cron schedule, if configured, keeps running as well.
+    Watch,
+}
+
+impl BackupTriggerMode {
+    pub const DEFAULT_QUIET_SECONDS: i32 = 60;
+    pub const DEFAULT_MIN_INTERVAL_SECONDS: i32 = 300;
+    pub const DEFAULT_MAX_DELAY_SECONDS: i32 = 1800;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupTriggerMode::Schedule => "schedule",
+            BackupTriggerMode::Watch => "watch",
+        }
+    }
+}
+
+/// What happens when a run is requested while a previous run of the same job is
+/// still queued or running. Runs of one job never execute at the same time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// The new run waits for the previous one to finish
+    #[default]
+    Queue,
+    /// The new run is recorded as `skipped`, with the reason, and never executes
+    Skip,
+    /// Previous runs are cancelled and the new one is queued
+    CancelPrevious,
+}
+
+impl OverlapPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverlapPolicy::Queue => "queue",
+            OverlapPolicy::Skip => "skip",
+            OverlapPolicy::CancelPrevious => "cancel_previous",
+        }
+    }
 }
 
 // A version of BackupJob for creating new entries, without the ID
@@ -30,6 +152,30 @@ pub struct NewBackupJob {
     pub schedule: Option<NewBackupSchedule>,
     pub name: String,
     pub mappings: HashMap<String, Vec<String>>,
+    /// Restore and hash-check files after each mapping is backed up (default: false)
+    pub verify_after_backup: Option<bool>,
+    /// Number of files sampled per verification; omit to verify every file
+    #[schema(example = 20)]
+    pub verification_sample_size: Option<i32>,
+    /// Client-side encryption (default: none; requires a master key). Changing it
+    /// on an existing job does not re-encrypt data already uploaded.
+    pub encryption: Option<BackupEncryption>,
+    /// Named exclusion templates, or `auto` to detect them from each source (default: none)
+    #[schema(example = json!(["rust", "node"]))]
+    pub ignore_templates: Option<Vec<String>>,
+    /// `watch` runs the job when its source paths change (default: schedule)
+    pub trigger_mode: Option<BackupTriggerMode>,
+    /// Seconds without changes before a watch-triggered run (default: 60)
+    #[schema(example = 60)]
+    pub watch_quiet_seconds: Option<i32>,
+    /// Minimum seconds between watch-triggered runs (default: 300)
+    #[schema(example = 300)]
+    pub watch_min_interval_seconds: Option<i32>,
+    /// Maximum seconds a change waits for the quiet period (default: 1800)
+    #[schema(example = 1800)]
+    pub watch_max_delay_seconds: Option<i32>,
+    /// What a new run does while a previous run is still pending (default: queue)
+    pub overlap_policy: Option<OverlapPolicy>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -72,6 +218,128 @@ pub struct BackupSchedule {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Table a registered schedule comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleKind {
+    /// `backup_schedules`: queues a run of the backup job
+    Backup,
+    /// `scan_schedules`: scans the schedule's root path
+    Scan,
+}
+
+/// A schedule registered in this instance's cron scheduler
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScheduledJobStatus {
+    pub kind: ScheduleKind,
+    pub schedule_id: Uuid,
+    /// Backup job of a `backup` schedule
+    pub backup_job_id: Option<Uuid>,
+    /// ID of the job inside the cron scheduler
+    pub scheduler_job_id: Uuid,
+    pub name: String,
+    pub cron_expression: String,
+    /// Next time the cron scheduler fires the schedule
+    pub next_tick: Option<DateTime<Utc>>,
+}
+
+/// Response of `GET /scheduler/status`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SchedulerStatus {
+    #[schema(example = "running")]
+    pub scheduler: String,
+    #[schema(example = "ok")]
+    pub status: String,
+    /// Schedules registered in the scheduler, soonest first
+    pub jobs: Vec<ScheduledJobStatus>,
+}
+
+/// Periodic scan of a directory (`scan_schedules`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScanSchedule {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub root_path: String,
+    pub recursive: bool,
+    pub max_depth: Option<i32>,
+    pub exclude_patterns: Vec<String>,
+    /// Cron expression with seconds, e.g. `0 0 2 * * *` for 2AM every day
+    #[schema(example = "0 0 2 * * *")]
+    pub cron_expression: String,
+    pub enabled: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// `running`, `success`, `failed` or `cancelled`
+    pub last_run_status: Option<String>,
+    pub last_scan_job_id: Option<Uuid>,
+    /// Next tick in the scheduler; empty while the schedule is disabled
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub total_runs: i32,
+    pub successful_runs: i32,
+    pub failed_runs: i32,
+    /// Percentage of runs that succeeded
+    pub success_rate: f64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Creates a scan schedule
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewScanSchedule {
+    pub name: String,
+    pub description: Option<String>,
+    /// Directory scanned on each run
+    pub root_path: String,
+    /// Scan subdirectories (default: true)
+    pub recursive: Option<bool>,
+    pub max_depth: Option<i32>,
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// Cron expression with seconds, e.g. `0 0 2 * * *` for 2AM every day
+    #[schema(example = "0 0 2 * * *")]
+    pub cron_expression: String,
+    /// Default: true
+    pub enabled: Option<bool>,
+}
+
+/// Partial update of a scan schedule; omitted fields keep their value
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct UpdateScanSchedule {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub root_path: Option<String>,
+    pub recursive: Option<bool>,
+    pub max_depth: Option<i32>,
+    pub exclude_patterns: Option<Vec<String>>,
+    #[schema(example = "0 30 1 * * *")]
+    pub cron_expression: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Saved scan configuration (`scan_configs`), as read to build a `ScanConfig`
+#[derive(Debug, Clone, FromRow)]
+pub struct ScanConfigRecord {
+    pub id: Uuid,
+    pub name: String,
+    /// `PENDING`, `RUNNING`, `COMPLETED`, `FAILED` or `CANCELLED`
+    pub status: Option<String>,
+    pub root_path: String,
+    pub recursive: Option<bool>,
+    pub follow_symlinks: bool,
+    pub max_depth: Option<i32>,
+    pub exclude_patterns: Option<Vec<String>>,
+    pub ignore_templates: Vec<String>,
+    pub exclude_mime_categories: Vec<String>,
+    pub force_rehash: bool,
+    pub paranoid_sample_rate: f64,
+    pub live_mode: bool,
+    pub index_content: bool,
+    pub content_max_bytes: i64,
+    /// `None` means the default extension list
+    pub content_extensions: Option<Vec<String>>,
+    pub capture_xattrs: bool,
+}
+
 #[derive(Deserialize, ToSchema)]
 pub struct NewBackupSchedule {
     pub name: String,
@@ -80,11 +348,32 @@ pub struct NewBackupSchedule {
     pub enabled: Option<bool>,
 }
 
+/// Distinguishes a field sent as `null` (`Some(None)`) from a missing one (`None`)
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
 // Update models for PATCH operations
 #[derive(Deserialize, ToSchema)]
 pub struct UpdateBackupJob {
     pub name: Option<String>,
     pub mappings: Option<HashMap<String, Vec<String>>>,
+    pub verify_after_backup: Option<bool>,
+    /// `null` clears the sample size (verify every cataloged file)
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<i32>, nullable)]
+    pub verification_sample_size: Option<Option<i32>>,
+    pub encryption: Option<BackupEncryption>,
+    pub ignore_templates: Option<Vec<String>>,
+    pub trigger_mode: Option<BackupTriggerMode>,
+    pub watch_quiet_seconds: Option<i32>,
+    pub watch_min_interval_seconds: Option<i32>,
+    pub watch_max_delay_seconds: Option<i32>,
+    pub overlap_policy: Option<OverlapPolicy>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -101,6 +390,8 @@ pub struct BackupExecutionLog {
     pub id: Uuid,
     pub backup_job_id: Uuid,
     pub schedule_id: Option<Uuid>,
+    /// Queued run this transfer belongs to (see `GET /runs/{id}`)
+    pub backup_run_id: Option<Uuid>,
     pub started_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
     pub status: String,
@@ -129,6 +420,9 @@ pub struct BackupExecutionLog {
 pub struct NewBackupExecutionLog {
     pub backup_job_id: Uuid,
     pub schedule_id: Option<Uuid>,
+    /// Set by the run queue; logs created through `POST /logs` are not tied to a run
+    #[serde(skip_deserializing)]
+    pub backup_run_id: Option<Uuid>,
     pub rclone_command: String,
     pub source_path: String,
     pub destination_path: String,
@@ -136,6 +430,422 @@ pub struct NewBackupExecutionLog {
     pub triggered_by: Option<String>,
 }
 
+/// One execution of a backup job, queued by `POST /backups/{id}/run`, a cron
+/// schedule or a watch trigger and carried out by the run queue workers
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, FromRow)]
+pub struct BackupRun {
+    pub id: Uuid,
+    pub backup_job_id: Uuid,
+    pub schedule_id: Option<Uuid>,
+    /// Cron tick of the schedule that queued the run
+    pub scheduled_for: Option<DateTime<Utc>>,
+    /// What queued the run (`manual`, `scheduler` or `watch`)
+    #[schema(example = "manual")]
+    pub triggered_by: String,
+    /// `queued`, `running`, `succeeded`, `failed`, `cancelled` or `skipped`
+    #[schema(example = "running")]
+    pub status: String,
+    /// Why the run was skipped or cancelled
+    #[schema(example = "Run 5f0c3a52-8d0e-4f7b-9a51-0b6f3f1f6c2e of this job is still running")]
+    pub status_reason: Option<String>,
+    /// Source -> destination transfers of the job
+    pub destinations_total: i32,
+    /// Transfers finished successfully so far
+    pub destinations_completed: i32,
+    /// Transfers that failed so far
+    pub destinations_failed: i32,
+    /// Files transferred by the finished transfers
+    pub files_transferred: i64,
+    /// Bytes transferred by the finished transfers
+    pub bytes_transferred: i64,
+    pub error_message: Option<String>,
+    pub queued_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Cancellation requested while the run executes in another b2cli instance
+    pub cancel_requested_at: Option<DateTime<Utc>>,
+}
+
+/// A run and the execution logs of the transfers it has started so far
+#[derive(Serialize, ToSchema)]
+pub struct BackupRunReport {
+    #[serde(flatten)]
+    pub run: BackupRun,
+    pub executions: Vec<BackupExecutionLog>,
+}
+
+// Restore
+#[derive(Deserialize, ToSchema)]
+pub struct RestoreRequest {
+    /// Backup destination to read from. Must be one of the job's mapped destinations;
+    /// may be omitted when the job has a single destination.
+    #[schema(example = "/mnt/backups/daily")]
+    pub destination: Option<String>,
+    /// Sub-paths or glob filters relative to the destination root. Empty restores everything.
+    #[schema(example = json!(["reports/2025/", "*.pdf"]))]
+    pub paths: Option<Vec<String>>,
+    /// Local directory where the files will be written
+    #[schema(example = "/tmp/restore/docs")]
+    pub target_path: String,
+    /// Reapply the cataloged owner, group and mode to the restored files (default true).
+    /// Changing the owner usually requires running as root; failures are counted in
+    /// `permission_errors` and do not fail the restore.
+    pub restore_permissions: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, FromRow)]
+pub struct RestoreExecutionLog {
+    pub id: Uuid,
+    pub backup_job_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub status: String,
+    pub rclone_command: String,
+    pub source_path: String,
+    pub destination_path: String,
+    pub target_path: String,
+    pub path_filters: Vec<String>,
+    pub files_transferred: Option<i32>,
+    pub files_checked: Option<i32>,
+    pub bytes_transferred: Option<i64>,
+    pub transfer_rate_mbps: Option<f32>,
+    pub duration_seconds: Option<i32>,
+    pub error_count: Option<i32>,
+    pub error_message: Option<String>,
+    pub rclone_stdout: Option<String>,
+    pub rclone_stderr: Option<String>,
+    pub triggered_by: Option<String>,
+    /// Restored files whose cataloged ownership and permissions were reapplied
+    pub permissions_applied: Option<i32>,
+    /// Restored files whose ownership or permissions could not be reapplied
+    pub permission_errors: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct NewRestoreExecutionLog {
+    pub backup_job_id: Uuid,
+    pub rclone_command: String,
+    pub source_path: String,
+    pub destination_path: String,
+    pub target_path: String,
+    pub path_filters: Vec<String>,
+    pub triggered_by: Option<String>,
+}
+
+// Restore verification
+#[derive(Serialize, Deserialize, ToSchema, Debug, FromRow)]
+pub struct BackupVerification {
+    pub id: Uuid,
+    pub backup_job_id: Uuid,
+    pub execution_log_id: Option<Uuid>,
+    pub restore_log_id: Option<Uuid>,
+    pub source_path: String,
+    pub destination_path: String,
+    pub sample_size: Option<i32>,
+    pub status: String,
+    pub files_checked: i32,
+    pub files_passed: i32,
+    pub files_failed: i32,
+    pub restorability_percent: Option<f32>,
+    pub error_message: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, FromRow)]
+pub struct BackupVerificationFile {
+    pub id: Uuid,
+    pub verification_id: Uuid,
+    pub file_catalog_id: Option<Uuid>,
+    pub file_path: String,
+    pub expected_hash: String,
+    pub actual_hash: Option<String>,
+    pub passed: bool,
+    pub error_message: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+pub struct NewBackupVerificationFile {
+    pub file_catalog_id: Option<Uuid>,
+    pub file_path: String,
+    pub expected_hash: String,
+    pub actual_hash: Option<String>,
+    pub passed: bool,
+    pub error_message: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BackupVerificationReport {
+    pub backup_job_id: Uuid,
+    /// Passed / checked over the latest completed verification of each mapping
+    pub restorability_percent: Option<f32>,
+    pub verifications: Vec<BackupVerification>,
+    /// Failed files of the most recent verification
+    pub failed_files: Vec<BackupVerificationFile>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JobRestorabilityScore {
+    pub backup_job_id: Uuid,
+    pub job_name: String,
+    pub files_checked: i64,
+    pub files_passed: i64,
+    pub restorability_percent: Option<f32>,
+    pub last_verified_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FleetRestorabilityScore {
+    /// Passed / checked over the latest completed verification of every mapping of every active job
+    pub restorability_percent: Option<f32>,
+    pub jobs_total: i64,
+    pub jobs_verified: i64,
+    pub jobs: Vec<JobRestorabilityScore>,
+}
+
+/// One exclusion rule, from the scan configuration or a `.b2ignore` file
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EffectiveFilterRule {
+    /// `config` or the path of the `.b2ignore` (relative to the source)
+    #[schema(example = "photos/.b2ignore")]
+    pub source: String,
+    /// The pattern as written
+    #[schema(example = "!*.raw")]
+    pub pattern: String,
+    /// Directory (relative to the source) below which the rule applies; empty = whole source
+    pub base: String,
+    pub negated: bool,
+    pub dir_only: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MappingEffectiveFilters {
+    pub source_path: String,
+    /// Rules in evaluation order (the last matching rule wins)
+    pub rules: Vec<EffectiveFilterRule>,
+    /// The same rules as passed to rclone, one per `--filter`
+    pub rclone_filters: Vec<String>,
+    /// Templates detected from marker files in the source but not applied
+    pub suggested_templates: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EffectiveFilters {
+    pub backup_job_id: Uuid,
+    pub mappings: Vec<MappingEffectiveFilters>,
+}
+
+/// A built-in exclusion template
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IgnoreTemplateInfo {
+    #[schema(example = "rust")]
+    pub name: String,
+    pub description: String,
+    /// Files in a source root that make `auto` pick this template
+    #[schema(example = json!(["Cargo.toml"]))]
+    pub markers: Vec<String>,
+    /// `.gitignore`-style patterns
+    #[schema(example = json!(["target/", "**/*.rs.bk"]))]
+    pub patterns: Vec<String>,
+}
+
+/// State of a live-mode catalog watcher (scan configs with `live_mode`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LiveWatcherStatus {
+    pub scan_config_id: Uuid,
+    pub root_path: String,
+    /// `starting`, `watching`, `rescanning` or `failed`
+    #[schema(example = "watching")]
+    pub state: String,
+    /// Events queued plus paths waiting for the debounce window
+    pub queue_depth: usize,
+    /// Events were lost; a full rescan is pending
+    pub overflowed: bool,
+    pub events_received: u64,
+    /// Catalog rows inserted, updated or marked deleted from events
+    pub changes_applied: u64,
+    /// Full rescans (startup, overflow, `.b2ignore` changes)
+    pub rescans: u64,
+    pub last_event_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Broad file type, derived from the detected MIME type (and the extension for source code)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MimeCategory {
+    Image,
+    Video,
+    Audio,
+    Archive,
+    /// PDF, office and OpenDocument files, RTF, e-books
+    Document,
+    SourceCode,
+    /// Other text: plain text, Markdown, CSV, JSON, configuration files
+    Text,
+    Other,
+}
+
+impl MimeCategory {
+    pub const ALL: [MimeCategory; 8] = [
+        MimeCategory::Image,
+        MimeCategory::Video,
+        MimeCategory::Audio,
+        MimeCategory::Archive,
+        MimeCategory::Document,
+        MimeCategory::SourceCode,
+        MimeCategory::Text,
+        MimeCategory::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MimeCategory::Image => "image",
+            MimeCategory::Video => "video",
+            MimeCategory::Audio => "audio",
+            MimeCategory::Archive => "archive",
+            MimeCategory::Document => "document",
+            MimeCategory::SourceCode => "source_code",
+            MimeCategory::Text => "text",
+            MimeCategory::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.as_str() == value)
+    }
+}
+
+/// A cataloged file matching a search
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FileSearchHit {
+    pub file_path: String,
+    pub file_name: String,
+    pub extension: Option<String>,
+    /// Detected from the file content, falling back to the extension
+    #[schema(example = "application/pdf")]
+    pub mime_type: Option<String>,
+    pub mime_category: Option<MimeCategory>,
+    pub file_size: i64,
+    pub modified_at: Option<DateTime<Utc>>,
+    /// `never`, `current`, or `stale` when the file changed after its last backup
+    #[schema(example = "current")]
+    pub backup_status: String,
+    pub last_backup_at: Option<DateTime<Utc>>,
+    /// Relevance score (only when sorted by relevance)
+    pub score: Option<f32>,
+}
+
+/// Number of matching files for one facet value
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchFacetCount {
+    #[schema(example = "pdf")]
+    pub value: String,
+    pub count: u64,
+}
+
+/// Facet counts over all matches, not only the returned page
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FileSearchFacets {
+    /// Most common extensions
+    pub extension: Vec<SearchFacetCount>,
+    /// Subdirectories of the searched directory (or of `/`) with matches
+    pub directory: Vec<SearchFacetCount>,
+}
+
+/// A page of file search results
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FileSearchResponse {
+    /// Total number of matching files
+    pub total: u64,
+    pub offset: usize,
+    pub results: Vec<FileSearchHit>,
+    pub facets: FileSearchFacets,
+}
+
+/// Files and bytes of one MIME category in the catalog
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MimeCategoryStats {
+    /// `null` for files cataloged before MIME detection that were not rescanned yet
+    pub category: Option<MimeCategory>,
+    pub files: i64,
+    pub total_size: i64,
+}
+
+/// Files and bytes of one MIME type in the catalog
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MimeTypeStats {
+    #[schema(example = "image/jpeg")]
+    pub mime_type: Option<String>,
+    pub files: i64,
+    pub total_size: i64,
+}
+
+/// Active catalog files grouped by detected type
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FileTypeStatsResponse {
+    pub total_files: i64,
+    pub total_size: i64,
+    /// Files whose content does not match their extension (see `metadata.mime_mismatch`)
+    pub mime_mismatches: i64,
+    pub categories: Vec<MimeCategoryStats>,
+    /// Most common MIME types, largest counts first
+    pub mime_types: Vec<MimeTypeStats>,
+}
+
+/// Permission/ownership audit run by `GET /files/audit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileAuditCheck {
+    /// Writable by everyone (`o+w`); symlinks are ignored
+    WorldWritable,
+    /// setuid or setgid bit set
+    Setuid,
+    /// Owner or group id with no account on the scanning host
+    UnknownOwner,
+    /// Not owned by the expected `uid`/`owner`
+    WrongOwner,
+}
+
+impl FileAuditCheck {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileAuditCheck::WorldWritable => "world_writable",
+            FileAuditCheck::Setuid => "setuid",
+            FileAuditCheck::UnknownOwner => "unknown_owner",
+            FileAuditCheck::WrongOwner => "wrong_owner",
+        }
+    }
+}
+
+/// A cataloged file flagged by an audit, with the ownership recorded at the last scan
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FileAuditEntry {
+    pub id: Uuid,
+    pub file_path: String,
+    pub file_size: i64,
+    /// Full `st_mode` (file type and permission bits)
+    pub mode: i64,
+    #[schema(example = "rwxrwxrwx")]
+    pub permissions: Option<String>,
+    pub uid: i64,
+    pub gid: i64,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    /// Link target, for symlinks cataloged without following them
+    pub symlink_target: Option<String>,
+}
+
+/// Result of a permission/ownership audit
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FileAuditResponse {
+    pub check: FileAuditCheck,
+    /// Number of flagged files (may exceed `files.len()`)
+    pub total: i64,
+    pub files: Vec<FileAuditEntry>,
+}
+
 // Rclone specific models
 #[derive(Debug, Deserialize)]
 pub struct RcloneLogEntry {
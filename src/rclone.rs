@@ -1,16 +1,21 @@
 // src/rclone.rs
 // Wrapper for rclone command with comprehensive logging
 
+use crate::cancel::CancelToken;
 use crate::models::{RcloneExecutionResult, RcloneLogEntry};
 use anyhow::{anyhow, Result};
-use std::path::PathBuf;
-use std::process::Stdio;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
 use tokio::fs;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
 use tracing::{debug, error, warn};
 use uuid::Uuid;
 
+/// How long a cancelled transfer gets to exit after SIGINT before it is killed
+pub const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Clone)]
 pub struct RcloneConfig {
     pub log_level: String,
@@ -36,15 +41,56 @@ impl Default for RcloneConfig {
     }
 }
 
+/// Output of a short, non-transfer rclone command (`lsd`, `rcat`, `deletefile`, ...)
+#[derive(Debug, Clone)]
+pub struct RcloneCommandOutput {
+    /// Process exit code, `None` if it was killed by the timeout
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+impl RcloneCommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
 pub struct RcloneWrapper {
     config: RcloneConfig,
     log_dir: PathBuf,
+    env: Vec<(String, String)>,
+    cancel: Option<CancelToken>,
 }
 
 impl RcloneWrapper {
     pub fn new(config: RcloneConfig, log_dir: Option<PathBuf>) -> Self {
         let log_dir = log_dir.unwrap_or_else(|| PathBuf::from("/tmp/b2cli_logs"));
-        Self { config, log_dir }
+        Self { config, log_dir, env: vec![], cancel: None }
+    }
+
+    /// Extra environment for the rclone process, e.g. `RCLONE_CONFIG_*` variables
+    /// describing an ephemeral remote. Never included in logged command lines.
+    pub fn with_env(mut self, env: Vec<(String, String)>) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Token that interrupts running transfers: rclone gets SIGINT and, if it
+    /// is still running after `CANCEL_GRACE_PERIOD`, SIGKILL. The transfer
+    /// still returns the statistics rclone logged up to that point.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Completes once the transfer was cancelled; never without a token
+    async fn cancelled(&self) {
+        match &self.cancel {
+            Some(cancel) => cancel.cancelled().await,
+            None => std::future::pending().await,
+        }
     }
 
     /// Execute rclone sync command with comprehensive logging
@@ -53,6 +99,105 @@ impl RcloneWrapper {
         job_id: Uuid,
         source: &str,
         destination: &str,
+    ) -> Result<RcloneExecutionResult> {
+        self.run_transfer("sync", job_id, source, destination, &[]).await
+    }
+
+    /// Execute rclone copy (never deletes on the destination side).
+    ///
+    /// Used by restores: `source` is the backup destination and `destination`
+    /// the local target directory. Each entry of `include_filters` becomes an
+    /// `--include` rule; an empty list copies everything.
+    pub async fn copy(
+        &self,
+        job_id: Uuid,
+        source: &str,
+        destination: &str,
+        include_filters: &[String],
+    ) -> Result<RcloneExecutionResult> {
+        let extra_args: Vec<String> = include_filters
+            .iter()
+            .flat_map(|filter| ["--include".to_string(), filter.clone()])
+            .collect();
+
+        self.run_transfer("copy", job_id, source, destination, &extra_args).await
+    }
+
+    /// Execute rclone copy restricted to the paths listed in `files_from`
+    /// (one per line, relative to `source`).
+    pub async fn copy_files_from(
+        &self,
+        job_id: Uuid,
+        source: &str,
+        destination: &str,
+        files_from: &Path,
+    ) -> Result<RcloneExecutionResult> {
+        let extra_args = vec![
+            "--files-from".to_string(),
+            files_from.to_string_lossy().to_string(),
+        ];
+
+        self.run_transfer("copy", job_id, source, destination, &extra_args).await
+    }
+
+    /// Run a short rclone command and capture its output.
+    ///
+    /// `stdin` is piped to the process when given (used by `rcat`). The
+    /// process is killed once `timeout` elapses; in that case `exit_code` is
+    /// `None` and `stderr` says so. Only a failure to spawn rclone is an `Err`.
+    pub async fn run_command(
+        &self,
+        args: &[&str],
+        stdin: Option<&[u8]>,
+        timeout: Duration,
+    ) -> Result<RcloneCommandOutput> {
+        let mut cmd = Command::new("rclone");
+        cmd.args(args);
+        for flag in &self.config.extra_flags {
+            cmd.arg(flag);
+        }
+
+        debug!("Executing rclone command: {:?}", cmd);
+        cmd.envs(self.env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+            .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let start_time = std::time::Instant::now();
+        let mut child = cmd.spawn()?;
+
+        if let (Some(data), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(data).await?;
+            // Dropping the pipe closes it so rcat sees EOF
+        }
+
+        match tokio::time::timeout(timeout, child.wait_with_output()).await {
+            Ok(output) => {
+                let output = output?;
+                Ok(RcloneCommandOutput {
+                    exit_code: output.status.code(),
+                    stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                    duration: start_time.elapsed(),
+                })
+            }
+            Err(_) => Ok(RcloneCommandOutput {
+                exit_code: None,
+                stdout: String::new(),
+                stderr: format!("rclone timed out after {}s", timeout.as_secs()),
+                duration: start_time.elapsed(),
+            }),
+        }
+    }
+
+    async fn run_transfer(
+        &self,
+        operation: &str,
+        job_id: Uuid,
+        source: &str,
+        destination: &str,
+        extra_args: &[String],
     ) -> Result<RcloneExecutionResult> {
         // Ensure log directory exists
         fs::create_dir_all(&self.log_dir).await?;
@@ -62,7 +207,7 @@ impl RcloneWrapper {
 
         // Build rclone command
         let mut cmd = Command::new("rclone");
-        cmd.arg("sync")
+        cmd.arg(operation)
             .arg(source)
             .arg(destination)
             .arg("--log-file")
@@ -93,10 +238,14 @@ impl RcloneWrapper {
         for flag in &self.config.extra_flags {
             cmd.arg(flag);
         }
+        for arg in extra_args {
+            cmd.arg(arg);
+        }
 
-        // Execute command
+        // Execute command (format before adding env so credentials never reach the logs)
         let command_str = format!("{:?}", cmd);
         debug!("Executing rclone command for job {}: {}", job_id, command_str);
+        cmd.envs(self.env.iter().map(|(k, v)| (k.as_str(), v.as_str())));
 
         let start_time = std::time::Instant::now();
         
@@ -135,10 +284,21 @@ impl RcloneWrapper {
                     }
                 }
             } => {},
+            _ = self.cancelled() => {},
         }
 
         // Wait for command to complete
-        let output = child.wait().await?;
+        let exited = tokio::select! {
+            status = child.wait() => Some(status?),
+            _ = self.cancelled() => None,
+        };
+        let output = match exited {
+            Some(status) => status,
+            None => {
+                warn!("Cancelling rclone {} for job {}", operation, job_id);
+                interrupt(&mut child).await?
+            }
+        };
         let duration = start_time.elapsed();
 
         debug!(
@@ -344,6 +504,24 @@ impl RcloneWrapper {
     }
 }
 
+/// Stops rclone like Ctrl+C would, so it can flush its log and final stats,
+/// and kills it if it is still running after `CANCEL_GRACE_PERIOD`.
+async fn interrupt(child: &mut Child) -> std::io::Result<ExitStatus> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: plain kill(2) on our own child, which has not been reaped yet
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGINT);
+        }
+        if let Ok(status) = tokio::time::timeout(CANCEL_GRACE_PERIOD, child.wait()).await {
+            return status;
+        }
+        warn!("rclone ignored SIGINT for {}s, killing it", CANCEL_GRACE_PERIOD.as_secs());
+    }
+    child.kill().await?;
+    child.wait().await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -389,6 +567,20 @@ mod tests {
         assert_eq!(config.extra_flags, vec!["--fast-list"]);
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_interrupt_stops_child_with_sigint() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let mut child = Command::new("sleep").arg("60").spawn().unwrap();
+        let started = std::time::Instant::now();
+
+        let status = interrupt(&mut child).await.unwrap();
+
+        assert_eq!(status.signal(), Some(libc::SIGINT));
+        assert!(started.elapsed() < CANCEL_GRACE_PERIOD);
+    }
+
     #[tokio::test]
     async fn test_parse_info_log() {
         let rclone = create_test_rclone();
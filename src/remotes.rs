@@ -0,0 +1,374 @@
+//! Resolução de destinos de backup.
+//!
+//! Um destino em `BackupJob.mappings` pode ser um caminho/remote do rclone
+//! (`/mnt/backups`, `gdrive:backups/docs`) ou uma referência a um provedor
+//! cadastrado em `cloud_providers`:
+//!
+//! ```text
+//! provider://<uuid>/sub/caminho
+//! ```
+//!
+//! Referências a provedores viram um remote efêmero do rclone, configurado
+//! inteiramente por variáveis de ambiente (`RCLONE_CONFIG_<REMOTE>_*`) passadas
+//! só para o processo do rclone, sem depender de `~/.config/rclone/rclone.conf`.
+//! Jobs com criptografia ganham mais uma camada, um remote `crypt` também efêmero.
+
+use crate::models::{BackupEncryption, BackupJob, CloudProvider};
+use crate::rclone::RcloneWrapper;
+use crate::{db, AppError};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Prefixo das referências a provedores nos mapeamentos
+pub const PROVIDER_SCHEME: &str = "provider://";
+
+/// Referência `provider://<uuid>/subpath` já parseada
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderRef {
+    pub provider_id: Uuid,
+    /// Sub-caminho dentro do bucket/prefixo do provedor (sem barras nas pontas)
+    pub subpath: String,
+}
+
+/// Destino pronto para ser passado ao rclone
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedRemote {
+    /// Caminho no formato do rclone (`remote:bucket/path` ou caminho local)
+    pub location: String,
+    /// Variáveis de ambiente que definem o remote efêmero (vazio para destinos comuns)
+    pub env: Vec<(String, String)>,
+    /// Onde os dados ficam de fato; num remote `crypt`, o destino envolvido
+    pub storage: String,
+}
+
+/// Faz o parse de uma referência a provedor.
+///
+/// # Retorna
+/// * `Ok(None)` - O destino não usa o esquema `provider://`
+/// * `Ok(Some(ProviderRef))` - Referência válida
+/// * `Err(AppError::BadRequest)` - Usa o esquema mas o UUID é inválido
+pub fn parse_provider_ref(location: &str) -> Result<Option<ProviderRef>, AppError> {
+    let Some(rest) = location.strip_prefix(PROVIDER_SCHEME) else {
+        return Ok(None);
+    };
+
+    let (id_part, subpath) = rest.split_once('/').unwrap_or((rest, ""));
+    let provider_id = Uuid::parse_str(id_part).map_err(|_| {
+        AppError::BadRequest(format!(
+            "Invalid provider reference '{}': expected provider://<uuid>/subpath",
+            location
+        ))
+    })?;
+
+    Ok(Some(ProviderRef {
+        provider_id,
+        subpath: subpath.trim_matches('/').to_string(),
+    }))
+}
+
+/// Resolve um destino para algo que o rclone entende.
+///
+/// Destinos sem `provider://` são devolvidos como estão.
+pub async fn resolve_location(pool: &PgPool, location: &str) -> Result<ResolvedRemote, AppError> {
+    let Some(provider_ref) = parse_provider_ref(location)? else {
+        return Ok(ResolvedRemote {
+            location: location.to_string(),
+            env: vec![],
+            storage: location.to_string(),
+        });
+    };
+
+    let provider = db::get_cloud_provider_by_id(pool, provider_ref.provider_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Cloud provider {} referenced by '{}' not found or inactive",
+                provider_ref.provider_id, location
+            ))
+        })?;
+
+    Ok(provider_remote(&provider, &provider_ref.subpath))
+}
+
+/// Resolve um destino de um backup job, aplicando a criptografia do job.
+///
+/// Com `encryption = rclone_crypt` o destino resolvido vira o `remote` de um
+/// remote `crypt` efêmero cujas senhas vêm de `backup_job_keys`; o rclone
+/// criptografa conteúdo e nomes antes do upload e descriptografa no restore.
+pub async fn resolve_job_location(
+    pool: &PgPool,
+    job: &BackupJob,
+    location: &str,
+) -> Result<ResolvedRemote, AppError> {
+    let remote = resolve_location(pool, location).await?;
+    if job.encryption != BackupEncryption::RcloneCrypt {
+        return Ok(remote);
+    }
+
+    let (password, salt) = db::get_backup_job_crypt_keys(pool, job.id)
+        .await?
+        .ok_or_else(|| {
+            AppError::InternalServerError(format!(
+                "Backup job {} is encrypted but has no encryption keys",
+                job.id
+            ))
+        })?;
+
+    Ok(crypt_remote(
+        job.id,
+        remote,
+        &obscure(&password).await?,
+        &obscure(&salt).await?,
+    ))
+}
+
+/// Envolve um destino em um remote `crypt` efêmero.
+///
+/// `obscured_password`/`obscured_salt` já devem estar no formato de
+/// `rclone obscure`. As variáveis do destino original são mantidas, já que o
+/// remote crypt depende dele.
+pub fn crypt_remote(
+    job_id: Uuid,
+    inner: ResolvedRemote,
+    obscured_password: &str,
+    obscured_salt: &str,
+) -> ResolvedRemote {
+    let name = format!("b2cli_crypt_{}", job_id.simple());
+    let env_prefix = format!("RCLONE_CONFIG_{}_", name.to_uppercase());
+    let mut env = inner.env;
+
+    for (key, value) in [
+        ("TYPE", "crypt"),
+        ("REMOTE", inner.location.as_str()),
+        ("PASSWORD", obscured_password),
+        ("PASSWORD2", obscured_salt),
+        ("FILENAME_ENCRYPTION", "standard"),
+        ("DIRECTORY_NAME_ENCRYPTION", "true"),
+    ] {
+        env.push((format!("{}{}", env_prefix, key), value.to_string()));
+    }
+
+    ResolvedRemote {
+        location: format!("{}:", name),
+        env,
+        storage: inner.storage,
+    }
+}
+
+/// Converte uma senha para o formato que o rclone espera na configuração.
+///
+/// A senha vai pelo stdin de `rclone obscure -`, nunca pela linha de comando.
+async fn obscure(secret: &str) -> Result<String, AppError> {
+    let output = RcloneWrapper::new(Default::default(), None)
+        .run_command(&["obscure", "-"], Some(secret.as_bytes()), Duration::from_secs(10))
+        .await
+        .map_err(AppError::RcloneError)?;
+
+    if !output.success() {
+        return Err(AppError::InternalServerError(format!(
+            "rclone obscure failed: {}",
+            output.stderr.trim()
+        )));
+    }
+    Ok(output.stdout.trim().to_string())
+}
+
+/// Valida as referências a provedores de um conjunto de mapeamentos
+/// (formato e existência do provedor ativo).
+pub async fn validate_mappings(
+    pool: &PgPool,
+    mappings: &HashMap<String, Vec<String>>,
+) -> Result<(), AppError> {
+    for destination in mappings.values().flatten() {
+        if parse_provider_ref(destination)?.is_some() {
+            resolve_location(pool, destination).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Nome do remote efêmero de um provedor (`b2cli_<uuid sem hífens>`)
+pub fn remote_name(provider_id: Uuid) -> String {
+    format!("b2cli_{}", provider_id.simple())
+}
+
+/// Monta o remote efêmero do rclone para um provedor.
+///
+/// O caminho final é `bucket/path_prefix/subpath`.
+pub fn provider_remote(provider: &CloudProvider, subpath: &str) -> ResolvedRemote {
+    let name = remote_name(provider.id);
+    let env_prefix = format!("RCLONE_CONFIG_{}_", name.to_uppercase());
+    let mut env: Vec<(String, String)> = Vec::new();
+    let mut set = |key: &str, value: &str| env.push((format!("{}{}", env_prefix, key), value.to_string()));
+
+    if provider.provider_type == "backblaze_b2" && provider.use_b2_native_api {
+        set("TYPE", "b2");
+        set("ACCOUNT", provider.b2_account_id.as_deref().unwrap_or(&provider.access_key));
+        set("KEY", provider.b2_application_key.as_deref().unwrap_or(&provider.secret_key));
+    } else {
+        set("TYPE", "s3");
+        set("PROVIDER", s3_provider_name(&provider.provider_type));
+        set("ACCESS_KEY_ID", &provider.access_key);
+        set("SECRET_ACCESS_KEY", &provider.secret_key);
+        if let Some(region) = provider.region.as_deref() {
+            set("REGION", region);
+        }
+        if let Some(endpoint) = default_endpoint(provider) {
+            set("ENDPOINT", &endpoint);
+        }
+    }
+
+    let path: Vec<&str> = [
+        provider.bucket.as_str(),
+        provider.path_prefix.as_deref().unwrap_or(""),
+        subpath,
+    ]
+    .iter()
+    .map(|part| part.trim_matches('/'))
+    .filter(|part| !part.is_empty())
+    .collect();
+
+    let location = format!("{}:{}", name, path.join("/"));
+    ResolvedRemote {
+        storage: location.clone(),
+        location,
+        env,
+    }
+}
+
+/// Valor de `provider` do backend s3 do rclone para cada tipo
+fn s3_provider_name(provider_type: &str) -> &'static str {
+    match provider_type {
+        "wasabi" => "Wasabi",
+        "scaleway" => "Scaleway",
+        "idrive_e2" => "IDrive",
+        _ => "Other",
+    }
+}
+
+/// Endpoint configurado ou o padrão do provedor a partir da região
+fn default_endpoint(provider: &CloudProvider) -> Option<String> {
+    if let Some(endpoint) = provider.endpoint.as_deref().filter(|e| !e.is_empty()) {
+        return Some(endpoint.to_string());
+    }
+
+    let region = provider.region.as_deref()?;
+    match provider.provider_type.as_str() {
+        "wasabi" => Some(format!("https://s3.{}.wasabisys.com", region)),
+        "scaleway" => Some(format!("https://s3.{}.scw.cloud", region)),
+        "backblaze_b2" => Some(format!("https://s3.{}.backblazeb2.com", region)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn create_test_provider(provider_type: &str) -> CloudProvider {
+        CloudProvider {
+            id: Uuid::parse_str("0f8fad5b-d9cb-469f-a165-70867728950e").unwrap(),
+            name: "Test".to_string(),
+            provider_type: provider_type.to_string(),
+            endpoint: None,
+            region: Some("us-east-1".to_string()),
+            bucket: "my-bucket".to_string(),
+            path_prefix: Some("backups/".to_string()),
+            access_key: "AKIA".to_string(),
+            secret_key: "secret".to_string(),
+            b2_account_id: None,
+            b2_application_key: None,
+            use_b2_native_api: false,
+            is_active: true,
+            is_default: false,
+            test_connectivity_at: None,
+            test_connectivity_status: None,
+            test_connectivity_message: None,
+            total_storage_bytes: 0,
+            total_egress_bytes: 0,
+            last_sync_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn env_value<'a>(remote: &'a ResolvedRemote, suffix: &str) -> Option<&'a str> {
+        remote
+            .env
+            .iter()
+            .find(|(k, _)| k.ends_with(suffix))
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_parse_provider_ref() {
+        let parsed = parse_provider_ref("provider://0f8fad5b-d9cb-469f-a165-70867728950e/docs/2025/")
+            .unwrap()
+            .unwrap();
+        assert_eq!(parsed.subpath, "docs/2025");
+
+        let bare = parse_provider_ref("provider://0f8fad5b-d9cb-469f-a165-70867728950e")
+            .unwrap()
+            .unwrap();
+        assert_eq!(bare.subpath, "");
+
+        assert!(parse_provider_ref("gdrive:backups").unwrap().is_none());
+        assert!(parse_provider_ref("/mnt/backups").unwrap().is_none());
+        assert!(parse_provider_ref("provider://not-a-uuid/docs").is_err());
+    }
+
+    #[test]
+    fn test_wasabi_remote() {
+        let provider = create_test_provider("wasabi");
+        let remote = provider_remote(&provider, "docs");
+
+        assert_eq!(
+            remote.location,
+            "b2cli_0f8fad5bd9cb469fa16570867728950e:my-bucket/backups/docs"
+        );
+        assert_eq!(env_value(&remote, "_TYPE"), Some("s3"));
+        assert_eq!(env_value(&remote, "_PROVIDER"), Some("Wasabi"));
+        assert_eq!(env_value(&remote, "_ENDPOINT"), Some("https://s3.us-east-1.wasabisys.com"));
+        assert!(remote
+            .env
+            .iter()
+            .all(|(k, _)| k.starts_with("RCLONE_CONFIG_B2CLI_0F8FAD5BD9CB469FA16570867728950E_")));
+    }
+
+    #[test]
+    fn test_crypt_remote_wraps_provider() {
+        let provider = create_test_provider("wasabi");
+        let inner = provider_remote(&provider, "docs");
+        let inner_location = inner.location.clone();
+        let job_id = Uuid::parse_str("6ba7b810-9dad-11d1-80b4-00c04fd430c8").unwrap();
+
+        let remote = crypt_remote(job_id, inner, "obscured-pass", "obscured-salt");
+
+        assert_eq!(remote.location, "b2cli_crypt_6ba7b8109dad11d180b400c04fd430c8:");
+        assert_eq!(env_value(&remote, "CRYPT_6BA7B8109DAD11D180B400C04FD430C8_TYPE"), Some("crypt"));
+        assert_eq!(env_value(&remote, "_REMOTE"), Some(inner_location.as_str()));
+        assert_eq!(remote.storage, inner_location);
+        assert_eq!(env_value(&remote, "_PASSWORD"), Some("obscured-pass"));
+        assert_eq!(env_value(&remote, "_PASSWORD2"), Some("obscured-salt"));
+        // O remote do provedor continua definido para o crypt usar
+        assert_eq!(env_value(&remote, "_ACCESS_KEY_ID"), Some("AKIA"));
+    }
+
+    #[test]
+    fn test_b2_native_remote() {
+        let mut provider = create_test_provider("backblaze_b2");
+        provider.use_b2_native_api = true;
+        provider.b2_account_id = Some("account".to_string());
+        provider.b2_application_key = Some("app-key".to_string());
+        provider.path_prefix = None;
+
+        let remote = provider_remote(&provider, "");
+        assert!(remote.location.ends_with(":my-bucket"));
+        assert_eq!(env_value(&remote, "_TYPE"), Some("b2"));
+        assert_eq!(env_value(&remote, "_ACCOUNT"), Some("account"));
+        assert_eq!(env_value(&remote, "_KEY"), Some("app-key"));
+    }
+}
@@ -0,0 +1,457 @@
+//! Restauração de backups.
+//!
+//! Faz o caminho inverso do `backup_worker`: lê um dos destinos mapeados de
+//! um backup job e copia os arquivos (todos, ou apenas os sub-caminhos/globs
+//! pedidos) para um diretório local com `rclone copy`. Cada restore gera um
+//! registro em `restore_execution_logs`, no mesmo formato dos logs de backup.
+//!
+//! Depois da cópia, dono, grupo e modo salvos no catálogo pelo scanner
+//! (`file_metadata`) são reaplicados aos arquivos restaurados.
+
+use crate::file_metadata::{self, UnixMetadata};
+use crate::models::{BackupJob, NewRestoreExecutionLog, RestoreExecutionLog, RestoreRequest};
+use crate::rclone::RcloneWrapper;
+use crate::{db, remotes, AppError};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Origem/destino de um mapeamento escolhido para restauração.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestoreSource {
+    /// Caminho original (chave do mapeamento)
+    pub source_path: String,
+    /// Destino do backup de onde os arquivos serão lidos
+    pub destination: String,
+}
+
+/// Executa um restore de um backup job.
+///
+/// # Argumentos
+/// * `pool` - Pool de conexão PostgreSQL
+/// * `job` - Backup job cujo destino será restaurado
+/// * `request` - Destino, filtros de caminho e diretório alvo
+///
+/// # Retorna
+/// * `Ok(RestoreExecutionLog)` - Log final do restore (status `completed` ou `failed`)
+/// * `Err(AppError::BadRequest)` - Destino ou filtros inválidos
+/// * `Err(AppError)` - Falha ao executar o rclone ou acessar o banco
+///
+/// # Comportamento
+/// - Usa `rclone copy`, nunca apaga nada no diretório alvo
+/// - O diretório alvo é criado se não existir
+/// - Um exit code diferente de zero do rclone não é erro da função: o log
+///   retornado fica com status `failed` e os detalhes do rclone
+/// - Com `restore_permissions` (padrão), um restore concluído reaplica dono e
+///   permissões do catálogo (`reapply_permissions`)
+pub async fn perform_restore(
+    pool: &PgPool,
+    job: &BackupJob,
+    request: &RestoreRequest,
+) -> Result<RestoreExecutionLog, AppError> {
+    let restore_source = resolve_restore_source(job, request.destination.as_deref())?;
+    let path_filters = request.paths.clone().unwrap_or_default();
+    let include_filters = build_include_filters(&path_filters)?;
+
+    let target_path = request.target_path.trim();
+    if target_path.is_empty() || !Path::new(target_path).is_absolute() {
+        return Err(AppError::BadRequest(
+            "target_path must be an absolute directory path".to_string(),
+        ));
+    }
+
+    let execution_log = execute_restore(
+        pool,
+        job,
+        &restore_source,
+        target_path,
+        path_filters,
+        &RestoreSelection::Include(include_filters),
+        "manual",
+    )
+    .await?;
+
+    if execution_log.status != "completed" || !request.restore_permissions.unwrap_or(true) {
+        return Ok(execution_log);
+    }
+
+    let stats = reapply_permissions(pool, &restore_source.source_path, target_path).await?;
+    db::update_restore_execution_log_permissions(pool, execution_log.id, stats.applied, stats.errors).await?;
+    if stats.errors > 0 {
+        tracing::warn!(
+            restore_id = %execution_log.id,
+            applied = stats.applied,
+            errors = stats.errors,
+            "Some restored files did not get their ownership/permissions back"
+        );
+    }
+
+    db::get_restore_execution_log_by_id(pool, execution_log.id)
+        .await?
+        .ok_or_else(|| AppError::InternalServerError("Restore log disappeared".to_string()))
+}
+
+/// Arquivos lidos do catálogo por vez em `reapply_permissions`
+const PERMISSION_PAGE_SIZE: i64 = 1_000;
+
+/// Resultado de `reapply_permissions`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PermissionRestoreStats {
+    /// Arquivos que terminaram com o dono e o modo do catálogo
+    pub applied: i32,
+    /// Arquivos em que `chown`/`chmod` falhou
+    pub errors: i32,
+}
+
+/// Reaplica dono, grupo e modo catalogados aos arquivos restaurados.
+///
+/// Cada arquivo ativo do catálogo sob `source_path` com metadados POSIX é
+/// procurado no mesmo caminho relativo dentro de `target_path`; os que não
+/// foram restaurados (fora dos filtros, links simbólicos) são ignorados.
+///
+/// # Argumentos
+/// * `source_path` - Origem do mapeamento, como está no catálogo
+/// * `target_path` - Diretório onde os arquivos foram restaurados
+pub async fn reapply_permissions(
+    pool: &PgPool,
+    source_path: &str,
+    target_path: &str,
+) -> Result<PermissionRestoreStats, AppError> {
+    let source_dir = format!("{}/", source_path.trim_end_matches('/'));
+    let target_dir = PathBuf::from(target_path);
+    let mut stats = PermissionRestoreStats::default();
+    let mut after = String::new();
+
+    loop {
+        let rows = sqlx::query!(
+            r#"
+            SELECT file_path, metadata->'unix' AS "unix!"
+            FROM file_catalog
+            WHERE starts_with(file_path, $1)
+              AND is_active = TRUE
+              AND metadata ? 'unix'
+              AND file_path > $2
+            ORDER BY file_path
+            LIMIT $3
+            "#,
+            source_dir,
+            after,
+            PERMISSION_PAGE_SIZE
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let Some(last) = rows.last() else { break };
+        after = last.file_path.clone();
+        let last_page = (rows.len() as i64) < PERMISSION_PAGE_SIZE;
+
+        let targets: Vec<(PathBuf, UnixMetadata)> = rows
+            .into_iter()
+            .filter_map(|row| {
+                let relative = row.file_path.strip_prefix(&source_dir)?;
+                let unix = serde_json::from_value(row.unix).ok()?;
+                Some((target_dir.join(relative), unix))
+            })
+            .collect();
+
+        let page = tokio::task::spawn_blocking(move || apply_permissions(&targets))
+            .await
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        stats.applied += page.applied;
+        stats.errors += page.errors;
+
+        if last_page {
+            break;
+        }
+    }
+
+    Ok(stats)
+}
+
+fn apply_permissions(targets: &[(PathBuf, UnixMetadata)]) -> PermissionRestoreStats {
+    let mut stats = PermissionRestoreStats::default();
+    for (path, unix) in targets {
+        match file_metadata::apply(path, unix) {
+            Ok(()) => stats.applied += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                tracing::debug!(path = %path.display(), error = %e, "Failed to reapply ownership/permissions");
+                stats.errors += 1;
+            }
+        }
+    }
+    stats
+}
+
+/// Seleção de arquivos repassada ao `rclone copy`.
+#[derive(Debug, Clone)]
+pub enum RestoreSelection {
+    /// Regras `--include` (lista vazia = tudo)
+    Include(Vec<String>),
+    /// Arquivo com a lista exata de caminhos, relativos à raiz do destino (`--files-from`)
+    FilesFrom(PathBuf),
+}
+
+/// Executa o `rclone copy` de um restore já validado e registra o log.
+///
+/// Usado pelo endpoint de restore e pela verificação pós-backup
+/// (`triggered_by = "verification"`). Destinos de jobs criptografados são
+/// lidos pela mesma camada crypt do backup, então os arquivos chegam
+/// descriptografados ao diretório alvo.
+pub async fn execute_restore(
+    pool: &PgPool,
+    job: &BackupJob,
+    restore_source: &RestoreSource,
+    target_path: &str,
+    path_filters: Vec<String>,
+    selection: &RestoreSelection,
+    triggered_by: &str,
+) -> Result<RestoreExecutionLog, AppError> {
+    let backup_job_id = job.id;
+    tracing::info!(
+        job_id = %backup_job_id,
+        destination = %restore_source.destination,
+        target = %target_path,
+        selection = ?selection,
+        "Starting restore"
+    );
+
+    let remote = remotes::resolve_job_location(pool, job, &restore_source.destination).await?;
+
+    let log_data = NewRestoreExecutionLog {
+        backup_job_id,
+        rclone_command: format_copy_command(&remote.location, target_path, selection),
+        source_path: restore_source.source_path.clone(),
+        destination_path: restore_source.destination.clone(),
+        target_path: target_path.to_string(),
+        path_filters,
+        triggered_by: Some(triggered_by.to_string()),
+    };
+    let execution_log = db::create_restore_execution_log(pool, &log_data).await?;
+
+    if let Err(e) = tokio::fs::create_dir_all(target_path).await {
+        db::fail_restore_execution_log(pool, execution_log.id, &e.to_string()).await?;
+        return Err(e.into());
+    }
+
+    let rclone = RcloneWrapper::new(Default::default(), Some(PathBuf::from("./logs")))
+        .with_env(remote.env);
+    let outcome = match selection {
+        RestoreSelection::Include(filters) => {
+            rclone
+                .copy(execution_log.id, &remote.location, target_path, filters)
+                .await
+        }
+        RestoreSelection::FilesFrom(list) => {
+            rclone
+                .copy_files_from(execution_log.id, &remote.location, target_path, list)
+                .await
+        }
+    };
+
+    match outcome {
+        Ok(result) => {
+            db::update_restore_execution_log_completion(pool, execution_log.id, &result).await?;
+            tracing::info!(
+                job_id = %backup_job_id,
+                restore_id = %execution_log.id,
+                exit_code = result.exit_code,
+                files_transferred = result.files_transferred,
+                "Restore finished"
+            );
+        }
+        Err(e) => {
+            tracing::error!(job_id = %backup_job_id, restore_id = %execution_log.id, error = %e, "Restore failed");
+            db::fail_restore_execution_log(pool, execution_log.id, &e.to_string()).await?;
+            return Err(AppError::RcloneError(e));
+        }
+    }
+
+    db::get_restore_execution_log_by_id(pool, execution_log.id)
+        .await?
+        .ok_or_else(|| AppError::InternalServerError("Restore log disappeared".to_string()))
+}
+
+/// Encontra o mapeamento correspondente ao destino pedido.
+///
+/// Se `destination` for `None`, só é aceito quando o job tem exatamente um
+/// destino no total. Barras finais são ignoradas na comparação.
+pub fn resolve_restore_source(
+    job: &BackupJob,
+    destination: Option<&str>,
+) -> Result<RestoreSource, AppError> {
+    let mappings: HashMap<String, Vec<String>> = serde_json::from_value(job.mappings.clone())?;
+
+    let mut candidates: Vec<RestoreSource> = mappings
+        .into_iter()
+        .flat_map(|(source_path, destinations)| {
+            destinations.into_iter().map(move |destination| RestoreSource {
+                source_path: source_path.clone(),
+                destination,
+            })
+        })
+        .collect();
+
+    match destination {
+        Some(wanted) => {
+            let wanted = wanted.trim_end_matches('/');
+            candidates
+                .into_iter()
+                .find(|c| c.destination.trim_end_matches('/') == wanted)
+                .ok_or_else(|| {
+                    AppError::BadRequest(format!(
+                        "Destination '{}' is not mapped in backup job {}",
+                        wanted, job.id
+                    ))
+                })
+        }
+        None if candidates.len() == 1 => Ok(candidates.remove(0)),
+        None => Err(AppError::BadRequest(format!(
+            "Backup job {} has {} destinations; specify which one to restore from",
+            job.id,
+            candidates.len()
+        ))),
+    }
+}
+
+/// Converte sub-caminhos/globs em regras `--include` do rclone.
+///
+/// - Caminhos são relativos à raiz do destino e ancorados nela (`/` inicial)
+/// - `dir/` restaura o diretório inteiro
+/// - Caminho sem glob pode ser arquivo ou diretório, então gera as duas regras
+/// - Globs (`*`, `?`, `[`, `{`) são repassados como estão
+/// - `..` não é aceito
+pub fn build_include_filters(paths: &[String]) -> Result<Vec<String>, AppError> {
+    let mut filters = Vec::new();
+
+    for raw in paths {
+        let trimmed = raw.trim().trim_start_matches("./");
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if Path::new(trimmed).components().any(|c| c == Component::ParentDir) {
+            return Err(AppError::BadRequest(format!(
+                "Restore path '{}' must not contain '..'",
+                raw
+            )));
+        }
+
+        let anchored = format!("/{}", trimmed.trim_start_matches('/'));
+        let is_glob = anchored.contains(['*', '?', '[', '{']);
+
+        if is_glob {
+            filters.push(anchored);
+        } else if anchored.ends_with('/') {
+            filters.push(format!("{}**", anchored));
+        } else {
+            filters.push(format!("{}/**", anchored));
+            filters.push(anchored);
+        }
+    }
+
+    Ok(filters)
+}
+
+fn format_copy_command(source: &str, target: &str, selection: &RestoreSelection) -> String {
+    let mut command = format!("rclone copy {:?} {:?}", source, target);
+    match selection {
+        RestoreSelection::Include(filters) => {
+            for filter in filters {
+                command.push_str(&format!(" --include {:?}", filter));
+            }
+        }
+        RestoreSelection::FilesFrom(list) => {
+            command.push_str(&format!(" --files-from {:?}", list));
+        }
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn create_test_job(mappings: serde_json::Value) -> BackupJob {
+        BackupJob {
+            status: "COMPLETED".to_string(),
+            ..BackupJob::new("Restore Test", mappings)
+        }
+    }
+
+    #[test]
+    fn test_resolve_single_destination_without_explicit_choice() {
+        let job = create_test_job(json!({ "/home/docs": ["/mnt/backup/docs"] }));
+
+        let resolved = resolve_restore_source(&job, None).unwrap();
+        assert_eq!(resolved.source_path, "/home/docs");
+        assert_eq!(resolved.destination, "/mnt/backup/docs");
+    }
+
+    #[test]
+    fn test_resolve_requires_choice_with_multiple_destinations() {
+        let job = create_test_job(json!({ "/home/docs": ["/mnt/a", "b2:bucket/docs"] }));
+
+        assert!(resolve_restore_source(&job, None).is_err());
+
+        let resolved = resolve_restore_source(&job, Some("b2:bucket/docs/")).unwrap();
+        assert_eq!(resolved.destination, "b2:bucket/docs");
+    }
+
+    #[test]
+    fn test_resolve_rejects_unknown_destination() {
+        let job = create_test_job(json!({ "/home/docs": ["/mnt/a"] }));
+        assert!(resolve_restore_source(&job, Some("/mnt/other")).is_err());
+    }
+
+    #[test]
+    fn test_build_include_filters() {
+        let paths = vec![
+            "reports/2025/".to_string(),
+            "./notes.txt".to_string(),
+            "*.pdf".to_string(),
+            "  ".to_string(),
+        ];
+
+        let filters = build_include_filters(&paths).unwrap();
+        assert_eq!(
+            filters,
+            vec!["/reports/2025/**", "/notes.txt/**", "/notes.txt", "/*.pdf"]
+        );
+    }
+
+    #[test]
+    fn test_build_include_filters_rejects_parent_dir() {
+        let paths = vec!["../etc/passwd".to_string()];
+        assert!(build_include_filters(&paths).is_err());
+    }
+
+    #[test]
+    fn test_empty_filters_restore_everything() {
+        assert!(build_include_filters(&[]).unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_apply_permissions_skips_files_not_restored() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let restored = dir.path().join("script.sh");
+        std::fs::write(&restored, "#!/bin/sh").unwrap();
+        std::fs::set_permissions(&restored, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let names = file_metadata::OwnerNames::default();
+        let mut unix = file_metadata::unix_metadata(&std::fs::metadata(&restored).unwrap(), &names).unwrap();
+        unix.mode = 0o100750;
+
+        let stats = apply_permissions(&[
+            (restored.clone(), unix.clone()),
+            (dir.path().join("filtrado.txt"), unix),
+        ]);
+        assert_eq!(stats, PermissionRestoreStats { applied: 1, errors: 0 });
+        let mode = std::fs::metadata(&restored).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o750);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{db, models::{BackupJob, BackupSchedule, ErrorResponse, NewBackupJob, NewBackupSchedule, UpdateBackupJob, UpdateBackupSchedule}, AppState, AppError, backup_worker};
+use crate::{crypto, db, ignore_templates, models::{BackupEncryption, BackupJob, BackupRun, BackupTriggerMode, BackupSchedule, EffectiveFilters, ErrorResponse, MappingEffectiveFilters, NewBackupJob, NewBackupSchedule, SchedulerStatus, UpdateBackupJob, UpdateBackupSchedule}, AppState, AppError, backup_triggers, backup_worker, remotes};
+use std::collections::HashMap;
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -6,7 +7,80 @@ use axum::{
     Json,
 };
 use uuid::Uuid;
-use tracing::{info, error};
+use tracing::info;
+
+/// Jobs criptografados precisam da chave mestra para gerar/abrir as chaves do crypt
+fn ensure_encryption_available(encryption: Option<BackupEncryption>) -> Result<(), AppError> {
+    if encryption.is_some_and(|e| e.is_encrypted()) && crypto::master().is_none() {
+        return Err(AppError::BadRequest(
+            "Client-side encryption requires a master key (B2CLI_MASTER_KEY or B2CLI_MASTER_PASSWORD)".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Tempos do modo `watch` precisam ser positivos; em modo `watch`, as origens
+/// precisam poder ser observadas antes de o job ser gravado
+async fn ensure_watch_settings(
+    trigger_mode: Option<BackupTriggerMode>,
+    seconds: [Option<i32>; 3],
+    sources: Option<&HashMap<String, Vec<String>>>,
+) -> Result<(), AppError> {
+    if seconds.iter().flatten().any(|&value| value <= 0) {
+        return Err(AppError::BadRequest(
+            "watch_quiet_seconds, watch_min_interval_seconds and watch_max_delay_seconds must be positive".to_string(),
+        ));
+    }
+    if trigger_mode == Some(BackupTriggerMode::Watch) {
+        for source in sources.into_iter().flat_map(|mappings| mappings.keys()) {
+            if let Err(e) = backup_triggers::check_watchable(source) {
+                return Err(AppError::BadRequest(format!(
+                    "trigger_mode watch requires existing, watchable source paths: {} ({})",
+                    source, e
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Inicia, substitui ou para os watchers do job conforme seu `trigger_mode`
+async fn sync_triggers(state: &AppState, job: &BackupJob) -> Result<(), AppError> {
+    state
+        .backup_triggers
+        .sync_job(job)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to watch backup sources: {}", e)))
+}
+
+/// Alinha o scheduler ao schedule do job: job desativado sai do scheduler
+async fn sync_job_schedule(state: &AppState, job: &BackupJob) -> Result<(), AppError> {
+    if !job.is_active {
+        state.schedules.remove_job(job.id).await?;
+        return Ok(());
+    }
+    if let Some(schedule) = db::get_backup_schedule_by_job_id(&state.db_pool, job.id).await? {
+        state.schedules.sync_schedule(&schedule).await?;
+    }
+    Ok(())
+}
+
+/// Sem amostra todos os arquivos são verificados; uma amostra precisa de ao menos um
+fn ensure_sample_size(sample_size: Option<i32>) -> Result<(), AppError> {
+    if sample_size.is_some_and(|size| size < 1) {
+        return Err(AppError::BadRequest(
+            "verification_sample_size must be at least 1 (omit it to verify every file)".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn ensure_known_templates(templates: Option<&[String]>) -> Result<(), AppError> {
+    match templates {
+        Some(names) => ignore_templates::validate(names).map_err(AppError::BadRequest),
+        None => Ok(()),
+    }
+}
 
 #[utoipa::path(
     post,
@@ -15,6 +89,7 @@ use tracing::{info, error};
     request_body(content = NewBackupJob, description = "New backup job details", example = json!({ "name": "My Daily Backup", "mappings": { "/home/user/docs": ["/mnt/backups/daily", "s3://my-bucket/daily"] } })),
     responses(
         (status = 201, description = "Backup job created successfully", body = BackupJob),
+        (status = 400, description = "Invalid provider:// reference in mappings, unknown ignore template, invalid verification sample size or watch settings, or encryption requested without a master key", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
@@ -22,39 +97,22 @@ pub async fn create_backup(
     State(state): State<AppState>,
     Json(payload): Json<NewBackupJob>,
 ) -> Result<impl IntoResponse, AppError> {
+    ensure_encryption_available(payload.encryption)?;
+    ensure_sample_size(payload.verification_sample_size)?;
+    ensure_known_templates(payload.ignore_templates.as_deref())?;
+    ensure_watch_settings(
+        payload.trigger_mode,
+        [payload.watch_quiet_seconds, payload.watch_min_interval_seconds, payload.watch_max_delay_seconds],
+        Some(&payload.mappings),
+    )
+    .await?;
+    remotes::validate_mappings(&state.db_pool, &payload.mappings).await?;
+
     let (backup_job, schedule_opt) = db::create_backup_job(&state.db_pool, &payload).await?;
+    sync_triggers(&state, &backup_job).await?;
 
     if let Some(schedule) = schedule_opt {
-        let db_pool = state.db_pool.clone();
-        let backup_job_id = backup_job.id;
-        let cron_expression = schedule.cron_expression.clone();
-        let schedule_id = schedule.id;
-
-        let job = tokio_cron_scheduler::Job::new_async(cron_expression.as_str(), move |_uuid, _l| {
-            let db_pool = db_pool.clone();
-            Box::pin(async move {
-                info!("Running scheduled backup for job {}", backup_job_id);
-                if let Err(e) = db::update_schedule_last_run(&db_pool, schedule_id, "running").await {
-                    error!("Failed to update schedule status: {}", e);
-                }
-
-                match db::get_backup_job_by_id(&db_pool, backup_job_id).await {
-                    Ok(Some(job)) => {
-                        if let Err(e) = backup_worker::perform_backup(&db_pool, &job).await {
-                            error!("Backup job {} failed: {}", backup_job_id, e);
-                        }
-                    }
-                    Ok(None) => error!("Backup job {} not found for scheduled run", backup_job_id),
-                    Err(e) => error!("Failed to get backup job {}: {}", backup_job_id, e),
-                }
-
-                if let Err(e) = db::update_schedule_last_run(&db_pool, schedule_id, "completed").await {
-                    error!("Failed to update schedule status: {}", e);
-                }
-            })
-        })?;
-
-        state.scheduler.add(job).await?;
+        state.schedules.sync_schedule(&schedule).await?;
     }
 
     Ok((StatusCode::CREATED, Json(backup_job)))
@@ -68,7 +126,8 @@ pub async fn create_backup(
         ("id" = Uuid, Path, description = "Backup Job ID")
     ),
     responses(
-        (status = 200, description = "Backup job started successfully"),
+        (status = 200, description = "Run skipped by the job's `skip` overlap policy because a previous run is still pending; `status_reason` says which", body = BackupRun),
+        (status = 202, description = "Backup run queued; follow its progress at `GET /runs/{id}`", body = BackupRun),
         (status = 404, description = "Backup job not found", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
@@ -77,18 +136,16 @@ pub async fn run_backup(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
-    let job = db::get_backup_job_by_id(&state.db_pool, id).await?;
-
-    match job {
-        Some(job) => {
-            backup_worker::perform_backup(&state.db_pool, &job).await?;
-            Ok(StatusCode::OK)
-        }
-        None => Err(AppError::NotFound(format!(
+    if db::get_backup_job_by_id(&state.db_pool, id).await?.is_none() {
+        return Err(AppError::NotFound(format!(
             "Backup job with ID {} not found",
             id
-        ))),
+        )));
     }
+
+    let run = state.run_queue.enqueue(id, None, "manual").await?;
+    let status = if run.status == "skipped" { StatusCode::OK } else { StatusCode::ACCEPTED };
+    Ok((status, Json(run)))
 }
 
 #[utoipa::path(
@@ -151,6 +208,8 @@ pub async fn delete_backup(
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     let rows_affected = db::delete_backup_job(&state.db_pool, id).await?;
+    state.backup_triggers.remove(id);
+    state.schedules.remove_job(id).await?;
 
     if rows_affected == 0 {
         Err(AppError::NotFound(format!(
@@ -181,14 +240,67 @@ pub async fn update_backup(
     Path(id): Path<Uuid>,
     Json(payload): Json<NewBackupJob>,
 ) -> Result<impl IntoResponse, AppError> {
+    ensure_encryption_available(payload.encryption)?;
+    ensure_sample_size(payload.verification_sample_size)?;
+    ensure_known_templates(payload.ignore_templates.as_deref())?;
+    ensure_watch_settings(
+        payload.trigger_mode,
+        [payload.watch_quiet_seconds, payload.watch_min_interval_seconds, payload.watch_max_delay_seconds],
+        Some(&payload.mappings),
+    )
+    .await?;
+    remotes::validate_mappings(&state.db_pool, &payload.mappings).await?;
+
     let updated_job = db::update_backup_job(&state.db_pool, id, &payload).await?;
 
     match updated_job {
-        Some(job) => Ok((StatusCode::OK, Json(job))),
+        Some(job) => {
+            sync_triggers(&state, &job).await?;
+            sync_job_schedule(&state, &job).await?;
+            Ok((StatusCode::OK, Json(job)))
+        }
         None => Err(AppError::NotFound(format!("Backup job with ID {} not found", id))),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/backups/{id}/effective-filters",
+    tag = "Backups",
+    params(
+        ("id" = Uuid, Path, description = "Backup Job ID")
+    ),
+    responses(
+        (status = 200, description = "Exclusion rules (scanner defaults and .b2ignore files) applied to each source, and their rclone --filter form", body = EffectiveFilters),
+        (status = 404, description = "Backup job not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn get_effective_filters(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let job = db::get_backup_job_by_id(&state.db_pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Backup job with ID {} not found", id)))?;
+
+    let mappings: std::collections::BTreeMap<String, Vec<String>> = serde_json::from_value(job.mappings)?;
+    let mut filters = Vec::with_capacity(mappings.len());
+    for source_path in mappings.into_keys() {
+        let ignores = backup_worker::effective_ignores(&source_path, &job.ignore_templates).await?;
+        let suggested_templates =
+            ignore_templates::suggestions(std::path::Path::new(&source_path), &job.ignore_templates).await;
+        filters.push(MappingEffectiveFilters {
+            source_path,
+            rules: ignores.rules(),
+            rclone_filters: ignores.to_rclone_filters(),
+            suggested_templates,
+        });
+    }
+
+    Ok((StatusCode::OK, Json(EffectiveFilters { backup_job_id: id, mappings: filters })))
+}
+
 // Schedule endpoints
 #[utoipa::path(
     post,
@@ -231,38 +343,8 @@ pub async fn create_schedule(
     let schedule = db::create_backup_schedule(&state.db_pool, id, &payload).await?;
     
     // Add the schedule to the scheduler if it's enabled
+    state.schedules.sync_schedule(&schedule).await?;
     if schedule.enabled {
-        let db_pool = state.db_pool.clone();
-        let backup_job_id = id;
-        let schedule_id = schedule.id;
-        let cron_expression = schedule.cron_expression.clone();
-        
-        let job = tokio_cron_scheduler::Job::new_async(cron_expression.as_str(), move |_uuid, _l| {
-            let db_pool = db_pool.clone();
-            Box::pin(async move {
-                info!("🕐 Running scheduled backup for job {}", backup_job_id);
-                if let Err(e) = db::update_schedule_last_run(&db_pool, schedule_id, "running").await {
-                    error!("Failed to update schedule status: {}", e);
-                }
-
-                match db::get_backup_job_by_id(&db_pool, backup_job_id).await {
-                    Ok(Some(job)) => {
-                        info!("📦 Executing backup: {}", job.name);
-                        if let Err(e) = backup_worker::perform_backup(&db_pool, &job).await {
-                            error!("❌ Backup job {} failed: {}", backup_job_id, e);
-                            let _ = db::update_schedule_last_run(&db_pool, schedule_id, "failed").await;
-                        } else {
-                            info!("✅ Backup job {} completed successfully", backup_job_id);
-                            let _ = db::update_schedule_last_run(&db_pool, schedule_id, "completed").await;
-                        }
-                    }
-                    Ok(None) => error!("Backup job {} not found for scheduled run", backup_job_id),
-                    Err(e) => error!("Failed to get backup job {}: {}", backup_job_id, e),
-                }
-            })
-        })?;
-
-        state.scheduler.add(job).await?;
         info!("📅 Schedule '{}' added to scheduler (cron: {})", schedule.name, schedule.cron_expression);
     }
     
@@ -315,6 +397,7 @@ pub async fn delete_schedule(
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     let rows_affected = db::delete_backup_schedule(&state.db_pool, id).await?;
+    state.schedules.remove_job(id).await?;
 
     if rows_affected == 0 {
         Err(AppError::NotFound(format!(
@@ -348,7 +431,10 @@ pub async fn update_schedule(
     let updated_schedule = db::update_backup_schedule(&state.db_pool, id, &payload).await?;
 
     match updated_schedule {
-        Some(schedule) => Ok((StatusCode::OK, Json(schedule))),
+        Some(schedule) => {
+            state.schedules.sync_schedule(&schedule).await?;
+            Ok((StatusCode::OK, Json(schedule)))
+        }
         None => Err(AppError::NotFound(format!(
             "No schedule found for backup job {}",
             id
@@ -376,10 +462,33 @@ pub async fn patch_backup(
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateBackupJob>,
 ) -> Result<impl IntoResponse, AppError> {
+    ensure_encryption_available(payload.encryption)?;
+    ensure_sample_size(payload.verification_sample_size.flatten())?;
+    ensure_known_templates(payload.ignore_templates.as_deref())?;
+    // O modo e as origens que valem depois do PATCH: o que faltar no corpo vem do job atual
+    let current = db::get_backup_job_by_id(&state.db_pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Backup job with ID {} not found", id)))?;
+    let current_mappings: HashMap<String, Vec<String>> =
+        serde_json::from_value(current.mappings).unwrap_or_default();
+    ensure_watch_settings(
+        Some(payload.trigger_mode.unwrap_or(current.trigger_mode)),
+        [payload.watch_quiet_seconds, payload.watch_min_interval_seconds, payload.watch_max_delay_seconds],
+        Some(payload.mappings.as_ref().unwrap_or(&current_mappings)),
+    )
+    .await?;
+    if let Some(mappings) = &payload.mappings {
+        remotes::validate_mappings(&state.db_pool, mappings).await?;
+    }
+
     let updated_job = db::patch_backup_job(&state.db_pool, id, &payload).await?;
 
     match updated_job {
-        Some(job) => Ok((StatusCode::OK, Json(job))),
+        Some(job) => {
+            sync_triggers(&state, &job).await?;
+            sync_job_schedule(&state, &job).await?;
+            Ok((StatusCode::OK, Json(job)))
+        }
         None => Err(AppError::NotFound(format!(
             "Backup job with ID {} not found",
             id
@@ -409,7 +518,10 @@ pub async fn patch_schedule(
     let updated_schedule = db::patch_backup_schedule(&state.db_pool, id, &payload).await?;
 
     match updated_schedule {
-        Some(schedule) => Ok((StatusCode::OK, Json(schedule))),
+        Some(schedule) => {
+            state.schedules.sync_schedule(&schedule).await?;
+            Ok((StatusCode::OK, Json(schedule)))
+        }
         None => Err(AppError::NotFound(format!(
             "No schedule found for backup job {}",
             id
@@ -481,20 +593,19 @@ pub async fn list_all_schedules(
     path = "/scheduler/status",
     tag = "System",
     responses(
-        (status = 200, description = "Scheduler status"),
+        (status = 200, description = "Scheduler status and the backup schedules registered in it, with their next tick", body = SchedulerStatus),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
 pub async fn scheduler_status(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Simples: apenas verificar se o scheduler está rodando
     // Se chegamos aqui, o scheduler foi iniciado com sucesso
-    
-    let status = serde_json::json!({
-        "scheduler": "running",
-        "status": "ok"
-    });
-    
+    let status = SchedulerStatus {
+        scheduler: "running".to_string(),
+        status: "ok".to_string(),
+        jobs: state.schedules.status().await,
+    };
+
     Ok((StatusCode::OK, Json(status)))
 }
@@ -1,6 +1,6 @@
 /// Rotas para varredura e busca de arquivos
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -9,12 +9,20 @@ use serde::Deserialize;
 use serde_json::json;
 use tracing::{debug, info};
 use uuid::Uuid;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::{
-    file_scanner::{FileScanner, ScanConfig},
+    content_extract, db,
+    file_scanner::{self, FileScanner, ScanCancelled, ScanConfig},
+    ignore_templates,
+    models::{
+        FileAuditCheck, FileAuditEntry, FileAuditResponse, FileSearchResponse, FileTypeStatsResponse,
+        MimeCategory, MimeCategoryStats, MimeTypeStats, ScanConfigRecord,
+    },
+    search::{self, SearchRequest, SearchSort},
     AppError, AppState,
 };
+use tantivy::Order;
 
 /// Parâmetros para criar uma configuração de scan
 #[derive(Debug, Deserialize, ToSchema)]
@@ -30,15 +38,55 @@ pub struct CreateScanConfig {
     pub recursive: bool,
     /// Profundidade máxima
     pub max_depth: Option<i32>,
+    /// Seguir links simbólicos; sem isso, cada link é catalogado com o destino
+    #[serde(default)]
+    pub follow_symlinks: bool,
     /// Padrões para excluir
     #[serde(default)]
     pub exclude_patterns: Vec<String>,
+    /// Templates de exclusão (`GET /ignore-templates`) ou `auto`
+    #[serde(default)]
+    pub ignore_templates: Vec<String>,
+    /// Categorias de arquivo (detectadas pelo conteúdo) que o scan não cataloga;
+    /// não afetam o backup nem os arquivos já catalogados
+    #[serde(default)]
+    pub exclude_mime_categories: Vec<MimeCategory>,
+    /// Recalcular o hash de todos os arquivos em toda execução
+    #[serde(default)]
+    pub force_rehash: bool,
+    /// Fração (0.0 a 1.0) dos arquivos inalterados reverificados a cada execução
+    #[serde(default)]
+    pub paranoid_sample_rate: f64,
+    /// Manter o catálogo da raiz atualizado em tempo real (watcher no sistema de arquivos)
+    #[serde(default)]
+    pub live_mode: bool,
+    /// Extrair o texto de documentos e código-fonte para a busca por conteúdo
+    #[serde(default)]
+    pub index_content: bool,
+    /// Tamanho máximo (bytes) de um arquivo com conteúdo extraído (padrão 1 MiB)
+    pub content_max_bytes: Option<i64>,
+    /// Extensões com conteúdo extraído; se omitido, texto, Markdown, CSV, JSON, TOML e código-fonte
+    pub content_extensions: Option<Vec<String>>,
+    /// Gravar atributos estendidos (xattrs) e ACLs POSIX no catálogo
+    #[serde(default)]
+    pub capture_xattrs: bool,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn category_names(categories: &[MimeCategory]) -> Vec<String> {
+    categories.iter().map(|category| category.as_str().to_string()).collect()
+}
+
+/// Parâmetros de uma execução de scan
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RunScanParams {
+    /// Recalcula o hash de todos os arquivos nesta execução, mesmo inalterados
+    pub force_rehash: Option<bool>,
+}
+
 /// Cria uma configuração de scan
 /// 
 /// Cria uma configuração de scan que pode ser executada posteriormente.
@@ -63,7 +111,26 @@ fn default_true() -> bool {
 ///   "root_path": "/workspace/projeto",
 ///   "recursive": true,
 ///   "max_depth": 5,
-///   "exclude_patterns": ["node_modules/*", "*.tmp", ".git/*"]
+///   "exclude_patterns": ["node_modules/", "*.tmp", ".git/"]
+/// }
+/// ```
+/// 
+/// **Com busca por conteúdo:**
+/// ```json
+/// {
+///   "name": "Scan Notas",
+///   "root_path": "/home/user/notas",
+///   "index_content": true,
+///   "content_extensions": ["md", "txt"]
+/// }
+/// ```
+/// 
+/// **Com templates detectados automaticamente:**
+/// ```json
+/// {
+///   "name": "Scan Monorepo",
+///   "root_path": "/workspace/monorepo",
+///   "ignore_templates": ["auto", "ide"]
 /// }
 /// ```
 /// 
@@ -72,7 +139,21 @@ fn default_true() -> bool {
 /// * `root_path` - Caminho absoluto da pasta para escanear
 /// * `recursive` - true = escanea subpastas, false = só a pasta atual
 /// * `max_depth` - (Opcional) Profundidade máxima. Se omitido = sem limite
-/// * `exclude_patterns` - (Opcional) Padrões glob para ignorar arquivos
+/// * `follow_symlinks` - (Opcional) Segue links simbólicos; por padrão os links são catalogados
+///   como tal, com o destino em `metadata.symlink_target`
+/// * `exclude_patterns` - (Opcional) Padrões no estilo `.gitignore` para ignorar arquivos e diretórios
+/// * `ignore_templates` - (Opcional) Templates de exclusão por linguagem, ou `auto` para detectar pela raiz
+/// * `exclude_mime_categories` - (Opcional) Categorias não catalogadas (`video`, `archive`...), pelo tipo detectado;
+///   só a catalogação é afetada
+/// * `force_rehash` - (Opcional) Sempre recalcular hashes; por padrão arquivos com tamanho e mtime
+///   iguais ao catálogo reaproveitam o hash salvo
+/// * `paranoid_sample_rate` - (Opcional) Fração dos arquivos inalterados reverificados a cada execução
+/// * `live_mode` - (Opcional) Observa a raiz e aplica mudanças ao catálogo conforme acontecem;
+///   faz um scan completo ao iniciar e sempre que o watcher perde eventos
+/// * `index_content` - (Opcional) Extrai o texto dos arquivos para `GET /files/search?content=...`
+/// * `content_max_bytes` - (Opcional) Arquivos maiores não têm o conteúdo extraído
+/// * `content_extensions` - (Opcional) Extensões com conteúdo extraído, no lugar da lista padrão
+/// * `capture_xattrs` - (Opcional) Grava os atributos estendidos em `metadata.xattrs`
 /// 
 /// # Retorna
 /// * `Ok(Json)` - Configuração criada com ID
@@ -97,31 +178,75 @@ pub async fn create_scan_config(
         "Criando configuração de scan"
     );
 
+    ignore_templates::validate(&payload.ignore_templates).map_err(AppError::BadRequest)?;
+    if !(0.0..=1.0).contains(&payload.paranoid_sample_rate) {
+        return Err(AppError::BadRequest(
+            "paranoid_sample_rate deve estar entre 0.0 e 1.0".to_string(),
+        ));
+    }
+    let content_max_bytes = payload.content_max_bytes.unwrap_or(content_extract::DEFAULT_CONTENT_MAX_BYTES);
+    if content_max_bytes <= 0 {
+        return Err(AppError::BadRequest(
+            "content_max_bytes deve ser maior que zero".to_string(),
+        ));
+    }
+
+    if payload.live_mode {
+        ensure_live_root(&payload.root_path).await?;
+    }
+
     // Inserir no banco
-    let id = sqlx::query_scalar!(
+    let record = sqlx::query_as!(
+        ScanConfigRecord,
         r#"
         INSERT INTO scan_configs (
             name, description, root_path, recursive, 
-            max_depth, exclude_patterns, status
-        ) VALUES ($1, $2, $3, $4, $5, $6, 'PENDING')
-        RETURNING id
+            max_depth, exclude_patterns, ignore_templates,
+            force_rehash, paranoid_sample_rate, live_mode,
+            index_content, content_max_bytes, content_extensions,
+            exclude_mime_categories, follow_symlinks, capture_xattrs, status
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, 'PENDING')
+        RETURNING id, name, status, root_path, recursive, follow_symlinks, max_depth,
+                  exclude_patterns, ignore_templates, exclude_mime_categories, force_rehash,
+                  paranoid_sample_rate, live_mode, index_content, content_max_bytes,
+                  content_extensions, capture_xattrs
         "#,
         payload.name,
         payload.description,
         payload.root_path,
         payload.recursive,
         payload.max_depth,
-        &payload.exclude_patterns
+        &payload.exclude_patterns,
+        &payload.ignore_templates,
+        payload.force_rehash,
+        payload.paranoid_sample_rate,
+        payload.live_mode,
+        payload.index_content,
+        content_max_bytes,
+        payload.content_extensions.as_deref(),
+        &category_names(&payload.exclude_mime_categories),
+        payload.follow_symlinks,
+        payload.capture_xattrs
     )
     .fetch_one(&state.db_pool)
     .await?;
 
+    let id = record.id;
+    if record.live_mode {
+        state
+            .live_catalog
+            .watch(id, ScanConfig::from_record(&record))
+            .map_err(|e| AppError::InternalServerError(format!("Falha ao iniciar watcher: {}", e)))?;
+    }
+
     Ok((
         StatusCode::CREATED,
         Json(json!({
             "id": id,
             "name": payload.name,
             "root_path": payload.root_path,
+            "live_mode": payload.live_mode,
+            "index_content": payload.index_content,
             "status": "PENDING",
             "message": "Configuração de scan criada. Use POST /files/scan/{id}/run para executar"
         }))
@@ -184,10 +309,470 @@ pub async fn find_duplicate_files(
     Ok((StatusCode::OK, Json(result)))
 }
 
+/// Filtros das estatísticas por tipo
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FileTypeStatsParams {
+    /// Apenas arquivos sob este diretório
+    pub directory: Option<String>,
+    /// Apenas uma categoria (as contagens por tipo MIME ficam restritas a ela)
+    pub mime_category: Option<MimeCategory>,
+}
+
+/// Estatísticas do catálogo por tipo de arquivo
+/// 
+/// Agrupa os arquivos ativos pela categoria e pelo tipo MIME detectados no
+/// scan, e conta os arquivos cujo conteúdo não corresponde à extensão.
+/// 
+/// # Retorna
+/// * `Ok(Json)` - Contagens e tamanhos por categoria e tipo MIME
+#[utoipa::path(
+    get,
+    path = "/files/stats",
+    tag = "File Catalog",
+    params(FileTypeStatsParams),
+    responses(
+        (status = 200, description = "Estatísticas por tipo", body = FileTypeStatsResponse),
+        (status = 400, description = "Categoria inválida"),
+        (status = 500, description = "Erro interno")
+    )
+)]
+pub async fn file_type_stats(
+    State(state): State<AppState>,
+    Query(params): Query<FileTypeStatsParams>,
+) -> Result<impl IntoResponse, AppError> {
+    debug!(directory = ?params.directory, category = ?params.mime_category, "Calculando estatísticas por tipo");
+
+    let directory = params.directory.map(|dir| dir.trim_end_matches('/').to_string());
+    let prefix = directory.as_ref().map(|dir| format!("{}/", dir));
+    let category = params.mime_category.map(|category| category.as_str());
+
+    let categories = sqlx::query!(
+        r#"
+        SELECT
+            mime_category,
+            COUNT(*) AS "files!",
+            COALESCE(SUM(file_size), 0)::BIGINT AS "total_size!",
+            COUNT(*) FILTER (WHERE metadata ? 'mime_mismatch') AS "mismatches!"
+        FROM file_catalog
+        WHERE is_active = TRUE
+          AND ($1::TEXT IS NULL OR parent_directory = $1 OR starts_with(parent_directory, $2))
+          AND ($3::TEXT IS NULL OR mime_category = $3)
+        GROUP BY mime_category
+        ORDER BY 2 DESC
+        "#,
+        directory,
+        prefix,
+        category
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let mime_types = sqlx::query!(
+        r#"
+        SELECT
+            mime_type,
+            COUNT(*) AS "files!",
+            COALESCE(SUM(file_size), 0)::BIGINT AS "total_size!"
+        FROM file_catalog
+        WHERE is_active = TRUE
+          AND ($1::TEXT IS NULL OR parent_directory = $1 OR starts_with(parent_directory, $2))
+          AND ($3::TEXT IS NULL OR mime_category = $3)
+        GROUP BY mime_type
+        ORDER BY 2 DESC, 1
+        LIMIT 20
+        "#,
+        directory,
+        prefix,
+        category
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let response = FileTypeStatsResponse {
+        total_files: categories.iter().map(|c| c.files).sum(),
+        total_size: categories.iter().map(|c| c.total_size).sum(),
+        mime_mismatches: categories.iter().map(|c| c.mismatches).sum(),
+        categories: categories
+            .into_iter()
+            .map(|c| MimeCategoryStats {
+                category: c.mime_category.as_deref().and_then(MimeCategory::parse),
+                files: c.files,
+                total_size: c.total_size,
+            })
+            .collect(),
+        mime_types: mime_types
+            .into_iter()
+            .map(|t| MimeTypeStats {
+                mime_type: t.mime_type,
+                files: t.files,
+                total_size: t.total_size,
+            })
+            .collect(),
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Parâmetros da auditoria de permissões
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FileAuditParams {
+    /// Verificação: `world_writable`, `setuid`, `unknown_owner` ou `wrong_owner`
+    pub check: FileAuditCheck,
+    /// Apenas arquivos sob este diretório
+    pub directory: Option<String>,
+    /// Dono esperado (uid), para `wrong_owner`
+    pub uid: Option<i64>,
+    /// Dono esperado (nome do usuário), para `wrong_owner`
+    pub owner: Option<String>,
+    /// Máximo de arquivos retornados (padrão 100, máximo 1000)
+    pub limit: Option<i64>,
+}
+
+/// Audita dono e permissões dos arquivos catalogados
+/// 
+/// Usa o dono, o grupo e o modo gravados no último scan de cada arquivo
+/// (arquivos catalogados antes disso não aparecem até serem varridos de novo).
+/// 
+/// # Exemplos de uso:
+/// - `GET /files/audit?check=world_writable&directory=/srv/www`
+/// - `GET /files/audit?check=wrong_owner&directory=/home/ana&owner=ana`
+/// 
+/// # Retorna
+/// * `Ok(Json)` - Total de arquivos encontrados e os primeiros `limit`, por caminho
+/// * `Err(BadRequest)` - `wrong_owner` sem `uid` nem `owner`
+#[utoipa::path(
+    get,
+    path = "/files/audit",
+    tag = "File Catalog",
+    params(FileAuditParams),
+    responses(
+        (status = 200, description = "Arquivos encontrados pela auditoria", body = FileAuditResponse),
+        (status = 400, description = "Parâmetros inválidos"),
+        (status = 500, description = "Erro interno")
+    )
+)]
+pub async fn audit_files(
+    State(state): State<AppState>,
+    Query(params): Query<FileAuditParams>,
+) -> Result<impl IntoResponse, AppError> {
+    debug!(check = ?params.check, directory = ?params.directory, "Auditando permissões do catálogo");
+
+    if params.check == FileAuditCheck::WrongOwner && params.uid.is_none() && params.owner.is_none() {
+        return Err(AppError::BadRequest(
+            "wrong_owner exige uid ou owner".to_string(),
+        ));
+    }
+
+    let directory = params.directory.map(|dir| dir.trim_end_matches('/').to_string());
+    let prefix = directory.as_ref().map(|dir| format!("{}/", dir));
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+
+    // Modo: 0o002 = escrita para todos, 0o6000 = setuid/setgid
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            id, file_path, file_size,
+            (metadata->'unix'->>'mode')::BIGINT AS "mode!",
+            metadata->'unix'->>'permissions' AS permissions,
+            (metadata->'unix'->>'uid')::BIGINT AS "uid!",
+            (metadata->'unix'->>'gid')::BIGINT AS "gid!",
+            metadata->'unix'->>'owner' AS owner,
+            metadata->'unix'->>'group' AS "group",
+            metadata->>'symlink_target' AS symlink_target,
+            COUNT(*) OVER () AS "total!"
+        FROM file_catalog
+        WHERE is_active = TRUE
+          AND metadata ? 'unix'
+          AND ($1::TEXT IS NULL OR parent_directory = $1 OR starts_with(parent_directory, $2))
+          AND CASE $3::TEXT
+              WHEN 'world_writable' THEN ((metadata->'unix'->>'mode')::BIGINT & 2) <> 0
+                                         AND mime_type IS DISTINCT FROM 'inode/symlink'
+              WHEN 'setuid' THEN ((metadata->'unix'->>'mode')::BIGINT & 3072) <> 0
+              WHEN 'unknown_owner' THEN metadata->'unix'->>'owner' IS NULL
+                                        OR metadata->'unix'->>'group' IS NULL
+              WHEN 'wrong_owner' THEN ($4::BIGINT IS NOT NULL AND (metadata->'unix'->>'uid')::BIGINT <> $4)
+                                      OR ($5::TEXT IS NOT NULL AND metadata->'unix'->>'owner' IS DISTINCT FROM $5)
+              ELSE FALSE
+          END
+        ORDER BY file_path
+        LIMIT $6
+        "#,
+        directory,
+        prefix,
+        params.check.as_str(),
+        params.uid,
+        params.owner,
+        limit
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let response = FileAuditResponse {
+        check: params.check,
+        total: rows.first().map_or(0, |row| row.total),
+        files: rows
+            .into_iter()
+            .map(|row| FileAuditEntry {
+                id: row.id,
+                file_path: row.file_path,
+                file_size: row.file_size,
+                mode: row.mode,
+                permissions: row.permissions,
+                uid: row.uid,
+                gid: row.gid,
+                owner: row.owner,
+                group: row.group,
+                symlink_target: row.symlink_target,
+            })
+            .collect(),
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Filtros para listar mudanças no catálogo
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FileChangesParams {
+    /// Apenas mudanças detectadas a partir deste instante (ISO 8601, UTC)
+    pub since: chrono::DateTime<chrono::Utc>,
+    /// Filtrar por tipo: `created`, `modified` ou `deleted`
+    pub change_type: Option<String>,
+    /// Máximo de eventos retornados (padrão 1000)
+    pub limit: Option<i64>,
+}
+
+/// Lista mudanças detectadas pelos scans
+/// 
+/// Eventos de `file_history` desde `since`, mais recentes primeiro:
+/// `created` (arquivo catalogado pela primeira vez), `modified` (tamanho,
+/// conteúdo ou mtime mudaram) e `deleted` (arquivo não encontrado em um scan
+/// completo da raiz e marcado como inativo). Mudanças só de atime são omitidas.
+/// 
+/// # Retorna
+/// * `Ok(Json)` - Lista de eventos
+#[utoipa::path(
+    get,
+    path = "/files/changes",
+    tag = "File Catalog",
+    params(FileChangesParams),
+    responses(
+        (status = 200, description = "Mudanças detectadas"),
+        (status = 400, description = "Tipo de mudança inválido"),
+        (status = 500, description = "Erro ao buscar mudanças")
+    )
+)]
+pub async fn list_file_changes(
+    State(state): State<AppState>,
+    Query(params): Query<FileChangesParams>,
+) -> Result<impl IntoResponse, AppError> {
+    debug!(since = %params.since, change_type = ?params.change_type, "Listando mudanças no catálogo");
+
+    if let Some(change_type) = params.change_type.as_deref() {
+        if !["created", "modified", "deleted"].contains(&change_type) {
+            return Err(AppError::BadRequest(format!(
+                "change_type inválido: {} (use created, modified ou deleted)",
+                change_type
+            )));
+        }
+    }
+
+    let changes = sqlx::query!(
+        r#"
+        SELECT
+            fh.id,
+            fh.file_catalog_id,
+            fc.file_path,
+            fc.is_active,
+            CASE fh.scan_type
+                WHEN 'initial' THEN 'created'
+                WHEN 'deleted' THEN 'deleted'
+                ELSE 'modified'
+            END AS "change_type!",
+            fh.file_size,
+            fh.content_hash,
+            fh.size_delta,
+            fh.modified_at,
+            fh.scan_job_id,
+            fh.scanned_at
+        FROM file_history fh
+        JOIN file_catalog fc ON fc.id = fh.file_catalog_id
+        WHERE fh.scanned_at >= $1
+          AND (fh.scan_type IN ('initial', 'deleted')
+               OR fh.size_changed OR fh.hash_changed OR fh.modified_changed)
+          AND ($2::TEXT IS NULL OR CASE fh.scan_type
+                WHEN 'initial' THEN 'created'
+                WHEN 'deleted' THEN 'deleted'
+                ELSE 'modified'
+              END = $2)
+        ORDER BY fh.scanned_at DESC
+        LIMIT $3
+        "#,
+        params.since.naive_utc(),
+        params.change_type,
+        params.limit.unwrap_or(1000).clamp(1, 10_000)
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let result: Vec<_> = changes.into_iter().map(|c| {
+        json!({
+            "id": c.id,
+            "file_catalog_id": c.file_catalog_id,
+            "file_path": c.file_path,
+            "change_type": c.change_type,
+            "is_active": c.is_active,
+            "file_size": c.file_size,
+            "size_delta": c.size_delta,
+            "content_hash": c.content_hash,
+            "modified_at": c.modified_at,
+            "scan_job_id": c.scan_job_id,
+            "detected_at": c.scanned_at
+        })
+    }).collect();
+
+    info!(count = result.len(), "Mudanças encontradas");
+
+    Ok((StatusCode::OK, Json(result)))
+}
+
+/// Parâmetros da busca de arquivos
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FileSearchParams {
+    /// Consulta na sintaxe do tantivy; termos sem campo buscam no nome e no caminho
+    /// (ex.: `relatorio AND extension:pdf`, `size:>1048576`, `modified:[2025-01-01T00:00:00Z TO *]`)
+    pub q: Option<String>,
+    /// Consulta no conteúdo dos arquivos (só configs de scan com `index_content`),
+    /// mesma sintaxe de `q`
+    pub content: Option<String>,
+    /// Filtrar por extensão (sem ponto)
+    pub extension: Option<String>,
+    /// Filtrar por categoria do tipo detectado (`image`, `video`, `audio`, `archive`,
+    /// `document`, `source_code`, `text`, `other`)
+    pub mime_category: Option<MimeCategory>,
+    /// Apenas arquivos sob este diretório; a faceta `directory` lista seus subdiretórios
+    pub directory: Option<String>,
+    /// Filtrar por status de backup: `never`, `current` ou `stale`
+    pub backup_status: Option<String>,
+    /// Ordenação: `relevance` (padrão), `size` ou `modified`
+    pub sort: Option<String>,
+    /// `asc` ou `desc` (padrão) para `size` e `modified`
+    pub order: Option<String>,
+    /// Resultados por página (padrão 50, máximo 500)
+    pub limit: Option<usize>,
+    /// Resultados a pular (paginação); `offset + limit` vai até 10000
+    pub offset: Option<usize>,
+}
+
+/// Busca arquivos no catálogo
+/// 
+/// Usa o índice de busca mantido junto com o `file_catalog` (só arquivos
+/// ativos). Além da página de resultados, retorna contagens por extensão e
+/// por subdiretório sobre todos os resultados.
+/// 
+/// # Retorna
+/// * `Ok(Json)` - Resultados, total e facetas
+#[utoipa::path(
+    get,
+    path = "/files/search",
+    tag = "File Catalog",
+    params(FileSearchParams),
+    responses(
+        (status = 200, description = "Resultados da busca", body = FileSearchResponse),
+        (status = 400, description = "Consulta ou filtro inválido, ou página além de 10000 resultados"),
+        (status = 500, description = "Índice de busca indisponível")
+    )
+)]
+pub async fn search_files(
+    Query(params): Query<FileSearchParams>,
+) -> Result<impl IntoResponse, AppError> {
+    debug!(q = ?params.q, "Buscando arquivos");
+
+    let index = search::index()
+        .ok_or_else(|| AppError::InternalServerError("Índice de busca não inicializado".to_string()))?;
+
+    if let Some(status) = params.backup_status.as_deref() {
+        if !["never", "current", "stale"].contains(&status) {
+            return Err(AppError::BadRequest(format!(
+                "backup_status inválido: {} (use never, current ou stale)",
+                status
+            )));
+        }
+    }
+    let order = match params.order.as_deref() {
+        None | Some("desc") => Order::Desc,
+        Some("asc") => Order::Asc,
+        Some(other) => {
+            return Err(AppError::BadRequest(format!("order inválido: {} (use asc ou desc)", other)));
+        }
+    };
+    let sort = match params.sort.as_deref() {
+        None | Some("relevance") => SearchSort::Relevance,
+        Some("size") => SearchSort::Size(order),
+        Some("modified") => SearchSort::Modified(order),
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "sort inválido: {} (use relevance, size ou modified)",
+                other
+            )));
+        }
+    };
+
+    let request = SearchRequest {
+        query: params.q,
+        content: params.content,
+        extension: params.extension,
+        mime_category: params.mime_category,
+        directory: params.directory,
+        backup_status: params.backup_status,
+        sort,
+        limit: params.limit.unwrap_or(50).clamp(1, search::MAX_PAGE_SIZE),
+        offset: params.offset.unwrap_or(0),
+    };
+    let response = tokio::task::spawn_blocking(move || index.search(&request))
+        .await
+        .map_err(|e| AppError::InternalServerError(e.to_string()))??;
+
+    info!(total = response.total, "Busca concluída");
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Reconstrói o índice de busca a partir do catálogo
+/// 
+/// Normalmente desnecessário: o índice acompanha scans e backups, e é
+/// reconstruído sozinho quando criado do zero. Útil se o diretório do índice
+/// foi apagado ou restaurado de outra máquina.
+/// 
+/// # Retorna
+/// * `Ok(Json)` - Quantidade de arquivos indexados
+#[utoipa::path(
+    post,
+    path = "/files/search/reindex",
+    tag = "File Catalog",
+    responses(
+        (status = 200, description = "Índice reconstruído"),
+        (status = 500, description = "Falha ao reconstruir o índice")
+    )
+)]
+pub async fn reindex_search(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let index = search::index()
+        .ok_or_else(|| AppError::InternalServerError("Índice de busca não inicializado".to_string()))?;
+
+    let indexed = index.reindex_under(&state.db_pool, None).await?;
+    search::commit_global().await?;
+
+    info!(indexed = indexed, "Índice de busca reconstruído");
+
+    Ok((StatusCode::OK, Json(json!({ "indexed_files": indexed }))))
+}
+
 /// Executa uma configuração de scan
 /// 
 /// Executa uma configuração de scan previamente criada.
 /// O scan é executado em background e retorna imediatamente.
+/// Com `?force_rehash=true` todos os arquivos são relidos nesta execução.
 /// 
 /// # Retorna
 /// * `Ok(Json)` - Scan iniciado com ID do job
@@ -196,7 +781,8 @@ pub async fn find_duplicate_files(
     path = "/files/scan/{id}/run",
     tag = "File Catalog",
     params(
-        ("id" = Uuid, Path, description = "ID da configuração de scan")
+        ("id" = Uuid, Path, description = "ID da configuração de scan"),
+        RunScanParams
     ),
     responses(
         (status = 202, description = "Scan iniciado"),
@@ -208,22 +794,12 @@ pub async fn find_duplicate_files(
 pub async fn run_scan_config(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
+    Query(params): Query<RunScanParams>,
 ) -> Result<impl IntoResponse, AppError> {
     info!(config_id = %id, "Executando configuração de scan");
 
     // Buscar configuração
-    let config_record = sqlx::query!(
-        r#"
-        SELECT id, name, root_path, recursive, max_depth, 
-               exclude_patterns, status, is_active
-        FROM scan_configs
-        WHERE id = $1 AND is_active = true
-        "#,
-        id
-    )
-    .fetch_optional(&state.db_pool)
-    .await?;
-
+    let config_record = db::get_scan_config(&state.db_pool, id).await?;
     let config_record = config_record
         .ok_or_else(|| AppError::NotFound(format!("Configuração {} não encontrada", id)))?;
 
@@ -242,11 +818,8 @@ pub async fn run_scan_config(
 
     // Criar configuração do scanner
     let scan_config = ScanConfig {
-        root_path: std::path::PathBuf::from(&config_record.root_path),
-        recursive: config_record.recursive.unwrap_or(true),
-        max_depth: config_record.max_depth,
-        exclude_patterns: config_record.exclude_patterns.unwrap_or_default(),
-        ..Default::default()
+        force_rehash: params.force_rehash.unwrap_or(config_record.force_rehash),
+        ..ScanConfig::from_record(&config_record)
     };
 
     // Executar scan em background
@@ -292,6 +865,32 @@ pub async fn run_scan_config(
                 .execute(&db_pool)
                 .await;
             }
+            Err(e) if e.is::<ScanCancelled>() => {
+                let scan_job_id = e.downcast_ref::<ScanCancelled>().map(|c| c.scan_job_id);
+                info!(config_id = %config_id, scan_job_id = ?scan_job_id, "Scan cancelado");
+
+                let _ = sqlx::query!(
+                    r#"
+                    UPDATE scan_configs 
+                    SET status = 'CANCELLED',
+                        last_scan_job_id = $2,
+                        total_runs = total_runs + 1
+                    WHERE id = $1
+                    "#,
+                    config_id,
+                    scan_job_id
+                )
+                .execute(&db_pool)
+                .await;
+
+                let _ = sqlx::query!(
+                    "UPDATE scan_jobs SET scan_config_id = $1 WHERE id = $2",
+                    config_id,
+                    scan_job_id
+                )
+                .execute(&db_pool)
+                .await;
+            }
             Err(e) => {
                 tracing::error!(
                     config_id = %config_id,
@@ -328,6 +927,156 @@ pub async fn run_scan_config(
     ))
 }
 
+/// Cancela um job de varredura em andamento
+///
+/// O walker para na próxima entrada e o job termina com status `cancelled`,
+/// mantendo as estatísticas do que já foi percorrido. Arquivos não
+/// visitados não são marcados como removidos.
+///
+/// # Argumentos
+/// * `id` - ID do job de varredura
+///
+/// # Retorna
+/// * `Ok(Json)` - Cancelamento solicitado
+#[utoipa::path(
+    post,
+    path = "/files/scan/jobs/{id}/cancel",
+    tag = "File Catalog",
+    params(
+        ("id" = Uuid, Path, description = "ID do job de varredura")
+    ),
+    responses(
+        (status = 202, description = "Cancelamento solicitado"),
+        (status = 404, description = "Job não encontrado"),
+        (status = 409, description = "Job não está em execução nesta instância, ou é uma sessão live"),
+        (status = 500, description = "Erro interno")
+    )
+)]
+pub async fn cancel_scan_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let job = sqlx::query!("SELECT status, scan_type FROM scan_jobs WHERE id = $1", id)
+        .fetch_optional(&state.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Job de varredura {} não encontrado", id)))?;
+
+    // Sessões live duram enquanto o live_mode da configuração estiver ativo
+    if job.scan_type.as_deref() == Some("live") {
+        return Err(AppError::Conflict(
+            "Sessões live são encerradas desativando o live_mode da configuração".to_string(),
+        ));
+    }
+    if job.status.as_deref() != Some("running") {
+        return Err(AppError::Conflict(format!(
+            "Job de varredura {} não está em execução (status {})",
+            id,
+            job.status.as_deref().unwrap_or("desconhecido")
+        )));
+    }
+    if !file_scanner::cancel_scan(id) {
+        return Err(AppError::Conflict(format!(
+            "Job de varredura {} não está em execução nesta instância",
+            id
+        )));
+    }
+
+    info!(scan_job_id = %id, "Cancelamento de varredura solicitado");
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "id": id,
+            "status": "running",
+            "message": "Cancelamento solicitado; o job termina como cancelled"
+        }))
+    ))
+}
+
+/// O modo live observa a raiz, então ela precisa ser um diretório existente
+async fn ensure_live_root(root_path: &str) -> Result<(), AppError> {
+    let is_dir = tokio::fs::metadata(root_path)
+        .await
+        .map(|m| m.is_dir())
+        .unwrap_or(false);
+    if !is_dir {
+        return Err(AppError::BadRequest(format!(
+            "live_mode exige que root_path seja um diretório existente: {}",
+            root_path
+        )));
+    }
+    Ok(())
+}
+
+/// Corpo de `PATCH /files/scan/{id}/live`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetLiveMode {
+    pub live_mode: bool,
+}
+
+/// Liga ou desliga o modo live de uma configuração de scan
+///
+/// Ligar inicia o watcher (com um scan completo da raiz); desligar para o
+/// watcher e encerra a sessão live em andamento.
+///
+/// # Argumentos
+/// * `id` - ID da configuração de scan
+/// * `live_mode` - Novo valor
+///
+/// # Retorna
+/// * `Ok(Json)` - Configuração com o novo `live_mode`
+#[utoipa::path(
+    patch,
+    path = "/files/scan/{id}/live",
+    tag = "File Catalog",
+    params(
+        ("id" = Uuid, Path, description = "ID da configuração de scan")
+    ),
+    request_body = SetLiveMode,
+    responses(
+        (status = 200, description = "Modo live alterado"),
+        (status = 400, description = "root_path não é um diretório existente"),
+        (status = 404, description = "Configuração não encontrada"),
+        (status = 500, description = "Erro interno")
+    )
+)]
+pub async fn set_scan_config_live_mode(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<SetLiveMode>,
+) -> Result<impl IntoResponse, AppError> {
+    info!(config_id = %id, live_mode = payload.live_mode, "Alterando modo live da configuração de scan");
+
+    if payload.live_mode {
+        let current = db::get_scan_config(&state.db_pool, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Configuração {} não encontrada", id)))?;
+        ensure_live_root(&current.root_path).await?;
+    }
+
+    let record = db::set_scan_config_live_mode(&state.db_pool, id, payload.live_mode)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Configuração {} não encontrada", id)))?;
+
+    if record.live_mode {
+        state
+            .live_catalog
+            .watch(id, ScanConfig::from_record(&record))
+            .map_err(|e| AppError::InternalServerError(format!("Falha ao iniciar watcher: {}", e)))?;
+    } else if state.live_catalog.unwatch(id) {
+        info!(config_id = %id, "Watcher live parado");
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "id": id,
+            "name": record.name,
+            "root_path": record.root_path,
+            "live_mode": record.live_mode
+        }))
+    ))
+}
+
 /// Lista todas as configurações de scan
 /// 
 /// Retorna todas as configurações de scan criadas
@@ -352,8 +1101,10 @@ pub async fn list_scan_configs(
         r#"
         SELECT 
             id, name, description, root_path, recursive,
-            max_depth, exclude_patterns, status, is_active,
-            last_run_at, last_scan_job_id, total_runs,
+            max_depth, exclude_patterns, ignore_templates, force_rehash,
+            paranoid_sample_rate, live_mode, index_content, content_max_bytes, content_extensions,
+            exclude_mime_categories, follow_symlinks, capture_xattrs, status, is_active, last_run_at,
+            last_scan_job_id, total_runs,
             successful_runs, failed_runs, created_at
         FROM scan_configs
         WHERE is_active = true
@@ -372,6 +1123,16 @@ pub async fn list_scan_configs(
             "recursive": c.recursive,
             "max_depth": c.max_depth,
             "exclude_patterns": c.exclude_patterns,
+            "ignore_templates": c.ignore_templates,
+            "exclude_mime_categories": c.exclude_mime_categories,
+            "force_rehash": c.force_rehash,
+            "paranoid_sample_rate": c.paranoid_sample_rate,
+            "live_mode": c.live_mode,
+            "index_content": c.index_content,
+            "content_max_bytes": c.content_max_bytes,
+            "content_extensions": c.content_extensions,
+            "follow_symlinks": c.follow_symlinks,
+            "capture_xattrs": c.capture_xattrs,
             "status": c.status,
             "last_run_at": c.last_run_at,
             "last_scan_job_id": c.last_scan_job_id,
@@ -425,6 +1186,10 @@ pub async fn list_scan_jobs(
             total_size_bytes,
             errors_count,
             duration_seconds,
+            files_hashed,
+            files_hash_skipped,
+            hash_mismatches,
+            files_deleted,
             created_at
         FROM scan_jobs
         ORDER BY created_at DESC
@@ -447,6 +1212,10 @@ pub async fn list_scan_jobs(
             "total_size_gb": j.total_size_bytes.unwrap_or(0) as f64 / 1_073_741_824.0,
             "errors_count": j.errors_count,
             "duration_seconds": j.duration_seconds,
+            "files_hashed": j.files_hashed,
+            "files_hash_skipped": j.files_hash_skipped,
+            "hash_mismatches": j.hash_mismatches,
+            "files_deleted": j.files_deleted,
             "created_at": j.created_at
         })
     }).collect();
@@ -497,6 +1266,10 @@ pub async fn get_scan_job_status(
             total_size_bytes,
             errors_count,
             duration_seconds,
+            files_hashed,
+            files_hash_skipped,
+            hash_mismatches,
+            files_deleted,
             error_message
         FROM scan_jobs
         WHERE id = $1
@@ -520,6 +1293,10 @@ pub async fn get_scan_job_status(
                 "total_size_gb": j.total_size_bytes.unwrap_or(0) as f64 / 1_073_741_824.0,
                 "errors_count": j.errors_count,
                 "duration_seconds": j.duration_seconds,
+                "files_hashed": j.files_hashed,
+                "files_hash_skipped": j.files_hash_skipped,
+                "hash_mismatches": j.hash_mismatches,
+                "files_deleted": j.files_deleted,
                 "error_message": j.error_message
             });
             
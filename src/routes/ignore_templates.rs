@@ -0,0 +1,19 @@
+use crate::{ignore_templates, models::IgnoreTemplateInfo};
+use axum::{http::StatusCode, response::IntoResponse, Json};
+
+/// Lista a biblioteca de templates de exclusão
+///
+/// Os nomes podem ser usados em `ignore_templates` de backup jobs e
+/// configurações de scan; `auto` aplica os templates cujos marcadores
+/// existem na raiz da origem.
+#[utoipa::path(
+    get,
+    path = "/ignore-templates",
+    tag = "File Catalog",
+    responses(
+        (status = 200, description = "Templates disponíveis", body = [IgnoreTemplateInfo])
+    )
+)]
+pub async fn list_ignore_templates() -> impl IntoResponse {
+    (StatusCode::OK, Json(ignore_templates::list()))
+}
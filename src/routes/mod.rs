@@ -5,4 +5,8 @@ pub mod logs;
 pub mod archive;
 pub mod providers;
 pub mod files;
+pub mod ignore_templates;
 pub mod scan_schedules;
+pub mod restore;
+pub mod verification;
+pub mod runs;
@@ -11,9 +11,11 @@ use uuid::Uuid;
 
 use crate::{
     db::{
-        create_cloud_provider, delete_cloud_provider, get_cloud_provider_by_id,
-        list_cloud_providers, test_cloud_provider_connectivity, update_cloud_provider,
+        count_backup_jobs_using_provider, create_cloud_provider, delete_cloud_provider,
+        get_cloud_provider_by_id,
+        list_cloud_providers, update_cloud_provider,
     },
+    connectivity,
     models::{CloudProviderType, ConnectivityTestResult, NewCloudProvider, UpdateCloudProvider},
     AppError, AppState,
 };
@@ -246,8 +248,14 @@ pub async fn delete_provider(
 ) -> Result<impl IntoResponse, AppError> {
     debug!(provider_id = %id, "Removendo provedor cloud");
 
-    // TODO: Verificar se o provedor não está sendo usado por algum backup job
-    // Isso deve ser implementado como uma foreign key constraint ou verificação manual
+    // Não remover provedores referenciados por backup jobs (provider://<id>/...)
+    let jobs_using = count_backup_jobs_using_provider(&state.db_pool, id).await?;
+    if jobs_using > 0 {
+        return Err(AppError::Conflict(format!(
+            "Provedor {} está em uso por {} backup job(s)",
+            id, jobs_using
+        )));
+    }
 
     delete_cloud_provider(&state.db_pool, id).await?;
 
@@ -258,8 +266,11 @@ pub async fn delete_provider(
 
 /// Testa conectividade com um provedor
 /// 
-/// Executa um teste de conectividade com o provedor cloud especificado,
-/// validando credenciais e acesso ao bucket.
+/// Executa um round-trip real com o rclone (listagem do bucket, gravação,
+/// leitura e remoção de um objeto de teste em `path_prefix`). Em `details`
+/// vêm a latência, as permissões `can_list`/`can_write`/`can_delete` e, em
+/// caso de falha, `error_kind` (`authentication`, `bucket_not_found`, `dns`,
+/// `network`, `permission_denied`, ...).
 /// 
 /// # Argumentos
 /// * `id` - UUID do provedor
@@ -286,7 +297,7 @@ pub async fn test_provider_connectivity(
 ) -> Result<impl IntoResponse, AppError> {
     debug!(provider_id = %id, "Testando conectividade do provedor cloud");
 
-    let test_result = test_cloud_provider_connectivity(&state.db_pool, id).await?;
+    let test_result = connectivity::test_provider(&state.db_pool, id).await?;
 
     info!(
         provider_id = %id,
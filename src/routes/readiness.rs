@@ -1,4 +1,4 @@
-use crate::{db, AppState};
+use crate::{db, models::LiveWatcherStatus, AppState};
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use serde::Serialize;
 use std::process::Command;
@@ -17,6 +17,10 @@ pub struct DependencyStatus {
 pub struct ReadinessResponse {
     rclone: DependencyStatus,
     database: DependencyStatus,
+    /// Live-mode catalog watchers with their state and queue depth
+    live_watchers: Vec<LiveWatcherStatus>,
+    /// Backup jobs whose source paths are watched (`trigger_mode = watch`)
+    watched_backup_jobs: Vec<uuid::Uuid>,
 }
 
 /// Readiness check endpoint
@@ -35,6 +39,8 @@ pub async fn readiness_check(State(state): State<AppState>) -> impl IntoResponse
     let response = ReadinessResponse {
         rclone: rclone_status,
         database: db_status,
+        live_watchers: state.live_catalog.statuses(),
+        watched_backup_jobs: state.backup_triggers.watched_jobs(),
     };
 
     (StatusCode::OK, Json(response))
@@ -0,0 +1,102 @@
+// src/routes/restore.rs
+// HTTP handlers for restoring backups and inspecting restore logs
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    db,
+    models::{ErrorResponse, RestoreExecutionLog, RestoreRequest},
+    restore, AppError, AppState,
+};
+
+#[derive(Deserialize, IntoParams)]
+pub struct RestoreLogsQueryParams {
+    /// Maximum number of logs to return (default: 50, max: 200)
+    pub limit: Option<i32>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/backups/{id}/restore",
+    tag = "Restore",
+    params(
+        ("id" = Uuid, Path, description = "Backup Job ID")
+    ),
+    request_body(content = RestoreRequest, description = "What to restore and where to", example = json!({ "destination": "/mnt/backups/daily", "paths": ["reports/2025/", "*.pdf"], "target_path": "/tmp/restore/docs" })),
+    responses(
+        (status = 200, description = "Restore finished; check `status` for the rclone outcome", body = RestoreExecutionLog),
+        (status = 400, description = "Invalid destination, path filter or target directory", body = ErrorResponse),
+        (status = 404, description = "Backup job not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn restore_backup(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<RestoreRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let job = db::get_backup_job_by_id(&state.db_pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Backup job with ID {} not found", id)))?;
+
+    let log = restore::perform_restore(&state.db_pool, &job, &payload).await?;
+    Ok((StatusCode::OK, Json(log)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/backups/{id}/restores",
+    tag = "Restore",
+    params(
+        ("id" = Uuid, Path, description = "Backup Job ID"),
+        RestoreLogsQueryParams
+    ),
+    responses(
+        (status = 200, description = "Restore executions for the backup job", body = Vec<RestoreExecutionLog>),
+        (status = 404, description = "Backup job not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn list_backup_restores(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<RestoreLogsQueryParams>,
+) -> Result<impl IntoResponse, AppError> {
+    if db::get_backup_job_by_id(&state.db_pool, id).await?.is_none() {
+        return Err(AppError::NotFound(format!("Backup job with ID {} not found", id)));
+    }
+
+    let logs = db::list_restore_execution_logs(&state.db_pool, id, params.limit).await?;
+    Ok((StatusCode::OK, Json(logs)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/restores/{id}",
+    tag = "Restore",
+    params(
+        ("id" = Uuid, Path, description = "Restore execution log ID")
+    ),
+    responses(
+        (status = 200, description = "Restore execution details", body = RestoreExecutionLog),
+        (status = 404, description = "Restore not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn get_restore(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    match db::get_restore_execution_log_by_id(&state.db_pool, id).await? {
+        Some(log) => Ok((StatusCode::OK, Json(log))),
+        None => Err(AppError::NotFound(format!("Restore with ID {} not found", id))),
+    }
+}
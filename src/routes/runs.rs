@@ -0,0 +1,115 @@
+// src/routes/runs.rs
+// HTTP handlers for queued backup runs
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    db,
+    models::{BackupRun, BackupRunReport, ErrorResponse},
+    run_queue::RunCancellation,
+    AppError, AppState,
+};
+
+#[derive(Deserialize, IntoParams)]
+pub struct RunQueryParams {
+    /// Only runs with this status (queued, running, succeeded, failed, cancelled, skipped)
+    pub status: Option<String>,
+    /// Maximum number of runs to return (default: 50, max: 200)
+    pub limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/backups/{id}/runs",
+    tag = "Backups",
+    params(
+        ("id" = Uuid, Path, description = "Backup Job ID"),
+        RunQueryParams
+    ),
+    responses(
+        (status = 200, description = "Runs of the backup job, newest first; skipped and cancelled runs carry their `status_reason`", body = [BackupRun]),
+        (status = 404, description = "Backup job not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn list_job_runs(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<RunQueryParams>,
+) -> Result<impl IntoResponse, AppError> {
+    if db::get_backup_job_by_id(&state.db_pool, id).await?.is_none() {
+        return Err(AppError::NotFound(format!("Backup job with ID {} not found", id)));
+    }
+
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+    let runs = db::list_backup_runs(&state.db_pool, id, params.status.as_deref(), limit).await?;
+    Ok((StatusCode::OK, Json(runs)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/runs/{id}",
+    tag = "Backups",
+    params(
+        ("id" = Uuid, Path, description = "Backup run ID")
+    ),
+    responses(
+        (status = 200, description = "Run status, progress and the execution logs of the transfers started so far", body = BackupRunReport),
+        (status = 404, description = "Run not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn get_run(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let run = db::get_backup_run_by_id(&state.db_pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Backup run with ID {} not found", id)))?;
+
+    let executions = db::list_backup_run_execution_logs(&state.db_pool, id).await?;
+    Ok((StatusCode::OK, Json(BackupRunReport { run, executions })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/runs/{id}/cancel",
+    tag = "Backups",
+    params(
+        ("id" = Uuid, Path, description = "Backup run ID")
+    ),
+    responses(
+        (status = 200, description = "Queued run cancelled before it started", body = BackupRun),
+        (status = 202, description = "Running run is being interrupted, by this or another b2cli instance; it ends as `cancelled` with the statistics of the transfers so far", body = BackupRun),
+        (status = 404, description = "Run not found", body = ErrorResponse),
+        (status = 409, description = "Run already finished", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn cancel_run(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    match state.run_queue.cancel(id).await? {
+        Some(RunCancellation::Dequeued(run)) => Ok((StatusCode::OK, Json(*run))),
+        Some(RunCancellation::Requested) => {
+            let run = db::get_backup_run_by_id(&state.db_pool, id)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Backup run with ID {} not found", id)))?;
+            Ok((StatusCode::ACCEPTED, Json(run)))
+        }
+        Some(RunCancellation::Finished(status)) => Err(AppError::Conflict(format!(
+            "Backup run {} already finished with status {}",
+            id, status
+        ))),
+        None => Err(AppError::NotFound(format!("Backup run with ID {} not found", id))),
+    }
+}
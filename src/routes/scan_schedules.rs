@@ -5,65 +5,43 @@ use axum::{
     response::IntoResponse,
     Json,
 };
-use serde::Deserialize;
-use serde_json::json;
-use tracing::{debug, info, error};
+use tracing::{debug, info};
 use uuid::Uuid;
-use utoipa::ToSchema;
-use crate::{AppError, AppState};
-
-/// Parâmetros para criar um agendamento de scan
-#[derive(Debug, Deserialize, ToSchema)]
-pub struct CreateScanSchedule {
-    /// Nome do agendamento
-    pub name: String,
-    /// Descrição opcional
-    pub description: Option<String>,
-    /// Diretório raiz para varredura
-    pub root_path: String,
-    /// Se deve varrer recursivamente
-    #[serde(default = "default_true")]
-    pub recursive: bool,
-    /// Profundidade máxima
-    pub max_depth: Option<i32>,
-    /// Padrões para excluir
-    #[serde(default)]
-    pub exclude_patterns: Vec<String>,
-    /// Expressão cron (ex: "0 2 * * *" para 2AM diariamente)
-    pub cron_expression: String,
-    /// Se está habilitado
-    #[serde(default = "default_true")]
-    pub enabled: bool,
-}
+use crate::{
+    db,
+    models::{ErrorResponse, NewScanSchedule, ScanSchedule, UpdateScanSchedule},
+    scan_schedules::validate_cron,
+    AppError, AppState,
+};
 
-fn default_true() -> bool {
-    true
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Agendamento {} não encontrado", id))
 }
 
 /// Cria um novo agendamento de scan
-/// 
-/// # Exemplos de cron:
-/// - `"0 2 * * *"` - Todos os dias às 2AM
-/// - `"0 3 * * 0"` - Domingos às 3AM
-/// - `"0 */6 * * *"` - A cada 6 horas
-/// - `"0 0 1 * *"` - Primeiro dia do mês à meia-noite
-/// 
+///
+/// A expressão cron tem 6 campos, começando pelos segundos:
+/// - `"0 0 2 * * *"` - Todos os dias às 2AM
+/// - `"0 0 3 * * Sun"` - Domingos às 3AM
+/// - `"0 0 */6 * * *"` - A cada 6 horas
+/// - `"0 0 0 1 * *"` - Primeiro dia do mês à meia-noite
+///
 /// # Retorna
-/// * `Ok(Json)` - Agendamento criado
+/// * `Ok(Json)` - Agendamento criado, já no scheduler se habilitado
 #[utoipa::path(
     post,
     path = "/files/scan/schedule",
     tag = "File Catalog",
-    request_body = CreateScanSchedule,
+    request_body(content = NewScanSchedule, example = json!({ "name": "Documentos", "root_path": "/home/user/docs", "cron_expression": "0 0 2 * * *" })),
     responses(
-        (status = 201, description = "Agendamento criado"),
-        (status = 400, description = "Parâmetros inválidos"),
-        (status = 500, description = "Erro interno")
+        (status = 201, description = "Agendamento criado", body = ScanSchedule),
+        (status = 400, description = "Expressão cron inválida", body = ErrorResponse),
+        (status = 500, description = "Erro interno", body = ErrorResponse)
     )
 )]
 pub async fn create_scan_schedule(
     State(state): State<AppState>,
-    Json(payload): Json<CreateScanSchedule>,
+    Json(payload): Json<NewScanSchedule>,
 ) -> Result<impl IntoResponse, AppError> {
     info!(
         name = %payload.name,
@@ -71,122 +49,14 @@ pub async fn create_scan_schedule(
         "Criando agendamento de scan"
     );
 
-    // Validar expressão cron básica (podemos melhorar isso depois)
-    // Por enquanto, apenas verificar se não está vazia
-    if payload.cron_expression.is_empty() {
-        return Err(AppError::BadRequest("Expressão cron não pode ser vazia".to_string()));
-    }
-
-    // Inserir no banco
-    let id = sqlx::query_scalar!(
-        r#"
-        INSERT INTO scan_schedules (
-            name, description, root_path, recursive, max_depth,
-            exclude_patterns, cron_expression, enabled
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        RETURNING id
-        "#,
-        payload.name,
-        payload.description,
-        payload.root_path,
-        payload.recursive,
-        payload.max_depth,
-        &payload.exclude_patterns,
-        payload.cron_expression,
-        payload.enabled
-    )
-    .fetch_one(&state.db_pool)
-    .await?;
-
-    // Adicionar ao scheduler se habilitado
-    if payload.enabled {
-        let db_pool = state.db_pool.clone();
-        let cron_expr = payload.cron_expression.clone();
-        let root_path = payload.root_path.clone();
-        
-        let job = tokio_cron_scheduler::Job::new_async(&cron_expr, move |_uuid, _l| {
-            let db_pool = db_pool.clone();
-            let schedule_id = id;
-            let root_path = root_path.clone();
-            
-            Box::pin(async move {
-                info!(schedule_id = %schedule_id, "Executando scan agendado");
-                
-                // Atualizar status
-                let _ = sqlx::query!(
-                    "UPDATE scan_schedules SET last_run_at = CURRENT_TIMESTAMP, last_run_status = 'running' WHERE id = $1",
-                    schedule_id
-                )
-                .execute(&db_pool)
-                .await;
-                
-                // Criar configuração
-                let config = crate::file_scanner::ScanConfig {
-                    root_path: std::path::PathBuf::from(&root_path),
-                    recursive: true,
-                    ..Default::default()
-                };
-                
-                // Executar scan
-                let mut scanner = crate::file_scanner::FileScanner::new(db_pool.clone(), config);
-                match scanner.start_scan().await {
-                    Ok(scan_job_id) => {
-                        info!(scan_job_id = %scan_job_id, "Scan agendado concluído");
-                        
-                        // Atualizar com sucesso
-                        let _ = sqlx::query!(
-                            r#"
-                            UPDATE scan_schedules 
-                            SET last_run_status = 'success',
-                                last_scan_job_id = $2,
-                                total_runs = total_runs + 1,
-                                successful_runs = successful_runs + 1
-                            WHERE id = $1
-                            "#,
-                            schedule_id,
-                            scan_job_id
-                        )
-                        .execute(&db_pool)
-                        .await;
-                    }
-                    Err(e) => {
-                        error!(error = %e, "Erro no scan agendado");
-                        
-                        // Atualizar com falha
-                        let _ = sqlx::query!(
-                            r#"
-                            UPDATE scan_schedules 
-                            SET last_run_status = 'failed',
-                                total_runs = total_runs + 1,
-                                failed_runs = failed_runs + 1
-                            WHERE id = $1
-                            "#,
-                            schedule_id
-                        )
-                        .execute(&db_pool)
-                        .await;
-                    }
-                }
-            })
-        });
+    validate_cron(&payload.cron_expression).map_err(AppError::BadRequest)?;
 
-        if let Ok(job) = job {
-            if let Err(e) = state.scheduler.add(job).await {
-                error!(error = %e, "Erro ao adicionar job ao scheduler");
-            }
-        }
-    }
+    let schedule = db::create_scan_schedule(&state.db_pool, &payload).await?;
+    state.schedules.sync_scan_schedule(&schedule).await?;
 
-    Ok((
-        StatusCode::CREATED,
-        Json(json!({
-            "id": id,
-            "name": payload.name,
-            "cron_expression": payload.cron_expression,
-            "enabled": payload.enabled,
-            "message": "Agendamento criado com sucesso"
-        }))
-    ))
+    // Reler para devolver o next_run_at gravado pelo registro
+    let schedule = db::get_scan_schedule(&state.db_pool, schedule.id).await?.unwrap_or(schedule);
+    Ok((StatusCode::CREATED, Json(schedule)))
 }
 
 /// Lista todos os agendamentos de scan
@@ -195,8 +65,8 @@ pub async fn create_scan_schedule(
     path = "/files/scan/schedules",
     tag = "File Catalog",
     responses(
-        (status = 200, description = "Lista de agendamentos"),
-        (status = 500, description = "Erro ao buscar agendamentos")
+        (status = 200, description = "Lista de agendamentos, com o resultado da última execução e o próximo disparo", body = [ScanSchedule]),
+        (status = 500, description = "Erro ao buscar agendamentos", body = ErrorResponse)
     )
 )]
 pub async fn list_scan_schedules(
@@ -204,49 +74,67 @@ pub async fn list_scan_schedules(
 ) -> Result<impl IntoResponse, AppError> {
     debug!("Listando agendamentos de scan");
 
-    let schedules = sqlx::query!(
-        r#"
-        SELECT 
-            id, name, description, root_path, recursive,
-            max_depth, exclude_patterns, cron_expression, enabled,
-            last_run_at, last_run_status, last_scan_job_id,
-            total_runs, successful_runs, failed_runs,
-            created_at, updated_at
-        FROM scan_schedules
-        ORDER BY created_at DESC
-        "#
+    let schedules = db::list_scan_schedules(&state.db_pool, false).await?;
+    Ok((StatusCode::OK, Json(schedules)))
+}
+
+/// Detalhes de um agendamento de scan
+#[utoipa::path(
+    get,
+    path = "/files/scan/schedule/{id}",
+    tag = "File Catalog",
+    params(
+        ("id" = Uuid, Path, description = "ID do agendamento")
+    ),
+    responses(
+        (status = 200, description = "Agendamento", body = ScanSchedule),
+        (status = 404, description = "Agendamento não encontrado", body = ErrorResponse),
+        (status = 500, description = "Erro ao buscar agendamento", body = ErrorResponse)
     )
-    .fetch_all(&state.db_pool)
-    .await?;
+)]
+pub async fn get_scan_schedule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let schedule = db::get_scan_schedule(&state.db_pool, id).await?.ok_or_else(|| not_found(id))?;
+    Ok((StatusCode::OK, Json(schedule)))
+}
 
-    let result: Vec<_> = schedules.into_iter().map(|s| {
-        json!({
-            "id": s.id,
-            "name": s.name,
-            "description": s.description,
-            "root_path": s.root_path,
-            "recursive": s.recursive,
-            "max_depth": s.max_depth,
-            "exclude_patterns": s.exclude_patterns,
-            "cron_expression": s.cron_expression,
-            "enabled": s.enabled,
-            "last_run_at": s.last_run_at,
-            "last_run_status": s.last_run_status,
-            "last_scan_job_id": s.last_scan_job_id,
-            "total_runs": s.total_runs,
-            "successful_runs": s.successful_runs,
-            "failed_runs": s.failed_runs,
-            "success_rate": if s.total_runs.unwrap_or(0) > 0 {
-                (s.successful_runs.unwrap_or(0) as f64 / s.total_runs.unwrap_or(1) as f64) * 100.0
-            } else {
-                0.0
-            },
-            "created_at": s.created_at,
-            "updated_at": s.updated_at
-        })
-    }).collect();
+/// Altera um agendamento de scan
+///
+/// Mudanças na expressão cron ou em `enabled` valem na hora; as demais
+/// (pasta, padrões de exclusão) a partir do próximo disparo.
+#[utoipa::path(
+    patch,
+    path = "/files/scan/schedule/{id}",
+    tag = "File Catalog",
+    params(
+        ("id" = Uuid, Path, description = "ID do agendamento")
+    ),
+    request_body(content = UpdateScanSchedule, example = json!({ "cron_expression": "0 30 1 * * *" })),
+    responses(
+        (status = 200, description = "Agendamento alterado", body = ScanSchedule),
+        (status = 400, description = "Expressão cron inválida", body = ErrorResponse),
+        (status = 404, description = "Agendamento não encontrado", body = ErrorResponse),
+        (status = 500, description = "Erro ao alterar", body = ErrorResponse)
+    )
+)]
+pub async fn update_scan_schedule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateScanSchedule>,
+) -> Result<impl IntoResponse, AppError> {
+    if let Some(cron_expression) = &payload.cron_expression {
+        validate_cron(cron_expression).map_err(AppError::BadRequest)?;
+    }
+
+    let schedule = db::patch_scan_schedule(&state.db_pool, id, &payload)
+        .await?
+        .ok_or_else(|| not_found(id))?;
+    state.schedules.sync_scan_schedule(&schedule).await?;
 
-    Ok((StatusCode::OK, Json(result)))
+    let schedule = db::get_scan_schedule(&state.db_pool, id).await?.unwrap_or(schedule);
+    Ok((StatusCode::OK, Json(schedule)))
 }
 
 /// Deleta um agendamento de scan
@@ -258,9 +146,9 @@ pub async fn list_scan_schedules(
         ("id" = Uuid, Path, description = "ID do agendamento")
     ),
     responses(
-        (status = 204, description = "Agendamento deletado"),
-        (status = 404, description = "Agendamento não encontrado"),
-        (status = 500, description = "Erro ao deletar")
+        (status = 204, description = "Agendamento deletado e removido do scheduler"),
+        (status = 404, description = "Agendamento não encontrado", body = ErrorResponse),
+        (status = 500, description = "Erro ao deletar", body = ErrorResponse)
     )
 )]
 pub async fn delete_scan_schedule(
@@ -269,18 +157,10 @@ pub async fn delete_scan_schedule(
 ) -> Result<impl IntoResponse, AppError> {
     info!(schedule_id = %id, "Deletando agendamento de scan");
 
-    let result = sqlx::query!(
-        "DELETE FROM scan_schedules WHERE id = $1",
-        id
-    )
-    .execute(&state.db_pool)
-    .await?;
-
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound(format!("Agendamento {} não encontrado", id)));
+    if db::delete_scan_schedule(&state.db_pool, id).await? == 0 {
+        return Err(not_found(id));
     }
-
-    // TODO: Remover do scheduler também
+    state.schedules.remove_scan_schedule(id).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -294,9 +174,9 @@ pub async fn delete_scan_schedule(
         ("id" = Uuid, Path, description = "ID do agendamento")
     ),
     responses(
-        (status = 200, description = "Status alterado"),
-        (status = 404, description = "Agendamento não encontrado"),
-        (status = 500, description = "Erro ao alterar")
+        (status = 200, description = "Status alterado; o agendamento entra ou sai do scheduler", body = ScanSchedule),
+        (status = 404, description = "Agendamento não encontrado", body = ErrorResponse),
+        (status = 500, description = "Erro ao alterar", body = ErrorResponse)
     )
 )]
 pub async fn toggle_scan_schedule(
@@ -305,33 +185,16 @@ pub async fn toggle_scan_schedule(
 ) -> Result<impl IntoResponse, AppError> {
     debug!(schedule_id = %id, "Alternando status do agendamento");
 
-    let current = sqlx::query!(
-        "SELECT enabled FROM scan_schedules WHERE id = $1",
-        id
-    )
-    .fetch_optional(&state.db_pool)
-    .await?;
-
-    match current {
-        Some(record) => {
-            let new_status = !record.enabled.unwrap_or(false);
-            
-            sqlx::query!(
-                "UPDATE scan_schedules SET enabled = $2 WHERE id = $1",
-                id,
-                new_status
-            )
-            .execute(&state.db_pool)
-            .await?;
-
-            // TODO: Adicionar/remover do scheduler
-
-            Ok((StatusCode::OK, Json(json!({
-                "id": id,
-                "enabled": new_status,
-                "message": if new_status { "Agendamento habilitado" } else { "Agendamento desabilitado" }
-            }))))
-        }
-        None => Err(AppError::NotFound(format!("Agendamento {} não encontrado", id)))
-    }
-}
\ No newline at end of file
+    let current = db::get_scan_schedule(&state.db_pool, id).await?.ok_or_else(|| not_found(id))?;
+    let toggle = UpdateScanSchedule {
+        enabled: Some(!current.enabled),
+        ..Default::default()
+    };
+    let schedule = db::patch_scan_schedule(&state.db_pool, id, &toggle)
+        .await?
+        .ok_or_else(|| not_found(id))?;
+    state.schedules.sync_scan_schedule(&schedule).await?;
+
+    let schedule = db::get_scan_schedule(&state.db_pool, id).await?.unwrap_or(schedule);
+    Ok((StatusCode::OK, Json(schedule)))
+}
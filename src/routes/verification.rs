@@ -0,0 +1,102 @@
+// src/routes/verification.rs
+// HTTP handlers for post-backup restore verification results
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    db,
+    models::{BackupVerificationReport, ErrorResponse, FleetRestorabilityScore},
+    verification::restorability_percent,
+    AppError, AppState,
+};
+
+#[derive(Deserialize, IntoParams)]
+pub struct VerificationQueryParams {
+    /// Maximum number of verification runs to return (default: 20, max: 200)
+    pub limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/backups/{id}/verification",
+    tag = "Verification",
+    params(
+        ("id" = Uuid, Path, description = "Backup Job ID"),
+        VerificationQueryParams
+    ),
+    responses(
+        (status = 200, description = "Restorability of the backup job and its recent verification runs", body = BackupVerificationReport),
+        (status = 404, description = "Backup job not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn get_backup_verification(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<VerificationQueryParams>,
+) -> Result<impl IntoResponse, AppError> {
+    if db::get_backup_job_by_id(&state.db_pool, id).await?.is_none() {
+        return Err(AppError::NotFound(format!("Backup job with ID {} not found", id)));
+    }
+
+    let limit = params.limit.unwrap_or(20).clamp(1, 200);
+    let verifications = db::list_backup_verifications(&state.db_pool, id, limit).await?;
+
+    let failed_files = match verifications.first() {
+        Some(latest) => db::list_failed_verification_files(&state.db_pool, latest.id).await?,
+        None => vec![],
+    };
+
+    let restorability = db::list_job_restorability(&state.db_pool, Some(id))
+        .await?
+        .into_iter()
+        .next()
+        .and_then(|score| score.restorability_percent);
+
+    Ok((
+        StatusCode::OK,
+        Json(BackupVerificationReport {
+            backup_job_id: id,
+            restorability_percent: restorability,
+            verifications,
+            failed_files,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/verification/score",
+    tag = "Verification",
+    responses(
+        (status = 200, description = "Fleet-wide restorability score", body = FleetRestorabilityScore),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn get_fleet_restorability(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let jobs = db::list_job_restorability(&state.db_pool, None).await?;
+
+    let files_checked: i64 = jobs.iter().map(|j| j.files_checked).sum();
+    let files_passed: i64 = jobs.iter().map(|j| j.files_passed).sum();
+    let jobs_verified = jobs.iter().filter(|j| j.files_checked > 0).count() as i64;
+
+    Ok((
+        StatusCode::OK,
+        Json(FleetRestorabilityScore {
+            restorability_percent: restorability_percent(files_passed, files_checked),
+            jobs_total: jobs.len() as i64,
+            jobs_verified,
+            jobs,
+        }),
+    ))
+}
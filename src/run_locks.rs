@@ -0,0 +1,109 @@
+//! Locks consultivos (advisory locks) do Postgres usados pela fila de backups.
+//!
+//! Os locks valem para todas as instâncias do b2cli ligadas ao mesmo banco:
+//!
+//! - `ENQUEUE_LOCKS` serializa os enfileiramentos de um job, para a política
+//!   de sobreposição ver as execuções pendentes de forma consistente
+//! - `JOB_LOCKS` é mantido enquanto uma execução do job está em andamento;
+//!   uma execução `running` sem o lock do job é órfã (processo morreu)
+//! - `CONCURRENCY_LOCKS` são as vagas de `B2CLI_MAX_CONCURRENT_BACKUPS`
+//! - `DESTINATION_LOCKS` são as vagas de cada destino
+//!   (`B2CLI_MAX_RUNS_PER_DESTINATION`)
+//!
+//! Locks de sessão ficam presos à conexão que os pegou, por isso são tomados
+//! em conexões próprias (`LockSession`), fora do pool: se o processo morrer,
+//! a conexão cai e o Postgres libera tudo. As chaves usam a forma de duas
+//! partes, `(espaço, hashtext(nome))`.
+
+use crate::cancel::CancelToken;
+use sqlx::{Connection, PgConnection, PgPool};
+use std::time::Duration;
+use tracing::info;
+
+pub const ENQUEUE_LOCKS: i32 = 0x6232_0001;
+pub const JOB_LOCKS: i32 = 0x6232_0002;
+pub const CONCURRENCY_LOCKS: i32 = 0x6232_0003;
+pub const DESTINATION_LOCKS: i32 = 0x6232_0004;
+
+/// Intervalo entre tentativas de pegar uma vaga ocupada
+const SLOT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Conexão dedicada que mantém locks de sessão; descartá-la libera todos
+pub struct LockSession {
+    conn: PgConnection,
+}
+
+impl LockSession {
+    /// Abre uma conexão nova com as mesmas opções do pool
+    pub async fn open(pool: &PgPool) -> Result<Self, sqlx::Error> {
+        let conn = PgConnection::connect_with(&pool.connect_options()).await?;
+        Ok(Self { conn })
+    }
+
+    pub fn connection(&mut self) -> &mut PgConnection {
+        &mut self.conn
+    }
+
+    /// Tenta pegar uma das `slots` vagas do recurso `name`, sem esperar.
+    ///
+    /// # Retorna
+    /// * Índice da vaga obtida, ou `None` se todas estão ocupadas
+    pub async fn try_slot(&mut self, space: i32, name: &str, slots: u32) -> Result<Option<u32>, sqlx::Error> {
+        for slot in 0..slots {
+            let key = format!("{}#{}", name, slot);
+            let acquired = sqlx::query_scalar!(
+                r#"SELECT pg_try_advisory_lock($1, hashtext($2)) AS "acquired!""#,
+                space,
+                key
+            )
+            .fetch_one(&mut self.conn)
+            .await?;
+            if acquired {
+                return Ok(Some(slot));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Libera todos os locks da sessão, mantendo a conexão
+    pub async fn unlock_all(&mut self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT pg_advisory_unlock_all()")
+            .execute(&mut self.conn)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Espera uma vaga livre no destino de backup.
+///
+/// # Argumentos
+/// * `destination` - Destino resolvido (`ResolvedRemote::storage`)
+/// * `slots` - Transferências simultâneas permitidas no destino
+/// * `cancel` - Interrompe a espera
+///
+/// # Retorna
+/// * Sessão que mantém a vaga até ser descartada, ou `None` se a execução
+///   foi cancelada durante a espera
+pub async fn acquire_destination(
+    pool: &PgPool,
+    destination: &str,
+    slots: u32,
+    cancel: &CancelToken,
+) -> Result<Option<LockSession>, sqlx::Error> {
+    let mut session = LockSession::open(pool).await?;
+    let mut waiting = false;
+
+    loop {
+        if session.try_slot(DESTINATION_LOCKS, destination, slots).await?.is_some() {
+            return Ok(Some(session));
+        }
+        if !waiting {
+            info!(destination, slots, "Destino ocupado por outras execuções; aguardando vaga");
+            waiting = true;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(SLOT_RETRY_INTERVAL) => {}
+            _ = cancel.cancelled() => return Ok(None),
+        }
+    }
+}
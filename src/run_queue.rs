@@ -0,0 +1,432 @@
+//! Fila persistente de execuções de backup.
+//!
+//! Toda execução de um backup job — `POST /backups/{id}/run`, schedules cron
+//! e disparos do modo `watch` — vira uma linha em `backup_runs` com status
+//! `queued` e volta imediatamente para quem pediu. Um pool de workers reserva
+//! as execuções em ordem de chegada (`FOR UPDATE SKIP LOCKED`), roda o backup
+//! e grava o status final (`succeeded` ou `failed`); o progresso fica em
+//! `GET /runs/{id}`.
+//!
+//! A fila sobrevive a reinícios: execuções `queued` são retomadas quando o
+//! processo volta, e as `running` cujo processo parou são marcadas como
+//! `failed`.
+//!
+//! `cancel` tira uma execução da fila antes de ela começar ou interrompe o
+//! backup em andamento; nos dois casos o status final é `cancelled`.
+//!
+//! Concorrência (válida para todas as instâncias ligadas ao banco, via
+//! advisory locks — ver `run_locks`):
+//! - Duas execuções do mesmo job nunca rodam ao mesmo tempo; o que acontece
+//!   com um novo pedido depende do `overlap_policy` do job
+//! - `B2CLI_MAX_CONCURRENT_BACKUPS` limita os backups simultâneos no total
+//! - `B2CLI_MAX_RUNS_PER_DESTINATION` limita as transferências simultâneas
+//!   para um mesmo destino (padrão 1)
+//! - Cada disparo de um schedule cron enfileira uma única execução, mesmo
+//!   com o schedule registrado em várias instâncias
+
+use crate::cancel::{CancelRegistry, CancelToken};
+use crate::models::{BackupRun, BackupSchedule};
+use crate::run_locks::{LockSession, CONCURRENCY_LOCKS};
+use crate::{backup_worker, db};
+use chrono::{DateTime, Timelike, Utc};
+use croner::Cron;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_cron_scheduler::{Job, JobSchedulerError};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Variável de ambiente com o número de workers da fila
+pub const WORKERS_ENV: &str = "B2CLI_BACKUP_WORKERS";
+/// Workers quando `B2CLI_BACKUP_WORKERS` não está definida
+pub const DEFAULT_WORKERS: usize = 2;
+/// Variável de ambiente com o máximo de backups simultâneos em todas as instâncias
+pub const MAX_CONCURRENT_ENV: &str = "B2CLI_MAX_CONCURRENT_BACKUPS";
+/// Variável de ambiente com o máximo de transferências simultâneas por destino
+pub const PER_DESTINATION_ENV: &str = "B2CLI_MAX_RUNS_PER_DESTINATION";
+/// Transferências por destino quando `B2CLI_MAX_RUNS_PER_DESTINATION` não está definida
+pub const DEFAULT_RUNS_PER_DESTINATION: u32 = 1;
+
+/// Intervalo entre consultas à fila sem nenhum enqueue local acordando os
+/// workers (ex.: execuções enfileiradas por outra instância); também é o
+/// intervalo em que um worker confere pedidos de cancelamento feitos em
+/// outra instância
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Intervalo entre buscas por execuções órfãs de instâncias que pararam
+const ORPHAN_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Até quanto tempo depois do horário do cron um disparo ainda é atribuído a ele
+const TICK_LOOKBACK: chrono::Duration = chrono::Duration::seconds(60);
+
+/// Limites de concorrência da fila; `None` é ilimitado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunLimits {
+    /// Backups simultâneos, somando todas as instâncias
+    pub max_concurrent: Option<u32>,
+    /// Transferências simultâneas para um mesmo destino
+    pub per_destination: Option<u32>,
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: None,
+            per_destination: Some(DEFAULT_RUNS_PER_DESTINATION),
+        }
+    }
+}
+
+impl RunLimits {
+    /// Limites de `B2CLI_MAX_CONCURRENT_BACKUPS` e `B2CLI_MAX_RUNS_PER_DESTINATION`;
+    /// `0` desliga o limite
+    pub fn from_env() -> Self {
+        let limit = |name: &str| std::env::var(name).ok().and_then(|value| value.parse::<u32>().ok());
+        let defaults = Self::default();
+        Self {
+            max_concurrent: limit(MAX_CONCURRENT_ENV).map_or(defaults.max_concurrent, |max| (max > 0).then_some(max)),
+            per_destination: limit(PER_DESTINATION_ENV).map_or(defaults.per_destination, |max| (max > 0).then_some(max)),
+        }
+    }
+}
+
+/// Resultado de `RunQueue::cancel`
+#[derive(Debug)]
+pub enum RunCancellation {
+    /// Execução ainda na fila; cancelada sem chegar a rodar
+    Dequeued(Box<BackupRun>),
+    /// Execução em andamento (nesta ou em outra instância); o worker vai
+    /// interrompê-la e gravar `cancelled`
+    Requested,
+    /// Execução já terminada, com o status final
+    Finished(String),
+}
+
+/// Fila de execuções de backup e seus workers
+pub struct RunQueue {
+    pool: PgPool,
+    wake: Notify,
+    running: Arc<CancelRegistry>,
+    limits: RunLimits,
+}
+
+impl RunQueue {
+    /// Cria a fila; nenhuma execução é consumida até `start`
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            wake: Notify::new(),
+            running: Arc::new(CancelRegistry::new()),
+            limits: RunLimits::default(),
+        }
+    }
+
+    /// Substitui os limites de concorrência (padrão: `RunLimits::default()`)
+    pub fn with_limits(mut self, limits: RunLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Número de workers configurado em `B2CLI_BACKUP_WORKERS` (mínimo 1)
+    pub fn workers_from_env() -> usize {
+        std::env::var(WORKERS_ENV)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|&workers| workers > 0)
+            .unwrap_or(DEFAULT_WORKERS)
+    }
+
+    /// Marca as execuções órfãs (processo parado) e inicia os workers.
+    ///
+    /// Com workers, as órfãs de outras instâncias que pararem depois também
+    /// são marcadas, a cada minuto.
+    ///
+    /// # Argumentos
+    /// * `workers` - Quantidade de backups executados ao mesmo tempo por esta instância
+    ///
+    /// # Retorna
+    /// * Quantidade de execuções que estavam `running` e foram marcadas como `failed`
+    pub async fn start(self: &Arc<Self>, workers: usize) -> Result<u64, sqlx::Error> {
+        let interrupted = db::fail_interrupted_backup_runs(&self.pool).await?;
+        for worker in 0..workers {
+            tokio::spawn(run_worker(self.clone(), worker));
+        }
+        if workers > 0 {
+            tokio::spawn(reap_orphaned_runs(self.pool.clone()));
+        }
+        info!(workers, limits = ?self.limits, "Fila de backups iniciada");
+        Ok(interrupted)
+    }
+
+    /// Enfileira uma execução do job conforme seu `overlap_policy` e acorda um worker.
+    ///
+    /// Com `skip` e uma execução anterior pendente, a execução retornada já
+    /// vem `skipped` (motivo em `status_reason`). Com `cancel_previous`, as
+    /// anteriores são canceladas.
+    ///
+    /// # Argumentos
+    /// * `backup_job_id` - Job a ser executado
+    /// * `schedule_id` - Schedule que disparou a execução (opcional)
+    /// * `triggered_by` - Origem do disparo (`manual`, `scheduler`, `watch`)
+    pub async fn enqueue(
+        &self,
+        backup_job_id: Uuid,
+        schedule_id: Option<Uuid>,
+        triggered_by: &str,
+    ) -> Result<BackupRun, sqlx::Error> {
+        let (run, superseded) = db::enqueue_backup_run(&self.pool, backup_job_id, schedule_id, triggered_by).await?;
+        self.enqueued(&run, superseded, triggered_by);
+        Ok(run)
+    }
+
+    /// Enfileira a execução de um disparo do cron do schedule, como `enqueue`.
+    ///
+    /// # Retorna
+    /// * `None` se outra instância já enfileirou o mesmo disparo
+    pub async fn enqueue_tick(
+        &self,
+        backup_job_id: Uuid,
+        schedule_id: Uuid,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<Option<BackupRun>, sqlx::Error> {
+        let Some((run, superseded)) =
+            db::enqueue_scheduled_backup_run(&self.pool, backup_job_id, schedule_id, scheduled_for).await?
+        else {
+            return Ok(None);
+        };
+        self.enqueued(&run, superseded, "scheduler");
+        Ok(Some(run))
+    }
+
+    /// Interrompe as execuções substituídas e acorda um worker para a nova
+    fn enqueued(&self, run: &BackupRun, superseded: Vec<Uuid>, triggered_by: &str) {
+        let backup_job_id = run.backup_job_id;
+        for previous in superseded {
+            // Execuções de outra instância veem o pedido em `cancel_requested_at`
+            self.running.cancel(previous);
+            info!(run_id = %previous, superseded_by = %run.id, "Execução de backup substituída por uma nova");
+        }
+
+        if run.status == "skipped" {
+            info!(
+                run_id = %run.id,
+                job_id = %backup_job_id,
+                triggered_by,
+                reason = run.status_reason.as_deref().unwrap_or_default(),
+                "Execução de backup pulada"
+            );
+        } else {
+            debug!(run_id = %run.id, job_id = %backup_job_id, triggered_by, "Execução de backup enfileirada");
+            self.wake.notify_one();
+        }
+    }
+
+    /// Cancela uma execução.
+    ///
+    /// # Retorna
+    /// * `None` se a execução não existe
+    pub async fn cancel(&self, run_id: Uuid) -> Result<Option<RunCancellation>, sqlx::Error> {
+        const REASON: &str = "Cancelled on request";
+
+        if let Some(run) = db::cancel_queued_backup_run(&self.pool, run_id, REASON).await? {
+            info!(run_id = %run_id, "Execução de backup removida da fila");
+            return Ok(Some(RunCancellation::Dequeued(Box::new(run))));
+        }
+        // Um worker pode ter reservado a execução entre as duas consultas:
+        // o pedido é gravado depois de a linha já não estar `queued`
+        if db::request_backup_run_cancel(&self.pool, run_id, REASON).await? {
+            self.running.cancel(run_id);
+            info!(run_id = %run_id, "Cancelamento da execução de backup solicitado");
+            return Ok(Some(RunCancellation::Requested));
+        }
+
+        let cancellation = db::get_backup_run_by_id(&self.pool, run_id)
+            .await?
+            .map(|run| RunCancellation::Finished(run.status));
+        Ok(cancellation)
+    }
+
+    /// Job do scheduler que enfileira uma execução a cada disparo do cron do schedule.
+    ///
+    /// O disparo é identificado pelo horário do cron (`scheduled_for`), não
+    /// pelo relógio de quem o recebeu, para que as instâncias concordem
+    /// sobre qual delas o enfileirou.
+    pub fn cron_job(self: &Arc<Self>, schedule: &BackupSchedule) -> Result<Job, JobSchedulerError> {
+        let queue = self.clone();
+        let schedule_id = schedule.id;
+        let backup_job_id = schedule.backup_job_id;
+        // Mesmas opções de parse do tokio-cron-scheduler
+        let cron = Cron::new(&schedule.cron_expression)
+            .with_seconds_required()
+            .with_dom_and_dow()
+            .parse()
+            .map_err(|_| JobSchedulerError::ParseSchedule)?;
+
+        Job::new_async(schedule.cron_expression.as_str(), move |_uuid, _l| {
+            let queue = queue.clone();
+            let scheduled_for = current_tick(&cron, Utc::now());
+            Box::pin(async move {
+                match queue.enqueue_tick(backup_job_id, schedule_id, scheduled_for).await {
+                    Ok(None) => {
+                        debug!(schedule_id = %schedule_id, %scheduled_for, "Disparo do schedule já enfileirado por outra instância");
+                    }
+                    Ok(Some(run)) => {
+                        let schedule_status = if run.status == "skipped" { "skipped" } else { "queued" };
+                        info!(run_id = %run.id, job_id = %backup_job_id, status = %run.status, "Disparo do schedule de backup registrado");
+                        if let Err(e) = db::update_schedule_last_run(&queue.pool, schedule_id, schedule_status).await {
+                            error!("Failed to update schedule status: {}", e);
+                        }
+                    }
+                    Err(e) => error!(job_id = %backup_job_id, error = %e, "Falha ao enfileirar backup agendado"),
+                }
+            })
+        })
+    }
+}
+
+/// Horário do disparo do cron que acabou de acontecer: a última ocorrência
+/// até `now`. Sem ocorrência em `TICK_LOOKBACK` (disparo muito atrasado),
+/// usa `now` truncado ao segundo.
+fn current_tick(cron: &Cron, now: DateTime<Utc>) -> DateTime<Utc> {
+    cron.iter_from(now - TICK_LOOKBACK)
+        .take_while(|tick| *tick <= now)
+        .last()
+        .unwrap_or_else(|| now.with_nanosecond(0).unwrap_or(now))
+}
+
+/// Consome a fila até o processo terminar.
+///
+/// Os locks da execução (vaga global e lock do job) ficam na conexão
+/// dedicada do worker e são liberados ao fim de cada execução; se o
+/// processo morrer, a conexão cai e o Postgres os libera.
+async fn run_worker(queue: Arc<RunQueue>, worker: usize) {
+    let mut session: Option<LockSession> = None;
+
+    loop {
+        let locks = match session.as_mut() {
+            Some(locks) => locks,
+            None => match LockSession::open(&queue.pool).await {
+                Ok(opened) => session.insert(opened),
+                Err(e) => {
+                    error!(worker, error = %e, "Falha ao abrir a conexão de locks da fila de backups");
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+            },
+        };
+
+        let claimed = claim_run(&queue, locks).await;
+        if let Ok(Some(run)) = &claimed {
+            let cancel = CancelToken::new();
+            let _registration = queue.running.register(run.id, cancel.clone());
+            tokio::select! {
+                _ = execute_run(&queue.pool, run, worker, &cancel, queue.limits.per_destination) => {}
+                _ = watch_cancel_request(&queue.pool, run.id, &cancel) => {}
+            }
+        }
+
+        if let Err(e) = locks.unlock_all().await {
+            // Descartar a conexão também libera os locks
+            warn!(worker, error = %e, "Falha ao liberar os locks da fila de backups");
+            session = None;
+        }
+
+        match claimed {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                let _ = tokio::time::timeout(POLL_INTERVAL, queue.wake.notified()).await;
+            }
+            Err(e) => {
+                error!(worker, error = %e, "Falha ao consultar a fila de backups");
+                session = None;
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Pega uma vaga global (se houver limite) e reserva a próxima execução
+async fn claim_run(queue: &RunQueue, locks: &mut LockSession) -> Result<Option<BackupRun>, sqlx::Error> {
+    if let Some(max_concurrent) = queue.limits.max_concurrent {
+        if locks.try_slot(CONCURRENCY_LOCKS, "backups", max_concurrent).await?.is_none() {
+            debug!(max_concurrent, "Limite global de backups simultâneos atingido");
+            return Ok(None);
+        }
+    }
+    db::claim_next_backup_run(locks.connection()).await
+}
+
+/// Cancela o token quando outra instância (ou `cancel_previous`) pede o
+/// cancelamento pelo banco; roda até a execução terminar
+async fn watch_cancel_request(pool: &PgPool, run_id: Uuid, cancel: &CancelToken) {
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        match db::is_backup_run_cancel_requested(pool, run_id).await {
+            Ok(true) if !cancel.is_cancelled() => {
+                info!(run_id = %run_id, "Cancelamento da execução pedido pelo banco");
+                cancel.cancel();
+            }
+            Ok(_) => {}
+            Err(e) => warn!(run_id = %run_id, error = %e, "Falha ao consultar pedido de cancelamento"),
+        }
+    }
+}
+
+/// Marca periodicamente as execuções órfãs de instâncias que pararam
+async fn reap_orphaned_runs(pool: PgPool) {
+    loop {
+        tokio::time::sleep(ORPHAN_CHECK_INTERVAL).await;
+        match db::fail_interrupted_backup_runs(&pool).await {
+            Ok(0) => {}
+            Ok(orphaned) => warn!(orphaned, "Execuções de backup órfãs marcadas como failed"),
+            Err(e) => error!(error = %e, "Falha ao procurar execuções de backup órfãs"),
+        }
+    }
+}
+
+/// Executa uma execução reservada e grava o status final
+async fn execute_run(
+    pool: &PgPool,
+    run: &BackupRun,
+    worker: usize,
+    cancel: &CancelToken,
+    destination_slots: Option<u32>,
+) {
+    info!(worker, run_id = %run.id, job_id = %run.backup_job_id, triggered_by = %run.triggered_by, "Executando backup da fila");
+    if let Some(schedule_id) = run.schedule_id {
+        if let Err(e) = db::update_schedule_last_run(pool, schedule_id, "running").await {
+            error!("Failed to update schedule status: {}", e);
+        }
+    }
+
+    let result = match db::get_backup_job_by_id(pool, run.backup_job_id).await {
+        Ok(Some(job)) => backup_worker::perform_backup_run(pool, &job, run, cancel, destination_slots)
+            .await
+            .map_err(|e| e.to_string()),
+        Ok(None) => Err(format!("Backup job with ID {} not found", run.backup_job_id)),
+        Err(e) => Err(e.to_string()),
+    };
+
+    let (status, schedule_status) = match &result {
+        Ok(()) => ("succeeded", "completed"),
+        Err(_) if cancel.is_cancelled() => {
+            info!(run_id = %run.id, job_id = %run.backup_job_id, "Execução de backup cancelada");
+            ("cancelled", "cancelled")
+        }
+        Err(e) => {
+            error!(run_id = %run.id, job_id = %run.backup_job_id, error = %e, "Execução de backup falhou");
+            ("failed", "failed")
+        }
+    };
+    let error_message = if status == "failed" { result.as_ref().err().map(String::as_str) } else { None };
+    if let Err(e) = db::finish_backup_run(pool, run.id, status, error_message).await {
+        error!(run_id = %run.id, error = %e, "Falha ao gravar o status final da execução");
+    }
+    if let Some(schedule_id) = run.schedule_id {
+        if let Err(e) = db::update_schedule_last_run(pool, schedule_id, schedule_status).await {
+            error!("Failed to update schedule status: {}", e);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Varreduras agendadas (`scan_schedules`).
+//!
+//! Cada agendamento ativado vira um job do scheduler (registrado pelo
+//! `ScheduleRegistry`). A configuração do scan é lida do banco a cada
+//! disparo, então mudar a pasta ou os padrões de exclusão não exige
+//! registrar o job de novo; só a expressão cron e o `enabled` exigem.
+//!
+//! Um disparo enquanto o scan anterior do mesmo agendamento ainda roda é
+//! ignorado. O resultado de cada execução fica em `last_run_status`
+//! (`running`, `success`, `failed` ou `cancelled`) e o próximo disparo em
+//! `next_run_at`.
+
+use crate::db;
+use crate::file_scanner::{FileScanner, ScanCancelled, ScanConfig};
+use crate::models::ScanSchedule;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+use tokio_cron_scheduler::{Job, JobSchedulerError};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Agendamentos com um scan em andamento neste processo
+static RUNNING_SCHEDULES: LazyLock<Mutex<HashSet<Uuid>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Retira o agendamento de `RUNNING_SCHEDULES` ao fim da execução
+struct RunningGuard(Uuid);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING_SCHEDULES.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// Confere a expressão cron do jeito que o scheduler vai interpretá-la
+///
+/// # Erros
+/// * Mensagem com a expressão inválida
+pub fn validate_cron(cron_expression: &str) -> Result<(), String> {
+    Job::new_async(cron_expression, |_uuid, _l| Box::pin(async {}))
+        .map(|_| ())
+        .map_err(|e| format!("Invalid cron expression '{}': {}", cron_expression, e))
+}
+
+/// Job do scheduler que executa o agendamento a cada disparo do cron e grava o próximo disparo
+pub fn cron_job(pool: PgPool, schedule: &ScanSchedule) -> Result<Job, JobSchedulerError> {
+    let schedule_id = schedule.id;
+
+    Job::new_async(schedule.cron_expression.as_str(), move |uuid, mut scheduler| {
+        let pool = pool.clone();
+        Box::pin(async move {
+            run_scheduled_scan(&pool, schedule_id).await;
+
+            match scheduler.next_tick_for_job(uuid).await {
+                Ok(next_tick) => {
+                    if let Err(e) = db::set_scan_schedule_next_run(&pool, schedule_id, next_tick).await {
+                        error!(schedule_id = %schedule_id, error = %e, "Falha ao gravar o próximo scan agendado");
+                    }
+                }
+                Err(e) => warn!(schedule_id = %schedule_id, error = %e, "Próximo disparo do scan agendado indisponível"),
+            }
+        })
+    })
+}
+
+/// Executa uma vez o scan de um agendamento e grava o resultado.
+///
+/// Não faz nada se o agendamento não existe mais, foi desativado ou ainda
+/// tem um scan em andamento neste processo.
+pub async fn run_scheduled_scan(pool: &PgPool, schedule_id: Uuid) {
+    let schedule = match db::get_scan_schedule(pool, schedule_id).await {
+        Ok(Some(schedule)) if schedule.enabled => schedule,
+        Ok(_) => {
+            debug!(schedule_id = %schedule_id, "Agendamento de scan removido ou desativado; disparo ignorado");
+            return;
+        }
+        Err(e) => {
+            error!(schedule_id = %schedule_id, error = %e, "Falha ao carregar agendamento de scan");
+            return;
+        }
+    };
+
+    if !RUNNING_SCHEDULES.lock().unwrap().insert(schedule_id) {
+        info!(schedule_id = %schedule_id, "Scan anterior do agendamento ainda em andamento; disparo ignorado");
+        return;
+    }
+    let _running = RunningGuard(schedule_id);
+
+    info!(schedule_id = %schedule_id, root_path = %schedule.root_path, "Executando scan agendado");
+    if let Err(e) = db::start_scan_schedule_run(pool, schedule_id).await {
+        error!(schedule_id = %schedule_id, error = %e, "Falha ao marcar início do scan agendado");
+    }
+
+    let mut scanner = FileScanner::new(pool.clone(), ScanConfig::from_schedule(&schedule));
+
+    let (status, scan_job_id) = match scanner.start_scan().await {
+        Ok(scan_job_id) => {
+            info!(schedule_id = %schedule_id, scan_job_id = %scan_job_id, "Scan agendado concluído");
+            ("success", Some(scan_job_id))
+        }
+        Err(e) => match e.downcast_ref::<ScanCancelled>() {
+            Some(cancelled) => {
+                info!(schedule_id = %schedule_id, scan_job_id = %cancelled.scan_job_id, "Scan agendado cancelado");
+                ("cancelled", Some(cancelled.scan_job_id))
+            }
+            None => {
+                error!(schedule_id = %schedule_id, error = %e, "Erro no scan agendado");
+                ("failed", None)
+            }
+        },
+    };
+
+    if let Err(e) = db::finish_scan_schedule_run(pool, schedule_id, status, scan_job_id).await {
+        error!(schedule_id = %schedule_id, error = %e, "Falha ao gravar resultado do scan agendado");
+    }
+}
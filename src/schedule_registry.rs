@@ -0,0 +1,306 @@
+//! Registro dos schedules carregados no `JobScheduler`.
+//!
+//! O scheduler só conhece jobs pelo UUID que ele mesmo gera; o registro guarda
+//! esse UUID para cada schedule (`backup_schedules` e `scan_schedules`), para
+//! que alterar, desativar ou remover um schedule (ou o backup job dono dele)
+//! troque ou tire o job do scheduler na hora, sem esperar um reinício.
+//!
+//! Mudanças feitas fora das rotas desta instância (outra instância, SQL
+//! direto) são alcançadas pela reconciliação periódica com o banco.
+
+use crate::db;
+use crate::models::{BackupSchedule, ScanSchedule, ScheduleKind, ScheduledJobStatus};
+use crate::run_queue::RunQueue;
+use crate::scan_schedules;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_cron_scheduler::{JobScheduler, JobSchedulerError};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Intervalo entre reconciliações com o banco
+pub const RECONCILE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Tipo e ID do schedule na sua tabela
+type ScheduleKey = (ScheduleKind, Uuid);
+
+/// Schedule com um job no scheduler
+struct Registered {
+    scheduler_job_id: Uuid,
+    backup_job_id: Option<Uuid>,
+    name: String,
+    cron_expression: String,
+}
+
+/// Schedule como está no banco
+enum Source<'a> {
+    Backup(&'a BackupSchedule),
+    Scan(&'a ScanSchedule),
+}
+
+impl Source<'_> {
+    fn key(&self) -> ScheduleKey {
+        match self {
+            Source::Backup(schedule) => (ScheduleKind::Backup, schedule.id),
+            Source::Scan(schedule) => (ScheduleKind::Scan, schedule.id),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        match self {
+            Source::Backup(schedule) => schedule.enabled,
+            Source::Scan(schedule) => schedule.enabled,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Source::Backup(schedule) => &schedule.name,
+            Source::Scan(schedule) => &schedule.name,
+        }
+    }
+
+    fn cron_expression(&self) -> &str {
+        match self {
+            Source::Backup(schedule) => &schedule.cron_expression,
+            Source::Scan(schedule) => &schedule.cron_expression,
+        }
+    }
+
+    fn backup_job_id(&self) -> Option<Uuid> {
+        match self {
+            Source::Backup(schedule) => Some(schedule.backup_job_id),
+            Source::Scan(_) => None,
+        }
+    }
+}
+
+/// Mapeia cada schedule para o job do scheduler que o dispara
+pub struct ScheduleRegistry {
+    pool: PgPool,
+    scheduler: Arc<JobScheduler>,
+    run_queue: Arc<RunQueue>,
+    // Mutex assíncrono: as trocas no scheduler acontecem com o registro travado,
+    // assim duas rotas alterando o mesmo schedule não deixam um job sobrando
+    schedules: Mutex<HashMap<ScheduleKey, Registered>>,
+}
+
+impl ScheduleRegistry {
+    /// Cria um registro vazio; os disparos de backup são enfileirados em `run_queue`
+    pub fn new(pool: PgPool, scheduler: Arc<JobScheduler>, run_queue: Arc<RunQueue>) -> Self {
+        Self {
+            pool,
+            scheduler,
+            run_queue,
+            schedules: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Alinha o scheduler a um schedule de backup recém-criado ou alterado:
+    /// registra, troca o job (cron diferente) ou remove (desativado).
+    ///
+    /// # Erros
+    /// * Expressão cron inválida ou falha do scheduler; o job anterior, se
+    ///   havia, já foi removido
+    pub async fn sync_schedule(&self, schedule: &BackupSchedule) -> Result<(), JobSchedulerError> {
+        let mut schedules = self.schedules.lock().await;
+        self.apply(&mut schedules, &Source::Backup(schedule)).await.map(|_| ())
+    }
+
+    /// Mesmo que `sync_schedule`, para um agendamento de scan
+    pub async fn sync_scan_schedule(&self, schedule: &ScanSchedule) -> Result<(), JobSchedulerError> {
+        let mut schedules = self.schedules.lock().await;
+        self.apply(&mut schedules, &Source::Scan(schedule)).await.map(|_| ())
+    }
+
+    /// Remove do scheduler um agendamento de scan apagado
+    ///
+    /// # Retorna
+    /// * `true` se o agendamento estava registrado
+    pub async fn remove_scan_schedule(&self, schedule_id: Uuid) -> Result<bool, JobSchedulerError> {
+        let mut schedules = self.schedules.lock().await;
+        let key = (ScheduleKind::Scan, schedule_id);
+        let registered = schedules.contains_key(&key);
+        self.unregister(&mut schedules, key).await?;
+        Ok(registered)
+    }
+
+    /// Remove do scheduler o schedule de um backup job (schedule apagado ou
+    /// job desativado)
+    ///
+    /// # Retorna
+    /// * `true` se o job tinha um schedule registrado
+    pub async fn remove_job(&self, backup_job_id: Uuid) -> Result<bool, JobSchedulerError> {
+        let mut schedules = self.schedules.lock().await;
+        let keys: Vec<ScheduleKey> = schedules
+            .iter()
+            .filter(|(_, registered)| registered.backup_job_id == Some(backup_job_id))
+            .map(|(key, _)| *key)
+            .collect();
+
+        for key in &keys {
+            self.unregister(&mut schedules, *key).await?;
+        }
+        Ok(!keys.is_empty())
+    }
+
+    /// Alinha o registro a `backup_schedules` e `scan_schedules`: registra os
+    /// schedules ativos que faltam, troca os que mudaram de cron e remove os
+    /// que não estão mais ativos.
+    ///
+    /// Falhas em um schedule (ex.: cron inválido) são registradas no log e não
+    /// impedem os demais.
+    ///
+    /// # Retorna
+    /// * Quantidade de schedules registrados, trocados ou removidos
+    pub async fn reconcile(&self) -> Result<usize, sqlx::Error> {
+        let backup_schedules = db::list_active_schedules(&self.pool).await?;
+        let scan_schedules = db::list_scan_schedules(&self.pool, true).await?;
+        let active: Vec<Source> = backup_schedules
+            .iter()
+            .map(Source::Backup)
+            .chain(scan_schedules.iter().map(Source::Scan))
+            .collect();
+
+        let mut schedules = self.schedules.lock().await;
+        let mut changed = 0;
+
+        let active_keys: HashSet<ScheduleKey> = active.iter().map(Source::key).collect();
+        let stale: Vec<ScheduleKey> = schedules.keys().filter(|key| !active_keys.contains(key)).copied().collect();
+        for key in stale {
+            match self.unregister(&mut schedules, key).await {
+                Ok(()) => changed += 1,
+                Err(e) => error!(schedule_id = %key.1, error = %e, "Falha ao remover schedule do scheduler"),
+            }
+        }
+
+        for source in &active {
+            match self.apply(&mut schedules, source).await {
+                Ok(true) => changed += 1,
+                Ok(false) => {}
+                Err(e) => error!(
+                    schedule_id = %source.key().1,
+                    cron = %source.cron_expression(),
+                    error = %e,
+                    "Falha ao registrar schedule '{}' no scheduler", source.name()
+                ),
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Reconcilia com o banco a cada `RECONCILE_INTERVAL`, até o processo terminar
+    pub fn start_reconciliation(self: &Arc<Self>) {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(RECONCILE_INTERVAL).await;
+                match registry.reconcile().await {
+                    Ok(0) => {}
+                    Ok(changed) => info!(changed, "Scheduler realinhado com os schedules do banco"),
+                    Err(e) => error!(error = %e, "Falha ao reconciliar schedules com o banco"),
+                }
+            }
+        });
+    }
+
+    /// Jobs registrados no scheduler, com o próximo disparo de cada um
+    pub async fn status(&self) -> Vec<ScheduledJobStatus> {
+        let schedules = self.schedules.lock().await;
+        let mut scheduler = self.scheduler.as_ref().clone();
+
+        let mut jobs = Vec::with_capacity(schedules.len());
+        for (&(kind, schedule_id), registered) in schedules.iter() {
+            let next_tick = match scheduler.next_tick_for_job(registered.scheduler_job_id).await {
+                Ok(next_tick) => next_tick,
+                Err(e) => {
+                    warn!(schedule_id = %schedule_id, error = %e, "Próximo disparo do schedule indisponível");
+                    None
+                }
+            };
+            jobs.push(ScheduledJobStatus {
+                kind,
+                schedule_id,
+                backup_job_id: registered.backup_job_id,
+                scheduler_job_id: registered.scheduler_job_id,
+                name: registered.name.clone(),
+                cron_expression: registered.cron_expression.clone(),
+                next_tick,
+            });
+        }
+
+        // Próximos disparos primeiro; sem próximo disparo no fim
+        jobs.sort_by_key(|job| (job.next_tick.is_none(), job.next_tick));
+        jobs
+    }
+
+    /// # Retorna
+    /// * `true` se o scheduler foi alterado
+    async fn apply(
+        &self,
+        schedules: &mut HashMap<ScheduleKey, Registered>,
+        source: &Source<'_>,
+    ) -> Result<bool, JobSchedulerError> {
+        let key = source.key();
+        let current = schedules.get(&key);
+        if !source.enabled() {
+            if current.is_none() {
+                return Ok(false);
+            }
+            self.unregister(schedules, key).await?;
+            return Ok(true);
+        }
+
+        if let Some(registered) = current {
+            if registered.cron_expression == source.cron_expression() {
+                return Ok(false);
+            }
+            self.unregister(schedules, key).await?;
+        }
+
+        let job = match source {
+            Source::Backup(schedule) => self.run_queue.cron_job(schedule)?,
+            Source::Scan(schedule) => scan_schedules::cron_job(self.pool.clone(), schedule)?,
+        };
+        let scheduler_job_id = self.scheduler.add(job).await?;
+        schedules.insert(
+            key,
+            Registered {
+                scheduler_job_id,
+                backup_job_id: source.backup_job_id(),
+                name: source.name().to_string(),
+                cron_expression: source.cron_expression().to_string(),
+            },
+        );
+        info!(schedule_id = %key.1, cron = %source.cron_expression(), "Schedule '{}' registrado no scheduler", source.name());
+
+        if key.0 == ScheduleKind::Scan {
+            let next_tick = self.scheduler.as_ref().clone().next_tick_for_job(scheduler_job_id).await?;
+            self.record_scan_next_run(key.1, next_tick).await;
+        }
+        Ok(true)
+    }
+
+    async fn unregister(&self, schedules: &mut HashMap<ScheduleKey, Registered>, key: ScheduleKey) -> Result<(), JobSchedulerError> {
+        if let Some(registered) = schedules.get(&key) {
+            self.scheduler.remove(&registered.scheduler_job_id).await?;
+            debug!(schedule_id = %key.1, "Schedule '{}' removido do scheduler", registered.name);
+            schedules.remove(&key);
+            if key.0 == ScheduleKind::Scan {
+                self.record_scan_next_run(key.1, None).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// `next_run_at` de um agendamento de scan; falhas só vão para o log
+    async fn record_scan_next_run(&self, schedule_id: Uuid, next_run_at: Option<chrono::DateTime<chrono::Utc>>) {
+        if let Err(e) = db::set_scan_schedule_next_run(&self.pool, schedule_id, next_run_at).await {
+            error!(schedule_id = %schedule_id, error = %e, "Falha ao gravar o próximo scan agendado");
+        }
+    }
+}
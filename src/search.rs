@@ -0,0 +1,756 @@
+//! Índice de busca de arquivos (tantivy).
+//!
+//! Espelha as linhas ativas do `file_catalog` num índice local, para buscas
+//! textuais e facetadas sem varrer o banco:
+//!
+//! - `path` (termo exato, chave do documento) e `path_tokens` (componentes do
+//!   caminho tokenizados), `name`, `extension`
+//! - `directory`: faceta hierárquica do diretório pai (`/home/user/docs`), que
+//!   permite filtrar uma subárvore e contar arquivos por subdiretório
+//! - `size`, `modified` e `backup_status` (`never`, `current` ou `stale`, quando
+//!   o arquivo mudou depois do último backup)
+//! - `mime_type` e `mime_category` (`image`, `video`, `archive`, `document`,
+//!   `source_code`...), detectados pelo scanner
+//! - `content`: texto extraído pelo scanner (`file_content`), não armazenado
+//!
+//! O índice é atualizado pelo scanner (a cada lote gravado e ao marcar
+//! remoções) e pelo backup; o commit acontece ao fim de cada scan, lote do
+//! modo live ou backup. Se o índice é criado do zero (primeira execução ou
+//! schema novo), ele é reconstruído a partir do catálogo na inicialização.
+
+use crate::models::{FileSearchFacets, FileSearchHit, FileSearchResponse, MimeCategory, SearchFacetCount};
+use chrono::{DateTime as ChronoDateTime, NaiveDateTime, Utc};
+use sqlx::PgPool;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tantivy::collector::{Count, FacetCollector, FacetCounts, TopDocs};
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::schema::{
+    Facet, FacetOptions, Field, IndexRecordOption, Schema, Value, FAST, INDEXED, STORED, STRING, TEXT,
+};
+use tantivy::{DateTime, DocAddress, Index, IndexReader, IndexWriter, Order, ReloadPolicy, TantivyDocument, Term};
+use tracing::{info, warn};
+
+/// Variável de ambiente com o diretório do índice
+pub const SEARCH_INDEX_DIR_ENV: &str = "B2CLI_SEARCH_INDEX_DIR";
+
+/// Diretório padrão do índice, relativo ao diretório de trabalho
+pub const DEFAULT_SEARCH_INDEX_DIR: &str = "data/search-index";
+
+/// Maior página aceita por `search`
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Maior `offset + limit` aceito por `search`: páginas mais fundas obrigam o
+/// tantivy a ordenar todos os resultados anteriores
+pub const MAX_RESULT_WINDOW: usize = 10_000;
+
+/// Valores retornados por faceta
+const FACET_LIMIT: usize = 20;
+
+/// Memória do writer do tantivy (dividida entre suas threads)
+const WRITER_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Linhas do catálogo lidas por consulta ao reconstruir o índice. Cada uma
+/// pode trazer até `content_max_bytes` de texto (1 MiB por padrão), então a
+/// página fica pequena para limitar a memória
+const REINDEX_PAGE_SIZE: i64 = 50;
+
+/// Índice do processo, inicializado em `init`
+static SEARCH_INDEX: OnceLock<Arc<SearchIndex>> = OnceLock::new();
+
+/// Erros do índice de busca
+#[derive(Debug)]
+pub enum SearchError {
+    /// Consulta do usuário com sintaxe inválida
+    InvalidQuery(String),
+    /// `init_at` com um diretório diferente do índice já aberto no processo
+    AlreadyOpen(PathBuf),
+    Index(tantivy::TantivyError),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::InvalidQuery(msg) => write!(f, "Invalid search query: {}", msg),
+            SearchError::AlreadyOpen(dir) => write!(f, "Search index already open at {}", dir.display()),
+            SearchError::Index(e) => write!(f, "Search index error: {}", e),
+            SearchError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<tantivy::TantivyError> for SearchError {
+    fn from(e: tantivy::TantivyError) -> Self {
+        SearchError::Index(e)
+    }
+}
+
+impl From<sqlx::Error> for SearchError {
+    fn from(e: sqlx::Error) -> Self {
+        SearchError::Database(e)
+    }
+}
+
+/// Ordenação dos resultados
+#[derive(Debug, Clone, Default)]
+pub enum SearchSort {
+    #[default]
+    Relevance,
+    Size(Order),
+    Modified(Order),
+}
+
+/// Uma busca: consulta livre, filtros e paginação
+#[derive(Debug, Clone, Default)]
+pub struct SearchRequest {
+    /// Sintaxe do tantivy (`relatorio AND extension:pdf`, `size:>1000000`);
+    /// termos sem campo buscam no nome e no caminho
+    pub query: Option<String>,
+    /// Mesma sintaxe de `query`, aplicada ao conteúdo extraído dos arquivos
+    pub content: Option<String>,
+    pub extension: Option<String>,
+    pub mime_category: Option<MimeCategory>,
+    /// Restringe à subárvore; também é a raiz da faceta de diretórios
+    pub directory: Option<String>,
+    pub backup_status: Option<String>,
+    pub sort: SearchSort,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Linha do catálogo como indexada
+struct CatalogRow {
+    file_path: String,
+    file_name: String,
+    extension: Option<String>,
+    mime_type: Option<String>,
+    mime_category: Option<String>,
+    file_size: i64,
+    modified_at: Option<NaiveDateTime>,
+    last_backup_at: Option<NaiveDateTime>,
+    content: Option<String>,
+}
+
+/// Campos do schema
+#[derive(Clone, Copy)]
+struct Fields {
+    path: Field,
+    path_tokens: Field,
+    name: Field,
+    extension: Field,
+    extension_facet: Field,
+    mime_type: Field,
+    mime_category: Field,
+    directory: Field,
+    size: Field,
+    modified: Field,
+    backup_status: Field,
+    last_backup: Field,
+    content: Field,
+}
+
+fn build_schema() -> (Schema, Fields) {
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        path: builder.add_text_field("path", STRING | STORED),
+        path_tokens: builder.add_text_field("path_tokens", TEXT),
+        name: builder.add_text_field("name", TEXT | STORED),
+        extension: builder.add_text_field("extension", STRING | STORED | FAST),
+        extension_facet: builder.add_facet_field("extension_facet", FacetOptions::default()),
+        mime_type: builder.add_text_field("mime_type", STRING | STORED),
+        mime_category: builder.add_text_field("mime_category", STRING | STORED),
+        directory: builder.add_facet_field("directory", FacetOptions::default()),
+        size: builder.add_u64_field("size", INDEXED | STORED | FAST),
+        modified: builder.add_date_field("modified", INDEXED | STORED | FAST),
+        backup_status: builder.add_text_field("backup_status", STRING | STORED | FAST),
+        last_backup: builder.add_date_field("last_backup", STORED),
+        content: builder.add_text_field("content", TEXT),
+    };
+    (builder.build(), fields)
+}
+
+/// Índice de busca aberto, com um writer compartilhado
+pub struct SearchIndex {
+    dir: PathBuf,
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    fields: Fields,
+    /// O índice foi criado agora e precisa ser preenchido a partir do catálogo
+    created: bool,
+}
+
+impl SearchIndex {
+    /// Abre (ou cria) o índice em `dir`.
+    ///
+    /// Um índice com schema diferente (versão anterior) é descartado e
+    /// recriado vazio; `needs_rebuild` indica esse caso.
+    pub fn open(dir: &Path) -> Result<Self, SearchError> {
+        let (schema, fields) = build_schema();
+        std::fs::create_dir_all(dir).map_err(|e| SearchError::Index(e.into()))?;
+
+        let existing = dir.join("meta.json").exists();
+        let (index, created) = match Index::open_in_dir(dir) {
+            Ok(index) if index.schema() == schema => (index, false),
+            Ok(_) | Err(_) if existing => {
+                warn!(dir = %dir.display(), "Índice de busca incompatível; recriando");
+                std::fs::remove_dir_all(dir).map_err(|e| SearchError::Index(e.into()))?;
+                std::fs::create_dir_all(dir).map_err(|e| SearchError::Index(e.into()))?;
+                (Index::create_in_dir(dir, schema)?, true)
+            }
+            _ => (Index::create_in_dir(dir, schema)?, true),
+        };
+
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let writer = index.writer(WRITER_MEMORY_BYTES)?;
+
+        Ok(Self {
+            dir: std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf()),
+            index,
+            reader,
+            writer: Mutex::new(writer),
+            fields,
+            created,
+        })
+    }
+
+    /// Diretório do índice (absoluto)
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Se o índice foi criado vazio e ainda não foi preenchido
+    pub fn needs_rebuild(&self) -> bool {
+        self.created && self.num_docs() == 0
+    }
+
+    /// Documentos visíveis para busca (após o último commit)
+    pub fn num_docs(&self) -> u64 {
+        self.reader.searcher().num_docs()
+    }
+
+    fn document(&self, row: &CatalogRow) -> TantivyDocument {
+        let f = &self.fields;
+        let mut doc = TantivyDocument::default();
+        doc.add_text(f.path, &row.file_path);
+        doc.add_text(f.path_tokens, &row.file_path);
+        doc.add_text(f.name, &row.file_name);
+        if let Some(extension) = row.extension.as_deref().filter(|e| !e.is_empty()) {
+            doc.add_text(f.extension, extension);
+            doc.add_facet(f.extension_facet, Facet::from_path([extension]));
+        }
+        if let Some(mime_type) = row.mime_type.as_deref() {
+            doc.add_text(f.mime_type, mime_type);
+        }
+        if let Some(category) = row.mime_category.as_deref() {
+            doc.add_text(f.mime_category, category);
+        }
+        let parent = Path::new(&row.file_path).parent().unwrap_or(Path::new("/"));
+        doc.add_facet(f.directory, directory_facet(parent));
+        doc.add_u64(f.size, row.file_size.max(0) as u64);
+        if let Some(modified) = row.modified_at {
+            doc.add_date(f.modified, to_tantivy_date(modified));
+        }
+        doc.add_text(f.backup_status, backup_status(row));
+        if let Some(last_backup) = row.last_backup_at {
+            doc.add_date(f.last_backup, to_tantivy_date(last_backup));
+        }
+        if let Some(content) = row.content.as_deref() {
+            doc.add_text(f.content, content);
+        }
+        doc
+    }
+
+    /// Substitui os documentos das linhas (pendente até `commit`)
+    fn upsert_rows(&self, rows: &[CatalogRow]) -> Result<(), SearchError> {
+        let writer = self.writer.lock().unwrap();
+        for row in rows {
+            writer.delete_term(Term::from_field_text(self.fields.path, &row.file_path));
+            writer.add_document(self.document(row))?;
+        }
+        Ok(())
+    }
+
+    /// Remove os caminhos e tudo abaixo deles (pendente até `commit`)
+    pub fn remove_paths(&self, paths: &[String]) {
+        let writer = self.writer.lock().unwrap();
+        for path in paths {
+            writer.delete_term(Term::from_field_text(self.fields.path, path));
+            writer.delete_term(Term::from_facet(self.fields.directory, &directory_facet(Path::new(path))));
+        }
+    }
+
+    /// Grava as mudanças pendentes e as torna visíveis para busca.
+    ///
+    /// Bloqueia (escreve segmentos no disco); em código async, use
+    /// `commit_index`, `commit_global` ou `spawn_blocking`.
+    pub fn commit(&self) -> Result<(), SearchError> {
+        self.writer.lock().unwrap().commit()?;
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    /// Reindexa os caminhos a partir do catálogo: linhas ativas são
+    /// (re)indexadas, as demais removidas. Pendente até `commit`.
+    pub async fn reindex_paths(&self, pool: &PgPool, paths: &[String]) -> Result<(), SearchError> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        let rows = sqlx::query_as!(
+            CatalogRow,
+            r#"
+            SELECT fc.file_path, fc.file_name, fc.extension, fc.mime_type, fc.mime_category,
+                   fc.file_size, fc.modified_at, fc.last_backup_at, fct.content AS "content?"
+            FROM file_catalog fc
+            LEFT JOIN file_content fct ON fct.file_catalog_id = fc.id
+            WHERE fc.is_active = TRUE AND fc.file_path = ANY($1)
+            "#,
+            paths
+        )
+        .fetch_all(pool)
+        .await?;
+
+        {
+            let writer = self.writer.lock().unwrap();
+            for path in paths {
+                writer.delete_term(Term::from_field_text(self.fields.path, path));
+            }
+        }
+        self.upsert_rows(&rows)
+    }
+
+    /// Reindexa `root` e todos os arquivos sob ele (ou o catálogo inteiro,
+    /// com `None`), removendo do índice o que não está mais ativo. Pendente até
+    /// `commit`.
+    ///
+    /// # Retorna
+    /// * Quantidade de arquivos indexados
+    pub async fn reindex_under(&self, pool: &PgPool, root: Option<&str>) -> Result<u64, SearchError> {
+        let root = root.map(|root| root.trim_end_matches('/').to_string());
+        let prefix = root.as_ref().map(|root| format!("{}/", root));
+        match &root {
+            Some(root) => self.remove_paths(std::slice::from_ref(root)),
+            None => {
+                self.writer.lock().unwrap().delete_all_documents()?;
+            }
+        }
+
+        let mut indexed = 0u64;
+        let mut after = String::new();
+        loop {
+            let rows = sqlx::query_as!(
+                CatalogRow,
+                r#"
+                SELECT fc.file_path, fc.file_name, fc.extension, fc.mime_type, fc.mime_category,
+                       fc.file_size, fc.modified_at, fc.last_backup_at, fct.content AS "content?"
+                FROM file_catalog fc
+                LEFT JOIN file_content fct ON fct.file_catalog_id = fc.id
+                WHERE fc.is_active = TRUE
+                  AND ($1::TEXT IS NULL OR fc.file_path = $1 OR starts_with(fc.file_path, $2))
+                  AND fc.file_path > $3
+                ORDER BY fc.file_path
+                LIMIT $4
+                "#,
+                root,
+                prefix,
+                after,
+                REINDEX_PAGE_SIZE
+            )
+            .fetch_all(pool)
+            .await?;
+
+            let Some(last) = rows.last() else {
+                break;
+            };
+            after = last.file_path.clone();
+            self.upsert_rows(&rows)?;
+            indexed += rows.len() as u64;
+        }
+
+        Ok(indexed)
+    }
+
+    /// Executa uma busca.
+    ///
+    /// Bloqueia (lê segmentos do índice); em código async, use `spawn_blocking`.
+    pub fn search(&self, request: &SearchRequest) -> Result<FileSearchResponse, SearchError> {
+        let limit = request.limit.clamp(1, MAX_PAGE_SIZE);
+        if request.offset.saturating_add(limit) > MAX_RESULT_WINDOW {
+            return Err(SearchError::InvalidQuery(format!(
+                "offset + limit must be at most {}",
+                MAX_RESULT_WINDOW
+            )));
+        }
+
+        let f = &self.fields;
+        let searcher = self.reader.searcher();
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        if let Some(text) = request.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            let mut parser = QueryParser::for_index(&self.index, vec![f.name, f.path_tokens]);
+            parser.set_field_boost(f.name, 2.0);
+            let query = parser
+                .parse_query(text)
+                .map_err(|e| SearchError::InvalidQuery(e.to_string()))?;
+            clauses.push((Occur::Must, query));
+        }
+        if let Some(text) = request.content.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            let query = QueryParser::for_index(&self.index, vec![f.content])
+                .parse_query(text)
+                .map_err(|e| SearchError::InvalidQuery(e.to_string()))?;
+            clauses.push((Occur::Must, query));
+        }
+        if let Some(extension) = request.extension.as_deref() {
+            let extension = extension.trim_start_matches('.').to_lowercase();
+            clauses.push((Occur::Must, term_query(Term::from_field_text(f.extension, &extension))));
+        }
+        if let Some(category) = request.mime_category {
+            clauses.push((Occur::Must, term_query(Term::from_field_text(f.mime_category, category.as_str()))));
+        }
+        let directory = request
+            .directory
+            .as_deref()
+            .map(|dir| directory_facet(Path::new(dir)))
+            .unwrap_or_else(Facet::root);
+        if !directory.is_root() {
+            clauses.push((Occur::Must, term_query(Term::from_facet(f.directory, &directory))));
+        }
+        if let Some(status) = request.backup_status.as_deref() {
+            clauses.push((Occur::Must, term_query(Term::from_field_text(f.backup_status, status))));
+        }
+        let query: Box<dyn Query> = if clauses.is_empty() {
+            Box::new(AllQuery)
+        } else {
+            Box::new(BooleanQuery::new(clauses))
+        };
+
+        let mut directory_facets = FacetCollector::for_field("directory");
+        directory_facets.add_facet(directory.clone());
+        let mut extension_facets = FacetCollector::for_field("extension_facet");
+        extension_facets.add_facet(Facet::root());
+        let top = TopDocs::with_limit(limit).and_offset(request.offset);
+
+        let (hits, total, directory_counts, extension_counts): (Vec<(Option<f32>, DocAddress)>, usize, FacetCounts, FacetCounts) =
+            match &request.sort {
+                SearchSort::Relevance => {
+                    let (docs, total, dirs, exts) =
+                        searcher.search(&*query, &(top, Count, directory_facets, extension_facets))?;
+                    (docs.into_iter().map(|(score, addr)| (Some(score), addr)).collect(), total, dirs, exts)
+                }
+                SearchSort::Size(order) => {
+                    let top = top.order_by_fast_field::<u64>("size", order.clone());
+                    let (docs, total, dirs, exts) =
+                        searcher.search(&*query, &(top, Count, directory_facets, extension_facets))?;
+                    (docs.into_iter().map(|(_, addr)| (None, addr)).collect(), total, dirs, exts)
+                }
+                SearchSort::Modified(order) => {
+                    let top = top.order_by_fast_field::<DateTime>("modified", order.clone());
+                    let (docs, total, dirs, exts) =
+                        searcher.search(&*query, &(top, Count, directory_facets, extension_facets))?;
+                    (docs.into_iter().map(|(_, addr)| (None, addr)).collect(), total, dirs, exts)
+                }
+            };
+
+        let mut results = Vec::with_capacity(hits.len());
+        for (score, address) in hits {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            results.push(self.hit(&doc, score));
+        }
+
+        let facet_values = |counts: &FacetCounts, facet: &Facet, as_path: bool| -> Vec<SearchFacetCount> {
+            counts
+                .top_k(facet.clone(), FACET_LIMIT)
+                .into_iter()
+                .map(|(facet, count)| SearchFacetCount {
+                    value: if as_path {
+                        facet.to_path_string()
+                    } else {
+                        facet.to_path().last().map(|v| v.to_string()).unwrap_or_default()
+                    },
+                    count,
+                })
+                .collect()
+        };
+
+        Ok(FileSearchResponse {
+            total: total as u64,
+            offset: request.offset,
+            results,
+            facets: FileSearchFacets {
+                extension: facet_values(&extension_counts, &Facet::root(), false),
+                directory: facet_values(&directory_counts, &directory, true),
+            },
+        })
+    }
+
+    fn hit(&self, doc: &TantivyDocument, score: Option<f32>) -> FileSearchHit {
+        let f = &self.fields;
+        let text = |field: Field| doc.get_first(field).and_then(|v| v.as_str()).map(str::to_string);
+        let date = |field: Field| {
+            doc.get_first(field)
+                .and_then(|v| v.as_datetime())
+                .and_then(|d| ChronoDateTime::<Utc>::from_timestamp(d.into_timestamp_secs(), 0))
+        };
+
+        FileSearchHit {
+            file_path: text(f.path).unwrap_or_default(),
+            file_name: text(f.name).unwrap_or_default(),
+            extension: text(f.extension),
+            mime_type: text(f.mime_type),
+            mime_category: text(f.mime_category).as_deref().and_then(MimeCategory::parse),
+            file_size: doc.get_first(f.size).and_then(|v| v.as_u64()).unwrap_or(0) as i64,
+            modified_at: date(f.modified),
+            backup_status: text(f.backup_status).unwrap_or_default(),
+            last_backup_at: date(f.last_backup),
+            score,
+        }
+    }
+}
+
+fn term_query(term: Term) -> Box<dyn Query> {
+    Box::new(TermQuery::new(term, IndexRecordOption::Basic))
+}
+
+/// Faceta de um diretório: um nível por componente do caminho
+fn directory_facet(dir: &Path) -> Facet {
+    Facet::from_path(dir.components().filter_map(|component| match component {
+        Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+        _ => None,
+    }))
+}
+
+fn to_tantivy_date(value: NaiveDateTime) -> DateTime {
+    DateTime::from_timestamp_secs(value.and_utc().timestamp())
+}
+
+/// `never` sem backup, `stale` se o arquivo mudou depois do último backup
+fn backup_status(row: &CatalogRow) -> &'static str {
+    match (row.last_backup_at, row.modified_at) {
+        (None, _) => "never",
+        (Some(backup), Some(modified)) if modified > backup => "stale",
+        _ => "current",
+    }
+}
+
+/// Abre o índice do processo em `B2CLI_SEARCH_INDEX_DIR` (ou no diretório padrão)
+///
+/// # Retorna
+/// * O índice; chamadas seguintes devolvem o mesmo índice
+pub fn init() -> Result<Arc<SearchIndex>, SearchError> {
+    let dir = std::env::var(SEARCH_INDEX_DIR_ENV).unwrap_or_else(|_| DEFAULT_SEARCH_INDEX_DIR.to_string());
+    init_at(Path::new(&dir))
+}
+
+/// Abre o índice do processo em `dir`
+///
+/// # Erros
+/// * `AlreadyOpen` se o processo já abriu o índice em outro diretório
+pub fn init_at(dir: &Path) -> Result<Arc<SearchIndex>, SearchError> {
+    let index = match SEARCH_INDEX.get() {
+        Some(index) => index,
+        None => {
+            let index = SearchIndex::open(dir)?;
+            info!(dir = %dir.display(), documents = index.num_docs(), "Índice de busca aberto");
+            SEARCH_INDEX.get_or_init(|| Arc::new(index))
+        }
+    };
+    if index.dir() != std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf()) {
+        return Err(SearchError::AlreadyOpen(index.dir().to_path_buf()));
+    }
+    Ok(index.clone())
+}
+
+/// Índice do processo, se inicializado
+pub fn index() -> Option<Arc<SearchIndex>> {
+    SEARCH_INDEX.get().cloned()
+}
+
+/// Commit de um índice fora do runtime async
+pub async fn commit_index(index: Arc<SearchIndex>) -> Result<(), SearchError> {
+    tokio::task::spawn_blocking(move || index.commit())
+        .await
+        .map_err(|e| SearchError::Index(tantivy::TantivyError::InternalError(e.to_string())))?
+}
+
+/// Commit do índice do processo fora do runtime async (no-op sem índice)
+pub async fn commit_global() -> Result<(), SearchError> {
+    match index() {
+        Some(index) => commit_index(index).await,
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(path: &str, size: i64) -> CatalogRow {
+        let path_ref = Path::new(path);
+        CatalogRow {
+            file_path: path.to_string(),
+            file_name: path_ref.file_name().unwrap().to_string_lossy().to_string(),
+            extension: path_ref.extension().map(|e| e.to_string_lossy().to_string()),
+            mime_type: None,
+            mime_category: None,
+            file_size: size,
+            modified_at: None,
+            last_backup_at: None,
+            content: None,
+        }
+    }
+
+    fn test_index() -> (tempfile::TempDir, SearchIndex) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let index = SearchIndex::open(dir.path()).unwrap();
+        index
+            .upsert_rows(&[
+                row("/data/docs/relatorio-anual.pdf", 300),
+                row("/data/docs/notas.txt", 10),
+                row("/data/src/main.rs", 200),
+                row("/data/src/lib/relatorio.rs", 50),
+            ])
+            .unwrap();
+        index.commit().unwrap();
+        (dir, index)
+    }
+
+    fn request(query: Option<&str>) -> SearchRequest {
+        SearchRequest {
+            query: query.map(str::to_string),
+            limit: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_by_name_and_facets() {
+        let (_dir, index) = test_index();
+        let response = index.search(&request(Some("relatorio"))).unwrap();
+        assert_eq!(response.total, 2);
+
+        let extensions: Vec<_> = response.facets.extension.iter().map(|f| f.value.as_str()).collect();
+        assert!(extensions.contains(&"pdf") && extensions.contains(&"rs"));
+        assert_eq!(response.facets.directory[0].value, "/data");
+    }
+
+    #[test]
+    fn test_directory_filter_and_size_sort() {
+        let (_dir, index) = test_index();
+        let response = index
+            .search(&SearchRequest {
+                directory: Some("/data/src".to_string()),
+                sort: SearchSort::Size(Order::Desc),
+                ..request(None)
+            })
+            .unwrap();
+        let paths: Vec<_> = response.results.iter().map(|h| h.file_path.as_str()).collect();
+        assert_eq!(paths, vec!["/data/src/main.rs", "/data/src/lib/relatorio.rs"]);
+        // Arquivos diretos de /data/src não são um subdiretório
+        assert_eq!(response.facets.directory.len(), 1);
+        assert_eq!(response.facets.directory[0].value, "/data/src/lib");
+    }
+
+    #[test]
+    fn test_remove_paths_drops_subtree() {
+        let (_dir, index) = test_index();
+        index.remove_paths(&["/data/src".to_string()]);
+        index.commit().unwrap();
+        assert_eq!(index.num_docs(), 2);
+    }
+
+    #[test]
+    fn test_mime_category_filter() {
+        let (_dir, index) = test_index();
+        index
+            .upsert_rows(&[CatalogRow {
+                mime_type: Some("image/png".to_string()),
+                mime_category: Some("image".to_string()),
+                ..row("/data/fotos/praia.png", 2048)
+            }])
+            .unwrap();
+        index.commit().unwrap();
+
+        let response = index
+            .search(&SearchRequest {
+                mime_category: Some(MimeCategory::Image),
+                ..request(None)
+            })
+            .unwrap();
+        assert_eq!(response.total, 1);
+        assert_eq!(response.results[0].mime_type.as_deref(), Some("image/png"));
+        assert_eq!(response.results[0].mime_category, Some(MimeCategory::Image));
+    }
+
+    #[test]
+    fn test_content_query_combines_with_filters() {
+        let (_dir, index) = test_index();
+        index
+            .upsert_rows(&[
+                CatalogRow {
+                    content: Some("Contrato de licenciamento, cláusula de renovação".to_string()),
+                    ..row("/data/docs/contrato.md", 40)
+                },
+                CatalogRow {
+                    content: Some("fn renovar_licenciamento() {}".to_string()),
+                    ..row("/data/src/licenca.rs", 30)
+                },
+            ])
+            .unwrap();
+        index.commit().unwrap();
+
+        let search = |extension: Option<&str>| {
+            index
+                .search(&SearchRequest {
+                    content: Some("licenciamento".to_string()),
+                    extension: extension.map(str::to_string),
+                    ..request(None)
+                })
+                .unwrap()
+        };
+        assert_eq!(search(None).total, 2);
+        let markdown = search(Some("md"));
+        assert_eq!(markdown.total, 1);
+        assert_eq!(markdown.results[0].file_path, "/data/docs/contrato.md");
+    }
+
+    #[test]
+    fn test_invalid_query_is_reported() {
+        let (_dir, index) = test_index();
+        let error = index.search(&request(Some("size:[1 TO"))).unwrap_err();
+        assert!(matches!(error, SearchError::InvalidQuery(_)));
+    }
+
+    #[test]
+    fn test_deep_pages_are_rejected() {
+        let (_dir, index) = test_index();
+        let page = |offset: usize| SearchRequest {
+            offset,
+            ..request(None)
+        };
+        assert_eq!(index.search(&page(MAX_RESULT_WINDOW - 10)).unwrap().total, 4);
+        let error = index.search(&page(MAX_RESULT_WINDOW - 9)).unwrap_err();
+        assert!(matches!(error, SearchError::InvalidQuery(_)));
+        assert!(index.search(&page(usize::MAX)).is_err());
+    }
+
+    #[test]
+    fn test_init_at_rejects_another_dir() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let other = tempfile::TempDir::new().unwrap();
+        let index = init_at(dir.path()).unwrap();
+        assert_eq!(index.dir(), dir.path());
+        assert!(Arc::ptr_eq(&index, &init_at(dir.path()).unwrap()));
+        let Err(error) = init_at(other.path()) else {
+            panic!("init_at opened a second index");
+        };
+        assert!(matches!(error, SearchError::AlreadyOpen(ref open) if open == dir.path()));
+    }
+}
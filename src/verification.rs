@@ -0,0 +1,269 @@
+//! Verificação de restore pós-backup.
+//!
+//! Depois que um mapeamento (origem -> destino) é sincronizado, restaura uma
+//! amostra (ou todos) dos arquivos catalogados da origem para um diretório
+//! temporário e compara o SHA256 restaurado com `file_catalog.content_hash`.
+//! O resultado por arquivo vai para `backup_verification_files` e os totais
+//! para `backup_verifications`, de onde sai o percentual de restorability.
+
+use crate::models::{BackupJob, BackupVerification, NewBackupVerificationFile};
+use crate::restore::{self, RestoreSelection, RestoreSource};
+use crate::{db, file_scanner, AppError};
+use sqlx::PgPool;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Arquivo do catálogo escolhido para verificação.
+struct VerificationCandidate {
+    file_catalog_id: Uuid,
+    file_path: String,
+    relative_path: String,
+    expected_hash: String,
+}
+
+/// Verifica um mapeamento recém-backupeado.
+///
+/// # Argumentos
+/// * `pool` - Pool de conexão PostgreSQL
+/// * `job` - Backup job (usa `verification_sample_size`)
+/// * `execution_log_id` - Log do backup que está sendo verificado
+/// * `source_path` - Origem do mapeamento
+/// * `destination` - Destino de onde os arquivos serão restaurados
+///
+/// # Retorna
+/// * `Ok(BackupVerification)` - Verificação concluída (mesmo que algum arquivo falhe)
+/// * `Err(AppError)` - Não foi possível executar a verificação; o registro fica `failed`
+///
+/// # Observações
+/// Os hashes esperados são os do pre-scan feito antes do sync. Um arquivo
+/// alterado entre o scan e o upload aparece como falha, o que é intencional:
+/// a cópia no destino não corresponde ao que foi catalogado.
+pub async fn verify_mapping(
+    pool: &PgPool,
+    job: &BackupJob,
+    execution_log_id: Option<Uuid>,
+    source_path: &str,
+    destination: &str,
+) -> Result<BackupVerification, AppError> {
+    let verification = db::create_backup_verification(
+        pool,
+        job.id,
+        execution_log_id,
+        source_path,
+        destination,
+        job.verification_sample_size,
+    )
+    .await?;
+
+    match run_verification(pool, job, &verification, source_path, destination).await {
+        Ok(verification) => {
+            tracing::info!(
+                job_id = %job.id,
+                verification_id = %verification.id,
+                checked = verification.files_checked,
+                failed = verification.files_failed,
+                restorability = ?verification.restorability_percent,
+                "Restore verification finished"
+            );
+            Ok(verification)
+        }
+        Err(e) => {
+            tracing::error!(job_id = %job.id, verification_id = %verification.id, error = %e, "Restore verification failed");
+            db::fail_backup_verification(pool, verification.id, &e.to_string()).await?;
+            Err(e)
+        }
+    }
+}
+
+async fn run_verification(
+    pool: &PgPool,
+    job: &BackupJob,
+    verification: &BackupVerification,
+    source_path: &str,
+    destination: &str,
+) -> Result<BackupVerification, AppError> {
+    let candidates = select_candidates(pool, source_path, job.verification_sample_size).await?;
+    if candidates.is_empty() {
+        return Ok(db::complete_backup_verification(pool, verification.id, None, &[], None).await?);
+    }
+
+    let work_dir = std::env::temp_dir().join(format!("b2cli_verify_{}", verification.id));
+    let target_dir = work_dir.join("restored");
+    let list_file = work_dir.join("files.txt");
+    tokio::fs::create_dir_all(&target_dir).await?;
+
+    let file_list: String = candidates
+        .iter()
+        .map(|c| format!("{}\n", c.relative_path))
+        .collect();
+    tokio::fs::write(&list_file, file_list).await?;
+
+    let restore_source = RestoreSource {
+        source_path: source_path.to_string(),
+        destination: destination.to_string(),
+    };
+    let restore_result = restore::execute_restore(
+        pool,
+        job,
+        &restore_source,
+        &target_dir.to_string_lossy(),
+        vec![],
+        &RestoreSelection::FilesFrom(list_file.clone()),
+        "verification",
+    )
+    .await;
+
+    let outcome = match restore_result {
+        Ok(restore_log) => {
+            let files = compare_restored_files(&target_dir, &candidates).await;
+            let passed = files.iter().filter(|f| f.passed).count() as i64;
+            let percent = restorability_percent(passed, files.len() as i64);
+            db::complete_backup_verification(pool, verification.id, Some(restore_log.id), &files, percent)
+                .await
+                .map_err(AppError::from)
+        }
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = tokio::fs::remove_dir_all(&work_dir).await {
+        tracing::warn!(path = %work_dir.display(), error = %e, "Failed to clean up verification directory");
+    }
+
+    outcome
+}
+
+/// Seleciona arquivos ativos e com hash da origem, aleatoriamente quando há amostra.
+async fn select_candidates(
+    pool: &PgPool,
+    source_path: &str,
+    sample_size: Option<i32>,
+) -> Result<Vec<VerificationCandidate>, AppError> {
+    let source_dir = format!("{}/", source_path.trim_end_matches('/'));
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, file_path, content_hash AS "content_hash!"
+        FROM file_catalog
+        WHERE starts_with(file_path, $1)
+          AND is_active = TRUE
+          AND content_hash IS NOT NULL
+        ORDER BY random()
+        LIMIT $2
+        "#,
+        source_dir,
+        sample_size.map(|n| n.max(0) as i64)
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let relative_path = row.file_path.strip_prefix(&source_dir)?.to_string();
+            Some(VerificationCandidate {
+                file_catalog_id: row.id,
+                file_path: row.file_path,
+                relative_path,
+                expected_hash: row.content_hash,
+            })
+        })
+        .collect())
+}
+
+async fn compare_restored_files(
+    target_dir: &Path,
+    candidates: &[VerificationCandidate],
+) -> Vec<NewBackupVerificationFile> {
+    let mut results = Vec::with_capacity(candidates.len());
+
+    for candidate in candidates {
+        let restored: PathBuf = target_dir.join(&candidate.relative_path);
+        let (actual_hash, error_message) = if !restored.exists() {
+            (None, Some("File was not restored".to_string()))
+        } else {
+            match file_scanner::calculate_file_hash(&restored).await {
+                Ok(hash) => (Some(hash), None),
+                Err(e) => (None, Some(format!("Failed to hash restored file: {}", e))),
+            }
+        };
+
+        let passed = actual_hash.as_deref() == Some(candidate.expected_hash.as_str());
+        let error_message = match (&error_message, passed) {
+            (None, false) => Some("SHA256 mismatch".to_string()),
+            _ => error_message,
+        };
+
+        results.push(NewBackupVerificationFile {
+            file_catalog_id: Some(candidate.file_catalog_id),
+            file_path: candidate.file_path.clone(),
+            expected_hash: candidate.expected_hash.clone(),
+            actual_hash,
+            passed,
+            error_message,
+        });
+    }
+
+    results
+}
+
+/// Percentual de arquivos restauráveis; `None` quando nada foi verificado.
+pub fn restorability_percent(passed: i64, checked: i64) -> Option<f32> {
+    if checked <= 0 {
+        None
+    } else {
+        Some((passed as f64 / checked as f64 * 100.0) as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_restorability_percent() {
+        assert_eq!(restorability_percent(0, 0), None);
+        assert_eq!(restorability_percent(49, 50), Some(98.0));
+        assert_eq!(restorability_percent(10, 10), Some(100.0));
+    }
+
+    #[tokio::test]
+    async fn test_compare_restored_files() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("docs")).unwrap();
+        std::fs::write(dir.path().join("docs/ok.txt"), b"hello").unwrap();
+        std::fs::write(dir.path().join("docs/changed.txt"), b"tampered").unwrap();
+
+        let hello_hash = file_scanner::calculate_file_hash(&dir.path().join("docs/ok.txt"))
+            .await
+            .unwrap();
+
+        let candidates = vec![
+            VerificationCandidate {
+                file_catalog_id: Uuid::new_v4(),
+                file_path: "/src/docs/ok.txt".to_string(),
+                relative_path: "docs/ok.txt".to_string(),
+                expected_hash: hello_hash.clone(),
+            },
+            VerificationCandidate {
+                file_catalog_id: Uuid::new_v4(),
+                file_path: "/src/docs/changed.txt".to_string(),
+                relative_path: "docs/changed.txt".to_string(),
+                expected_hash: hello_hash.clone(),
+            },
+            VerificationCandidate {
+                file_catalog_id: Uuid::new_v4(),
+                file_path: "/src/docs/missing.txt".to_string(),
+                relative_path: "docs/missing.txt".to_string(),
+                expected_hash: hello_hash,
+            },
+        ];
+
+        let results = compare_restored_files(dir.path(), &candidates).await;
+        assert!(results[0].passed);
+        assert!(!results[1].passed);
+        assert_eq!(results[1].error_message.as_deref(), Some("SHA256 mismatch"));
+        assert!(!results[2].passed);
+        assert!(results[2].actual_hash.is_none());
+    }
+}
@@ -6,7 +6,7 @@ use axum::{
     http::{Request, StatusCode},
     Router,
 };
-use b2cli::{AppState, models::*};
+use b2cli::{AppState, backup_triggers::BackupTriggers, live_catalog::LiveCatalog, models::*, run_queue::RunQueue, schedule_registry::ScheduleRegistry};
 use hyper::body::Bytes;
 use http_body_util::BodyExt;
 use serde_json::{json, Value};
@@ -18,29 +18,63 @@ mod common;
 use common::TestDatabase;
 
 // Helper para criar app de teste
-async fn create_test_app() -> Router {
+async fn create_test_app() -> (Router, TestDatabase) {
     let test_db = TestDatabase::new().await;
     let scheduler = JobScheduler::new().await.expect("Failed to create scheduler");
+    // Sem workers: as execuções ficam na fila para os testes inspecionarem
+    let run_queue = Arc::new(RunQueue::new(test_db.pool.clone()));
+    
+    let scheduler = Arc::new(scheduler);
     
     let app_state = AppState {
         db_pool: test_db.pool.clone(),
-        scheduler: Arc::new(scheduler),
+        scheduler: scheduler.clone(),
+        live_catalog: Arc::new(LiveCatalog::new(test_db.pool.clone())),
+        backup_triggers: Arc::new(BackupTriggers::new(test_db.pool.clone(), run_queue.clone())),
+        schedules: Arc::new(ScheduleRegistry::new(test_db.pool.clone(), scheduler, run_queue.clone())),
+        run_queue,
     };
 
     // Criar app usando as mesmas rotas do main
-    Router::new()
+    let app = Router::new()
         .route("/health", axum::routing::get(b2cli::routes::health::health_check))
         .route("/readiness", axum::routing::get(b2cli::routes::readiness::readiness_check))
         .route("/backups", 
             axum::routing::post(b2cli::routes::backups::create_backup)
                 .get(b2cli::routes::backups::list_backups))
-        .route("/backups/:id", 
+        .route("/backups/{id}", 
             axum::routing::get(b2cli::routes::backups::get_backup)
                 .put(b2cli::routes::backups::update_backup)
+                .patch(b2cli::routes::backups::patch_backup)
                 .delete(b2cli::routes::backups::delete_backup))
-        .route("/backups/:id/run", 
+        .route("/backups/{id}/run", 
             axum::routing::post(b2cli::routes::backups::run_backup))
-        .with_state(app_state)
+        .route("/backups/{id}/runs",
+            axum::routing::get(b2cli::routes::runs::list_job_runs))
+        .route("/backups/{id}/schedule",
+            axum::routing::get(b2cli::routes::backups::get_schedule)
+                .put(b2cli::routes::backups::update_schedule)
+                .patch(b2cli::routes::backups::patch_schedule)
+                .delete(b2cli::routes::backups::delete_schedule))
+        .route("/scheduler/status",
+            axum::routing::get(b2cli::routes::backups::scheduler_status))
+        .route("/files/scan/schedule",
+            axum::routing::post(b2cli::routes::scan_schedules::create_scan_schedule))
+        .route("/files/scan/schedules",
+            axum::routing::get(b2cli::routes::scan_schedules::list_scan_schedules))
+        .route("/files/scan/schedule/{id}",
+            axum::routing::get(b2cli::routes::scan_schedules::get_scan_schedule)
+                .patch(b2cli::routes::scan_schedules::update_scan_schedule)
+                .delete(b2cli::routes::scan_schedules::delete_scan_schedule))
+        .route("/files/scan/schedule/{id}/toggle",
+            axum::routing::patch(b2cli::routes::scan_schedules::toggle_scan_schedule))
+        .route("/runs/{id}",
+            axum::routing::get(b2cli::routes::runs::get_run))
+        .route("/runs/{id}/cancel",
+            axum::routing::post(b2cli::routes::runs::cancel_run))
+        .with_state(app_state);
+    // O DB de teste é removido quando `TestDatabase` é descartado
+    (app, test_db)
 }
 
 async fn parse_response_body(body: Body) -> Value {
@@ -50,7 +84,7 @@ async fn parse_response_body(body: Body) -> Value {
 
 #[tokio::test]
 async fn test_health_endpoint() {
-    let app = create_test_app().await;
+    let (app, _db) = create_test_app().await;
     
     let response = app
         .oneshot(
@@ -70,7 +104,7 @@ async fn test_health_endpoint() {
 
 #[tokio::test]
 async fn test_readiness_endpoint() {
-    let app = create_test_app().await;
+    let (app, _db) = create_test_app().await;
     
     let response = app
         .oneshot(
@@ -91,7 +125,7 @@ async fn test_readiness_endpoint() {
 
 #[tokio::test]
 async fn test_create_backup_job() {
-    let app = create_test_app().await;
+    let (app, _db) = create_test_app().await;
     
     let new_job = json!({
         "name": "Test Backup",
@@ -123,7 +157,7 @@ async fn test_create_backup_job() {
 
 #[tokio::test]
 async fn test_list_backup_jobs() {
-    let app = create_test_app().await;
+    let (app, _db) = create_test_app().await;
     
     // Primeiro, criar um backup job
     let new_job = json!({
@@ -169,7 +203,7 @@ async fn test_list_backup_jobs() {
 
 #[tokio::test]
 async fn test_get_backup_job() {
-    let app = create_test_app().await;
+    let (app, _db) = create_test_app().await;
     
     // Criar backup job
     let new_job = json!({
@@ -216,7 +250,7 @@ async fn test_get_backup_job() {
 
 #[tokio::test]
 async fn test_get_nonexistent_backup() {
-    let app = create_test_app().await;
+    let (app, _db) = create_test_app().await;
     let fake_id = "00000000-0000-0000-0000-000000000000";
     
     let response = app
@@ -234,7 +268,7 @@ async fn test_get_nonexistent_backup() {
 
 #[tokio::test]
 async fn test_delete_backup_job() {
-    let app = create_test_app().await;
+    let (app, _db) = create_test_app().await;
     
     // Criar backup job
     let new_job = json!({
@@ -292,7 +326,7 @@ async fn test_delete_backup_job() {
 
 #[tokio::test]
 async fn test_invalid_backup_job_creation() {
-    let app = create_test_app().await;
+    let (app, _db) = create_test_app().await;
     
     let invalid_job = json!({
         "name": "", // Nome vazio deve falhar
@@ -315,9 +349,364 @@ async fn test_invalid_backup_job_creation() {
     assert!(response.status().is_client_error());
 }
 
+#[tokio::test]
+async fn test_watch_trigger_settings() {
+    let (app, _db) = create_test_app().await;
+
+    let invalid_job = json!({
+        "name": "Watch Backup",
+        "mappings": { "/tmp": ["/tmp/backup"] },
+        "trigger_mode": "watch",
+        "watch_quiet_seconds": 0
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/backups")
+                .header("content-type", "application/json")
+                .body(Body::from(invalid_job.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let source = tempfile::TempDir::new().unwrap();
+    let mut mappings = serde_json::Map::new();
+    mappings.insert(source.path().to_string_lossy().to_string(), json!(["/tmp/backup"]));
+    let new_job = json!({
+        "name": "Watch Backup",
+        "mappings": mappings,
+        "trigger_mode": "watch",
+        "watch_quiet_seconds": 5
+    });
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/backups")
+                .header("content-type", "application/json")
+                .body(Body::from(new_job.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let body = parse_response_body(response.into_body()).await;
+    assert_eq!(body["trigger_mode"], "watch");
+    assert_eq!(body["watch_quiet_seconds"], 5);
+    assert_eq!(body["watch_min_interval_seconds"], 300);
+    assert_eq!(body["watch_max_delay_seconds"], 1800);
+}
+
+#[tokio::test]
+async fn test_patch_to_watch_checks_existing_sources() {
+    let (app, _db) = create_test_app().await;
+
+    let new_job = json!({
+        "name": "Schedule Backup",
+        "mappings": { "/tmp/b2cli-missing-watch-source": ["/tmp/backup"] }
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/backups")
+                .header("content-type", "application/json")
+                .body(Body::from(new_job.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let job_id = parse_response_body(response.into_body()).await["id"].as_str().unwrap().to_string();
+
+    // Sem mapeamentos no corpo, vale a origem já gravada, que não existe
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PATCH")
+                .uri(format!("/backups/{}", job_id))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "trigger_mode": "watch" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/backups/{}", job_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = parse_response_body(response.into_body()).await;
+    assert_eq!(body["trigger_mode"], "schedule");
+}
+
+#[tokio::test]
+async fn test_run_backup_is_queued() {
+    let (app, _db) = create_test_app().await;
+
+    let new_job = json!({
+        "name": "Queued Backup",
+        "mappings": { "/tmp/test": ["/tmp/backup", "/tmp/backup2"] }
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/backups")
+                .header("content-type", "application/json")
+                .body(Body::from(new_job.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let job_id = parse_response_body(response.into_body()).await["id"].as_str().unwrap().to_string();
+
+    // A requisição volta assim que a execução entra na fila
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/backups/{}/run", job_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let run = parse_response_body(response.into_body()).await;
+    assert_eq!(run["backup_job_id"], job_id);
+    assert_eq!(run["status"], "queued");
+    assert_eq!(run["triggered_by"], "manual");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/runs/{}", run["id"].as_str().unwrap()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report = parse_response_body(response.into_body()).await;
+    assert_eq!(report["id"], run["id"]);
+    assert_eq!(report["status"], "queued");
+    assert_eq!(report["executions"], json!([]));
+
+    // Cancelar antes de um worker reservar tira a execução da fila
+    let cancel_uri = format!("/runs/{}/cancel", run["id"].as_str().unwrap());
+    let response = app
+        .clone()
+        .oneshot(Request::builder().method("POST").uri(&cancel_uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let cancelled = parse_response_body(response.into_body()).await;
+    assert_eq!(cancelled["status"], "cancelled");
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().method("POST").uri(&cancel_uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/backups/{}/runs?status=cancelled", job_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let runs = parse_response_body(response.into_body()).await;
+    assert_eq!(runs.as_array().unwrap().len(), 1);
+    assert_eq!(runs[0]["id"], run["id"]);
+    assert_eq!(runs[0]["status_reason"], "Cancelled on request");
+
+    // Jobs e execuções inexistentes
+    for uri in [
+        format!("/backups/{}/run", uuid::Uuid::new_v4()),
+        format!("/backups/{}/runs", uuid::Uuid::new_v4()),
+        format!("/runs/{}", uuid::Uuid::new_v4()),
+        format!("/runs/{}/cancel", uuid::Uuid::new_v4()),
+    ] {
+        let method = if uri.ends_with("/run") || uri.ends_with("/cancel") { "POST" } else { "GET" };
+        let response = app
+            .clone()
+            .oneshot(Request::builder().method(method).uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
+
+async fn scheduled_jobs(app: &Router) -> Vec<Value> {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/scheduler/status").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let status = parse_response_body(response.into_body()).await;
+    assert_eq!(status["scheduler"], "running");
+    status["jobs"].as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn test_schedule_changes_are_applied_to_the_scheduler() {
+    let (app, _db) = create_test_app().await;
+
+    let new_job = json!({
+        "name": "Scheduled Backup",
+        "mappings": { "/tmp/test": ["/tmp/backup"] },
+        "schedule": { "name": "Daily", "cron_expression": "0 0 3 * * *" }
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/backups")
+                .header("content-type", "application/json")
+                .body(Body::from(new_job.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let job_id = parse_response_body(response.into_body()).await["id"].as_str().unwrap().to_string();
+    let schedule_uri = format!("/backups/{}/schedule", job_id);
+
+    let jobs = scheduled_jobs(&app).await;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0]["kind"], "backup");
+    assert_eq!(jobs[0]["backup_job_id"], job_id);
+    assert_eq!(jobs[0]["cron_expression"], "0 0 3 * * *");
+    assert!(jobs[0]["next_tick"].is_string());
+    let first_scheduler_job = jobs[0]["scheduler_job_id"].clone();
+
+    // Nova expressão: o job do scheduler é trocado, não duplicado
+    let updated = json!({ "name": "Nightly", "cron_expression": "0 30 1 * * *" });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri(&schedule_uri)
+                .header("content-type", "application/json")
+                .body(Body::from(updated.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let jobs = scheduled_jobs(&app).await;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0]["cron_expression"], "0 30 1 * * *");
+    assert_ne!(jobs[0]["scheduler_job_id"], first_scheduler_job);
+
+    // Desativar e reativar
+    for (enabled, registered) in [(false, 0), (true, 1)] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PATCH")
+                    .uri(&schedule_uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "enabled": enabled }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(scheduled_jobs(&app).await.len(), registered);
+    }
+
+    // Remover o backup job (soft delete) tira o schedule do scheduler
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/backups/{}", job_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(scheduled_jobs(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_run_backup_with_skip_policy_reports_skipped_run() {
+    let (app, _db) = create_test_app().await;
+
+    let new_job = json!({
+        "name": "Skip Overlap Backup",
+        "mappings": { "/tmp/test": ["/tmp/backup"] },
+        "overlap_policy": "skip"
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/backups")
+                .header("content-type", "application/json")
+                .body(Body::from(new_job.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let job = parse_response_body(response.into_body()).await;
+    assert_eq!(job["overlap_policy"], "skip");
+    let run_uri = format!("/backups/{}/run", job["id"].as_str().unwrap());
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().method("POST").uri(&run_uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let first = parse_response_body(response.into_body()).await;
+
+    // A primeira execução ainda está na fila: a nova é pulada, com o motivo
+    let response = app
+        .clone()
+        .oneshot(Request::builder().method("POST").uri(&run_uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let skipped = parse_response_body(response.into_body()).await;
+    assert_eq!(skipped["status"], "skipped");
+    assert!(skipped["status_reason"].as_str().unwrap().contains(first["id"].as_str().unwrap()));
+}
+
 #[tokio::test]
 async fn test_malformed_json() {
-    let app = create_test_app().await;
+    let (app, _db) = create_test_app().await;
     
     let malformed_json = r#"{"name": "Test", "mappings": invalid_json}"#;
     
@@ -334,4 +723,166 @@ async fn test_malformed_json() {
         .unwrap();
     
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_scan_schedule_lifecycle() {
+    let (app, _db) = create_test_app().await;
+
+    let create = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/files/scan/schedule")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // Cron de 5 campos não é aceito pelo scheduler
+    let response = app
+        .clone()
+        .oneshot(create(json!({ "name": "Docs", "root_path": "/tmp/docs", "cron_expression": "0 2 * * *" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(create(json!({ "name": "Docs", "root_path": "/tmp/docs", "cron_expression": "0 0 2 * * *" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let schedule = parse_response_body(response.into_body()).await;
+    assert_eq!(schedule["enabled"], true);
+    assert_eq!(schedule["recursive"], true);
+    assert_eq!(schedule["exclude_patterns"], json!([]));
+    assert_eq!(schedule["total_runs"], 0);
+    assert!(schedule["next_run_at"].is_string());
+    let schedule_uri = format!("/files/scan/schedule/{}", schedule["id"].as_str().unwrap());
+
+    let jobs = scheduled_jobs(&app).await;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0]["kind"], "scan");
+    assert_eq!(jobs[0]["schedule_id"], schedule["id"]);
+    assert!(jobs[0]["backup_job_id"].is_null());
+
+    // Trocar o cron troca o job no scheduler
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PATCH")
+                .uri(&schedule_uri)
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "cron_expression": "0 30 1 * * *" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let jobs = scheduled_jobs(&app).await;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0]["cron_expression"], "0 30 1 * * *");
+
+    // Desativar tira do scheduler e limpa o próximo disparo
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PATCH")
+                .uri(format!("{}/toggle", schedule_uri))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let toggled = parse_response_body(response.into_body()).await;
+    assert_eq!(toggled["enabled"], false);
+    assert!(toggled["next_run_at"].is_null());
+    assert!(scheduled_jobs(&app).await.is_empty());
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/files/scan/schedules").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let schedules = parse_response_body(response.into_body()).await;
+    assert_eq!(schedules.as_array().unwrap().len(), 1);
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().method("DELETE").uri(&schedule_uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    for method in ["GET", "DELETE"] {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().method(method).uri(&schedule_uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
+
+#[tokio::test]
+async fn test_patch_verification_sample_size() {
+    let (app, _db) = create_test_app().await;
+
+    let send = |method: &str, uri: &str, body: Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let new_job = json!({
+        "name": "Sampled Backup",
+        "mappings": { "/tmp/test": ["/tmp/backup"] },
+        "verify_after_backup": true,
+        "verification_sample_size": 0
+    });
+    let response = app.clone().oneshot(send("POST", "/backups", new_job.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let mut new_job = new_job;
+    new_job["verification_sample_size"] = json!(5);
+    let response = app.clone().oneshot(send("POST", "/backups", new_job)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let job_uri = format!("/backups/{}", parse_response_body(response.into_body()).await["id"].as_str().unwrap());
+
+    let response = app.clone().oneshot(send("PATCH", &job_uri, json!({ "verification_sample_size": -1 }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Campo ausente mantém a amostra; null volta a verificar todos os arquivos
+    let response = app.clone().oneshot(send("PATCH", &job_uri, json!({ "name": "Sampled Backup 2" }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(parse_response_body(response.into_body()).await["verification_sample_size"], 5);
+
+    let response = app.clone().oneshot(send("PATCH", &job_uri, json!({ "verification_sample_size": null }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(parse_response_body(response.into_body()).await["verification_sample_size"].is_null());
+}
+
+#[tokio::test]
+async fn test_encrypted_job_is_not_created_without_crypt_keys() {
+    // Nenhuma chave mestra neste binário: as senhas do crypt não podem ser geradas
+    let test_db = TestDatabase::new().await;
+    let new_job: NewBackupJob = serde_json::from_value(json!({
+        "name": "Encrypted Backup",
+        "mappings": { "/tmp/test": ["/tmp/backup"] },
+        "encryption": "rclone_crypt"
+    }))
+    .unwrap();
+
+    assert!(b2cli::db::create_backup_job(&test_db.pool, &new_job).await.is_err());
+
+    // O INSERT do job foi desfeito junto
+    let jobs = b2cli::db::list_backup_jobs(&test_db.pool).await.unwrap();
+    assert!(jobs.iter().all(|job| job.name != "Encrypted Backup"));
+}
@@ -2,30 +2,28 @@
 // Helpers compartilhados entre testes
 
 use sqlx::{PgPool, Row};
-use std::sync::atomic::{AtomicU32, Ordering};
 use tempfile::TempDir;
 use std::path::PathBuf;
 use std::fs;
-use uuid::Uuid;
 use serde_json::json;
 use b2cli::{models::BackupJob, AppState};
 use axum::Router;
 use std::sync::Arc;
 use tokio_cron_scheduler::JobScheduler;
 
-// Contador para garantir DBs únicos
-static DB_COUNTER: AtomicU32 = AtomicU32::new(0);
-
-/// Setup de banco de dados para testes
+/// Setup de banco de dados para testes.
+///
+/// Cada instância cria um DB com nome único (vários binários de teste rodam
+/// no mesmo Postgres) e o remove ao ser descartada.
 pub struct TestDatabase {
     pub pool: PgPool,
     pub db_name: String,
+    admin_url: String,
 }
 
 impl TestDatabase {
     pub async fn new() -> Self {
-        let db_id = DB_COUNTER.fetch_add(1, Ordering::SeqCst);
-        let db_name = format!("b2cli_test_{}", db_id);
+        let db_name = format!("b2cli_test_{}", uuid::Uuid::new_v4().simple());
         
         // Conectar ao postgres para criar o DB de teste
         let admin_url = std::env::var("DATABASE_URL")
@@ -53,14 +51,29 @@ impl TestDatabase {
             .await
             .expect("Failed to run migrations");
         
-        Self { pool, db_name }
+        Self { pool, db_name, admin_url }
     }
 }
 
 impl Drop for TestDatabase {
     fn drop(&mut self) {
-        // Cleanup será feito async em production
-        // Por enquanto deixamos o DB para debug
+        // O pool pertence ao runtime do teste, que pode já estar parando: o DROP
+        // roda num runtime próprio e FORCE derruba as conexões que sobraram
+        let admin_url = self.admin_url.clone();
+        let drop_sql = format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", self.db_name);
+        let dropped = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            runtime.block_on(async {
+                let admin_pool = PgPool::connect(&admin_url).await?;
+                sqlx::query(&drop_sql).execute(&admin_pool).await?;
+                admin_pool.close().await;
+                Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+            })
+        })
+        .join();
+        if let Ok(Err(e)) = dropped {
+            eprintln!("Failed to drop test database {}: {}", self.db_name, e);
+        }
     }
 }
 
@@ -151,19 +164,9 @@ impl MockRclone {
 
 /// Criar BackupJob para testes
 pub fn create_test_backup_job(name: &str, source: &str, destinations: Vec<&str>) -> BackupJob {
-    let mappings = json!({
-        source: destinations
-    });
-    
     BackupJob {
-        id: Uuid::new_v4(),
-        name: name.to_string(),
-        mappings,
         status: "PENDING".to_string(),
-        is_active: true,
-        created_at: chrono::Utc::now(),
-        updated_at: chrono::Utc::now(),
-        deleted_at: None,
+        ..BackupJob::new(name, json!({ source: destinations }))
     }
 }
 
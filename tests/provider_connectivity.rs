@@ -0,0 +1,180 @@
+// tests/provider_connectivity.rs
+// Teste de conectividade contra um S3 local (`rclone serve s3`)
+//
+// Precisam do rclone com `serve s3` no PATH, por isso ficam marcados com `#[ignore]`:
+// `cargo test --test provider_connectivity -- --ignored`
+
+use b2cli::connectivity::{probe_provider, ConnectivityErrorKind};
+use b2cli::models::CloudProvider;
+use chrono::Utc;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::process::{Child, Command};
+use uuid::Uuid;
+
+const ACCESS_KEY: &str = "b2cli-test-key";
+const SECRET_KEY: &str = "b2cli-test-secret";
+const BUCKET: &str = "backups";
+
+/// `rclone serve s3` servindo um diretório temporário; cada subdiretório é um bucket
+struct LocalS3 {
+    _child: Child,
+    root: TempDir,
+    endpoint: String,
+}
+
+impl LocalS3 {
+    async fn start() -> Self {
+        let installed = Command::new("rclone")
+            .arg("version")
+            .output()
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+        assert!(installed, "rclone not installed; these tests need `rclone serve s3`");
+
+        let root = TempDir::new().unwrap();
+        std::fs::create_dir_all(root.path().join(BUCKET)).unwrap();
+
+        let addr: SocketAddr = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+
+        let child = Command::new("rclone")
+            .arg("serve")
+            .arg("s3")
+            .arg(root.path())
+            .arg("--addr")
+            .arg(addr.to_string())
+            .arg("--auth-key")
+            .arg(format!("{},{}", ACCESS_KEY, SECRET_KEY))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .expect("failed to spawn rclone serve s3");
+
+        for _ in 0..50 {
+            if TcpStream::connect_timeout(&addr, Duration::from_millis(100)).is_ok() {
+                return Self {
+                    _child: child,
+                    root,
+                    endpoint: format!("http://{}", addr),
+                };
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        panic!("rclone serve s3 did not start listening on {}", addr);
+    }
+
+    fn provider(&self) -> CloudProvider {
+        create_test_provider(&self.endpoint)
+    }
+}
+
+fn create_test_provider(endpoint: &str) -> CloudProvider {
+    CloudProvider {
+        id: Uuid::new_v4(),
+        name: "Local S3".to_string(),
+        provider_type: "backblaze_b2".to_string(),
+        endpoint: Some(endpoint.to_string()),
+        region: Some("us-east-1".to_string()),
+        bucket: BUCKET.to_string(),
+        path_prefix: Some("b2cli/".to_string()),
+        access_key: ACCESS_KEY.to_string(),
+        secret_key: SECRET_KEY.to_string(),
+        b2_account_id: None,
+        b2_application_key: None,
+        use_b2_native_api: false,
+        is_active: true,
+        is_default: false,
+        test_connectivity_at: None,
+        test_connectivity_status: None,
+        test_connectivity_message: None,
+        total_storage_bytes: 0,
+        total_egress_bytes: 0,
+        last_sync_at: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn count_files_recursive(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| {
+                    let path = e.path();
+                    if path.is_dir() { count_files_recursive(&path) } else { 1 }
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[tokio::test]
+#[ignore = "requires rclone serve s3"]
+async fn test_round_trip_succeeds() {
+    let s3 = LocalS3::start().await;
+
+    let report = probe_provider(&s3.provider()).await;
+
+    assert!(report.success(), "probe failed: {:?}", report);
+    assert!(report.can_list);
+    assert!(report.can_write);
+    assert_eq!(report.can_delete, Some(true));
+    assert!(report.latency_ms.is_some());
+    assert_eq!(
+        report.steps.iter().map(|s| s.step).collect::<Vec<_>>(),
+        vec!["list", "write", "stat", "delete"]
+    );
+    // O objeto de teste não pode ficar para trás
+    assert_eq!(count_files_recursive(&s3.root.path().join(BUCKET)), 0);
+}
+
+#[tokio::test]
+#[ignore = "requires rclone serve s3"]
+async fn test_wrong_secret_is_authentication_error() {
+    let s3 = LocalS3::start().await;
+
+    let mut provider = s3.provider();
+    provider.secret_key = "wrong-secret".to_string();
+    let report = probe_provider(&provider).await;
+
+    assert!(!report.success());
+    assert!(!report.can_list);
+    assert_eq!(report.error_kind, Some(ConnectivityErrorKind::Authentication));
+    assert_eq!(report.steps.len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires rclone serve s3"]
+async fn test_missing_bucket_is_reported() {
+    let s3 = LocalS3::start().await;
+
+    let mut provider = s3.provider();
+    provider.bucket = "does-not-exist".to_string();
+    let report = probe_provider(&provider).await;
+
+    assert!(!report.success());
+    assert_eq!(report.error_kind, Some(ConnectivityErrorKind::BucketNotFound));
+}
+
+#[tokio::test]
+#[ignore = "requires rclone serve s3"]
+async fn test_unresolvable_endpoint_is_dns_error() {
+    // Só precisa do rclone, não do servidor
+    let _s3 = LocalS3::start().await;
+
+    let provider = create_test_provider("http://b2cli-connectivity-test.invalid");
+    let report = probe_provider(&provider).await;
+
+    assert!(!report.success());
+    assert_eq!(report.error_kind, Some(ConnectivityErrorKind::Dns));
+}
@@ -0,0 +1,286 @@
+// tests/run_queue.rs
+// Testes da fila persistente de execuções de backup (backup_runs)
+
+use std::sync::Arc;
+use std::time::Duration;
+use b2cli::{db, models::{BackupRun, NewBackupJob, NewBackupSchedule}, run_queue::{RunCancellation, RunQueue}};
+use serde_json::json;
+use sqlx::{Connection, PgConnection};
+use uuid::Uuid;
+
+mod common;
+use common::{TestDatabase, TestFixtures};
+
+async fn create_job(db: &TestDatabase, mappings: serde_json::Value) -> Uuid {
+    create_job_with_policy(db, mappings, "queue").await
+}
+
+async fn create_job_with_policy(db: &TestDatabase, mappings: serde_json::Value, overlap_policy: &str) -> Uuid {
+    let new_job: NewBackupJob = serde_json::from_value(json!({
+        "name": "Queue Backup",
+        "mappings": mappings,
+        "overlap_policy": overlap_policy,
+    }))
+    .unwrap();
+    db::create_backup_job(&db.pool, &new_job).await.unwrap().0.id
+}
+
+/// Conexão própria, como a de um worker: os locks do job ficam nela
+async fn worker_connection(db: &TestDatabase) -> PgConnection {
+    PgConnection::connect_with(&db.pool.connect_options()).await.unwrap()
+}
+
+/// Espera a execução sair de `queued`/`running`
+async fn wait_finished(db: &TestDatabase, run_id: Uuid) -> BackupRun {
+    for _ in 0..300 {
+        let run = db::get_backup_run_by_id(&db.pool, run_id).await.unwrap().unwrap();
+        if run.finished_at.is_some() {
+            return run;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("run {} did not finish", run_id);
+}
+
+#[tokio::test]
+async fn test_worker_runs_queued_backup_and_records_progress() {
+    let db = TestDatabase::new().await;
+    let fixtures = TestFixtures::new();
+    fixtures.create_test_structure();
+    let source = fixtures.source_dir.to_string_lossy().to_string();
+    let first = fixtures.backup_dir.join("first").to_string_lossy().to_string();
+    let second = fixtures.backup_dir.join("second").to_string_lossy().to_string();
+    let job_id = create_job(&db, json!({ source: [first, second] })).await;
+
+    let queue = Arc::new(RunQueue::new(db.pool.clone()));
+    let queued = queue.enqueue(job_id, None, "manual").await.unwrap();
+    assert_eq!(queued.status, "queued");
+    assert!(queued.started_at.is_none());
+
+    queue.start(1).await.unwrap();
+    let run = wait_finished(&db, queued.id).await;
+
+    // Sem rclone no ambiente as transferências falham; com rclone, passam.
+    // Em ambos os casos o progresso cobre os dois destinos.
+    assert_eq!(run.destinations_total, 2);
+    assert_eq!(run.destinations_completed + run.destinations_failed, 2);
+    assert!(run.started_at.is_some());
+    if run.destinations_failed == 0 {
+        assert_eq!(run.status, "succeeded");
+        assert!(run.error_message.is_none());
+    } else {
+        assert_eq!(run.status, "failed");
+        assert!(run.error_message.is_some());
+    }
+
+    let executions = db::list_backup_run_execution_logs(&db.pool, run.id).await.unwrap();
+    assert_eq!(executions.len(), 2);
+    assert!(executions.iter().all(|log| log.backup_run_id == Some(run.id)));
+    assert!(executions.iter().all(|log| log.triggered_by.as_deref() == Some("manual")));
+}
+
+#[tokio::test]
+async fn test_start_fails_runs_interrupted_by_restart() {
+    let db = TestDatabase::new().await;
+    let job_id = create_job(&db, json!({ "/tmp/queue-source": ["/tmp/queue-backup"] })).await;
+
+    // Execução reservada por um processo que parou no meio do backup
+    let interrupted = db::create_backup_run(&db.pool, job_id, None, "manual").await.unwrap();
+    let mut conn = worker_connection(&db).await;
+    let claimed = db::claim_next_backup_run(&mut conn).await.unwrap().unwrap();
+    assert_eq!(claimed.id, interrupted.id);
+    assert_eq!(claimed.status, "running");
+    assert!(db::has_pending_backup_run(&db.pool, job_id).await.unwrap());
+    assert!(db::claim_next_backup_run(&mut conn).await.unwrap().is_none());
+
+    // Enquanto a conexão do processo está aberta, a execução não é órfã
+    let queue = Arc::new(RunQueue::new(db.pool.clone()));
+    assert_eq!(queue.start(0).await.unwrap(), 0);
+
+    conn.close().await.unwrap();
+    assert_eq!(queue.start(0).await.unwrap(), 1);
+
+    let run = db::get_backup_run_by_id(&db.pool, interrupted.id).await.unwrap().unwrap();
+    assert_eq!(run.status, "failed");
+    assert!(run.finished_at.is_some());
+    assert!(!db::has_pending_backup_run(&db.pool, job_id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_cancel_dequeues_queued_run() {
+    let db = TestDatabase::new().await;
+    let job_id = create_job(&db, json!({ "/tmp/queue-source": ["/tmp/queue-backup"] })).await;
+    let queue = Arc::new(RunQueue::new(db.pool.clone()));
+    let queued = queue.enqueue(job_id, None, "manual").await.unwrap();
+
+    let run = match queue.cancel(queued.id).await.unwrap() {
+        Some(RunCancellation::Dequeued(run)) => run,
+        other => panic!("unexpected cancellation: {:?}", other),
+    };
+    assert_eq!(run.status, "cancelled");
+    assert!(run.started_at.is_none());
+    assert!(run.finished_at.is_some());
+
+    // Fora da fila: nenhum worker reserva a execução cancelada
+    let mut conn = worker_connection(&db).await;
+    assert!(db::claim_next_backup_run(&mut conn).await.unwrap().is_none());
+    assert!(!db::has_pending_backup_run(&db.pool, job_id).await.unwrap());
+
+    assert!(matches!(
+        queue.cancel(queued.id).await.unwrap(),
+        Some(RunCancellation::Finished(status)) if status == "cancelled"
+    ));
+    assert!(queue.cancel(Uuid::new_v4()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_cancel_running_run_of_another_instance_is_requested_in_database() {
+    let db = TestDatabase::new().await;
+    let job_id = create_job(&db, json!({ "/tmp/queue-source": ["/tmp/queue-backup"] })).await;
+    let queue = Arc::new(RunQueue::new(db.pool.clone()));
+    let queued = queue.enqueue(job_id, None, "manual").await.unwrap();
+
+    // Reservada fora dos workers desta fila
+    let mut conn = worker_connection(&db).await;
+    db::claim_next_backup_run(&mut conn).await.unwrap().unwrap();
+
+    assert!(matches!(queue.cancel(queued.id).await.unwrap(), Some(RunCancellation::Requested)));
+    let run = db::get_backup_run_by_id(&db.pool, queued.id).await.unwrap().unwrap();
+    assert_eq!(run.status, "running");
+    assert!(run.cancel_requested_at.is_some());
+    assert!(db::is_backup_run_cancel_requested(&db.pool, queued.id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_claim_never_runs_two_runs_of_the_same_job() {
+    let db = TestDatabase::new().await;
+    let job_id = create_job(&db, json!({ "/tmp/queue-source": ["/tmp/queue-backup"] })).await;
+    let queue = Arc::new(RunQueue::new(db.pool.clone()));
+    let first = queue.enqueue(job_id, None, "manual").await.unwrap();
+    let second = queue.enqueue(job_id, None, "scheduler").await.unwrap();
+    assert_eq!(second.status, "queued");
+
+    let mut first_worker = worker_connection(&db).await;
+    let mut second_worker = worker_connection(&db).await;
+    assert_eq!(db::claim_next_backup_run(&mut first_worker).await.unwrap().unwrap().id, first.id);
+    // A segunda execução espera a primeira terminar
+    assert!(db::claim_next_backup_run(&mut second_worker).await.unwrap().is_none());
+
+    db::finish_backup_run(&db.pool, first.id, "succeeded", None).await.unwrap();
+    sqlx::query("SELECT pg_advisory_unlock_all()").execute(&mut first_worker).await.unwrap();
+    assert_eq!(db::claim_next_backup_run(&mut second_worker).await.unwrap().unwrap().id, second.id);
+}
+
+#[tokio::test]
+async fn test_skip_policy_records_skipped_run_with_reason() {
+    let db = TestDatabase::new().await;
+    let job_id = create_job_with_policy(&db, json!({ "/tmp/queue-source": ["/tmp/queue-backup"] }), "skip").await;
+    let queue = Arc::new(RunQueue::new(db.pool.clone()));
+    let first = queue.enqueue(job_id, None, "manual").await.unwrap();
+    assert_eq!(first.status, "queued");
+
+    let skipped = queue.enqueue(job_id, None, "scheduler").await.unwrap();
+    assert_eq!(skipped.status, "skipped");
+    assert!(skipped.finished_at.is_some());
+    assert!(skipped.status_reason.unwrap().contains(&first.id.to_string()));
+
+    let listed = db::list_backup_runs(&db.pool, job_id, Some("skipped"), 50).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, skipped.id);
+    assert_eq!(db::list_backup_runs(&db.pool, job_id, None, 50).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_cron_tick_is_enqueued_once_across_instances() {
+    let db = TestDatabase::new().await;
+    let job_id = create_job(&db, json!({ "/tmp/queue-source": ["/tmp/queue-backup"] })).await;
+    let new_schedule: NewBackupSchedule = serde_json::from_value(json!({
+        "name": "Every minute",
+        "cron_expression": "0 * * * * *",
+    }))
+    .unwrap();
+    let schedule = db::create_backup_schedule(&db.pool, job_id, &new_schedule).await.unwrap();
+    // Duas instâncias com o mesmo schedule recebem o mesmo disparo
+    let first_instance = RunQueue::new(db.pool.clone());
+    let second_instance = RunQueue::new(db.pool.clone());
+    let tick = "2025-08-09T10:00:00Z".parse().unwrap();
+
+    let (first, second) = tokio::join!(
+        first_instance.enqueue_tick(job_id, schedule.id, tick),
+        second_instance.enqueue_tick(job_id, schedule.id, tick),
+    );
+    let enqueued: Vec<BackupRun> = [first.unwrap(), second.unwrap()].into_iter().flatten().collect();
+    assert_eq!(enqueued.len(), 1);
+    assert_eq!(enqueued[0].scheduled_for, Some(tick));
+    assert_eq!(enqueued[0].triggered_by, "scheduler");
+
+    let next_tick = "2025-08-09T10:01:00Z".parse().unwrap();
+    assert!(first_instance.enqueue_tick(job_id, schedule.id, next_tick).await.unwrap().is_some());
+    assert_eq!(db::list_backup_runs(&db.pool, job_id, None, 50).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_cancel_previous_policy_supersedes_pending_runs() {
+    let db = TestDatabase::new().await;
+    let job_id = create_job_with_policy(&db, json!({ "/tmp/queue-source": ["/tmp/queue-backup"] }), "cancel_previous").await;
+    let queue = Arc::new(RunQueue::new(db.pool.clone()));
+    let running = queue.enqueue(job_id, None, "manual").await.unwrap();
+    let mut conn = worker_connection(&db).await;
+    db::claim_next_backup_run(&mut conn).await.unwrap().unwrap();
+    let queued = queue.enqueue(job_id, None, "manual").await.unwrap();
+
+    let latest = queue.enqueue(job_id, None, "scheduler").await.unwrap();
+    assert_eq!(latest.status, "queued");
+
+    let superseded = db::get_backup_run_by_id(&db.pool, queued.id).await.unwrap().unwrap();
+    assert_eq!(superseded.status, "cancelled");
+    assert!(superseded.status_reason.unwrap().contains(&latest.id.to_string()));
+
+    // A execução em andamento recebe o pedido; o worker dela grava o cancelamento
+    let interrupted = db::get_backup_run_by_id(&db.pool, running.id).await.unwrap().unwrap();
+    assert_eq!(interrupted.status, "running");
+    assert!(interrupted.cancel_requested_at.is_some());
+}
+
+#[tokio::test]
+async fn test_source_change_enqueues_watch_run() {
+    use b2cli::backup_triggers::BackupTriggers;
+
+    let db = TestDatabase::new().await;
+    let fixtures = TestFixtures::new();
+    fixtures.create_test_structure();
+    let source = fixtures.source_dir.to_string_lossy().to_string();
+    let destination = fixtures.backup_dir.to_string_lossy().to_string();
+    let new_job: NewBackupJob = serde_json::from_value(json!({
+        "name": "Watch Backup",
+        "mappings": { source: [destination] },
+        "trigger_mode": "watch",
+        "watch_quiet_seconds": 1,
+        "watch_min_interval_seconds": 1,
+        "watch_max_delay_seconds": 1,
+    }))
+    .unwrap();
+    let job = db::create_backup_job(&db.pool, &new_job).await.unwrap().0;
+
+    // Sem workers: o disparo fica na fila
+    let queue = Arc::new(RunQueue::new(db.pool.clone()));
+    let triggers = BackupTriggers::new(db.pool.clone(), queue);
+    triggers.sync_job(&job).await.unwrap();
+    assert_eq!(triggers.watched_jobs(), vec![job.id]);
+    assert!(db::list_backup_runs(&db.pool, job.id, None, 50).await.unwrap().is_empty());
+
+    fixtures.create_test_file("changed.txt", "nova versão");
+
+    let mut runs = vec![];
+    for _ in 0..100 {
+        runs = db::list_backup_runs(&db.pool, job.id, None, 50).await.unwrap();
+        if !runs.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(runs.len(), 1, "source change did not enqueue a run");
+    assert_eq!(runs[0].triggered_by, "watch");
+    assert_eq!(runs[0].status, "queued");
+    assert!(triggers.remove(job.id));
+}
@@ -0,0 +1,955 @@
+// tests/scanner_pipeline.rs
+// Testes do pipeline do FileScanner (walker → workers de hash → escritor em lote)
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tempfile::TempDir;
+use uuid::Uuid;
+use b2cli::cancel::CancelToken;
+use b2cli::file_scanner::{self, FileScanner, ScanCancelled, ScanConfig};
+use b2cli::search::SearchIndex;
+
+mod common;
+use common::{TestDatabase, count_files_recursive};
+
+fn test_scan_data() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_scan_data")
+}
+
+/// Configuração sem exclusões, para que todo arquivo da árvore seja catalogado
+fn config_for(root: &Path) -> ScanConfig {
+    ScanConfig {
+        root_path: root.to_path_buf(),
+        exclude_patterns: vec![],
+        ..Default::default()
+    }
+}
+
+fn copy_tree(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_tree(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+/// Índice de busca próprio do teste, no lugar do índice do processo
+fn search_index() -> (TempDir, Arc<SearchIndex>) {
+    let dir = TempDir::new().unwrap();
+    let index = Arc::new(SearchIndex::open(dir.path()).unwrap());
+    (dir, index)
+}
+
+/// Linha do catálogo lida pelos testes de MIME: nome, tipo, categoria e metadata
+type MimeRow = (String, Option<String>, Option<String>, Option<serde_json::Value>);
+
+async fn run_scan(db: &TestDatabase, config: ScanConfig) -> Uuid {
+    FileScanner::new(db.pool.clone(), config)
+        .start_scan()
+        .await
+        .expect("scan failed")
+}
+
+async fn run_indexed_scan(db: &TestDatabase, config: ScanConfig, index: &Arc<SearchIndex>) -> Uuid {
+    FileScanner::new(db.pool.clone(), config)
+        .with_search_index(index.clone())
+        .start_scan()
+        .await
+        .expect("scan failed")
+}
+
+async fn job_counters(db: &TestDatabase, scan_job_id: Uuid) -> (i64, i64, i64) {
+    let row: (Option<i64>, Option<i64>, Option<i64>) = sqlx::query_as(
+        "SELECT files_scanned::BIGINT, files_hashed::BIGINT, files_hash_skipped::BIGINT FROM scan_jobs WHERE id = $1",
+    )
+    .bind(scan_job_id)
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+    (row.0.unwrap_or(0), row.1.unwrap_or(0), row.2.unwrap_or(0))
+}
+
+async fn catalog_count(db: &TestDatabase) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM file_catalog")
+        .fetch_one(&db.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_pipeline_catalogs_test_scan_data() {
+    let db = TestDatabase::new().await;
+    let root = test_scan_data();
+    let expected = count_files_recursive(&root) as i64;
+
+    // Lotes pequenos e vários workers: força várias transações concorrendo com o walker
+    let config = ScanConfig {
+        hash_workers: 4,
+        queue_capacity: 2,
+        db_batch_size: 3,
+        ..config_for(&root)
+    };
+
+    let first = run_scan(&db, config.clone()).await;
+    assert_eq!(catalog_count(&db).await, expected);
+    assert_eq!(job_counters(&db, first).await, (expected, expected, 0));
+
+    let root_total: i64 = sqlx::query_scalar(
+        "SELECT total_files::BIGINT FROM directory_catalog WHERE directory_path = $1",
+    )
+    .bind(root.to_string_lossy().to_string())
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(root_total, expected);
+
+    // Segunda passada: nada mudou, nenhum arquivo é relido nem duplicado
+    let second = run_scan(&db, config).await;
+    assert_eq!(catalog_count(&db).await, expected);
+    assert_eq!(job_counters(&db, second).await, (expected, 0, expected));
+}
+
+#[tokio::test]
+async fn test_batched_writes_derive_history() {
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    copy_tree(&test_scan_data(), &root);
+    let expected = count_files_recursive(&root) as i64;
+
+    let first = run_scan(&db, config_for(&root)).await;
+    let initial: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM file_history WHERE scan_job_id = $1 AND scan_type = 'initial'",
+    )
+    .bind(first)
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(initial, expected);
+
+    // Um arquivo alterado e um novo: só eles geram histórico na segunda passada
+    let changed = root.join("README.md");
+    fs::write(&changed, "conteúdo totalmente diferente e maior que antes").unwrap();
+    fs::write(root.join("novo.txt"), "novo").unwrap();
+
+    let second = run_scan(&db, config_for(&root)).await;
+    let history: Vec<(String, String, bool, bool)> = sqlx::query_as(
+        r#"
+        SELECT fc.file_path, fh.scan_type, fh.size_changed, fh.hash_changed
+        FROM file_history fh
+        JOIN file_catalog fc ON fc.id = fh.file_catalog_id
+        WHERE fh.scan_job_id = $1 AND (fh.size_changed OR fh.hash_changed OR fh.scan_type = 'initial')
+        ORDER BY fc.file_path
+        "#,
+    )
+    .bind(second)
+    .fetch_all(&db.pool)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        history,
+        vec![
+            (changed.to_string_lossy().to_string(), "manual".to_string(), true, true),
+            (root.join("novo.txt").to_string_lossy().to_string(), "initial".to_string(), false, false),
+        ]
+    );
+    assert_eq!(catalog_count(&db).await, expected + 1);
+}
+
+async fn hash_mismatches(db: &TestDatabase, scan_job_id: Uuid) -> i64 {
+    sqlx::query_scalar("SELECT hash_mismatches::BIGINT FROM scan_jobs WHERE id = $1")
+        .bind(scan_job_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_force_rehash_rereads_unchanged_files() {
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    write_files(&root, &["a.txt", "b.txt", "sub/c.txt"]);
+    run_scan(&db, config_for(&root)).await;
+
+    // Conteúdo trocado com o mesmo tamanho e mtime: só o rehash percebe
+    let silent = root.join("a.txt");
+    let modified = fs::metadata(&silent).unwrap().modified().unwrap();
+    fs::write(&silent, "A.TXT").unwrap();
+    fs::File::options().write(true).open(&silent).unwrap().set_modified(modified).unwrap();
+
+    let normal = run_scan(&db, config_for(&root)).await;
+    assert_eq!(job_counters(&db, normal).await, (3, 0, 3));
+    assert_eq!(hash_mismatches(&db, normal).await, 0);
+
+    let forced = run_scan(&db, ScanConfig { force_rehash: true, ..config_for(&root) }).await;
+    assert_eq!(job_counters(&db, forced).await, (3, 3, 0));
+    assert_eq!(hash_mismatches(&db, forced).await, 1);
+
+    let stored: String = sqlx::query_scalar("SELECT content_hash FROM file_catalog WHERE file_path = $1")
+        .bind(silent.to_string_lossy().to_string())
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, file_scanner::calculate_file_hash(&silent).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_removed_files_are_marked_deleted() {
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    // Raiz vizinha com o mesmo prefixo textual: não pode ser afetada
+    let sibling = temp.path().join("data-other");
+    copy_tree(&test_scan_data(), &root);
+    fs::create_dir_all(&sibling).unwrap();
+    fs::write(sibling.join("keep.txt"), "keep").unwrap();
+
+    run_scan(&db, config_for(&sibling)).await;
+    run_scan(&db, config_for(&root)).await;
+
+    let removed = root.join("code/rust/backup_engine.rs");
+    fs::remove_file(&removed).unwrap();
+    let second = run_scan(&db, config_for(&root)).await;
+
+    let files_deleted: i64 = sqlx::query_scalar("SELECT files_deleted FROM scan_jobs WHERE id = $1")
+        .bind(second)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(files_deleted, 1);
+
+    let inactive: Vec<String> = sqlx::query_scalar(
+        "SELECT file_path FROM file_catalog WHERE is_active = FALSE ORDER BY file_path",
+    )
+    .fetch_all(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(inactive, vec![removed.to_string_lossy().to_string()]);
+
+    let deleted_events: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM file_history WHERE scan_job_id = $1 AND scan_type = 'deleted'",
+    )
+    .bind(second)
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(deleted_events, 1);
+
+    // Arquivo recriado volta a ficar ativo sem novo evento de remoção
+    fs::write(&removed, "fn main() {}").unwrap();
+    let third = run_scan(&db, config_for(&root)).await;
+    let active: Option<bool> = sqlx::query_scalar("SELECT is_active FROM file_catalog WHERE file_path = $1")
+        .bind(removed.to_string_lossy().to_string())
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(active, Some(true));
+    let (scanned, _, _) = job_counters(&db, third).await;
+    assert_eq!(scanned, count_files_recursive(&root) as i64);
+}
+
+/// Caminhos inativos em ordem de bytes (independe da collation do banco)
+async fn inactive_paths(db: &TestDatabase) -> Vec<String> {
+    let mut paths: Vec<String> = sqlx::query_scalar("SELECT file_path FROM file_catalog WHERE is_active = FALSE")
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+    paths.sort();
+    paths
+}
+
+async fn files_deleted(db: &TestDatabase, scan_job_id: Uuid) -> i64 {
+    sqlx::query_scalar("SELECT files_deleted FROM scan_jobs WHERE id = $1")
+        .bind(scan_job_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap()
+}
+
+/// Arquivos ativos do catálogo, relativos a `root`, em ordem de bytes
+async fn active_relative_paths(db: &TestDatabase, root: &Path) -> Vec<String> {
+    let paths: Vec<String> = sqlx::query_scalar("SELECT file_path FROM file_catalog WHERE is_active = TRUE")
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+    let mut relative: Vec<String> = paths
+        .iter()
+        .map(|path| Path::new(path).strip_prefix(root).unwrap().to_string_lossy().to_string())
+        .collect();
+    relative.sort();
+    relative
+}
+
+async fn scan_output(db: &TestDatabase, scan_job_id: Uuid) -> serde_json::Value {
+    sqlx::query_scalar("SELECT scan_output FROM scan_jobs WHERE id = $1")
+        .bind(scan_job_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap()
+}
+
+fn write_files(root: &Path, files: &[&str]) {
+    for file in files {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, file).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_excluded_directories_are_pruned_and_counted() {
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    write_files(
+        &root,
+        &["app.js", "debug.log", "node_modules/left-pad/index.js", "node_modules/left-pad/package.json", "src/main.js"],
+    );
+
+    let config = ScanConfig {
+        exclude_patterns: vec!["node_modules/".to_string(), "*.log".to_string()],
+        ..config_for(&root)
+    };
+    let scan_job_id = run_scan(&db, config).await;
+
+    assert_eq!(active_relative_paths(&db, &root).await, vec!["app.js", "src/main.js"]);
+    // O diretório podado conta uma vez; os arquivos dele nem chegam a ser vistos
+    let output = scan_output(&db, scan_job_id).await;
+    assert_eq!(output["skipped_directories"], 1);
+    assert_eq!(output["skipped_files"], 1);
+}
+
+#[tokio::test]
+async fn test_include_patterns_match_directories_and_files() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    write_files(&root, &["README.md", "notes.txt", "docs/guide.md", "docs/api/ref.md", "src/main.rs"]);
+
+    for (includes, expected) in [
+        (vec!["docs/", "README.md"], vec!["README.md", "docs/api/ref.md", "docs/guide.md"]),
+        (vec!["src"], vec!["src/main.rs"]),
+        (vec!["*.md"], vec!["README.md", "docs/api/ref.md", "docs/guide.md"]),
+    ] {
+        let db = TestDatabase::new().await;
+        let config = ScanConfig {
+            include_patterns: includes.iter().map(|p| p.to_string()).collect(),
+            ..config_for(&root)
+        };
+        run_scan(&db, config).await;
+        assert_eq!(active_relative_paths(&db, &root).await, expected, "includes {:?}", includes);
+    }
+}
+
+#[tokio::test]
+async fn test_b2ignore_files_apply_to_their_subtree() {
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    write_files(
+        &root,
+        &["keep.txt", "old.bak", "secret.txt", "build/out.bin", "sub/ok.txt", "sub/secret.txt", "sub/deep/secret.txt"],
+    );
+    fs::write(root.join(".b2ignore"), "*.bak\nbuild/\n").unwrap();
+    fs::write(root.join("sub/.b2ignore"), "# só vale dentro de sub/\nsecret.txt\n").unwrap();
+
+    let scan_job_id = run_scan(&db, config_for(&root)).await;
+
+    let cataloged: Vec<String> = active_relative_paths(&db, &root)
+        .await
+        .into_iter()
+        .filter(|path| !path.ends_with(".b2ignore"))
+        .collect();
+    assert_eq!(cataloged, vec!["keep.txt", "secret.txt", "sub/ok.txt"]);
+    let output = scan_output(&db, scan_job_id).await;
+    assert_eq!(output["skipped_directories"], 1);
+    assert_eq!(output["skipped_files"], 3);
+}
+
+#[tokio::test]
+async fn test_partial_scans_only_mark_listed_directories() {
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    copy_tree(&test_scan_data(), &root);
+    run_scan(&db, config_for(&root)).await;
+
+    let top_level = root.join("README.md");
+    let nested = root.join("code/rust/backup_engine.rs");
+    fs::remove_file(&top_level).unwrap();
+    fs::remove_file(&nested).unwrap();
+
+    // Não recursivo: só a raiz é listada, os subdiretórios ficam como estão
+    let scan = run_scan(&db, ScanConfig { recursive: false, ..config_for(&root) }).await;
+    assert_eq!(files_deleted(&db, scan).await, 1);
+    assert_eq!(inactive_paths(&db).await, vec![top_level.to_string_lossy().to_string()]);
+
+    // `code` (profundidade 1) é listado, `code/rust` não
+    let scan = run_scan(&db, ScanConfig { max_depth: Some(1), ..config_for(&root) }).await;
+    assert_eq!(files_deleted(&db, scan).await, 0);
+
+    // Excluído pelos filtros mas ainda no disco: continua ativo
+    let filtered = root.join("code/sql/backup_reports.sql");
+    let scan = run_scan(&db, ScanConfig { exclude_patterns: vec!["*.sql".to_string()], ..config_for(&root) }).await;
+    assert_eq!(files_deleted(&db, scan).await, 1);
+    assert_eq!(
+        inactive_paths(&db).await,
+        vec![top_level.to_string_lossy().to_string(), nested.to_string_lossy().to_string()]
+    );
+    assert!(!inactive_paths(&db).await.contains(&filtered.to_string_lossy().to_string()));
+}
+
+#[tokio::test]
+async fn test_scan_with_errors_marks_nothing_deleted() {
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    copy_tree(&test_scan_data(), &root);
+    let config = ScanConfig { follow_symlinks: true, ..config_for(&root) };
+    run_scan(&db, config.clone()).await;
+
+    // Link para si mesmo: a entrada existe, mas o metadata falha (ELOOP)
+    let unreadable = root.join("code/rust/backup_engine.rs");
+    fs::remove_file(&unreadable).unwrap();
+    std::os::unix::fs::symlink(&unreadable, &unreadable).unwrap();
+    fs::remove_file(root.join("README.md")).unwrap();
+
+    let scan = run_scan(&db, config).await;
+    let errors: Option<i32> = sqlx::query_scalar("SELECT errors_count FROM scan_jobs WHERE id = $1")
+        .bind(scan)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert!(errors.unwrap_or(0) > 0);
+    assert_eq!(files_deleted(&db, scan).await, 0);
+    assert!(inactive_paths(&db).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_cancelled_scan_keeps_partial_stats_and_catalog() {
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    copy_tree(&test_scan_data(), &root);
+    run_scan(&db, config_for(&root)).await;
+
+    let removed = root.join("code/rust/backup_engine.rs");
+    fs::remove_file(&removed).unwrap();
+
+    // Cancelado antes do walk: nada é percorrido, nada pode ser dado como removido
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let err = FileScanner::new(db.pool.clone(), config_for(&root))
+        .with_cancel(cancel)
+        .start_scan()
+        .await
+        .expect_err("cancelled scan must fail");
+    let scan_job_id = err.downcast_ref::<ScanCancelled>().expect("ScanCancelled").scan_job_id;
+
+    let (status, files_deleted, completed): (Option<String>, i64, bool) = sqlx::query_as(
+        "SELECT status, files_deleted, completed_at IS NOT NULL FROM scan_jobs WHERE id = $1",
+    )
+    .bind(scan_job_id)
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(status.as_deref(), Some("cancelled"));
+    assert_eq!(files_deleted, 0);
+    assert!(completed);
+    assert_eq!(job_counters(&db, scan_job_id).await.0, 0);
+
+    let active: Option<bool> = sqlx::query_scalar("SELECT is_active FROM file_catalog WHERE file_path = $1")
+        .bind(removed.to_string_lossy().to_string())
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(active, Some(true));
+
+    // O registro só vale enquanto a varredura roda
+    assert!(!file_scanner::cancel_scan(scan_job_id));
+}
+
+#[tokio::test]
+async fn test_apply_changes_updates_catalog_incrementally() {
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    copy_tree(&test_scan_data(), &root);
+    run_scan(&db, config_for(&root)).await;
+
+    let mut scanner = FileScanner::new(db.pool.clone(), config_for(&root));
+    scanner.start_live_session(None).await.unwrap();
+
+    // Arquivo novo, diretório renomeado e arquivo alterado
+    let created = root.join("novo.txt");
+    fs::write(&created, "novo").unwrap();
+    fs::rename(root.join("media"), root.join("midia")).unwrap();
+    fs::write(root.join("README.md"), "alterado").unwrap();
+
+    let result = scanner
+        .apply_changes(&[created.clone(), root.join("media"), root.join("midia"), root.join("README.md")])
+        .await
+        .unwrap();
+
+    let media_files = count_files_recursive(&root.join("midia")) as i64;
+    assert_eq!(result.files_deleted, media_files);
+    assert_eq!(result.files_updated, 2 + media_files);
+    assert!(!result.needs_rescan);
+
+    let active: Vec<String> = sqlx::query_scalar(
+        "SELECT file_path FROM file_catalog WHERE is_active = TRUE AND starts_with(file_path, $1)",
+    )
+    .bind(root.join("midia").to_string_lossy().to_string())
+    .fetch_all(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(active.len() as i64, media_files);
+
+    let still_active_old: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM file_catalog WHERE is_active = TRUE AND starts_with(file_path, $1)",
+    )
+    .bind(format!("{}/", root.join("media").to_string_lossy()))
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(still_active_old, 0);
+
+    // Caminhos fora da raiz são ignorados
+    let outside = scanner.apply_changes(&[temp.path().join("fora.txt")]).await.unwrap();
+    assert_eq!(outside.files_updated + outside.files_deleted, 0);
+
+    scanner.finish_live_session().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_live_watcher_applies_filesystem_events() {
+    use b2cli::live_catalog::LiveCatalog;
+    use std::time::Duration;
+
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("live");
+    fs::create_dir_all(&root).unwrap();
+
+    let config_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO scan_configs (name, root_path, live_mode) VALUES ('live', $1, TRUE) RETURNING id",
+    )
+    .bind(root.to_string_lossy().to_string())
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+
+    let live = LiveCatalog::new(db.pool.clone());
+    live.watch(config_id, config_for(&root)).unwrap();
+
+    // Espera o scan inicial terminar antes de gerar eventos
+    let mut waited = Duration::ZERO;
+    while live.statuses()[0].state != "watching" {
+        assert!(waited < Duration::from_secs(10), "watcher did not start");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        waited += Duration::from_millis(50);
+    }
+
+    let file = root.join("watched.txt");
+    fs::write(&file, "evento").unwrap();
+
+    let mut waited = Duration::ZERO;
+    loop {
+        let found: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM file_catalog WHERE file_path = $1 AND is_active")
+            .bind(file.to_string_lossy().to_string())
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        if found == 1 {
+            break;
+        }
+        assert!(waited < Duration::from_secs(15), "live event was not applied");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        waited += Duration::from_millis(100);
+    }
+
+    let status = &live.statuses()[0];
+    assert!(status.events_received > 0);
+    assert!(!status.overflowed);
+    assert!(live.unwatch(config_id));
+}
+
+#[tokio::test]
+async fn test_live_mode_off_stops_the_watcher() {
+    use b2cli::db;
+    use b2cli::live_catalog::LiveCatalog;
+    use std::time::Duration;
+
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("live");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("kept.txt"), "catalogado").unwrap();
+    fs::write(root.join("skipped.log"), "filtrado").unwrap();
+
+    let config_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO scan_configs (name, root_path, live_mode, exclude_patterns) \
+         VALUES ('live', $1, TRUE, '{*.log}') RETURNING id",
+    )
+    .bind(root.to_string_lossy().to_string())
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+
+    // start_all monta a configuração a partir da linha salva
+    let live = LiveCatalog::new(db.pool.clone());
+    assert_eq!(live.start_all().await.unwrap(), 1);
+
+    let mut waited = Duration::ZERO;
+    while live.statuses()[0].state != "watching" {
+        assert!(waited < Duration::from_secs(10), "watcher did not start");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        waited += Duration::from_millis(50);
+    }
+
+    let cataloged: Vec<String> = sqlx::query_scalar("SELECT file_name FROM file_catalog WHERE is_active")
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(cataloged, vec!["kept.txt".to_string()]);
+
+    let record = db::set_scan_config_live_mode(&db.pool, config_id, false).await.unwrap().unwrap();
+    assert!(!record.live_mode);
+    assert!(live.unwatch(config_id));
+    assert!(live.statuses().is_empty());
+    assert!(db::list_live_scan_configs(&db.pool).await.unwrap().is_empty());
+
+    // Sem o watcher, a task esvazia a fila e encerra a sessão live
+    let mut waited = Duration::ZERO;
+    loop {
+        let status: String = sqlx::query_scalar(
+            "SELECT status FROM scan_jobs WHERE scan_config_id = $1 AND scan_type = 'live'",
+        )
+        .bind(config_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        if status == "completed" {
+            break;
+        }
+        assert!(waited < Duration::from_secs(10), "live session was not finished");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        waited += Duration::from_millis(100);
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_results_do_not_depend_on_concurrency() {
+    let db_sequential = TestDatabase::new().await;
+    let db_parallel = TestDatabase::new().await;
+    let root = test_scan_data();
+
+    let sequential = ScanConfig {
+        hash_workers: 1,
+        queue_capacity: 1,
+        db_batch_size: 1,
+        ..config_for(&root)
+    };
+    run_scan(&db_sequential, sequential).await;
+    run_scan(&db_parallel, config_for(&root)).await;
+
+    let query = "SELECT file_path, content_hash FROM file_catalog ORDER BY file_path";
+    let a: Vec<(String, Option<String>)> = sqlx::query_as(query).fetch_all(&db_sequential.pool).await.unwrap();
+    let b: Vec<(String, Option<String>)> = sqlx::query_as(query).fetch_all(&db_parallel.pool).await.unwrap();
+    assert!(!a.is_empty());
+    assert_eq!(a, b);
+}
+
+/// Benchmark: `cargo test --test scanner_pipeline -- --ignored --nocapture`
+///
+/// Replica `test_scan_data` e gera `B2CLI_BENCH_FILES` (padrão 100k) arquivos
+/// pequenos, comparando o pipeline com 1 worker e lotes de 1 com os limites
+/// padrão. Mede só o ganho de paralelismo e de gravação em lote do pipeline:
+/// o walker recursivo anterior não existe mais, então não há comparação com ele
+/// (números medidos no README).
+#[tokio::test]
+#[ignore]
+async fn bench_pipeline_many_small_files() {
+    let total: usize = std::env::var("B2CLI_BENCH_FILES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(100_000);
+
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("bench");
+    copy_tree(&test_scan_data(), &root);
+    for i in 0..total {
+        let dir = root.join(format!("generated/{:03}", i % 500));
+        if i < 500 {
+            fs::create_dir_all(&dir).unwrap();
+        }
+        fs::write(dir.join(format!("file_{}.txt", i)), format!("conteúdo {}", i)).unwrap();
+    }
+    let files = count_files_recursive(&root);
+
+    let mut timings = Vec::new();
+    for (label, config) in [
+        (
+            "1 worker",
+            ScanConfig { hash_workers: 1, queue_capacity: 1, db_batch_size: 1, ..config_for(&root) },
+        ),
+        ("pipeline", config_for(&root)),
+    ] {
+        let db = TestDatabase::new().await;
+        let started = Instant::now();
+        run_scan(&db, config).await;
+        let elapsed = started.elapsed();
+        assert_eq!(catalog_count(&db).await, files as i64);
+
+        println!(
+            "{:>10}: {} arquivos em {:.2?} ({:.0} arquivos/s)",
+            label,
+            files,
+            elapsed,
+            files as f64 / elapsed.as_secs_f64()
+        );
+        timings.push(elapsed);
+    }
+
+    println!("speedup: {:.1}x", timings[0].as_secs_f64() / timings[1].as_secs_f64());
+    assert!(timings[1] < timings[0], "default limits should beat a single worker");
+}
+
+#[tokio::test]
+async fn test_scan_updates_search_index() {
+    use b2cli::search::SearchRequest;
+
+    let db = TestDatabase::new().await;
+    let (_index_dir, index) = search_index();
+
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    copy_tree(&test_scan_data(), &root);
+    fs::write(root.join("relatorio-trimestral.pdf"), "pdf").unwrap();
+    run_indexed_scan(&db, config_for(&root), &index).await;
+
+    let search_root = |query: &str| SearchRequest {
+        query: Some(query.to_string()),
+        limit: 10,
+        ..Default::default()
+    };
+    let response = index.search(&search_root("trimestral")).unwrap();
+    assert_eq!(response.total, 1);
+    assert_eq!(response.results[0].extension.as_deref(), Some("pdf"));
+    assert_eq!(response.results[0].backup_status, "never");
+
+    // Removido do disco: sai do índice no próximo scan completo
+    fs::remove_file(root.join("relatorio-trimestral.pdf")).unwrap();
+    run_indexed_scan(&db, config_for(&root), &index).await;
+    assert_eq!(index.search(&search_root("trimestral")).unwrap().total, 0);
+}
+
+#[tokio::test]
+async fn test_scan_indexes_document_content() {
+    use b2cli::search::SearchRequest;
+
+    let db = TestDatabase::new().await;
+    let (_index_dir, index) = search_index();
+
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    copy_tree(&test_scan_data().join("documents"), &root.join("documents"));
+    copy_tree(&test_scan_data().join("code"), &root.join("code"));
+    fs::write(root.join("notas.md"), "Reunião sobre a migração do datacenter").unwrap();
+
+    let config = || ScanConfig {
+        index_content: true,
+        ..config_for(&root)
+    };
+    run_indexed_scan(&db, config(), &index).await;
+
+    let search_content = |content: &str| {
+        index
+            .search(&SearchRequest {
+                content: Some(content.to_string()),
+                limit: 10,
+                ..Default::default()
+            })
+            .unwrap()
+    };
+    let response = search_content("BackupEngine");
+    assert_eq!(response.total, 1);
+    assert_eq!(response.results[0].file_name, "backup_engine.rs");
+    assert_eq!(search_content("datacenter").total, 1);
+    // .pdf não está na lista padrão de extensões
+    assert_eq!(search_content("LICENCIAMENTO").total, 0);
+
+    // Conteúdo alterado: o texto antigo sai do índice
+    fs::write(root.join("notas.md"), "Pauta: orçamento do próximo trimestre").unwrap();
+    run_indexed_scan(&db, config(), &index).await;
+    assert_eq!(search_content("datacenter").total, 0);
+    assert_eq!(search_content("orçamento").total, 1);
+
+    let stored: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM file_content fct JOIN file_catalog fc ON fc.id = fct.file_catalog_id WHERE fc.file_path LIKE $1",
+    )
+    .bind(format!("{}/%", root.display()))
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(stored, 5, "4 arquivos de código + notas.md");
+}
+
+#[tokio::test]
+async fn test_scan_detects_mime_types() {
+    use b2cli::models::MimeCategory;
+
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    copy_tree(&test_scan_data().join("code"), &root.join("code"));
+    fs::create_dir_all(root.join("media")).unwrap();
+    fs::write(root.join("media/foto.png"), b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR").unwrap();
+    fs::write(root.join("media/clipe.mkv"), b"\x1a\x45\xdf\xa3\x01\x00\x00\x00").unwrap();
+    // Um "PDF" que na verdade é um ZIP
+    fs::write(root.join("contrato.pdf"), b"PK\x03\x04\x14\x00\x00\x00\x08\x00").unwrap();
+
+    let config = ScanConfig {
+        exclude_mime_categories: vec![MimeCategory::Video],
+        ..config_for(&root)
+    };
+    run_scan(&db, config).await;
+
+    let rows: Vec<MimeRow> = sqlx::query_as(
+        "SELECT file_name, mime_type, mime_category, metadata FROM file_catalog WHERE file_path LIKE $1 ORDER BY file_name",
+    )
+    .bind(format!("{}/%", root.display()))
+    .fetch_all(&db.pool)
+    .await
+    .unwrap();
+    let by_name = |name: &str| {
+        rows.iter()
+            .find(|row| row.0 == name)
+            .unwrap_or_else(|| panic!("{} não catalogado", name))
+    };
+
+    assert!(rows.iter().all(|row| row.0 != "clipe.mkv"), "categoria excluída não deve ser catalogada");
+
+    let png = by_name("foto.png");
+    assert_eq!(png.1.as_deref(), Some("image/png"));
+    assert_eq!(png.2.as_deref(), Some("image"));
+
+    let fake_pdf = by_name("contrato.pdf");
+    assert_eq!(fake_pdf.1.as_deref(), Some("application/zip"));
+    assert_eq!(fake_pdf.2.as_deref(), Some("archive"));
+    let mismatch = &fake_pdf.3.as_ref().unwrap()["mime_mismatch"];
+    assert_eq!(mismatch["extension"], "application/pdf");
+
+    assert_eq!(by_name("backup_engine.rs").2.as_deref(), Some("source_code"));
+}
+
+#[tokio::test]
+async fn test_excluded_mime_category_keeps_cataloged_files_active() {
+    use b2cli::models::MimeCategory;
+
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("clipe.mkv"), b"\x1a\x45\xdf\xa3\x01\x00\x00\x00").unwrap();
+    fs::write(root.join("notas.txt"), "texto").unwrap();
+    run_scan(&db, config_for(&root)).await;
+
+    // A categoria só deixa de ser catalogada: o que já está no catálogo fica ativo
+    let config = ScanConfig {
+        exclude_mime_categories: vec![MimeCategory::Video],
+        ..config_for(&root)
+    };
+    let scan_job_id = run_scan(&db, config).await;
+    assert_eq!(files_deleted(&db, scan_job_id).await, 0);
+    assert!(inactive_paths(&db).await.is_empty());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_scan_captures_posix_metadata_and_symlinks() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let db = TestDatabase::new().await;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("data");
+    fs::create_dir_all(root.join("sub")).unwrap();
+    fs::write(root.join("publico.txt"), "qualquer um escreve").unwrap();
+    fs::set_permissions(root.join("publico.txt"), fs::Permissions::from_mode(0o666)).unwrap();
+    fs::write(root.join("privado.txt"), "só o dono").unwrap();
+    fs::set_permissions(root.join("privado.txt"), fs::Permissions::from_mode(0o600)).unwrap();
+    symlink("privado.txt", root.join("atalho")).unwrap();
+    // Ciclo: só é percorrido com follow_symlinks, e no máximo uma vez
+    symlink("..", root.join("sub/volta")).unwrap();
+
+    run_scan(&db, config_for(&root)).await;
+
+    let rows: Vec<(String, Option<String>, Option<String>, serde_json::Value)> = sqlx::query_as(
+        "SELECT file_name, mime_type, content_hash, metadata FROM file_catalog WHERE file_path LIKE $1 ORDER BY file_name",
+    )
+    .bind(format!("{}/%", root.display()))
+    .fetch_all(&db.pool)
+    .await
+    .unwrap();
+    let by_name = |name: &str| {
+        rows.iter()
+            .find(|row| row.0 == name)
+            .unwrap_or_else(|| panic!("{} não catalogado", name))
+    };
+
+    let public = by_name("publico.txt");
+    assert_eq!(public.3["unix"]["mode"].as_u64().unwrap() & 0o7777, 0o666);
+    assert_eq!(public.3["unix"]["permissions"], "rw-rw-rw-");
+    assert_eq!(public.3["unix"]["uid"].as_u64(), Some(owner_uid(&root.join("publico.txt"))));
+
+    let link = by_name("atalho");
+    assert_eq!(link.1.as_deref(), Some("inode/symlink"));
+    assert!(link.2.is_none(), "links não seguidos não têm hash");
+    assert_eq!(link.3["symlink_target"], "privado.txt");
+    assert_eq!(by_name("volta").3["symlink_target"], "..");
+
+    let world_writable: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT file_name FROM file_catalog
+        WHERE file_path LIKE $1
+          AND ((metadata->'unix'->>'mode')::BIGINT & 2) <> 0
+          AND mime_type IS DISTINCT FROM 'inode/symlink'
+        "#,
+    )
+    .bind(format!("{}/%", root.display()))
+    .fetch_all(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(world_writable, vec!["publico.txt"]);
+
+    // Seguindo links: o destino é catalogado como arquivo comum e o ciclo termina
+    let config = ScanConfig {
+        follow_symlinks: true,
+        ..config_for(&root)
+    };
+    run_scan(&db, config).await;
+
+    let (mime_type, content_hash): (Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT mime_type, content_hash FROM file_catalog WHERE file_path = $1",
+    )
+    .bind(root.join("atalho").to_string_lossy().to_string())
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(mime_type.as_deref(), Some("text/plain"));
+    assert!(content_hash.is_some());
+}
+
+#[cfg(unix)]
+fn owner_uid(path: &Path) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).unwrap().uid() as u64
+}
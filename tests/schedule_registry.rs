@@ -0,0 +1,143 @@
+// tests/schedule_registry.rs
+// Testes da reconciliação entre backup_schedules/scan_schedules e o scheduler em memória
+
+use std::sync::Arc;
+use b2cli::{
+    db,
+    models::{NewBackupJob, NewScanSchedule, ScheduleKind, UpdateScanSchedule},
+    run_queue::RunQueue,
+    scan_schedules,
+    schedule_registry::ScheduleRegistry,
+};
+use serde_json::json;
+use tokio_cron_scheduler::JobScheduler;
+use uuid::Uuid;
+
+mod common;
+use common::{TestDatabase, TestFixtures};
+
+async fn create_scheduled_job(db: &TestDatabase, cron_expression: &str) -> Uuid {
+    let new_job: NewBackupJob = serde_json::from_value(json!({
+        "name": format!("Scheduled Backup {}", cron_expression),
+        "mappings": { "/tmp/schedule-source": ["/tmp/schedule-backup"] },
+        "schedule": { "name": "Reconciled", "cron_expression": cron_expression },
+    }))
+    .unwrap();
+    db::create_backup_job(&db.pool, &new_job).await.unwrap().0.id
+}
+
+async fn create_scan_schedule(db: &TestDatabase, root_path: &str, cron_expression: &str) -> Uuid {
+    let new_schedule: NewScanSchedule = serde_json::from_value(json!({
+        "name": format!("Scheduled Scan {}", cron_expression),
+        "root_path": root_path,
+        "cron_expression": cron_expression,
+    }))
+    .unwrap();
+    db::create_scan_schedule(&db.pool, &new_schedule).await.unwrap().id
+}
+
+async fn create_registry(db: &TestDatabase) -> ScheduleRegistry {
+    let scheduler = Arc::new(JobScheduler::new().await.unwrap());
+    let run_queue = Arc::new(RunQueue::new(db.pool.clone()));
+    ScheduleRegistry::new(db.pool.clone(), scheduler, run_queue)
+}
+
+#[tokio::test]
+async fn test_reconcile_follows_changes_made_directly_in_the_database() {
+    let db = TestDatabase::new().await;
+    let kept = create_scheduled_job(&db, "0 0 3 * * *").await;
+    let changed = create_scheduled_job(&db, "0 0 4 * * *").await;
+    let disabled = create_scheduled_job(&db, "0 0 5 * * *").await;
+    let deleted = create_scheduled_job(&db, "0 0 6 * * *").await;
+
+    let registry = create_registry(&db).await;
+    assert_eq!(registry.reconcile().await.unwrap(), 4);
+    assert_eq!(registry.reconcile().await.unwrap(), 0);
+
+    // Alterações feitas por outra instância ou direto no banco
+    sqlx::query!("UPDATE backup_schedules SET cron_expression = '0 15 4 * * *' WHERE backup_job_id = $1", changed)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    sqlx::query!("UPDATE backup_schedules SET enabled = false WHERE backup_job_id = $1", disabled)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    db::delete_backup_job(&db.pool, deleted).await.unwrap();
+
+    assert_eq!(registry.reconcile().await.unwrap(), 3);
+    let jobs = registry.status().await;
+    let mut registered: Vec<(Uuid, String)> =
+        jobs.iter().map(|job| (job.backup_job_id.unwrap(), job.cron_expression.clone())).collect();
+    registered.sort();
+    let mut expected = vec![(kept, "0 0 3 * * *".to_string()), (changed, "0 15 4 * * *".to_string())];
+    expected.sort();
+    assert_eq!(registered, expected);
+    assert!(jobs.iter().all(|job| job.next_tick.is_some()));
+}
+
+#[tokio::test]
+async fn test_invalid_cron_does_not_block_other_schedules() {
+    let db = TestDatabase::new().await;
+    let valid = create_scheduled_job(&db, "0 0 3 * * *").await;
+    create_scheduled_job(&db, "not a cron").await;
+
+    let registry = create_registry(&db).await;
+    assert_eq!(registry.reconcile().await.unwrap(), 1);
+    let jobs = registry.status().await;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].backup_job_id, Some(valid));
+
+    assert!(registry.remove_job(valid).await.unwrap());
+    assert!(!registry.remove_job(valid).await.unwrap());
+    assert!(registry.status().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_reconcile_registers_scan_schedules_and_tracks_next_run() {
+    let db = TestDatabase::new().await;
+    let schedule_id = create_scan_schedule(&db, "/tmp/schedule-scan", "0 0 2 * * *").await;
+
+    let registry = create_registry(&db).await;
+    assert_eq!(registry.reconcile().await.unwrap(), 1);
+    let jobs = registry.status().await;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].kind, ScheduleKind::Scan);
+    assert_eq!(jobs[0].schedule_id, schedule_id);
+    assert_eq!(jobs[0].backup_job_id, None);
+
+    let schedule = db::get_scan_schedule(&db.pool, schedule_id).await.unwrap().unwrap();
+    assert_eq!(schedule.next_run_at, jobs[0].next_tick);
+    assert!(schedule.next_run_at.is_some());
+
+    // Desativado no banco: sai do scheduler e perde o próximo disparo
+    let disable = UpdateScanSchedule { enabled: Some(false), ..Default::default() };
+    db::patch_scan_schedule(&db.pool, schedule_id, &disable).await.unwrap();
+    assert_eq!(registry.reconcile().await.unwrap(), 1);
+    assert!(registry.status().await.is_empty());
+
+    let schedule = db::get_scan_schedule(&db.pool, schedule_id).await.unwrap().unwrap();
+    assert!(schedule.next_run_at.is_none());
+}
+
+#[tokio::test]
+async fn test_run_scheduled_scan_records_the_result() {
+    let db = TestDatabase::new().await;
+    let fixtures = TestFixtures::new();
+    fixtures.create_test_structure();
+
+    let ok = create_scan_schedule(&db, fixtures.source_dir.to_str().unwrap(), "0 0 2 * * *").await;
+    let missing = create_scan_schedule(&db, "/nonexistent/b2cli-scan-root", "0 0 3 * * *").await;
+
+    scan_schedules::run_scheduled_scan(&db.pool, ok).await;
+    let schedule = db::get_scan_schedule(&db.pool, ok).await.unwrap().unwrap();
+    assert_eq!(schedule.last_run_status.as_deref(), Some("success"));
+    assert_eq!((schedule.total_runs, schedule.successful_runs, schedule.failed_runs), (1, 1, 0));
+    assert!(schedule.last_run_at.is_some());
+    assert!(schedule.last_scan_job_id.is_some());
+
+    scan_schedules::run_scheduled_scan(&db.pool, missing).await;
+    let schedule = db::get_scan_schedule(&db.pool, missing).await.unwrap().unwrap();
+    assert_eq!(schedule.last_run_status.as_deref(), Some("failed"));
+    assert_eq!((schedule.total_runs, schedule.successful_runs, schedule.failed_runs), (1, 0, 1));
+}